@@ -0,0 +1,6 @@
+-- down.sql
+
+DROP TABLE IF EXISTS customer_payments;
+DROP INDEX IF EXISTS idx_sales_customer_id;
+ALTER TABLE sales DROP COLUMN IF EXISTS customer_id;
+DROP TABLE IF EXISTS customers;
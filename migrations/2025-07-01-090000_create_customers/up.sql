@@ -0,0 +1,30 @@
+-- up.sql
+-- Table des clients (bars, boutiques...) pouvant acheter à crédit
+CREATE TABLE customers (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    phone TEXT,
+    address TEXT,
+    credit_limit NUMERIC(12, 2) NOT NULL DEFAULT 0 CHECK (credit_limit >= 0), -- Encours maximum autorisé
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON customers FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Une vente peut être rattachée à un client (vente à crédit)
+ALTER TABLE sales ADD COLUMN customer_id UUID REFERENCES customers(id) ON DELETE RESTRICT;
+CREATE INDEX idx_sales_customer_id ON sales (customer_id);
+
+-- Règlements effectués par les clients pour solder leur encours
+CREATE TABLE customer_payments (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE RESTRICT,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- L'utilisateur qui a encaissé
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    note TEXT,
+    date TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON customer_payments FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_customer_payments_customer_id ON customer_payments (customer_id);
@@ -0,0 +1,29 @@
+-- up.sql
+-- Règlements (moyens de paiement) rattachés à une vente.
+-- Une vente peut être réglée en plusieurs fois et avec plusieurs moyens de paiement.
+CREATE TABLE payments (
+    id UUID PRIMARY KEY,
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    method TEXT NOT NULL CHECK (method IN ('cash', 'mobile_money', 'cheque', 'credit')),
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0), -- Montant imputé sur la vente
+    cash_tendered NUMERIC(12, 2), -- Espèces remises par le client (paiement en espèces uniquement)
+    change_due NUMERIC(12, 2) NOT NULL DEFAULT 0 CHECK (change_due >= 0), -- Monnaie rendue
+    reference TEXT, -- N° de transaction Mobile Money, n° de chèque...
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (cash_tendered IS NULL OR cash_tendered >= amount)
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON payments FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_payments_sale_id ON payments (sale_id);
+
+-- Reprise de l'existant : les ventes rattachées à un client ont été portées en compte,
+-- les autres ont été réglées intégralement en espèces.
+INSERT INTO payments (id, sale_id, method, amount, cash_tendered, change_due)
+SELECT gen_random_uuid(), id, 'credit', total_amount, NULL, 0
+FROM sales
+WHERE customer_id IS NOT NULL AND total_amount > 0;
+
+INSERT INTO payments (id, sale_id, method, amount, cash_tendered, change_due)
+SELECT gen_random_uuid(), id, 'cash', total_amount, total_amount, 0
+FROM sales
+WHERE customer_id IS NULL AND total_amount > 0;
@@ -0,0 +1,12 @@
+-- down.sql
+
+ALTER TABLE sales DROP COLUMN IF EXISTS deposit_total;
+DROP INDEX IF EXISTS idx_sale_items_deposit_item_id;
+ALTER TABLE sale_items
+    DROP COLUMN IF EXISTS deposit_amount,
+    DROP COLUMN IF EXISTS deposit_quantity_returned,
+    DROP COLUMN IF EXISTS deposit_quantity_out,
+    DROP COLUMN IF EXISTS deposit_unit_amount,
+    DROP COLUMN IF EXISTS deposit_item_id;
+ALTER TABLE products DROP COLUMN IF EXISTS deposit_item_id;
+DROP TABLE IF EXISTS deposit_items;
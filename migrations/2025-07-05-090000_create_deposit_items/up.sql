@@ -0,0 +1,26 @@
+-- up.sql
+-- Consignes : un article de consigne (casier, palette...) par conditionnement.
+CREATE TABLE deposit_items (
+    id UUID PRIMARY KEY,
+    packaging_description TEXT NOT NULL UNIQUE, -- Conditionnement concerné, ex: "Casier 65cl de 12"
+    deposit_amount NUMERIC(10, 2) NOT NULL DEFAULT 0 CHECK (deposit_amount >= 0), -- Montant de la consigne par unité
+    empties_in_stock INTEGER NOT NULL DEFAULT 0 CHECK (empties_in_stock >= 0), -- Vides physiquement présents au dépôt
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON deposit_items FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Un produit est rattaché à la consigne de son conditionnement
+ALTER TABLE products ADD COLUMN deposit_item_id UUID REFERENCES deposit_items(id) ON DELETE SET NULL;
+
+-- Consigne facturée (emballages sortis) ou remboursée (vides rendus) sur chaque ligne de vente
+ALTER TABLE sale_items
+    ADD COLUMN deposit_item_id UUID REFERENCES deposit_items(id) ON DELETE RESTRICT,
+    ADD COLUMN deposit_unit_amount NUMERIC(10, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN deposit_quantity_out INTEGER NOT NULL DEFAULT 0 CHECK (deposit_quantity_out >= 0),
+    ADD COLUMN deposit_quantity_returned INTEGER NOT NULL DEFAULT 0 CHECK (deposit_quantity_returned >= 0),
+    ADD COLUMN deposit_amount NUMERIC(12, 2) NOT NULL DEFAULT 0; -- Net : (sortis - rendus) x consigne unitaire
+CREATE INDEX idx_sale_items_deposit_item_id ON sale_items (deposit_item_id);
+
+-- Total net des consignes de la vente, en plus du montant des marchandises
+ALTER TABLE sales ADD COLUMN deposit_total NUMERIC(12, 2) NOT NULL DEFAULT 0;
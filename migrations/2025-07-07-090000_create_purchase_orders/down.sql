@@ -0,0 +1,6 @@
+-- down.sql
+
+ALTER TABLE products DROP COLUMN IF EXISTS cost_price;
+DROP TABLE IF EXISTS purchase_order_lines;
+DROP TABLE IF EXISTS purchase_orders;
+DROP TABLE IF EXISTS suppliers;
@@ -0,0 +1,45 @@
+-- up.sql
+-- Fournisseurs (brasseries, distributeurs...)
+CREATE TABLE suppliers (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    phone TEXT,
+    address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON suppliers FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Bons de commande fournisseur
+CREATE TABLE purchase_orders (
+    id UUID PRIMARY KEY,
+    po_number TEXT NOT NULL UNIQUE,
+    supplier_id UUID NOT NULL REFERENCES suppliers(id) ON DELETE RESTRICT,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- L'utilisateur qui a créé la commande
+    status TEXT NOT NULL DEFAULT 'draft'
+        CHECK (status IN ('draft', 'sent', 'partially_received', 'received')),
+    order_date TIMESTAMPTZ NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON purchase_orders FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_purchase_orders_supplier_id ON purchase_orders (supplier_id);
+
+-- Lignes des bons de commande
+CREATE TABLE purchase_order_lines (
+    id UUID PRIMARY KEY,
+    purchase_order_id UUID NOT NULL REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    quantity_ordered INTEGER NOT NULL CHECK (quantity_ordered > 0),
+    quantity_received INTEGER NOT NULL DEFAULT 0 CHECK (quantity_received >= 0),
+    unit_cost NUMERIC(12, 2) NOT NULL CHECK (unit_cost >= 0), -- Prix d'achat unitaire (mis à jour à la réception)
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (quantity_received <= quantity_ordered)
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON purchase_order_lines FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_purchase_order_lines_purchase_order_id ON purchase_order_lines (purchase_order_id);
+
+-- Dernier prix d'achat connu du produit, enregistré à la réception
+ALTER TABLE products ADD COLUMN cost_price NUMERIC(12, 2);
@@ -0,0 +1,4 @@
+-- down.sql
+
+DROP TABLE IF EXISTS stock_movements;
+DROP FUNCTION IF EXISTS prevent_stock_movement_change();
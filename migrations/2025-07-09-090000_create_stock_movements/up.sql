@@ -0,0 +1,35 @@
+-- up.sql
+-- Journal des mouvements de stock : chaque variation du stock d'un produit y est inscrite
+CREATE TABLE stock_movements (
+    id UUID PRIMARY KEY,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE RESTRICT, -- NULL pour les mouvements système (solde d'ouverture)
+    reason TEXT NOT NULL
+        CHECK (reason IN ('sale', 'receipt', 'adjustment', 'return', 'breakage')),
+    quantity_delta INTEGER NOT NULL CHECK (quantity_delta <> 0),
+    stock_after INTEGER NOT NULL, -- Stock du produit après le mouvement
+    reference TEXT, -- Document d'origine (n° de vente, n° de bon de commande...)
+    note TEXT, -- Commentaire libre (casse, solde d'ouverture...)
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_stock_movements_product_id_created_at ON stock_movements (product_id, created_at);
+
+-- Le journal est immuable : on corrige par un nouveau mouvement, jamais en réécrivant l'historique.
+-- Seule la suppression du produit lui-même emporte ses mouvements.
+CREATE OR REPLACE FUNCTION prevent_stock_movement_change()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'DELETE' AND NOT EXISTS (SELECT 1 FROM products WHERE id = OLD.product_id) THEN
+    RETURN OLD;
+  END IF;
+  RAISE EXCEPTION 'Les mouvements de stock ne peuvent être ni modifiés ni supprimés';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER stock_movements_immutable BEFORE UPDATE OR DELETE ON stock_movements FOR EACH ROW EXECUTE PROCEDURE prevent_stock_movement_change();
+
+-- Solde d'ouverture : le stock existant devient le premier mouvement de chaque produit
+INSERT INTO stock_movements (id, product_id, user_id, reason, quantity_delta, stock_after, note)
+SELECT gen_random_uuid(), id, NULL, 'adjustment', stock_in_sale_units, stock_in_sale_units, 'Solde d''ouverture'
+FROM products
+WHERE stock_in_sale_units <> 0;
@@ -0,0 +1,12 @@
+-- down.sql
+
+DROP TABLE IF EXISTS credit_note_items;
+DROP TABLE IF EXISTS credit_notes;
+ALTER TABLE sale_items
+    DROP CONSTRAINT IF EXISTS sale_items_quantity_returned_max_check,
+    DROP COLUMN IF EXISTS quantity_returned;
+ALTER TABLE sales
+    DROP COLUMN IF EXISTS voided_by,
+    DROP COLUMN IF EXISTS voided_at,
+    DROP COLUMN IF EXISTS void_reason,
+    DROP COLUMN IF EXISTS status;
@@ -0,0 +1,44 @@
+-- up.sql
+-- État de la vente : la ligne d'origine est conservée, annulée ou non
+ALTER TABLE sales
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'completed'
+        CHECK (status IN ('completed', 'partially_returned', 'returned', 'voided')),
+    ADD COLUMN void_reason TEXT,
+    ADD COLUMN voided_at TIMESTAMPTZ,
+    ADD COLUMN voided_by UUID REFERENCES users(id) ON DELETE RESTRICT;
+
+-- Quantité déjà reprise par avoir sur chaque ligne de vente
+ALTER TABLE sale_items
+    ADD COLUMN quantity_returned INTEGER NOT NULL DEFAULT 0 CHECK (quantity_returned >= 0),
+    ADD CONSTRAINT sale_items_quantity_returned_max_check CHECK (quantity_returned <= quantity);
+
+-- Avoirs : retour partiel de marchandise sur une vente
+CREATE TABLE credit_notes (
+    id UUID PRIMARY KEY,
+    credit_note_number TEXT NOT NULL UNIQUE,
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE RESTRICT,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- L'utilisateur qui a enregistré le retour
+    reason TEXT NOT NULL,
+    total_amount NUMERIC(12, 2) NOT NULL CHECK (total_amount >= 0), -- Montant remboursé ou crédité au client
+    date TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON credit_notes FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_credit_notes_sale_id ON credit_notes (sale_id);
+CREATE INDEX idx_credit_notes_date ON credit_notes (date);
+
+-- Lignes d'avoir : articles repris
+CREATE TABLE credit_note_items (
+    id UUID PRIMARY KEY,
+    credit_note_id UUID NOT NULL REFERENCES credit_notes(id) ON DELETE CASCADE,
+    sale_item_id UUID NOT NULL REFERENCES sale_items(id) ON DELETE RESTRICT,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(10, 2) NOT NULL,
+    total_price NUMERIC(12, 2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON credit_note_items FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_credit_note_items_credit_note_id ON credit_note_items (credit_note_id);
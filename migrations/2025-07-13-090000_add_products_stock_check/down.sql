@@ -0,0 +1,2 @@
+-- down.sql
+ALTER TABLE products DROP CONSTRAINT products_stock_non_negative;
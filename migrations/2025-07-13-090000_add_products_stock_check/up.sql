@@ -0,0 +1,14 @@
+-- up.sql
+-- Le stock d'un produit ne peut jamais devenir négatif, quelle que soit l'origine de l'écriture.
+
+-- Régularise d'abord les éventuels stocks négatifs hérités des ventes concurrentes,
+-- en inscrivant la correction au journal des mouvements
+INSERT INTO stock_movements (id, product_id, user_id, reason, quantity_delta, stock_after, reference)
+SELECT gen_random_uuid(), id, NULL, 'adjustment', -stock_in_sale_units, 0, 'Régularisation stock négatif'
+FROM products
+WHERE stock_in_sale_units < 0;
+
+UPDATE products SET stock_in_sale_units = 0 WHERE stock_in_sale_units < 0;
+
+ALTER TABLE products
+    ADD CONSTRAINT products_stock_non_negative CHECK (stock_in_sale_units >= 0);
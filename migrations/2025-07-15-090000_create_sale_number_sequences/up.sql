@@ -0,0 +1,14 @@
+-- up.sql
+-- Compteurs de numérotation des ventes : une série par préfixe et par caisse, remise à zéro chaque année.
+-- Le compteur est incrémenté dans la transaction de la vente (la ligne reste verrouillée jusqu'au commit) :
+-- contrairement à une SEQUENCE PostgreSQL, une vente annulée par rollback ne consomme pas de numéro,
+-- ce qui garantit une suite sans trou.
+CREATE TABLE sale_number_sequences (
+    series TEXT NOT NULL, -- Préfixe et caisse, ex. 'VTE-01'
+    period INTEGER NOT NULL, -- Année de la série, 0 si le compteur n'est jamais remis à zéro
+    last_value BIGINT NOT NULL CHECK (last_value > 0), -- Dernier numéro attribué
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (series, period)
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON sale_number_sequences FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
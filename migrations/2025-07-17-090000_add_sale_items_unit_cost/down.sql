@@ -0,0 +1,3 @@
+-- down.sql
+ALTER TABLE products DROP CONSTRAINT products_cost_price_non_negative;
+ALTER TABLE sale_items DROP COLUMN unit_cost;
@@ -0,0 +1,14 @@
+-- up.sql
+-- Coût d'achat unitaire figé sur chaque ligne au moment de la vente, pour le calcul des marges.
+-- NULL lorsque le coût du produit n'était pas connu : la ligne est alors exclue des marges.
+ALTER TABLE sale_items ADD COLUMN unit_cost NUMERIC(12, 2);
+
+-- Les ventes antérieures reprennent le dernier prix d'achat connu, faute de mieux
+UPDATE sale_items
+SET unit_cost = products.cost_price
+FROM products
+WHERE sale_items.product_id = products.id
+  AND products.cost_price IS NOT NULL;
+
+ALTER TABLE products
+    ADD CONSTRAINT products_cost_price_non_negative CHECK (cost_price IS NULL OR cost_price >= 0);
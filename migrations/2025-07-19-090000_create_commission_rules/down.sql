@@ -0,0 +1,2 @@
+-- down.sql
+DROP TABLE commission_rules;
@@ -0,0 +1,16 @@
+-- up.sql
+-- Règles de commission des vendeurs : un pourcentage du chiffre d'affaires ou un montant par unité vendue (casier).
+-- La règle sans vendeur (user_id NULL) s'applique à tous ceux qui n'ont pas de règle propre.
+CREATE TABLE commission_rules (
+    id UUID PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE, -- NULL pour la règle par défaut
+    kind TEXT NOT NULL CHECK (kind IN ('percent', 'per_unit')),
+    rate NUMERIC(12, 2) NOT NULL CHECK (rate >= 0), -- Pourcentage, ou montant en XAF par unité vendue
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON commission_rules FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Une seule règle par vendeur, et une seule règle par défaut
+CREATE UNIQUE INDEX idx_commission_rules_user_id ON commission_rules (user_id) WHERE user_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_commission_rules_default ON commission_rules ((user_id IS NULL)) WHERE user_id IS NULL;
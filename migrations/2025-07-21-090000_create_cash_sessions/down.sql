@@ -0,0 +1,8 @@
+-- down.sql
+
+ALTER TABLE sales DROP COLUMN IF EXISTS void_cash_session_id;
+ALTER TABLE credit_notes DROP COLUMN IF EXISTS cash_session_id;
+DROP INDEX IF EXISTS idx_customer_payments_date;
+ALTER TABLE customer_payments DROP COLUMN IF EXISTS method;
+DROP TABLE IF EXISTS cash_movements;
+DROP TABLE IF EXISTS cash_sessions;
@@ -0,0 +1,52 @@
+-- up.sql
+-- Sessions de caisse : ouverture avec un fond de caisse, clôture avec le montant compté.
+-- Les montants attendus et comptés sont figés à la clôture pour l'audit.
+CREATE TABLE cash_sessions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- Le caissier qui tient la caisse
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    opening_float NUMERIC(12, 2) NOT NULL CHECK (opening_float >= 0),
+    closed_at TIMESTAMPTZ,
+    closed_by UUID REFERENCES users(id) ON DELETE RESTRICT,
+    expected_cash NUMERIC(12, 2), -- Espèces attendues au moment de la clôture
+    counted_cash NUMERIC(12, 2) CHECK (counted_cash >= 0),
+    closing_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT cash_sessions_closing_check CHECK (
+        (closed_at IS NULL AND closed_by IS NULL AND expected_cash IS NULL AND counted_cash IS NULL)
+        OR (closed_at IS NOT NULL AND closed_by IS NOT NULL AND expected_cash IS NOT NULL AND counted_cash IS NOT NULL)
+    )
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON cash_sessions FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+CREATE INDEX idx_cash_sessions_opened_at ON cash_sessions (opened_at);
+
+-- Une seule session ouverte par caissier
+CREATE UNIQUE INDEX idx_cash_sessions_open_user_id ON cash_sessions (user_id) WHERE closed_at IS NULL;
+
+-- Entrées et sorties d'espèces hors ventes (apport de monnaie, remboursement, dépense, dépôt en banque...)
+CREATE TABLE cash_movements (
+    id UUID PRIMARY KEY,
+    cash_session_id UUID NOT NULL REFERENCES cash_sessions(id) ON DELETE RESTRICT,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    kind TEXT NOT NULL CHECK (kind IN ('in', 'out')),
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_cash_movements_cash_session_id ON cash_movements (cash_session_id);
+
+-- Moyen de paiement des règlements clients : seuls les règlements en espèces entrent dans le tiroir-caisse.
+-- Les règlements existants ont été encaissés en espèces.
+ALTER TABLE customer_payments
+    ADD COLUMN method TEXT NOT NULL DEFAULT 'cash' CHECK (method IN ('cash', 'mobile_money', 'cheque'));
+CREATE INDEX idx_customer_payments_date ON customer_payments (date);
+
+-- Session dont le tiroir a rendu les espèces d'un avoir ou d'une vente annulée,
+-- quel que soit l'utilisateur qui a enregistré le retour ou l'annulation
+ALTER TABLE credit_notes
+    ADD COLUMN cash_session_id UUID REFERENCES cash_sessions(id) ON DELETE RESTRICT;
+ALTER TABLE sales
+    ADD COLUMN void_cash_session_id UUID REFERENCES cash_sessions(id) ON DELETE RESTRICT;
+CREATE INDEX idx_credit_notes_cash_session_id ON credit_notes (cash_session_id);
+CREATE INDEX idx_sales_void_cash_session_id ON sales (void_cash_session_id);
@@ -0,0 +1,4 @@
+-- down.sql
+
+DROP TABLE IF EXISTS product_import_lines;
+DROP TABLE IF EXISTS product_imports;
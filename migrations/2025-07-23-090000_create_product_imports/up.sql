@@ -0,0 +1,24 @@
+-- up.sql
+-- Journal des imports en masse du catalogue (fichiers CSV ou XLSX).
+-- Chaque import garde la trace des produits créés ou modifiés et du détail des changements.
+CREATE TABLE product_imports (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- L'utilisateur qui a lancé l'import
+    file_name TEXT NOT NULL,
+    rows_created INTEGER NOT NULL CHECK (rows_created >= 0),
+    rows_updated INTEGER NOT NULL CHECK (rows_updated >= 0),
+    rows_unchanged INTEGER NOT NULL CHECK (rows_unchanged >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_product_imports_created_at ON product_imports (created_at);
+
+-- Une ligne par produit créé ou modifié ; les lignes inchangées ne sont que comptées
+CREATE TABLE product_import_lines (
+    id UUID PRIMARY KEY,
+    product_import_id UUID NOT NULL REFERENCES product_imports(id) ON DELETE CASCADE,
+    product_id UUID REFERENCES products(id) ON DELETE SET NULL, -- Le journal survit à la suppression du produit
+    line_number INTEGER NOT NULL, -- Ligne du fichier importé
+    action TEXT NOT NULL CHECK (action IN ('created', 'updated')),
+    changes TEXT NOT NULL -- ex: "Prix : 5000 → 5500 ; Stock : 12 → 20"
+);
+CREATE INDEX idx_product_import_lines_product_import_id ON product_import_lines (product_import_id);
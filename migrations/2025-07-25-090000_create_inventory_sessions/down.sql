@@ -0,0 +1,12 @@
+-- down.sql
+
+-- Les écarts d'inventaire déjà passés redeviennent de simples ajustements
+ALTER TABLE stock_movements DISABLE TRIGGER stock_movements_immutable;
+UPDATE stock_movements SET reason = 'adjustment' WHERE reason = 'inventory';
+ALTER TABLE stock_movements ENABLE TRIGGER stock_movements_immutable;
+ALTER TABLE stock_movements DROP CONSTRAINT stock_movements_reason_check;
+ALTER TABLE stock_movements ADD CONSTRAINT stock_movements_reason_check
+    CHECK (reason IN ('sale', 'receipt', 'adjustment', 'return', 'breakage'));
+
+DROP TABLE IF EXISTS inventory_lines;
+DROP TABLE IF EXISTS inventory_sessions;
@@ -0,0 +1,42 @@
+-- up.sql
+-- Inventaires physiques : le stock théorique de chaque produit est figé à l'ouverture,
+-- les quantités comptées sont saisies (ou importées) puis les écarts sont passés au journal à la validation.
+CREATE TABLE inventory_sessions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT, -- L'utilisateur qui a ouvert l'inventaire
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'approved', 'cancelled')),
+    note TEXT,
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMPTZ, -- Date de validation ou d'abandon
+    closed_by UUID REFERENCES users(id) ON DELETE RESTRICT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT inventory_sessions_closing_check CHECK (
+        (status = 'open' AND closed_at IS NULL AND closed_by IS NULL)
+        OR (status <> 'open' AND closed_at IS NOT NULL AND closed_by IS NOT NULL)
+    )
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON inventory_sessions FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Un seul inventaire en cours à la fois
+CREATE UNIQUE INDEX idx_inventory_sessions_open ON inventory_sessions ((status)) WHERE status = 'open';
+
+-- Une ligne par produit, avec le stock théorique et les prix figés à l'ouverture
+CREATE TABLE inventory_lines (
+    id UUID PRIMARY KEY,
+    inventory_session_id UUID NOT NULL REFERENCES inventory_sessions(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    expected_stock INTEGER NOT NULL,
+    counted_stock INTEGER CHECK (counted_stock >= 0), -- NULL tant que le produit n'a pas été compté
+    unit_cost NUMERIC(10, 2), -- Prix de revient à l'ouverture, pour valoriser l'écart
+    unit_price NUMERIC(10, 2) NOT NULL, -- Prix de vente à l'ouverture
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (inventory_session_id, product_id)
+);
+CREATE TRIGGER set_timestamp BEFORE UPDATE ON inventory_lines FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Les écarts d'inventaire ont leur propre motif dans le journal des mouvements de stock
+ALTER TABLE stock_movements DROP CONSTRAINT stock_movements_reason_check;
+ALTER TABLE stock_movements ADD CONSTRAINT stock_movements_reason_check
+    CHECK (reason IN ('sale', 'receipt', 'adjustment', 'return', 'breakage', 'inventory'));
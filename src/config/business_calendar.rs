@@ -0,0 +1,317 @@
+// src/config/business_calendar.rs
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, Months, NaiveDate, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use std::{env, sync::OnceLock};
+
+/// Fuseau horaire et heure de début de la journée commerciale, lus depuis l'environnement (`.env`).
+///
+/// - `BUSINESS_TIMEZONE` : fuseau IANA du dépôt (par défaut `Africa/Douala`) ;
+/// - `BUSINESS_DAY_START` : heure locale de début de journée au format `HH:MM` (par défaut `00:00`).
+///   Avec `06:00`, une vente enregistrée à 2 h du matin compte pour la journée de la veille,
+///   ce qui permet de clôturer une équipe de nuit qui dépasse minuit.
+///
+/// Toutes les bornes renvoyées sont des intervalles semi-ouverts `[début, fin)` en UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusinessCalendar {
+    pub timezone: Tz,
+    pub day_start: NaiveTime,
+}
+
+/// Intervalle de temps `[start, end)` exprimé en UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl DateRange {
+    /// Dernier instant inclus dans l'intervalle, pour les requêtes en `BETWEEN`
+    /// (PostgreSQL stocke les horodatages à la microseconde).
+    pub fn last_instant(&self) -> DateTime<Utc> {
+        self.end - Duration::microseconds(1)
+    }
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        Self {
+            timezone: chrono_tz::Africa::Douala,
+            day_start: NaiveTime::MIN,
+        }
+    }
+}
+
+static CALENDAR: OnceLock<BusinessCalendar> = OnceLock::new();
+
+/// Calendrier commercial de l'application, chargé au premier appel.
+pub fn business_calendar() -> &'static BusinessCalendar {
+    CALENDAR.get_or_init(BusinessCalendar::from_env)
+}
+
+impl BusinessCalendar {
+    /// Charge la configuration ; une valeur absente ou invalide garde sa valeur par défaut.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let timezone = match env::var("BUSINESS_TIMEZONE") {
+            Ok(value) => value.trim().parse::<Tz>().unwrap_or_else(|_| {
+                log::warn!(
+                    "Fuseau horaire '{}' inconnu, utilisation de {}",
+                    value,
+                    default.timezone
+                );
+                default.timezone
+            }),
+            Err(_) => default.timezone,
+        };
+        let day_start = env::var("BUSINESS_DAY_START")
+            .ok()
+            .and_then(|value| NaiveTime::parse_from_str(value.trim(), "%H:%M").ok())
+            .unwrap_or(default.day_start);
+        Self {
+            timezone,
+            day_start,
+        }
+    }
+
+    /// Journée commerciale à laquelle appartient un instant donné.
+    pub fn business_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        let local = at.with_timezone(&self.timezone).naive_local();
+        (local - (self.day_start - NaiveTime::MIN)).date()
+    }
+
+    /// Journée commerciale en cours.
+    pub fn today(&self) -> NaiveDate {
+        self.business_date(Utc::now())
+    }
+
+    /// Instant UTC auquel commence une journée commerciale.
+    pub fn day_start_utc(&self, date: NaiveDate) -> DateTime<Utc> {
+        let local = date.and_time(self.day_start);
+        match self.timezone.from_local_datetime(&local) {
+            LocalResult::Single(at) => at.with_timezone(&Utc),
+            // Heure d'hiver : l'heure locale existe deux fois, on retient la première
+            LocalResult::Ambiguous(first, _) => first.with_timezone(&Utc),
+            // Heure d'été : l'heure locale n'existe pas, la journée commence à la fin du saut
+            LocalResult::None => self
+                .timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+                .map(|at| at.with_timezone(&Utc))
+                .unwrap_or_else(|| Utc.from_utc_datetime(&local)),
+        }
+    }
+
+    /// Journées commerciales de `first` à `last` incluses.
+    pub fn days(&self, first: NaiveDate, last: NaiveDate) -> DateRange {
+        let (first, last) = if first <= last {
+            (first, last)
+        } else {
+            (last, first)
+        };
+        DateRange {
+            start: self.day_start_utc(first),
+            end: self.day_start_utc(last + Duration::days(1)),
+        }
+    }
+
+    /// Journée commerciale contenant `date`.
+    pub fn day(&self, date: NaiveDate) -> DateRange {
+        self.days(date, date)
+    }
+
+    /// Semaine ISO (du lundi au dimanche) contenant `date`.
+    pub fn iso_week(&self, date: NaiveDate) -> DateRange {
+        let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+        self.days(monday, monday + Duration::days(6))
+    }
+
+    /// Mois calendaire contenant `date`.
+    pub fn month(&self, date: NaiveDate) -> DateRange {
+        let first = date.with_day(1).unwrap();
+        let next_month = if first.month() == 12 {
+            NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)
+        }
+        .unwrap();
+        self.days(first, next_month - Duration::days(1))
+    }
+
+    /// Année civile contenant `date`.
+    pub fn year(&self, date: NaiveDate) -> DateRange {
+        self.days(
+            NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap(),
+        )
+    }
+
+    /// Première et dernière journées commerciales couvertes par un intervalle.
+    pub fn bounds(&self, range: &DateRange) -> (NaiveDate, NaiveDate) {
+        (
+            self.business_date(range.start),
+            self.business_date(range.last_instant()),
+        )
+    }
+
+    /// Période de même durée qui précède immédiatement `range`.
+    /// Une suite de mois entiers (mois, année) est comparée aux mois qui la précèdent,
+    /// les autres périodes au même nombre de journées.
+    pub fn previous_period(&self, range: &DateRange) -> DateRange {
+        let (first, last) = self.bounds(range);
+        let is_whole_months = first.day() == 1 && (last + Duration::days(1)).day() == 1;
+        if is_whole_months {
+            let months =
+                (last.year() - first.year()) * 12 + last.month() as i32 - first.month() as i32 + 1;
+            let months = Months::new(months as u32);
+            let previous_first = first - months;
+            self.days(previous_first, first - Duration::days(1))
+        } else {
+            let length = (last - first).num_days() + 1;
+            self.days(first - Duration::days(length), first - Duration::days(1))
+        }
+    }
+
+    /// Mêmes journées un an plus tôt (le 29 février devient le 28).
+    pub fn same_period_last_year(&self, range: &DateRange) -> DateRange {
+        let (first, last) = self.bounds(range);
+        let year = Months::new(12);
+        self.days(first - year, last - year)
+    }
+
+    /// Libellé d'une période, par exemple « du 14/07/2025 au 20/07/2025 ».
+    pub fn format_range(&self, range: &DateRange) -> String {
+        let (first, last) = self.bounds(range);
+        if first == last {
+            format!("le {}", first.format("%d/%m/%Y"))
+        } else {
+            format!(
+                "du {} au {}",
+                first.format("%d/%m/%Y"),
+                last.format("%d/%m/%Y")
+            )
+        }
+    }
+
+    /// Formate un instant dans l'heure locale du dépôt.
+    pub fn format_local(&self, at: DateTime<Utc>, format: &str) -> String {
+        at.with_timezone(&self.timezone).format(format).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_local_day_boundaries() {
+        // Douala est à UTC+1 : la journée du 15 commence le 14 à 23 h UTC
+        let calendar = BusinessCalendar::default();
+        let range = calendar.day(date(2025, 7, 15));
+        assert_eq!(range.start, utc("2025-07-14T23:00:00Z"));
+        assert_eq!(range.end, utc("2025-07-15T23:00:00Z"));
+        assert_eq!(
+            calendar.business_date(utc("2025-07-14T23:30:00Z")),
+            date(2025, 7, 15)
+        );
+    }
+
+    #[test]
+    fn test_shift_crossing_midnight() {
+        let calendar = BusinessCalendar {
+            day_start: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            ..BusinessCalendar::default()
+        };
+        // 2 h du matin heure locale le 16 : encore la journée du 15
+        assert_eq!(
+            calendar.business_date(utc("2025-07-16T01:00:00Z")),
+            date(2025, 7, 15)
+        );
+        let range = calendar.day(date(2025, 7, 15));
+        assert_eq!(range.start, utc("2025-07-15T05:00:00Z"));
+        assert_eq!(range.end, utc("2025-07-16T05:00:00Z"));
+    }
+
+    #[test]
+    fn test_iso_week_and_calendar_month() {
+        let calendar = BusinessCalendar::default();
+        // Le jeudi 1er janvier 2026 appartient à la semaine ISO commençant le lundi 29 décembre
+        let week = calendar.iso_week(date(2026, 1, 1));
+        assert_eq!(week, calendar.days(date(2025, 12, 29), date(2026, 1, 4)));
+
+        let february = calendar.month(date(2024, 2, 10));
+        assert_eq!(february, calendar.days(date(2024, 2, 1), date(2024, 2, 29)));
+        let december = calendar.month(date(2025, 12, 31));
+        assert_eq!(december.end, calendar.day_start_utc(date(2026, 1, 1)));
+    }
+
+    #[test]
+    fn test_comparison_periods() {
+        let calendar = BusinessCalendar::default();
+
+        // Semaine : les sept journées précédentes
+        let week = calendar.iso_week(date(2025, 7, 16));
+        assert_eq!(
+            calendar.previous_period(&week),
+            calendar.days(date(2025, 7, 7), date(2025, 7, 13))
+        );
+
+        // Mois entier : le mois précédent, quelle que soit sa longueur
+        let march = calendar.month(date(2025, 3, 10));
+        assert_eq!(
+            calendar.previous_period(&march),
+            calendar.month(date(2025, 2, 1))
+        );
+        let year = calendar.year(date(2025, 6, 1));
+        assert_eq!(
+            calendar.previous_period(&year),
+            calendar.year(date(2024, 6, 1))
+        );
+
+        // Période libre de 10 jours
+        let custom = calendar.days(date(2025, 7, 11), date(2025, 7, 20));
+        assert_eq!(
+            calendar.previous_period(&custom),
+            calendar.days(date(2025, 7, 1), date(2025, 7, 10))
+        );
+
+        // Même période l'an dernier, 29 février compris
+        let leap = calendar.days(date(2024, 2, 29), date(2024, 3, 1));
+        assert_eq!(
+            calendar.same_period_last_year(&leap),
+            calendar.days(date(2023, 2, 28), date(2023, 3, 1))
+        );
+        assert_eq!(
+            calendar.format_range(&calendar.day(date(2025, 7, 15))),
+            "le 15/07/2025"
+        );
+    }
+
+    #[test]
+    fn test_daylight_saving_transitions() {
+        let calendar = BusinessCalendar {
+            timezone: chrono_tz::Europe::Paris,
+            day_start: NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        };
+        // 02:30 n'existe pas le 30 mars 2025 à Paris : la journée commence à 03:30 (UTC+2)
+        assert_eq!(
+            calendar.day_start_utc(date(2025, 3, 30)),
+            utc("2025-03-30T01:30:00Z")
+        );
+        // 02:30 existe deux fois le 26 octobre 2025 : on retient la première (UTC+2)
+        assert_eq!(
+            calendar.day_start_utc(date(2025, 10, 26)),
+            utc("2025-10-26T00:30:00Z")
+        );
+    }
+}
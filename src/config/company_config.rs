@@ -0,0 +1,62 @@
+// src/config/company_config.rs
+
+use std::{env, path::PathBuf, sync::OnceLock};
+
+/// Identité du dépôt imprimée en tête des documents, lue depuis l'environnement (`.env`).
+///
+/// - `DEPOT_NAME` : raison sociale (par défaut `DEPOT BOISSONS`) ;
+/// - `DEPOT_ADDRESS` et `DEPOT_PHONE` : coordonnées, facultatives ;
+/// - `DEPOT_LOGO` : chemin d'un logo au format JPEG, facultatif.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompanyConfig {
+    pub name: String,
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub logo_path: Option<PathBuf>,
+}
+
+impl Default for CompanyConfig {
+    fn default() -> Self {
+        Self {
+            name: "DEPOT BOISSONS".to_string(),
+            address: None,
+            phone: None,
+            logo_path: None,
+        }
+    }
+}
+
+static COMPANY: OnceLock<CompanyConfig> = OnceLock::new();
+
+/// Identité du dépôt, chargée au premier appel.
+pub fn company_config() -> &'static CompanyConfig {
+    COMPANY.get_or_init(CompanyConfig::from_env)
+}
+
+impl CompanyConfig {
+    /// Charge la configuration ; une variable absente ou vide garde sa valeur par défaut.
+    pub fn from_env() -> Self {
+        let read = |key: &str| {
+            env::var(key)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            name: read("DEPOT_NAME").unwrap_or_else(|| Self::default().name),
+            address: read("DEPOT_ADDRESS"),
+            phone: read("DEPOT_PHONE"),
+            logo_path: read("DEPOT_LOGO").map(PathBuf::from),
+        }
+    }
+
+    /// Ligne de coordonnées sous le nom du dépôt, par exemple « Akwa, Douala — Tél. 699 00 00 00 ».
+    pub fn contact_line(&self) -> Option<String> {
+        let parts = [
+            self.address.clone(),
+            self.phone.as_ref().map(|phone| format!("Tél. {}", phone)),
+        ];
+        let parts = parts.into_iter().flatten().collect::<Vec<_>>();
+        (!parts.is_empty()).then(|| parts.join(" — "))
+    }
+}
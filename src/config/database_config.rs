@@ -0,0 +1,59 @@
+// src/config/database_config.rs
+
+use std::{env, time::Duration};
+
+/// Paramètres du pool de connexions PostgreSQL, lus depuis l'environnement (fichier `.env`).
+///
+/// - `DB_POOL_MAX_SIZE` : nombre maximal de connexions ouvertes (par défaut 10) ;
+/// - `DB_POOL_MIN_IDLE` : connexions gardées ouvertes au repos (par défaut 1) ;
+/// - `DB_POOL_CONNECTION_TIMEOUT_SECS` : attente maximale d'une connexion libre (par défaut 5 s) ;
+/// - `DB_POOL_IDLE_TIMEOUT_SECS` : fermeture des connexions inutilisées au-delà de ce délai (par défaut 300 s).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabasePoolConfig {
+    pub max_size: u32,
+    pub min_idle: u32,
+    pub connection_timeout: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl Default for DatabasePoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: 1,
+            connection_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl DatabasePoolConfig {
+    /// Charge la configuration ; une variable absente ou invalide garde sa valeur par défaut.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let read = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let max_size = read("DB_POOL_MAX_SIZE")
+            .filter(|size| *size > 0)
+            .map(|size| size as u32)
+            .unwrap_or(default.max_size);
+        Self {
+            max_size,
+            // Le pool refuse un minimum supérieur à sa taille maximale
+            min_idle: read("DB_POOL_MIN_IDLE")
+                .map(|idle| (idle as u32).min(max_size))
+                .unwrap_or(default.min_idle.min(max_size)),
+            connection_timeout: read("DB_POOL_CONNECTION_TIMEOUT_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.connection_timeout),
+            idle_timeout: read("DB_POOL_IDLE_TIMEOUT_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.idle_timeout),
+        }
+    }
+}
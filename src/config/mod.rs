@@ -1,2 +1,6 @@
 // src/config/mod.rs
+pub mod business_calendar;
+pub mod company_config;
+pub mod database_config;
+pub mod numbering_config;
 pub mod printer_config;
@@ -0,0 +1,87 @@
+// src/config/numbering_config.rs
+
+use std::env;
+
+/// Paramètres de numérotation des ventes, lus depuis l'environnement (fichier `.env`).
+///
+/// - `SALE_NUMBER_PREFIX` : préfixe des numéros (par défaut `VTE`) ;
+/// - `CASH_REGISTER_ID` : identifiant de la caisse, chaque caisse ayant sa propre série (par défaut `01`) ;
+/// - `SALE_NUMBER_YEARLY_RESET` : remise à zéro du compteur au 1er janvier (par défaut `true`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaleNumberingConfig {
+    pub prefix: String,
+    pub register_id: String,
+    pub yearly_reset: bool,
+}
+
+impl Default for SaleNumberingConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "VTE".to_string(),
+            register_id: "01".to_string(),
+            yearly_reset: true,
+        }
+    }
+}
+
+impl SaleNumberingConfig {
+    /// Charge la configuration ; une variable absente ou vide garde sa valeur par défaut.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let read = |key: &str| {
+            env::var(key)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            prefix: read("SALE_NUMBER_PREFIX").unwrap_or(default.prefix),
+            register_id: read("CASH_REGISTER_ID").unwrap_or(default.register_id),
+            yearly_reset: read("SALE_NUMBER_YEARLY_RESET")
+                .map(|value| !matches!(value.to_lowercase().as_str(), "false" | "0" | "non"))
+                .unwrap_or(default.yearly_reset),
+        }
+    }
+
+    /// Identifiant de la série en base : un compteur par préfixe et par caisse.
+    pub fn series(&self) -> String {
+        format!("{}-{}", self.prefix, self.register_id)
+    }
+
+    /// Période du compteur : l'année si la série est remise à zéro chaque année, 0 sinon.
+    pub fn period(&self, year: i32) -> i32 {
+        if self.yearly_reset { year } else { 0 }
+    }
+
+    /// Formate un numéro de vente, ex. `VTE-01-2025-000042` (ou `VTE-01-000042` sans remise à zéro).
+    pub fn format_number(&self, year: i32, value: i64) -> String {
+        if self.yearly_reset {
+            format!("{}-{}-{:06}", self.series(), year, value)
+        } else {
+            format!("{}-{:06}", self.series(), value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_number_with_yearly_reset() {
+        let config = SaleNumberingConfig::default();
+        assert_eq!(config.period(2025), 2025);
+        assert_eq!(config.format_number(2025, 42), "VTE-01-2025-000042");
+    }
+
+    #[test]
+    fn test_format_number_without_reset() {
+        let config = SaleNumberingConfig {
+            prefix: "TK".to_string(),
+            register_id: "C2".to_string(),
+            yearly_reset: false,
+        };
+        assert_eq!(config.period(2025), 0);
+        assert_eq!(config.format_number(2025, 1234567), "TK-C2-1234567");
+    }
+}
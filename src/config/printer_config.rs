@@ -18,20 +18,172 @@ pub struct PrinterConfig {
     pub printer_type: PrinterType,
     pub paper_width: u32,
     pub is_default: bool,
+    /// Paramètres de la liaison série, ignorés pour les autres types d'imprimante.
+    #[serde(default)]
+    pub serial: SerialSettings,
+    /// Identifiants du périphérique, pour les imprimantes USB.
+    #[serde(default)]
+    pub usb: UsbSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UsbSettings {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Point de sortie bulk ; celui du pilote d'impression est utilisé s'il est absent.
+    pub endpoint: Option<u8>,
+}
+
+impl Default for UsbSettings {
+    /// Epson TM-T20, le modèle configuré avant que les identifiants soient modifiables.
+    fn default() -> Self {
+        Self {
+            vendor_id: 0x04b8,
+            product_id: 0x0202,
+            endpoint: None,
+        }
+    }
+}
+
+/// Modèle d'imprimante ticket USB connu, avec sa largeur de ligne en police A.
+#[derive(Debug)]
+pub struct UsbPrinterModel {
+    pub name: &'static str,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub paper_width: u32,
+}
+
+pub const USB_PRINTER_MODELS: &[UsbPrinterModel] = &[
+    UsbPrinterModel {
+        name: "Epson TM-T20 / TM-T88",
+        vendor_id: 0x04b8,
+        product_id: 0x0202,
+        paper_width: 48,
+    },
+    UsbPrinterModel {
+        name: "Epson TM-T20II",
+        vendor_id: 0x04b8,
+        product_id: 0x0e15,
+        paper_width: 48,
+    },
+    UsbPrinterModel {
+        name: "Epson TM-T20III",
+        vendor_id: 0x04b8,
+        product_id: 0x0e28,
+        paper_width: 48,
+    },
+    UsbPrinterModel {
+        name: "Star TSP100",
+        vendor_id: 0x0519,
+        product_id: 0x0003,
+        paper_width: 48,
+    },
+    UsbPrinterModel {
+        name: "Xprinter / POS-80 générique",
+        vendor_id: 0x0fe6,
+        product_id: 0x811e,
+        paper_width: 48,
+    },
+    UsbPrinterModel {
+        name: "POS-58 générique",
+        vendor_id: 0x0416,
+        product_id: 0x5011,
+        paper_width: 32,
+    },
+];
+
+/// Modèle connu correspondant aux identifiants d'un périphérique USB.
+pub fn find_usb_printer_model(vendor_id: u16, product_id: u16) -> Option<&'static UsbPrinterModel> {
+    USB_PRINTER_MODELS
+        .iter()
+        .find(|m| m.vendor_id == vendor_id && m.product_id == product_id)
+}
+
+/// Lit un identifiant USB hexadécimal, avec ou sans préfixe `0x` (ex : `04b8`, `0x04B8`).
+pub fn parse_usb_id(value: &str) -> Option<u16> {
+    let value = value.trim();
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+/// Lit une adresse de point de sortie USB hexadécimale (ex : `01`, `0x02`).
+pub fn parse_usb_endpoint(value: &str) -> Option<u8> {
+    parse_usb_id(value).and_then(|v| u8::try_from(v).ok())
+}
+
+/// Parité de la liaison série.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SerialParity {
+    None,
+    Odd,
+    Even,
+}
+
+/// Contrôle de flux de la liaison série.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SerialFlowControl {
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SerialSettings {
+    pub baud_rate: u32,
+    pub parity: SerialParity,
+    /// 5 à 8 bits de données.
+    pub data_bits: u8,
+    /// 1 ou 2 bits de stop.
+    pub stop_bits: u8,
+    pub flow_control: SerialFlowControl,
+}
+
+impl Default for SerialSettings {
+    /// 9600 bauds, 8 bits, sans parité, 1 bit de stop (8N1) : le réglage d'usine des imprimantes ticket.
+    fn default() -> Self {
+        Self {
+            baud_rate: 9600,
+            parity: SerialParity::None,
+            data_bits: 8,
+            stop_bits: 1,
+            flow_control: SerialFlowControl::None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default)]
 struct ConfigFile {
     printers: Vec<PrinterConfig>,
+    /// Impression du ticket sur l'imprimante par défaut dès la validation d'une vente.
+    #[serde(default)]
+    auto_print_receipts: bool,
 }
 
 const CONFIG_PATH: &str = "printer_config.json";
 
+fn read_config_file() -> Option<ConfigFile> {
+    fs::read_to_string(CONFIG_PATH)
+        .ok()
+        .and_then(|data| serde_json::from_str::<ConfigFile>(&data).ok())
+}
+
+fn write_config_file(config_file: &ConfigFile) -> std::io::Result<()> {
+    let data = serde_json::to_string_pretty(config_file)?;
+    fs::write(CONFIG_PATH, data)
+}
+
 pub fn load_printers() -> Vec<PrinterConfig> {
     if Path::new(CONFIG_PATH).exists() {
-        fs::read_to_string(CONFIG_PATH)
-            .ok()
-            .and_then(|data| serde_json::from_str::<ConfigFile>(&data).ok())
+        read_config_file()
             .map(|config| config.printers)
             .unwrap_or_else(|| vec![default_printer()]) // Fallback si le fichier est corrompu
     } else {
@@ -43,6 +195,8 @@ pub fn load_printers() -> Vec<PrinterConfig> {
             printer_type: PrinterType::USB,
             paper_width: 48,
             is_default: true,
+            serial: SerialSettings::default(),
+            usb: UsbSettings::default(),
         }]
     }
 }
@@ -50,9 +204,22 @@ pub fn load_printers() -> Vec<PrinterConfig> {
 pub fn save_printers(printers: &[PrinterConfig]) -> std::io::Result<()> {
     let config_file = ConfigFile {
         printers: printers.to_vec(),
+        auto_print_receipts: auto_print_receipts(),
     };
-    let data = serde_json::to_string_pretty(&config_file)?;
-    fs::write(CONFIG_PATH, data)
+    write_config_file(&config_file)
+}
+
+/// Indique si le ticket doit être imprimé automatiquement après chaque vente.
+pub fn auto_print_receipts() -> bool {
+    read_config_file().is_some_and(|config| config.auto_print_receipts)
+}
+
+pub fn set_auto_print_receipts(enabled: bool) -> std::io::Result<()> {
+    let config_file = ConfigFile {
+        printers: load_printers(),
+        auto_print_receipts: enabled,
+    };
+    write_config_file(&config_file)
 }
 
 fn default_printer() -> PrinterConfig {
@@ -62,5 +229,7 @@ fn default_printer() -> PrinterConfig {
         printer_type: PrinterType::USB,
         paper_width: 48,
         is_default: true,
+        serial: SerialSettings::default(),
+        usb: UsbSettings::default(),
     }
 }
@@ -1,15 +1,27 @@
 // src/db.rs
 
+use crate::config::database_config::DatabasePoolConfig;
 use crate::error::{AppError, AppResult};
-use diesel::{pg::PgConnection, prelude::*};
+use diesel::{
+    pg::PgConnection,
+    prelude::*,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use dotenvy::dotenv;
-use std::env;
+use std::{env, sync::OnceLock};
 
-pub const MIGRATIONS: EmbeddedMigrations =
-    embed_migrations!("migrations"); 
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
-/// Initialise la base de données et exécute les migrations en attente.
+/// Pool de connexions partagé par toute l'application.
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+/// Connexion empruntée au pool ; elle y retourne automatiquement à la fin de sa portée.
+pub type DbConn = PooledConnection<ConnectionManager<PgConnection>>;
+
+static POOL: OnceLock<DbPool> = OnceLock::new();
+
+/// Initialise le pool de connexions et exécute les migrations en attente.
 pub fn init() -> AppResult<()> {
     log::info!("Initialisation de la base de données et exécution des migrations...");
 
@@ -22,27 +34,85 @@ pub fn init() -> AppResult<()> {
         AppError::Generic("La variable d'environnement DATABASE_URL doit être définie.".to_string())
     })?;
 
-    // On gère l'erreur de connexion à la base de données
-    let mut conn =
-        PgConnection::establish(&database_url).map_err(|e| AppError::Database(Box::new(e)))?;
+    init_pool(database_url)
+}
+
+/// Initialise le pool sur la base désignée par `TEST_DATABASE_URL`, sans lire le fichier .env :
+/// les tests n'écrivent jamais dans la base du magasin.
+/// Renvoie `false` si la variable n'est pas définie, le test appelant est alors sauté.
+#[cfg(test)]
+pub fn init_test() -> AppResult<bool> {
+    match env::var("TEST_DATABASE_URL") {
+        Ok(database_url) => init_pool(database_url).map(|_| true),
+        Err(_) => Ok(false),
+    }
+}
+
+fn init_pool(database_url: String) -> AppResult<()> {
+    // Le pool n'est créé qu'une fois : un second appel se contente des migrations
+    if POOL.get().is_none() {
+        let config = DatabasePoolConfig::from_env();
+        log::info!(
+            "Création du pool de connexions ({} connexions au plus, attente maximale {:?})...",
+            config.max_size,
+            config.connection_timeout
+        );
+        let pool = Pool::builder()
+            .max_size(config.max_size)
+            .min_idle(Some(config.min_idle))
+            .connection_timeout(config.connection_timeout)
+            .idle_timeout(Some(config.idle_timeout))
+            .build(ConnectionManager::<PgConnection>::new(database_url))?;
+        let _ = POOL.set(pool);
+    }
 
     log::info!("Exécution des migrations en attente...");
+    let mut conn = get_conn()?;
     conn.run_pending_migrations(MIGRATIONS)?;
     log::info!("Migrations de la base de données terminées avec succès.");
 
     Ok(())
 }
 
-/// Établit et retourne une nouvelle connexion à la base de données.
-pub fn get_conn() -> AppResult<PgConnection> {
-    // On peut ne pas recharger dotenvy à chaque appel si on est sûr
-    // qu'il a été appelé au démarrage, mais c'est plus sûr de le laisser.
-    dotenv()
-        .map_err(|e| AppError::Generic(format!("Impossible de charger le fichier .env: {}", e)))?;
+/// Emprunte une connexion au pool.
+/// Si aucune connexion ne se libère dans le délai configuré, renvoie une erreur de pool.
+pub fn get_conn() -> AppResult<DbConn> {
+    let pool = pool()?;
+    pool.get().map_err(|e| {
+        let state = pool.state();
+        log::error!(
+            "Aucune connexion disponible ({}/{} occupées) : {}",
+            state.connections - state.idle_connections,
+            pool.max_size(),
+            e
+        );
+        AppError::ConnectionPool(format!(
+            "toutes les connexions à la base sont occupées ({}/{}), réessayez dans un instant",
+            state.connections - state.idle_connections,
+            pool.max_size()
+        ))
+    })
+}
 
-    let database_url = env::var("DATABASE_URL").map_err(|_| {
-        AppError::Generic("La variable d'environnement DATABASE_URL doit être définie.".to_string())
-    })?;
+/// Vérifie l'état du pool : signale son épuisement, puis s'assure que la base répond.
+pub fn health_check() -> AppResult<()> {
+    let pool = pool()?;
+    let state = pool.state();
+    if state.idle_connections == 0 && state.connections >= pool.max_size() {
+        return Err(AppError::ConnectionPool(format!(
+            "pool épuisé : {} connexions sur {} sont occupées",
+            state.connections,
+            pool.max_size()
+        )));
+    }
+
+    let mut conn = get_conn()?;
+    diesel::sql_query("SELECT 1").execute(&mut conn)?;
+    Ok(())
+}
 
-    PgConnection::establish(&database_url).map_err(|e| AppError::Database(Box::new(e)))
+fn pool() -> AppResult<&'static DbPool> {
+    POOL.get().ok_or_else(|| {
+        AppError::ConnectionPool("le pool de connexions n'est pas initialisé".to_string())
+    })
 }
@@ -3,6 +3,7 @@
 use bcrypt::BcryptError;
 use bigdecimal::ParseBigDecimalError;
 use diesel::ConnectionError;
+use diesel::r2d2::PoolError;
 use diesel::result::Error as DieselError;
 use rust_xlsxwriter::XlsxError;
 use slint::PlatformError;
@@ -19,6 +20,9 @@ pub enum AppError {
     /// On utilise `Box<dyn StdError ...>` pour pouvoir y stocker `DieselError` et `ConnectionError`.
     Database(Box<dyn StdError + Send + Sync>),
 
+    /// Erreur du pool de connexions (pool non initialisé ou épuisé).
+    ConnectionPool(String),
+
     /// Erreur provenant de l'interface graphique (Slint).
     Platform(PlatformError),
 
@@ -51,6 +55,7 @@ impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AppError::Database(err) => write!(f, "Erreur de base de données : {}", err),
+            AppError::ConnectionPool(msg) => write!(f, "Erreur du pool de connexions : {}", msg),
             AppError::Platform(err) => write!(f, "Erreur de la plateforme UI : {}", err),
             AppError::Seeding(msg) => write!(f, "Erreur de seeding : {}", msg),
             AppError::Authentication(msg) => write!(f, "Erreur d'authentification : {}", msg),
@@ -72,7 +77,8 @@ impl StdError for AppError {
             AppError::Platform(err) => Some(err),
             AppError::Io(err) => Some(err),
             AppError::ExcelGeneration(err) => Some(err),
-            AppError::Seeding(_)
+            AppError::ConnectionPool(_)
+            | AppError::Seeding(_)
             | AppError::Authentication(_)
             | AppError::Unauthorized(_)
             | AppError::ValidationError(_)
@@ -98,6 +104,13 @@ impl From<ConnectionError> for AppError {
     }
 }
 
+// Convertit les erreurs de création du pool de connexions en AppError::ConnectionPool.
+impl From<PoolError> for AppError {
+    fn from(err: PoolError) -> Self {
+        AppError::ConnectionPool(err.to_string())
+    }
+}
+
 // Convertit les erreurs de Bcrypt en AppError::Generic (ou une nouvelle variante si vous préférez).
 impl From<BcryptError> for AppError {
     fn from(err: BcryptError) -> Self {
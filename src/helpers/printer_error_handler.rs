@@ -1,6 +1,6 @@
 // src/helpers/printer_error_handler.rs
 
-use crate::config::printer_config::PrinterType;
+use crate::config::printer_config::{PrinterType, parse_usb_endpoint, parse_usb_id};
 use slint::SharedString;
 
 #[derive(Debug, Clone)]
@@ -186,6 +186,38 @@ impl PrinterErrorHandler {
         Ok(())
     }
 
+    /// Validation des identifiants d'une imprimante USB (hexadécimal, point de sortie facultatif)
+    pub fn validate_usb_ids(
+        vendor_id: &str,
+        product_id: &str,
+        endpoint: &str,
+    ) -> Result<(), PrinterError> {
+        for (label, value) in [("Vendor ID", vendor_id), ("Product ID", product_id)] {
+            if parse_usb_id(value).is_none() {
+                return Err(PrinterError::ValidationError(format!(
+                    "{} invalide: '{}'. Format attendu: 4 chiffres hexadécimaux (ex: 04b8)",
+                    label,
+                    value.trim()
+                )));
+            }
+        }
+
+        if !endpoint.trim().is_empty() {
+            match parse_usb_endpoint(endpoint) {
+                // Le bit de poids fort désigne un point d'entrée, inutilisable pour imprimer
+                Some(address) if address & 0x80 == 0 => {}
+                _ => {
+                    return Err(PrinterError::ValidationError(format!(
+                        "Point de sortie USB invalide: '{}'. Ex: 01, 02 (laisser vide pour la détection automatique)",
+                        endpoint.trim()
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn validate_network_port(port: &str) -> Result<(), PrinterError> {
         if !port.contains(':') {
             return Err(PrinterError::NetworkError(
@@ -308,13 +340,13 @@ impl PrinterErrorHandler {
         match printer_type {
             "Serial" => {
                 if cfg!(windows) {
-                    "Ports série Windows : COM1, COM2, COM3, etc. Vérifiez le Gestionnaire de périphériques.".to_string()
+                    "Ports série Windows : COM1, COM2, COM3, etc. Les ports détectés sont suggérés ; réglez la vitesse et le format (8N1 par défaut) comme sur l'imprimante.".to_string()
                 } else {
-                    "Ports série Unix/Linux : /dev/ttyUSB0, /dev/ttyACM0, etc. Utilisez 'ls /dev/tty*' pour lister.".to_string()
+                    "Ports série Unix/Linux : /dev/ttyUSB0, /dev/ttyACM0, etc. Les ports détectés sont suggérés ; réglez la vitesse et le format (8N1 par défaut) comme sur l'imprimante.".to_string()
                 }
             }
             "USB" => {
-                "Imprimantes USB directes. Généralement détectées automatiquement.".to_string()
+                "Imprimantes USB directes : choisissez un périphérique détecté ou un modèle connu. Le Vendor ID et le Product ID (hexadécimal) désignent l'imprimante, le port sert de libellé.".to_string()
             }
             "Network" => {
                 "Imprimantes réseau au format IP:PORT. Port standard: 9100. Ex: 192.168.1.100:9100"
@@ -356,6 +388,15 @@ mod tests {
         assert!(PrinterErrorHandler::validate_network_port("192.168.1.100:0").is_err());
     }
 
+    #[test]
+    fn test_usb_ids_validation() {
+        assert!(PrinterErrorHandler::validate_usb_ids("04b8", "0x0E15", "").is_ok());
+        assert!(PrinterErrorHandler::validate_usb_ids("04b8", "0202", "01").is_ok());
+        assert!(PrinterErrorHandler::validate_usb_ids("", "0202", "").is_err());
+        assert!(PrinterErrorHandler::validate_usb_ids("04b8", "1FFFF", "").is_err());
+        assert!(PrinterErrorHandler::validate_usb_ids("04b8", "0202", "81").is_err()); // Point d'entrée
+    }
+
     #[test]
     fn test_paper_width_validation() {
         assert!(PrinterErrorHandler::validate_paper_width("32").is_ok());
@@ -30,6 +30,7 @@ fn main() -> AppResult<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     log::info!("Application en cours de démarrage...");
     db::init()?;
+    db::health_check()?;
 
     // 2. Tâches de démarrage optionnelles (comme le seeding)
     run_startup_tasks()?;
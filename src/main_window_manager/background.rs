@@ -0,0 +1,185 @@
+// src/main_window_manager/background.rs
+
+use crate::ui;
+use slint::Weak;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::OnceLock,
+};
+use tokio::runtime::{Builder, Runtime};
+
+/// Vue de la fenêtre principale dont l'indicateur de chargement suit une tâche de fond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadingState {
+    Dashboard,
+    Products,
+    Sales,
+    Customers,
+    Purchases,
+    Users,
+    Reports,
+    CashRegister,
+}
+
+impl LoadingState {
+    fn apply(self, ui: &ui::MainWindow, loading: bool) {
+        match self {
+            LoadingState::Dashboard => ui.set_dashboard_loading(loading),
+            LoadingState::Products => ui.set_products_loading(loading),
+            LoadingState::Sales => ui.set_sales_loading(loading),
+            LoadingState::Customers => ui.set_customers_loading(loading),
+            LoadingState::Purchases => ui.set_purchases_loading(loading),
+            LoadingState::Users => ui.set_users_loading(loading),
+            LoadingState::Reports => ui.set_report_is_loading(loading),
+            LoadingState::CashRegister => ui.set_cash_loading(loading),
+        }
+    }
+}
+
+/// Suivi des tâches en cours, tenu exclusivement sur le thread de l'interface.
+#[derive(Default)]
+struct TaskTracker {
+    total: i32,
+    pending: HashMap<LoadingState, u32>,
+    /// Numéro de la dernière demande émise pour chaque vue.
+    latest: HashMap<LoadingState, u64>,
+}
+
+thread_local! {
+    static TRACKER: RefCell<TaskTracker> = RefCell::new(TaskTracker::default());
+}
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .thread_name("depot-worker")
+            .enable_all()
+            .build()
+            .expect("Impossible de démarrer l'exécuteur des tâches de fond")
+    })
+}
+
+/// Exécute `job` (requêtes, génération de fichiers) hors du thread de l'interface, puis transmet
+/// son résultat à `on_done` sur le thread de l'interface via `slint::invoke_from_event_loop`.
+/// L'indicateur de chargement de `state` reste actif tant qu'une tâche de cette vue est en cours.
+/// Doit être appelée depuis le thread de l'interface.
+pub fn spawn<T, J, D>(
+    handle: &Weak<ui::MainWindow>,
+    state: Option<LoadingState>,
+    job: J,
+    on_done: D,
+) where
+    T: Send + 'static,
+    J: FnOnce() -> T + Send + 'static,
+    D: FnOnce(&ui::MainWindow, T) + Send + 'static,
+{
+    run(handle, state, false, job, on_done);
+}
+
+/// Comme [`spawn`], mais seul le résultat de la dernière demande de la vue est appliqué :
+/// une réponse lente ne peut pas écraser celle d'une recherche ou d'un filtre plus récent.
+pub fn spawn_latest<T, J, D>(handle: &Weak<ui::MainWindow>, state: LoadingState, job: J, on_done: D)
+where
+    T: Send + 'static,
+    J: FnOnce() -> T + Send + 'static,
+    D: FnOnce(&ui::MainWindow, T) + Send + 'static,
+{
+    run(handle, Some(state), true, job, on_done);
+}
+
+fn run<T, J, D>(
+    handle: &Weak<ui::MainWindow>,
+    state: Option<LoadingState>,
+    latest_only: bool,
+    job: J,
+    on_done: D,
+) where
+    T: Send + 'static,
+    J: FnOnce() -> T + Send + 'static,
+    D: FnOnce(&ui::MainWindow, T) + Send + 'static,
+{
+    let ticket = begin_task(handle, state);
+    let handle = handle.clone();
+
+    runtime().spawn_blocking(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(job));
+        if result.is_err() {
+            log::error!("Une tâche de fond s'est interrompue de manière inattendue");
+        }
+
+        let posted = slint::invoke_from_event_loop(move || {
+            let is_latest = end_task(&handle, state, ticket);
+            let Ok(value) = result else {
+                return;
+            };
+            if latest_only && !is_latest {
+                log::debug!("Résultat périmé ignoré pour {:?}", state);
+                return;
+            }
+            if let Some(ui) = handle.upgrade() {
+                on_done(&ui, value);
+            }
+        });
+        if let Err(e) = posted {
+            log::warn!(
+                "Impossible de transmettre le résultat à l'interface : {}",
+                e
+            );
+        }
+    });
+}
+
+/// Enregistre une nouvelle tâche et active les indicateurs ; renvoie le numéro de la demande.
+fn begin_task(handle: &Weak<ui::MainWindow>, state: Option<LoadingState>) -> u64 {
+    TRACKER.with(|tracker| {
+        let mut tracker = tracker.borrow_mut();
+        tracker.total += 1;
+        let ticket = match state {
+            Some(state) => {
+                *tracker.pending.entry(state).or_insert(0) += 1;
+                let latest = tracker.latest.entry(state).or_insert(0);
+                *latest += 1;
+                *latest
+            }
+            None => 0,
+        };
+
+        if let Some(ui) = handle.upgrade() {
+            ui.set_pending_tasks(tracker.total);
+            if let Some(state) = state {
+                state.apply(&ui, true);
+            }
+        }
+        ticket
+    })
+}
+
+/// Clôt une tâche et éteint les indicateurs devenus inutiles.
+/// Renvoie `true` si aucune demande plus récente n'a été émise pour la même vue.
+fn end_task(handle: &Weak<ui::MainWindow>, state: Option<LoadingState>, ticket: u64) -> bool {
+    TRACKER.with(|tracker| {
+        let mut tracker = tracker.borrow_mut();
+        tracker.total = (tracker.total - 1).max(0);
+        let (still_pending, is_latest) = match state {
+            Some(state) => {
+                let pending = tracker.pending.entry(state).or_insert(1);
+                *pending = pending.saturating_sub(1);
+                let still_pending = *pending > 0;
+                (still_pending, tracker.latest.get(&state) == Some(&ticket))
+            }
+            None => (false, true),
+        };
+
+        if let Some(ui) = handle.upgrade() {
+            ui.set_pending_tasks(tracker.total);
+            if let Some(state) = state.filter(|_| !still_pending) {
+                state.apply(&ui, false);
+            }
+        }
+        is_latest
+    })
+}
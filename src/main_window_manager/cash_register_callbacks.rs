@@ -0,0 +1,328 @@
+// src/main_window_manager/cash_register_callbacks.rs
+
+use crate::{
+    config::{business_calendar::business_calendar, printer_config},
+    error::{AppError, AppResult},
+    models::{CashMovementKind, CashSession},
+    queries::{self, ZReport},
+    services::{print_queue::PrintJob, printing_service},
+    ui,
+};
+use bigdecimal::{BigDecimal, Num};
+use slint::{ModelRc, VecModel, Weak};
+use uuid::Uuid;
+
+use super::{
+    background::{self, LoadingState},
+    show_error_dialog, show_info_dialog,
+};
+
+/// Nombre de sessions affichées dans l'historique.
+const HISTORY_LIMIT: i64 = 30;
+
+fn session_to_ui(session: &CashSession, cashier: &str) -> ui::CashSessionUI {
+    let calendar = business_calendar();
+    let variance = session.variance();
+    ui::CashSessionUI {
+        id: session.id.to_string().into(),
+        cashier: cashier.into(),
+        opened_at: calendar
+            .format_local(session.opened_at, "%d/%m/%Y %H:%M")
+            .into(),
+        closed_at: session
+            .closed_at
+            .map(|at| calendar.format_local(at, "%d/%m/%Y %H:%M"))
+            .unwrap_or_default()
+            .into(),
+        expected: session
+            .expected_cash
+            .as_ref()
+            .map(|amount| format!("{:.0} XAF", amount))
+            .unwrap_or_default()
+            .into(),
+        counted: session
+            .counted_cash
+            .as_ref()
+            .map(|amount| format!("{:.0} XAF", amount))
+            .unwrap_or_default()
+            .into(),
+        variance: variance
+            .as_ref()
+            .map(format_variance)
+            .unwrap_or_default()
+            .into(),
+        has_shortage: variance.is_some_and(|v| v < BigDecimal::from(0)),
+    }
+}
+
+/// Écart signé, par exemple « +500 XAF » ou « -1500 XAF ».
+fn format_variance(variance: &BigDecimal) -> String {
+    if *variance > BigDecimal::from(0) {
+        format!("+{:.0} XAF", variance)
+    } else {
+        format!("{:.0} XAF", variance)
+    }
+}
+
+/// Affiche la session ouverte (rapport X en cours) et l'historique des sessions.
+fn apply_cash_state(
+    ui: &ui::MainWindow,
+    report: Option<&ZReport>,
+    history: &[(CashSession, String)],
+) {
+    let calendar = business_calendar();
+    ui.set_cash_session_open(report.is_some());
+    match report {
+        Some(report) => {
+            ui.set_cash_session_id(report.session.id.to_string().into());
+            ui.set_cash_session_info(
+                format!(
+                    "Caisse ouverte le {} — fond de caisse {:.0} XAF",
+                    calendar.format_local(report.session.opened_at, "%d/%m/%Y à %H:%M"),
+                    report.session.opening_float
+                )
+                .into(),
+            );
+            let tenders = report
+                .tenders
+                .iter()
+                .map(|t| format!("{} {:.0} XAF", t.label, t.amount))
+                .collect::<Vec<_>>();
+            let mut sales_summary = if tenders.is_empty() {
+                format!("{} vente(s)", report.sales_count)
+            } else {
+                format!("{} vente(s) : {}", report.sales_count, tenders.join(", "))
+            };
+            if report.customer_cash_payments > BigDecimal::from(0) {
+                sales_summary.push_str(&format!(
+                    " — règlements clients en espèces {:.0} XAF",
+                    report.customer_cash_payments
+                ));
+            }
+            if report.cash_refunds > BigDecimal::from(0) {
+                sales_summary.push_str(&format!(
+                    " — avoirs remboursés {:.0} XAF",
+                    report.cash_refunds
+                ));
+            }
+            if report.voided_sales > 0 {
+                sales_summary.push_str(&format!(
+                    " — {} vente(s) annulée(s), {:.0} XAF rendus",
+                    report.voided_sales, report.void_refunds
+                ));
+            }
+            ui.set_cash_sales_summary(sales_summary.into());
+            ui.set_cash_sales(format!("{:.0} XAF", report.cash_sales).into());
+            ui.set_cash_in(format!("{:.0} XAF", report.cash_in()).into());
+            ui.set_cash_out(format!("{:.0} XAF", report.cash_out()).into());
+            ui.set_cash_expected(format!("{:.0} XAF", report.expected_cash).into());
+            let movements = report
+                .movements
+                .iter()
+                .map(|m| ui::CashMovementUI {
+                    time: calendar.format_local(m.created_at, "%H:%M").into(),
+                    kind: m.kind().map(|k| k.label()).unwrap_or("?").into(),
+                    amount: format!("{:.0} XAF", m.amount).into(),
+                    reason: m.reason.clone().into(),
+                    is_out: m.kind() == Some(CashMovementKind::Out),
+                })
+                .collect::<Vec<_>>();
+            ui.set_cash_movements(ModelRc::new(VecModel::from(movements)));
+        }
+        None => {
+            ui.set_cash_session_id("".into());
+            ui.set_cash_movements(ModelRc::new(VecModel::default()));
+        }
+    }
+
+    let sessions = history
+        .iter()
+        .map(|(session, cashier)| session_to_ui(session, cashier))
+        .collect::<Vec<_>>();
+    ui.set_cash_sessions(ModelRc::new(VecModel::from(sessions)));
+}
+
+/// Imprime un rapport de caisse sur l'imprimante par défaut.
+/// En cas d'échec, le rapport reste dans la file d'impression et sera réimprimé.
+fn print_report(report: &ZReport) -> AppResult<()> {
+    let printers = printer_config::load_printers();
+    let printer = printers
+        .iter()
+        .find(|p| p.is_default)
+        .or_else(|| printers.first())
+        .ok_or_else(|| AppError::PrintingError("Aucune imprimante configurée".to_string()))?;
+    printing_service::print_z_report(report, printer).and_then(PrintJob::into_result)
+}
+
+fn parse_amount(value: &str) -> Option<BigDecimal> {
+    BigDecimal::from_str_radix(value.trim(), 10).ok()
+}
+
+/// Configure les callbacks de la vue « Caisse ».
+/// Un caissier ne voit que ses propres sessions ; un administrateur voit celles de tous.
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, is_admin: bool) {
+    let load_cash_session = {
+        let handle = main_window_handle.clone();
+        move || {
+            background::spawn_latest(
+                &handle,
+                LoadingState::CashRegister,
+                move || -> AppResult<_> {
+                    let report = queries::get_open_cash_session(current_user_id)?
+                        .map(|session| queries::get_cash_session_report(session.id))
+                        .transpose()?;
+                    let history = queries::get_cash_sessions(
+                        (!is_admin).then_some(current_user_id),
+                        HISTORY_LIMIT,
+                    )?;
+                    Ok((report, history))
+                },
+                |ui, result| match result {
+                    Ok((report, history)) => apply_cash_state(ui, report.as_ref(), &history),
+                    Err(e) => log::error!("Erreur lors du chargement de la caisse: {}", e),
+                },
+            );
+        }
+    };
+
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_request_cash_session({
+        let load_fn = load_cash_session.clone();
+        move || load_fn()
+    });
+
+    // --- Ouverture avec le fond de caisse ---
+    ui.on_open_cash_session({
+        let handle = main_window_handle.clone();
+        let load_fn = load_cash_session.clone();
+        move |amount| {
+            let Some(opening_float) = parse_amount(&amount) else {
+                show_error_dialog("Montant invalide", "Le fond de caisse est invalide.");
+                return;
+            };
+            match queries::open_cash_session(current_user_id, opening_float) {
+                Ok(_) => {
+                    if let Some(ui) = handle.upgrade() {
+                        ui.set_cash_opening_float("".into());
+                    }
+                    load_fn();
+                }
+                Err(e) => show_error_dialog("Ouverture de caisse", &e.to_string()),
+            }
+        }
+    });
+
+    // --- Entrées et sorties d'espèces ---
+    ui.on_record_cash_movement({
+        let handle = main_window_handle.clone();
+        let load_fn = load_cash_session.clone();
+        move |kind_index, amount, reason| {
+            let Some(ui) = handle.upgrade() else {
+                return;
+            };
+            let Ok(session_id) = Uuid::parse_str(&ui.get_cash_session_id()) else {
+                return;
+            };
+            let kind = usize::try_from(kind_index)
+                .ok()
+                .and_then(|i| CashMovementKind::ALL.get(i).copied())
+                .unwrap_or(CashMovementKind::In);
+            let Some(amount) = parse_amount(&amount) else {
+                show_error_dialog("Montant invalide", "Le montant du mouvement est invalide.");
+                return;
+            };
+            match queries::record_cash_movement(
+                session_id,
+                current_user_id,
+                kind,
+                amount,
+                reason.to_string(),
+            ) {
+                Ok(_) => {
+                    ui.set_cash_movement_amount("".into());
+                    ui.set_cash_movement_reason("".into());
+                    load_fn();
+                }
+                Err(e) => show_error_dialog("Mouvement de caisse", &e.to_string()),
+            }
+        }
+    });
+
+    // --- Clôture : calcul de l'écart, archivage puis impression du rapport Z ---
+    ui.on_close_cash_session({
+        let handle = main_window_handle.clone();
+        let load_fn = load_cash_session.clone();
+        move |counted, note| {
+            let Some(ui) = handle.upgrade() else {
+                return;
+            };
+            let Ok(session_id) = Uuid::parse_str(&ui.get_cash_session_id()) else {
+                return;
+            };
+            let Some(counted_cash) = parse_amount(&counted) else {
+                show_error_dialog("Montant invalide", "Le montant compté est invalide.");
+                return;
+            };
+            let note = Some(note.to_string());
+            let load_fn = load_fn.clone();
+            background::spawn(
+                &handle,
+                Some(LoadingState::CashRegister),
+                move || {
+                    queries::close_cash_session(session_id, current_user_id, counted_cash, note)
+                        .map(|report| {
+                            let printed = print_report(&report);
+                            (report, printed)
+                        })
+                },
+                move |ui, result| match result {
+                    Ok((report, printed)) => {
+                        ui.set_cash_counted("".into());
+                        ui.set_cash_closing_note("".into());
+                        load_fn();
+                        let variance = report.variance().unwrap_or_default();
+                        let mut message = format!(
+                            "Caisse clôturée. Attendu : {:.0} XAF — écart : {}.",
+                            report.expected_cash,
+                            format_variance(&variance)
+                        );
+                        if let Err(e) = printed {
+                            log::error!("Impression du rapport Z impossible: {}", e);
+                            message.push_str(&format!(
+                                "\nLe rapport Z n'a pas pu être imprimé ({}) ; il reste disponible dans l'historique.",
+                                e
+                            ));
+                        }
+                        show_info_dialog("Clôture de caisse", &message);
+                    }
+                    Err(e) => show_error_dialog("Clôture de caisse", &e.to_string()),
+                },
+            );
+        }
+    });
+
+    // --- Impression d'un rapport X (session ouverte) ou Z (session clôturée) ---
+    ui.on_print_cash_report({
+        let handle = main_window_handle.clone();
+        move |session_id| {
+            let Ok(session_id) = Uuid::parse_str(&session_id) else {
+                return;
+            };
+            background::spawn(
+                &handle,
+                None,
+                move || {
+                    let report = queries::get_cash_session_report(session_id)?;
+                    print_report(&report)
+                },
+                |_, result| match result {
+                    Ok(()) => {
+                        show_info_dialog("Impression", "Rapport de caisse envoyé à l'imprimante.")
+                    }
+                    Err(e) => show_error_dialog("Erreur d'impression", &e.to_string()),
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,250 @@
+// src/main_window_manager/customer_callbacks.rs
+
+use crate::{models::PaymentMethod, queries, ui};
+use bigdecimal::{BigDecimal, Num};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use super::{
+    background::{self, LoadingState},
+    show_error_dialog, show_info_dialog,
+};
+
+/// Convertit un client et son encours en modèle UI.
+pub fn customer_to_ui(c: &queries::CustomerWithBalance) -> ui::CustomerUI {
+    ui::CustomerUI {
+        id: c.customer.id.to_string().into(),
+        name: c.customer.name.clone().into(),
+        phone: c.customer.phone.clone().unwrap_or_default().into(),
+        address: c.customer.address.clone().unwrap_or_default().into(),
+        balance: format!("{:.0} XAF", c.balance).into(),
+        credit_limit: format!("{:.0} XAF", c.customer.credit_limit).into(),
+        available_credit: format!("{:.0} XAF", c.available_credit()).into(),
+        over_limit: c.balance > c.customer.credit_limit,
+        empties_owed: c.empties_owed as i32,
+    }
+}
+
+/// Configure les callbacks de la vue clients.
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, is_admin: bool) {
+    let search_state = Arc::new(Mutex::new(String::new()));
+
+    // Fonction pour charger les clients avec la recherche courante
+    let load_customers = {
+        let handle = main_window_handle.clone();
+        let state = search_state.clone();
+        move || {
+            let search = state.lock().unwrap().clone();
+            background::spawn_latest(
+                &handle,
+                LoadingState::Customers,
+                move || queries::get_customers(Some(search)),
+                |ui, result| match result {
+                    Ok(customers) => {
+                        let total_outstanding: BigDecimal =
+                            customers.iter().map(|c| c.balance.clone()).sum();
+                        let model = customers.iter().map(customer_to_ui).collect::<Vec<_>>();
+                        ui.set_customers_model(ModelRc::new(VecModel::from(model)));
+                        ui.set_customers_total_outstanding(
+                            format!("{:.0} XAF", total_outstanding).into(),
+                        );
+                    }
+                    Err(e) => {
+                        log::error!("Erreur lors du chargement des clients: {}", e);
+                        ui.set_customers_model(ModelRc::new(VecModel::default()));
+                    }
+                },
+            );
+        }
+    };
+
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_request_customers({
+        let load_fn = load_customers.clone();
+        move || load_fn()
+    });
+
+    ui.on_search_customers({
+        let state = search_state.clone();
+        let load_fn = load_customers.clone();
+        move |query| {
+            *state.lock().unwrap() = query.to_string();
+            load_fn();
+        }
+    });
+
+    // --- AJOUT D'UN CLIENT ---
+    ui.on_add_customer_clicked({
+        let load_fn = load_customers.clone();
+        move || {
+            if !is_admin {
+                return;
+            }
+            if let Ok(dialog) = ui::CustomerDialog::new() {
+                dialog.set_dialog_title("Nouveau Client".into());
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+
+                dialog.on_save_clicked(move |name, phone, address, limit_str| {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        match BigDecimal::from_str_radix(&limit_str, 10) {
+                            Ok(limit) => match queries::create_customer(
+                                name.to_string(),
+                                Some(phone.to_string()),
+                                Some(address.to_string()),
+                                limit,
+                            ) {
+                                Ok(_) => {
+                                    load_fn_clone();
+                                    let _ = d.hide();
+                                }
+                                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                            },
+                            Err(_) => d.set_status_message("Le plafond est invalide.".into()),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // --- ÉDITION D'UN CLIENT ---
+    ui.on_edit_customer_clicked({
+        let load_fn = load_customers.clone();
+        move |customer_id_str| {
+            if !is_admin {
+                return;
+            }
+            let Ok(customer_id) = Uuid::parse_str(&customer_id_str) else {
+                return;
+            };
+            let customer = match queries::get_customer_with_balance(customer_id) {
+                Ok(c) => c.customer,
+                Err(e) => {
+                    show_error_dialog("Erreur", &format!("Client introuvable: {}", e));
+                    return;
+                }
+            };
+            if let Ok(dialog) = ui::CustomerDialog::new() {
+                dialog.set_dialog_title("Modifier le Client".into());
+                dialog.set_customer_name(customer.name.into());
+                dialog.set_phone(customer.phone.unwrap_or_default().into());
+                dialog.set_address(customer.address.unwrap_or_default().into());
+                dialog.set_credit_limit(format!("{:.0}", customer.credit_limit).into());
+
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+                dialog.on_save_clicked(move |name, phone, address, limit_str| {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        match BigDecimal::from_str_radix(&limit_str, 10) {
+                            Ok(limit) => match queries::update_customer(
+                                customer_id,
+                                name.to_string(),
+                                Some(phone.to_string()),
+                                Some(address.to_string()),
+                                limit,
+                            ) {
+                                Ok(_) => {
+                                    load_fn_clone();
+                                    let _ = d.hide();
+                                }
+                                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                            },
+                            Err(_) => d.set_status_message("Le plafond est invalide.".into()),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // --- RÈGLEMENT D'UN CLIENT ---
+    ui.on_record_customer_payment_clicked({
+        let load_fn = load_customers.clone();
+        let main_handle = main_window_handle.clone();
+        move |customer_id_str| {
+            let Ok(customer_id) = Uuid::parse_str(&customer_id_str) else {
+                return;
+            };
+            let customer = match queries::get_customer_with_balance(customer_id) {
+                Ok(c) => c,
+                Err(e) => {
+                    show_error_dialog("Erreur", &format!("Client introuvable: {}", e));
+                    return;
+                }
+            };
+            if customer.balance <= BigDecimal::from(0) {
+                show_info_dialog(
+                    "Règlement",
+                    &format!("{} n'a aucun encours à régler.", customer.customer.name),
+                );
+                return;
+            }
+            if let Ok(dialog) = ui::CustomerPaymentDialog::new() {
+                dialog.set_customer_name(customer.customer.name.clone().into());
+                dialog.set_balance(format!("{:.0} XAF", customer.balance).into());
+
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+                let main_handle = main_handle.clone();
+                dialog.on_save_clicked(move |amount_str, method_index, note| {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        let method = usize::try_from(method_index)
+                            .ok()
+                            .and_then(|i| PaymentMethod::ALL.get(i).copied())
+                            .unwrap_or(PaymentMethod::Cash);
+                        match BigDecimal::from_str_radix(&amount_str, 10) {
+                            Ok(amount) => match queries::record_customer_payment(
+                                customer_id,
+                                current_user_id,
+                                amount,
+                                method,
+                                Some(note.to_string()),
+                            ) {
+                                Ok(new_balance) => {
+                                    load_fn_clone();
+                                    if let Some(main_ui) = main_handle.upgrade() {
+                                        main_ui.invoke_refresh_dashboard();
+                                    }
+                                    let _ = d.hide();
+                                    show_info_dialog(
+                                        "Règlement enregistré",
+                                        &format!("Nouvel encours : {:.0} XAF", new_balance),
+                                    );
+                                }
+                                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                            },
+                            Err(_) => d.set_status_message("Le montant est invalide.".into()),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // Chargement initial
+    load_customers();
+}
@@ -1,45 +1,80 @@
 // src/main_window_manager/dashboard_callbacks.rs
+use super::background::{self, LoadingState};
 use crate::{queries, ui};
 use slint::Weak;
 
 pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
     let dashboard_handle = main_window_handle.clone();
 
-    // Fonction pour charger les données du dashboard
+    // Fonction pour charger les données du dashboard, hors du thread de l'interface
     let load_dashboard_data = {
         let handle = dashboard_handle.clone();
         move || {
-            if let Some(ui) = handle.upgrade() {
-                log::info!("Chargement des données du tableau de bord...");
+            log::info!("Chargement des données du tableau de bord...");
+            background::spawn_latest(
+                &handle,
+                LoadingState::Dashboard,
+                || {
+                    (
+                        queries::get_today_sales_summary(),
+                        queries::get_low_stock_products(50),
+                        // Alerte en dessous de 10 % de marge brute sur le prix de vente
+                        queries::get_low_margin_products(10),
+                    )
+                },
+                |ui, (summary, low_stock, low_margin)| {
+                    match summary {
+                        Ok((revenue, count)) => {
+                            ui.set_today_revenue(format!("{} XAF", revenue).into());
+                            ui.set_today_sales_count(count.to_string().into());
+                        }
+                        Err(e) => log::error!("Erreur chargement résumé ventes: {}", e),
+                    }
 
-                match queries::get_today_sales_summary() {
-                    Ok((revenue, count)) => {
-                        ui.set_today_revenue(format!("{} XAF", revenue).into());
-                        ui.set_today_sales_count(count.to_string().into());
+                    match low_stock {
+                        Ok(products) => {
+                            let model = products
+                                .into_iter()
+                                .map(|p| ui::LowStockProductUI {
+                                    name: p.name.into(),
+                                    stock_info: format!(
+                                        "{} {}",
+                                        p.stock_in_sale_units, p.packaging_description
+                                    )
+                                    .into(),
+                                })
+                                .collect::<Vec<_>>();
+                            ui.set_low_stock_products_model(
+                                std::rc::Rc::new(slint::VecModel::from(model)).into(),
+                            );
+                        }
+                        Err(e) => log::error!("Erreur chargement stock bas: {}", e),
                     }
-                    Err(e) => log::error!("Erreur chargement résumé ventes: {}", e),
-                }
 
-                match queries::get_low_stock_products(50) {
-                    Ok(products) => {
-                        let model = products
-                            .into_iter()
-                            .map(|p| ui::LowStockProductUI {
-                                name: p.name.into(),
-                                stock_info: format!(
-                                    "{} {}",
-                                    p.stock_in_sale_units, p.packaging_description
-                                )
-                                .into(),
-                            })
-                            .collect::<Vec<_>>();
-                        ui.set_low_stock_products_model(
-                            std::rc::Rc::new(slint::VecModel::from(model)).into(),
-                        );
+                    match low_margin {
+                        Ok(products) => {
+                            let model = products
+                                .into_iter()
+                                .map(|(p, rate)| ui::LowMarginProductUI {
+                                    name: format!("{} — {}", p.name, p.packaging_description)
+                                        .into(),
+                                    margin_info: format!(
+                                        "{:.1} % (vente {:.0} XAF, revient {:.0} XAF)",
+                                        rate,
+                                        p.price_per_sale_unit,
+                                        p.cost_price.unwrap_or_default()
+                                    )
+                                    .into(),
+                                })
+                                .collect::<Vec<_>>();
+                            ui.set_low_margin_products_model(
+                                std::rc::Rc::new(slint::VecModel::from(model)).into(),
+                            );
+                        }
+                        Err(e) => log::error!("Erreur chargement marges faibles: {}", e),
                     }
-                    Err(e) => log::error!("Erreur chargement stock bas: {}", e),
-                }
-            }
+                },
+            );
         }
     };
 
@@ -53,47 +88,12 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
         });
 
     // Callback pour le rafraîchissement manuel
-    let refresh_handle = dashboard_handle.clone();
     main_window_handle
         .upgrade()
         .unwrap()
         .on_refresh_dashboard(move || {
             log::info!("Rafraîchissement manuel du tableau de bord...");
-            if let Some(ui) = refresh_handle.upgrade() {
-                // Optionnel : Afficher un indicateur de chargement
-                ui.set_dashboard_loading(true);
-
-                match queries::get_today_sales_summary() {
-                    Ok((revenue, count)) => {
-                        ui.set_today_revenue(format!("{} XAF", revenue).into());
-                        ui.set_today_sales_count(count.to_string().into());
-                    }
-                    Err(e) => log::error!("Erreur rafraîchissement résumé ventes: {}", e),
-                }
-
-                match queries::get_low_stock_products(50) {
-                    Ok(products) => {
-                        let model = products
-                            .into_iter()
-                            .map(|p| ui::LowStockProductUI {
-                                name: p.name.into(),
-                                stock_info: format!(
-                                    "{} {}",
-                                    p.stock_in_sale_units, p.packaging_description
-                                )
-                                .into(),
-                            })
-                            .collect::<Vec<_>>();
-                        ui.set_low_stock_products_model(
-                            std::rc::Rc::new(slint::VecModel::from(model)).into(),
-                        );
-                    }
-                    Err(e) => log::error!("Erreur rafraîchissement stock bas: {}", e),
-                }
-
-                ui.set_dashboard_loading(false);
-                log::info!("Tableau de bord rafraîchi avec succès");
-            }
+            load_dashboard_data();
         });
 }
 
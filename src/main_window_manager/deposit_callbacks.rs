@@ -0,0 +1,125 @@
+// src/main_window_manager/deposit_callbacks.rs
+
+use crate::{queries, ui};
+use bigdecimal::{BigDecimal, Num};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use uuid::Uuid;
+
+use super::background;
+
+/// Convertit une consigne et la situation de ses emballages en modèle UI.
+fn deposit_item_to_ui(d: &queries::DepositItemStatus) -> ui::DepositItemUI {
+    ui::DepositItemUI {
+        id: d.item.id.to_string().into(),
+        packaging_description: d.item.packaging_description.clone().into(),
+        deposit_amount: format!("{:.0}", d.item.deposit_amount).into(),
+        empties_in_stock: d.item.empties_in_stock,
+        empties_outstanding: d.empties_outstanding as i32,
+        empties_with_customers: d.empties_with_customers as i32,
+    }
+}
+
+/// Recharge la liste des consignes dans le dialogue, en tâche de fond.
+fn load_deposit_items(main_handle: &Weak<ui::MainWindow>, dialog: &ui::DepositItemsDialog) {
+    let dialog_weak = dialog.as_weak();
+    background::spawn(
+        main_handle,
+        None,
+        queries::get_deposit_items,
+        move |_, result| {
+            let Some(dialog) = dialog_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(items) => {
+                    let model = items.iter().map(deposit_item_to_ui).collect::<Vec<_>>();
+                    dialog.set_deposit_items(ModelRc::new(VecModel::from(model)));
+                }
+                Err(e) => {
+                    log::error!("Erreur lors du chargement des consignes: {}", e);
+                    dialog.set_status_message(format!("Erreur: {}", e).into());
+                }
+            }
+        },
+    );
+}
+
+/// Configure la gestion des consignes (réservée aux administrateurs).
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_manage_deposits_clicked({
+        let main_handle = main_window_handle.clone();
+        move || {
+            let Ok(dialog) = ui::DepositItemsDialog::new() else {
+                return;
+            };
+            load_deposit_items(&main_handle, &dialog);
+
+            let dialog_handle = dialog.as_weak();
+            let main_handle = main_handle.clone();
+            dialog.on_save_clicked(move |id_str, packaging, amount_str, empties_str| {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Ok(amount) = BigDecimal::from_str_radix(&amount_str, 10) else {
+                    d.set_status_message("Le montant de la consigne est invalide.".into());
+                    return;
+                };
+                let Ok(empties) = empties_str.trim().parse::<i32>() else {
+                    d.set_status_message("Le stock de vides est invalide.".into());
+                    return;
+                };
+
+                let existing_id = if id_str.is_empty() {
+                    None
+                } else {
+                    match Uuid::parse_str(&id_str) {
+                        Ok(id) => Some(id),
+                        Err(_) => return,
+                    }
+                };
+                let packaging = packaging.to_string();
+
+                d.set_busy(true);
+                let dialog_weak = d.as_weak();
+                background::spawn(
+                    &main_handle,
+                    None,
+                    move || match existing_id {
+                        Some(id) => queries::update_deposit_item(id, amount, empties),
+                        None => queries::create_deposit_item(packaging, amount, empties),
+                    },
+                    move |main_ui, result| {
+                        let Some(d) = dialog_weak.upgrade() else {
+                            return;
+                        };
+                        d.set_busy(false);
+                        match result {
+                            Ok(_) => {
+                                d.set_selected_id("".into());
+                                d.set_packaging_description("".into());
+                                d.set_deposit_amount("".into());
+                                d.set_empties_in_stock("0".into());
+                                d.set_status_message("".into());
+                                load_deposit_items(&main_ui.as_weak(), &d);
+                                // Les produits du conditionnement viennent peut-être d'être rattachés
+                                main_ui.invoke_request_products();
+                            }
+                            Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                        }
+                    },
+                );
+            });
+
+            let dialog_handle_close = dialog.as_weak();
+            dialog.on_close_clicked(move || {
+                if let Some(d) = dialog_handle_close.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+
+            let _ = dialog.run();
+        }
+    });
+}
@@ -0,0 +1,464 @@
+// src/main_window_manager/inventory_callbacks.rs
+
+use crate::{
+    config::business_calendar::business_calendar,
+    error::AppResult,
+    models::InventorySession,
+    queries::{self, InventoryReport},
+    services::{product_import_service, report_generator_service},
+    ui,
+};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use std::path::Path;
+use uuid::Uuid;
+
+use super::{
+    background::{self, LoadingState},
+    show_error_dialog, show_info_dialog,
+};
+
+/// Nombre d'inventaires affichés dans l'historique.
+const HISTORY_LIMIT: i64 = 20;
+
+fn line_to_ui(detail: &queries::InventoryLineDetail) -> ui::InventoryLineUI {
+    let variance = detail.line.variance();
+    ui::InventoryLineUI {
+        id: detail.line.id.to_string().into(),
+        product: detail.product_label().into(),
+        sku: detail.sku.clone().unwrap_or_default().into(),
+        expected: detail.line.expected_stock,
+        counted: detail
+            .line
+            .counted_stock
+            .map(|c| c.to_string())
+            .unwrap_or_default()
+            .into(),
+        variance: variance
+            .filter(|v| *v != 0)
+            .map(|v| format!("{:+}", v))
+            .unwrap_or_default()
+            .into(),
+        variance_value: detail
+            .line
+            .variance_cost()
+            .filter(|_| variance.is_some_and(|v| v != 0))
+            .map(|v| format!("{:.0} XAF", v))
+            .unwrap_or_default()
+            .into(),
+        has_variance: variance.is_some_and(|v| v != 0),
+        is_shortage: variance.is_some_and(|v| v < 0),
+    }
+}
+
+fn report_summary(report: &InventoryReport) -> String {
+    format!(
+        "{} produit(s) compté(s), {} à compter — {} écart(s) : {:.0} XAF au prix de revient, {:.0} XAF au prix de vente",
+        report.counted_count(),
+        report.uncounted_count(),
+        report.discrepancies().count(),
+        report.total_variance_cost(),
+        report.total_variance_value()
+    )
+}
+
+/// Recharge l'inventaire en cours et l'historique dans le dialogue, en tâche de fond.
+fn load_inventory(main_handle: &Weak<ui::MainWindow>, dialog: &ui::InventoryDialog) {
+    let dialog_weak = dialog.as_weak();
+    background::spawn(
+        main_handle,
+        Some(LoadingState::Products),
+        || {
+            let report = queries::get_open_inventory().and_then(|session| {
+                session
+                    .map(|s| queries::get_inventory_report(s.id))
+                    .transpose()
+            });
+            (report, queries::get_inventory_sessions(HISTORY_LIMIT))
+        },
+        move |_, (report, sessions)| {
+            if let Some(dialog) = dialog_weak.upgrade() {
+                show_inventory(&dialog, report, sessions);
+            }
+        },
+    );
+}
+
+fn show_inventory(
+    dialog: &ui::InventoryDialog,
+    report: AppResult<Option<InventoryReport>>,
+    sessions: AppResult<Vec<(InventorySession, String)>>,
+) {
+    let calendar = business_calendar();
+    match report {
+        Ok(Some(report)) => {
+            dialog.set_session_open(true);
+            dialog.set_session_id(report.session.id.to_string().into());
+            let mut info = format!(
+                "Inventaire ouvert le {} par {}",
+                calendar.format_local(report.session.opened_at, "%d/%m/%Y à %H:%M"),
+                report.opened_by
+            );
+            if let Some(note) = &report.session.note {
+                info.push_str(&format!(" — {}", note));
+            }
+            dialog.set_session_info(info.into());
+            dialog.set_summary(report_summary(&report).into());
+            let lines = report.lines.iter().map(line_to_ui).collect::<Vec<_>>();
+            dialog.set_lines(ModelRc::new(VecModel::from(lines)));
+        }
+        Ok(None) => {
+            dialog.set_session_open(false);
+            dialog.set_session_id("".into());
+            dialog.set_lines(ModelRc::new(VecModel::default()));
+        }
+        Err(e) => {
+            log::error!("Erreur lors du chargement de l'inventaire: {}", e);
+            dialog.set_status_message(format!("Erreur: {}", e).into());
+        }
+    }
+
+    match sessions {
+        Ok(sessions) => {
+            let model = sessions
+                .iter()
+                .map(|(session, user_name)| ui::InventorySessionUI {
+                    id: session.id.to_string().into(),
+                    date: calendar
+                        .format_local(session.opened_at, "%d/%m/%Y %H:%M")
+                        .into(),
+                    user_name: user_name.clone().into(),
+                    status: session.status().map(|s| s.label()).unwrap_or("?").into(),
+                })
+                .collect::<Vec<_>>();
+            dialog.set_sessions(ModelRc::new(VecModel::from(model)));
+        }
+        Err(e) => log::error!("Erreur lors du chargement des inventaires: {}", e),
+    }
+}
+
+/// Exécute une écriture sur l'inventaire en tâche de fond, le dialogue restant occupé jusqu'à la fin.
+/// En cas de succès, l'inventaire est rechargé et `on_success` reçoit le résultat.
+fn run_inventory_write<T, J, S>(
+    main_handle: &Weak<ui::MainWindow>,
+    dialog: &ui::InventoryDialog,
+    job: J,
+    on_success: S,
+) where
+    T: Send + 'static,
+    J: FnOnce() -> AppResult<T> + Send + 'static,
+    S: FnOnce(&ui::MainWindow, T) + Send + 'static,
+{
+    dialog.set_busy(true);
+    let dialog_weak = dialog.as_weak();
+    background::spawn(
+        main_handle,
+        Some(LoadingState::Products),
+        job,
+        move |main_ui, result| {
+            let Some(d) = dialog_weak.upgrade() else {
+                return;
+            };
+            d.set_busy(false);
+            match result {
+                Ok(value) => {
+                    d.set_status_message("".into());
+                    load_inventory(&main_ui.as_weak(), &d);
+                    on_success(main_ui, value);
+                }
+                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+            }
+        },
+    );
+}
+
+/// Génère un PDF d'inventaire en tâche de fond et indique où il a été enregistré.
+fn generate_pdf<G>(
+    main_handle: &Weak<ui::MainWindow>,
+    session_id: Uuid,
+    title: &'static str,
+    generate: G,
+) where
+    G: FnOnce(&InventoryReport) -> AppResult<String> + Send + 'static,
+{
+    background::spawn(
+        main_handle,
+        Some(LoadingState::Products),
+        move || queries::get_inventory_report(session_id).and_then(|report| generate(&report)),
+        move |_, result| match result {
+            Ok(path) => show_info_dialog(title, &format!("Document sauvegardé : {}", path)),
+            Err(e) => show_error_dialog("Erreur de génération", &e.to_string()),
+        },
+    );
+}
+
+fn current_session_id(dialog: &ui::InventoryDialog) -> Option<Uuid> {
+    Uuid::parse_str(&dialog.get_session_id()).ok()
+}
+
+/// Configure les inventaires physiques (réservés aux administrateurs).
+/// Les lectures, écritures et documents de l'inventaire sont produits en tâche de fond.
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_inventory_clicked({
+        let main_handle = main_window_handle.clone();
+        move || {
+            let Ok(dialog) = ui::InventoryDialog::new() else {
+                return;
+            };
+            load_inventory(&main_handle, &dialog);
+
+            // --- Ouverture : le stock théorique est figé ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_start_clicked(move |note| {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let note = Some(note.trim().to_string()).filter(|n| !n.is_empty());
+                run_inventory_write(
+                    &handle,
+                    &d,
+                    move || queries::start_inventory(current_user_id, note),
+                    |_, _| {},
+                );
+            });
+
+            // --- Saisie d'une quantité comptée ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_count_edited(move |line_id, value| {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Ok(line_id) = Uuid::parse_str(&line_id) else {
+                    return;
+                };
+                let value = value.trim();
+                let counted = if value.is_empty() {
+                    None
+                } else {
+                    match value.parse::<i32>() {
+                        Ok(counted) => Some(counted),
+                        Err(_) => {
+                            d.set_status_message("La quantité comptée est invalide.".into());
+                            return;
+                        }
+                    }
+                };
+                run_inventory_write(
+                    &handle,
+                    &d,
+                    move || queries::set_inventory_count(line_id, counted),
+                    |_, _| {},
+                );
+            });
+
+            // --- Import des comptages depuis un fichier ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_import_clicked(move |path| {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Some(session_id) = current_session_id(&d) else {
+                    return;
+                };
+                let path = path.trim().trim_matches('"').to_string();
+                d.set_file_path("".into());
+                run_inventory_write(
+                    &handle,
+                    &d,
+                    move || {
+                        product_import_service::read_import_file(Path::new(&path))
+                            .and_then(|file| queries::import_inventory_counts(session_id, &file))
+                    },
+                    |_, count| {
+                        show_info_dialog(
+                            "Import des comptages",
+                            &format!("{} quantité(s) comptée(s) importée(s).", count),
+                        );
+                    },
+                );
+            });
+
+            // --- Feuille de comptage imprimable ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_print_sheet_clicked(move || {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Some(session_id) = current_session_id(&d) else {
+                    return;
+                };
+                generate_pdf(
+                    &handle,
+                    session_id,
+                    "Feuille de comptage",
+                    report_generator_service::generate_inventory_count_sheet_pdf,
+                );
+            });
+
+            // --- Rapport d'écarts (inventaire en cours ou passé) ---
+            let handle = main_handle.clone();
+            dialog.on_variance_report_clicked(move |session_id| {
+                let Ok(session_id) = Uuid::parse_str(&session_id) else {
+                    return;
+                };
+                generate_pdf(
+                    &handle,
+                    session_id,
+                    "Rapport d'écarts",
+                    report_generator_service::generate_inventory_variance_pdf,
+                );
+            });
+
+            // --- Validation : les écarts sont passés au journal des mouvements ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_approve_clicked(move || {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Some(session_id) = current_session_id(&d) else {
+                    return;
+                };
+                let dialog_weak = d.as_weak();
+                background::spawn(
+                    &handle,
+                    Some(LoadingState::Products),
+                    move || queries::get_inventory_report(session_id),
+                    move |main_ui, result| {
+                        let Some(d) = dialog_weak.upgrade() else {
+                            return;
+                        };
+                        match result {
+                            Ok(report) => confirm_approval(
+                                &main_ui.as_weak(),
+                                &d,
+                                session_id,
+                                &report,
+                                current_user_id,
+                            ),
+                            Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                        }
+                    },
+                );
+            });
+
+            // --- Abandon, sans effet sur le stock ---
+            let dialog_handle = dialog.as_weak();
+            let handle = main_handle.clone();
+            dialog.on_cancel_inventory_clicked(move || {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Some(session_id) = current_session_id(&d) else {
+                    return;
+                };
+                let Ok(confirm) = ui::ConfirmDialog::new() else {
+                    return;
+                };
+                confirm.set_dialog_title("Abandonner l'Inventaire".into());
+                confirm.set_message(
+                    "Les quantités comptées seront perdues et le stock restera inchangé.".into(),
+                );
+                let confirm_handle = confirm.as_weak();
+                let dialog_handle = d.as_weak();
+                let handle = handle.clone();
+                confirm.on_ok_clicked(move || {
+                    if let Some(c) = confirm_handle.upgrade() {
+                        let _ = c.hide();
+                    }
+                    let Some(d) = dialog_handle.upgrade() else {
+                        return;
+                    };
+                    run_inventory_write(
+                        &handle,
+                        &d,
+                        move || queries::cancel_inventory(session_id, current_user_id),
+                        |_, _| {},
+                    );
+                });
+                let confirm_handle_cancel = confirm.as_weak();
+                confirm.on_cancel_clicked(move || {
+                    if let Some(c) = confirm_handle_cancel.upgrade() {
+                        let _ = c.hide();
+                    }
+                });
+                let _ = confirm.run();
+            });
+
+            let dialog_handle_close = dialog.as_weak();
+            dialog.on_close_clicked(move || {
+                if let Some(d) = dialog_handle_close.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+
+            let _ = dialog.run();
+        }
+    });
+}
+
+/// Demande confirmation avant de valider l'inventaire, d'après l'état des comptages.
+fn confirm_approval(
+    main_handle: &Weak<ui::MainWindow>,
+    dialog: &ui::InventoryDialog,
+    session_id: Uuid,
+    report: &InventoryReport,
+    current_user_id: Uuid,
+) {
+    let Ok(confirm) = ui::ConfirmDialog::new() else {
+        return;
+    };
+    confirm.set_dialog_title("Valider l'Inventaire".into());
+    let mut message = format!(
+        "{} écart(s) seront passés au journal des mouvements de stock.",
+        report.discrepancies().count()
+    );
+    if report.uncounted_count() > 0 {
+        message.push_str(&format!(
+            "\n{} produit(s) non compté(s) garderont leur stock actuel.",
+            report.uncounted_count()
+        ));
+    }
+    confirm.set_message(message.into());
+
+    let confirm_handle = confirm.as_weak();
+    let dialog_handle = dialog.as_weak();
+    let handle = main_handle.clone();
+    confirm.on_ok_clicked(move || {
+        if let Some(c) = confirm_handle.upgrade() {
+            let _ = c.hide();
+        }
+        let Some(d) = dialog_handle.upgrade() else {
+            return;
+        };
+        run_inventory_write(
+            &handle,
+            &d,
+            move || queries::approve_inventory(session_id, current_user_id),
+            // Le stock des produits a changé : la liste principale est rechargée
+            |main_ui, report| {
+                main_ui.invoke_request_products();
+                show_info_dialog(
+                    "Inventaire validé",
+                    &format!(
+                        "{} écart(s) passé(s) au stock, pour {:.0} XAF au prix de revient.",
+                        report.discrepancies().count(),
+                        report.total_variance_cost()
+                    ),
+                );
+            },
+        );
+    });
+    let confirm_handle_cancel = confirm.as_weak();
+    confirm.on_cancel_clicked(move || {
+        if let Some(c) = confirm_handle_cancel.upgrade() {
+            let _ = c.hide();
+        }
+    });
+    let _ = confirm.run();
+}
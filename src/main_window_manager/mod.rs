@@ -1,9 +1,16 @@
 // src/main_window_manager/mod.rs
 
 // Déclarer les sous-modules
+mod background;
+mod cash_register_callbacks;
+mod customer_callbacks;
 mod dashboard_callbacks;
+mod deposit_callbacks;
+mod inventory_callbacks;
 mod printer_callbacks;
 mod product_callbacks;
+mod product_import_callbacks;
+mod purchase_callbacks;
 mod reporting_callbacks;
 mod sale_callbacks;
 mod user_callbacks;
@@ -44,6 +51,7 @@ pub fn run(user: &User) -> AppResult<WindowCloseReason> {
     log::info!("Déclenchement du chargement initial des données...");
     main_window.invoke_request_dashboard_data();
     main_window.invoke_request_products();
+    main_window.invoke_request_cash_session();
     if user.role == "Admin" {
         main_window.invoke_request_users();
     }
@@ -88,15 +96,21 @@ fn setup_callbacks(
 
     // Déléguer aux modules spécialisés
     dashboard_callbacks::setup(&main_window_handle);
-    product_callbacks::setup(&main_window_handle);
+    product_callbacks::setup(&main_window_handle, user.id);
     sale_callbacks::setup(&main_window.as_weak(), user.id, user.role == "Admin");
+    customer_callbacks::setup(&main_window_handle, user.id, user.role == "Admin");
+    cash_register_callbacks::setup(&main_window_handle, user.id, user.role == "Admin");
 
     // Configuration des callbacks pour les imprimantes
     printer_callbacks::setup(&main_window_handle);
 
     if user.role == "Admin" {
         user_callbacks::setup(&main_window_handle, user.id);
-        reporting_callbacks::setup(&main_window_handle);
+        reporting_callbacks::setup(&main_window_handle, user.id);
+        deposit_callbacks::setup(&main_window_handle);
+        product_import_callbacks::setup(&main_window_handle, user.id);
+        inventory_callbacks::setup(&main_window_handle, user.id);
+        purchase_callbacks::setup(&main_window_handle, user.id);
     }
 }
 
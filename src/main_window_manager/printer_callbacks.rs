@@ -1,12 +1,24 @@
 // src/main_window_manager/printer_callbacks.rs
 
 use crate::{
-    config::printer_config::{self, PrinterConfig, PrinterType},
+    config::{
+        business_calendar::business_calendar,
+        printer_config::{
+            self, PrinterConfig, PrinterType, SerialFlowControl, SerialParity, SerialSettings,
+            USB_PRINTER_MODELS, UsbPrinterModel, UsbSettings, parse_usb_endpoint, parse_usb_id,
+        },
+    },
+    error::AppError,
     helpers::printer_error_handler::{PrinterError, PrinterErrorHandler},
-    services::printing_service,
-    ui::{self, MainWindow, PrinterUI},
+    models::Receipt,
+    services::{
+        print_queue::{self, PrintJob, PrintJobStatus},
+        printing_service::{self, UsbPrinterDevice},
+    },
+    ui::{self, MainWindow, PrintJobUI, PrinterUI, UsbDeviceUI},
 };
 use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use uuid::Uuid;
 
 /// Convertit une configuration d'imprimante en modèle UI
 fn config_to_ui(config: &PrinterConfig) -> PrinterUI {
@@ -16,6 +28,19 @@ fn config_to_ui(config: &PrinterConfig) -> PrinterUI {
         printer_type: format!("{:?}", config.printer_type).into(),
         paper_width: config.paper_width.to_string().into(),
         is_default: config.is_default,
+        baud_rate: config.serial.baud_rate.to_string().into(),
+        parity: format!("{:?}", config.serial.parity).into(),
+        data_bits: config.serial.data_bits.to_string().into(),
+        stop_bits: config.serial.stop_bits.to_string().into(),
+        flow_control: format!("{:?}", config.serial.flow_control).into(),
+        vendor_id: format!("{:04x}", config.usb.vendor_id).into(),
+        product_id: format!("{:04x}", config.usb.product_id).into(),
+        usb_endpoint: config
+            .usb
+            .endpoint
+            .map(|e| format!("{:02x}", e))
+            .unwrap_or_default()
+            .into(),
     }
 }
 
@@ -32,6 +57,95 @@ fn ui_to_config(ui_printer: &PrinterUI) -> PrinterConfig {
         },
         paper_width: ui_printer.paper_width.parse().unwrap_or(48),
         is_default: ui_printer.is_default,
+        serial: ui_to_serial_settings(ui_printer),
+        usb: ui_to_usb_settings(ui_printer),
+    }
+}
+
+/// Identifiants USB saisis dans le formulaire (validés au préalable)
+fn ui_to_usb_settings(ui_printer: &PrinterUI) -> UsbSettings {
+    let defaults = UsbSettings::default();
+    UsbSettings {
+        vendor_id: parse_usb_id(&ui_printer.vendor_id).unwrap_or(defaults.vendor_id),
+        product_id: parse_usb_id(&ui_printer.product_id).unwrap_or(defaults.product_id),
+        endpoint: parse_usb_endpoint(&ui_printer.usb_endpoint),
+    }
+}
+
+/// Convertit un périphérique USB détecté en modèle UI
+fn usb_device_to_ui(device: &UsbPrinterDevice) -> UsbDeviceUI {
+    UsbDeviceUI {
+        name: device.display_name().into(),
+        vendor_id: format!("{:04x}", device.vendor_id).into(),
+        product_id: format!("{:04x}", device.product_id).into(),
+        endpoint: device
+            .endpoint
+            .map(|e| format!("{:02x}", e))
+            .unwrap_or_default()
+            .into(),
+        paper_width: device
+            .model
+            .map_or(48, |m| m.paper_width)
+            .to_string()
+            .into(),
+    }
+}
+
+/// Convertit un modèle d'imprimante connu en modèle UI
+fn usb_model_to_ui(model: &UsbPrinterModel) -> UsbDeviceUI {
+    UsbDeviceUI {
+        name: model.name.into(),
+        vendor_id: format!("{:04x}", model.vendor_id).into(),
+        product_id: format!("{:04x}", model.product_id).into(),
+        endpoint: "".into(),
+        paper_width: model.paper_width.to_string().into(),
+    }
+}
+
+/// Paramètres de liaison série saisis dans le formulaire (8N1 à 9600 bauds par défaut)
+fn ui_to_serial_settings(ui_printer: &PrinterUI) -> SerialSettings {
+    let defaults = SerialSettings::default();
+    SerialSettings {
+        baud_rate: ui_printer.baud_rate.parse().unwrap_or(defaults.baud_rate),
+        parity: match ui_printer.parity.as_str() {
+            "Odd" => SerialParity::Odd,
+            "Even" => SerialParity::Even,
+            _ => SerialParity::None,
+        },
+        data_bits: ui_printer.data_bits.parse().unwrap_or(defaults.data_bits),
+        stop_bits: ui_printer.stop_bits.parse().unwrap_or(defaults.stop_bits),
+        flow_control: match ui_printer.flow_control.as_str() {
+            "Software" => SerialFlowControl::Software,
+            "Hardware" => SerialFlowControl::Hardware,
+            _ => SerialFlowControl::None,
+        },
+    }
+}
+
+/// Convertit un travail de la file d'impression en modèle UI
+fn print_job_to_ui(job: &PrintJob) -> PrintJobUI {
+    let calendar = business_calendar();
+    let mut status = job.status.label().to_string();
+    if let (PrintJobStatus::Retrying, Some(at)) = (job.status, job.next_attempt_at) {
+        status.push_str(&format!(" à {}", calendar.format_local(at, "%H:%M:%S")));
+    }
+    PrintJobUI {
+        id: job.id.to_string().into(),
+        title: job.title.clone().into(),
+        printer_name: job.printer_name.clone().into(),
+        date: calendar
+            .format_local(job.created_at, "%d/%m/%Y %H:%M")
+            .into(),
+        status: status.into(),
+        attempts: job.attempts as i32,
+        error: job.last_error.clone().unwrap_or_default().into(),
+        is_failed: matches!(
+            job.status,
+            PrintJobStatus::Retrying | PrintJobStatus::Failed
+        ),
+        is_printed: job.status == PrintJobStatus::Printed,
+        can_reprint: job.can_reprint(),
+        can_cancel: job.can_cancel(),
     }
 }
 
@@ -155,9 +269,31 @@ pub fn setup(main_window_handle: &Weak<MainWindow>) {
     setup_clear_notifications_callback(main_window_handle);
     setup_port_suggestions_callback(main_window_handle);
     setup_help_message_callback(main_window_handle);
+    setup_auto_print_callback(main_window_handle);
+    setup_usb_devices_callback(main_window_handle);
+    setup_print_queue_callbacks(main_window_handle);
+
+    // Modèles USB connus, proposés dans le formulaire d'ajout
+    let models: Vec<UsbDeviceUI> = USB_PRINTER_MODELS.iter().map(usb_model_to_ui).collect();
+    let mut model_names: Vec<slint::SharedString> = vec!["Choisir un modèle...".into()];
+    model_names.extend(models.iter().map(|m| m.name.clone()));
+    ui.set_usb_printer_models(ModelRc::new(VecModel::from(models)));
+    ui.set_usb_printer_model_names(ModelRc::new(VecModel::from(model_names)));
 
     // Charger les imprimantes au démarrage
     ui.invoke_load_printers();
+    ui.invoke_load_print_queue();
+
+    // Les travaux en attente (y compris ceux d'une session précédente) sont envoyés en arrière-plan
+    let queue_handle = main_window_handle.clone();
+    print_queue::start_worker(move || {
+        let queue_handle = queue_handle.clone();
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = queue_handle.upgrade() {
+                ui.invoke_load_print_queue();
+            }
+        });
+    });
 }
 
 /// Configure le callback pour charger les imprimantes
@@ -181,6 +317,7 @@ fn setup_load_printers_callback(main_window_handle: &Weak<MainWindow>) {
                         // Créer le modèle et l'assigner à l'interface
                         let model = ModelRc::new(VecModel::from(ui_model));
                         ui.set_printers_model(model);
+                        ui.set_auto_print_receipts(printer_config::auto_print_receipts());
 
                         log::info!("Modèle d'imprimantes chargé avec succès");
                     }
@@ -237,7 +374,18 @@ fn setup_add_printer_callback(main_window_handle: &Weak<MainWindow>) {
                     &new_printer_ui.printer_type,
                     &new_printer_ui.paper_width,
                     &existing_names,
-                ) {
+                )
+                .and_then(|_| {
+                    if new_printer_ui.printer_type.as_str() == "USB" {
+                        PrinterErrorHandler::validate_usb_ids(
+                            &new_printer_ui.vendor_id,
+                            &new_printer_ui.product_id,
+                            &new_printer_ui.usb_endpoint,
+                        )
+                    } else {
+                        Ok(())
+                    }
+                }) {
                     Ok(_) => {
                         // Validation réussie, continuer avec l'ajout
                         add_printer_validated(&add_handle, new_printer_ui, existing_configs);
@@ -609,7 +757,17 @@ fn setup_port_suggestions_callback(main_window_handle: &Weak<MainWindow>) {
         .unwrap()
         .on_get_printer_port_suggestions(move |printer_type| {
             if let Some(ui) = suggestions_handle.upgrade() {
-                let suggestions = PrinterErrorHandler::get_port_suggestions(&printer_type);
+                // Les ports série réellement présents passent avant les exemples génériques
+                let detected_ports = if printer_type.as_str() == "Serial" {
+                    printing_service::list_serial_ports()
+                } else {
+                    Vec::new()
+                };
+                let suggestions = if detected_ports.is_empty() {
+                    PrinterErrorHandler::get_port_suggestions(&printer_type)
+                } else {
+                    detected_ports
+                };
                 let slint_suggestions: Vec<slint::SharedString> =
                     suggestions.into_iter().map(|s| s.into()).collect();
 
@@ -634,6 +792,135 @@ fn setup_help_message_callback(main_window_handle: &Weak<MainWindow>) {
         });
 }
 
+/// Configure le callback de l'impression automatique des tickets
+fn setup_auto_print_callback(main_window_handle: &Weak<MainWindow>) {
+    let auto_print_handle = main_window_handle.clone();
+
+    main_window_handle
+        .upgrade()
+        .unwrap()
+        .on_set_auto_print_receipts(move |enabled| {
+            clear_notifications(&auto_print_handle);
+
+            match printer_config::set_auto_print_receipts(enabled) {
+                Ok(_) => display_success(
+                    &auto_print_handle,
+                    if enabled {
+                        "Les tickets seront imprimés après chaque vente"
+                    } else {
+                        "Impression automatique des tickets désactivée"
+                    },
+                    "🧾",
+                ),
+                Err(e) => {
+                    let error =
+                        PrinterError::SystemError(format!("Erreur lors de la sauvegarde: {}", e));
+                    display_error(&auto_print_handle, &error);
+                    if let Some(ui) = auto_print_handle.upgrade() {
+                        ui.set_auto_print_receipts(!enabled);
+                    }
+                }
+            }
+        });
+}
+
+/// Configure le callback de recherche des imprimantes USB branchées
+fn setup_usb_devices_callback(main_window_handle: &Weak<MainWindow>) {
+    let usb_handle = main_window_handle.clone();
+
+    main_window_handle
+        .upgrade()
+        .unwrap()
+        .on_refresh_usb_devices(move || {
+            if let Some(ui) = usb_handle.upgrade() {
+                let devices = printing_service::list_usb_printers();
+                log::info!("{} imprimante(s) USB détectée(s)", devices.len());
+
+                let model: Vec<UsbDeviceUI> = devices.iter().map(usb_device_to_ui).collect();
+                ui.set_usb_devices(ModelRc::new(VecModel::from(model)));
+            }
+        });
+}
+
+/// Configure les callbacks de la file d'impression (affichage, réimpression, annulation)
+fn setup_print_queue_callbacks(main_window_handle: &Weak<MainWindow>) {
+    let ui = main_window_handle.upgrade().unwrap();
+
+    let load_handle = main_window_handle.clone();
+    ui.on_load_print_queue(move || {
+        if let Some(ui) = load_handle.upgrade() {
+            match print_queue::list_jobs() {
+                Ok(jobs) => {
+                    let model: Vec<PrintJobUI> = jobs.iter().map(print_job_to_ui).collect();
+                    ui.set_print_jobs(ModelRc::new(VecModel::from(model)));
+                }
+                Err(e) => log::error!("Erreur lors du chargement de la file d'impression: {}", e),
+            }
+        }
+    });
+
+    let reprint_handle = main_window_handle.clone();
+    ui.on_reprint_print_job(move |job_id| {
+        clear_notifications(&reprint_handle);
+        let result = Uuid::parse_str(&job_id)
+            .map_err(|_| AppError::ValidationError("Identifiant de travail invalide".to_string()))
+            .and_then(print_queue::reprint);
+        match result {
+            Ok(()) => display_success(
+                &reprint_handle,
+                "Document remis dans la file d'impression",
+                "🖨️",
+            ),
+            Err(e) => display_error(&reprint_handle, &PrinterError::SystemError(e.to_string())),
+        }
+        if let Some(ui) = reprint_handle.upgrade() {
+            ui.invoke_load_print_queue();
+        }
+    });
+
+    let cancel_handle = main_window_handle.clone();
+    ui.on_cancel_print_job(move |job_id| {
+        clear_notifications(&cancel_handle);
+        let result = Uuid::parse_str(&job_id)
+            .map_err(|_| AppError::ValidationError("Identifiant de travail invalide".to_string()))
+            .and_then(print_queue::cancel);
+        match result {
+            Ok(()) => display_success(&cancel_handle, "Travail d'impression annulé", "🗑️"),
+            Err(e) => display_error(&cancel_handle, &PrinterError::SystemError(e.to_string())),
+        }
+        if let Some(ui) = cancel_handle.upgrade() {
+            ui.invoke_load_print_queue();
+        }
+    });
+}
+
+/// Classe une erreur d'impression selon le type d'imprimante pour l'afficher au caissier
+fn printing_error(config: &PrinterConfig, error: &AppError) -> PrinterError {
+    let message = format!("'{}' : {}", config.name, error);
+    match (error, &config.printer_type) {
+        (AppError::Io(_) | AppError::PrintingError(_), PrinterType::Network) => {
+            PrinterError::NetworkError(message)
+        }
+        (AppError::Io(_) | AppError::PrintingError(_), _) => PrinterError::ConnectionError(message),
+        _ => PrinterError::SystemError(message),
+    }
+}
+
+/// Imprime un ticket de caisse sur l'imprimante par défaut et renvoie le nom de celle-ci.
+/// En cas d'échec, le ticket reste dans la file d'impression et sera réimprimé automatiquement.
+pub fn print_receipt_on_default_printer(receipt: &Receipt) -> Result<String, PrinterError> {
+    let printer = get_default_printer().ok_or_else(|| {
+        PrinterError::ConfigurationError(
+            "Aucune imprimante par défaut. Configurez-en une dans les paramètres d'impression"
+                .to_string(),
+        )
+    })?;
+    printing_service::print_receipt(receipt, &printer)
+        .and_then(PrintJob::into_result)
+        .map(|_| printer.name.clone())
+        .map_err(|e| printing_error(&printer, &e))
+}
+
 /// Fonction utilitaire pour obtenir l'imprimante par défaut
 pub fn get_default_printer() -> Option<PrinterConfig> {
     let configs = printer_config::load_printers();
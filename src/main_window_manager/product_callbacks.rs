@@ -1,11 +1,12 @@
 // src/main_window_manager/product_callbacks.rs
 
-use crate::{queries, ui};
+use crate::{config::business_calendar::business_calendar, queries, ui};
 use bigdecimal::Num;
-use slint::{ComponentHandle, Weak};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
 use uuid::Uuid;
 use std::sync::{Arc, Mutex};
 
+use super::background::{self, LoadingState};
 use super::{show_info_dialog, show_error_dialog};
 
 /// Structure pour maintenir l'état des filtres et de la pagination
@@ -33,7 +34,7 @@ impl Default for ProductsState {
 }
 
 /// Configure tous les callbacks liés à la gestion des produits sur la fenêtre principale.
-pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
     // État partagé pour les filtres et la pagination
     let products_state = Arc::new(Mutex::new(ProductsState::default()));
 
@@ -43,22 +44,26 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
         let state = products_state.clone();
         
         move || {
-            if let Some(ui) = products_handle.upgrade() {
-                let current_state = state.lock().unwrap().clone();
-                log::info!("Chargement des produits - Page: {}, Recherche: '{}', Filtre: {:?}", 
-                          current_state.current_page, current_state.search_query, current_state.stock_filter);
-                
-                let params = queries::ProductSearchParams::new()
-                    .with_search(if current_state.search_query.is_empty() {
-                        None
-                    } else {
-                        Some(current_state.search_query.clone())
-                    })
-                    .with_stock_filter(current_state.stock_filter)
-                    .with_sort(current_state.sort_by, current_state.sort_order)
-                    .with_pagination(current_state.current_page, current_state.page_size);
-                
-                match queries::get_products_paginated(params) {
+            let current_state = state.lock().unwrap().clone();
+            log::info!("Chargement des produits - Page: {}, Recherche: '{}', Filtre: {:?}", 
+                      current_state.current_page, current_state.search_query, current_state.stock_filter);
+            
+            let params = queries::ProductSearchParams::new()
+                .with_search(if current_state.search_query.is_empty() {
+                    None
+                } else {
+                    Some(current_state.search_query.clone())
+                })
+                .with_stock_filter(current_state.stock_filter)
+                .with_sort(current_state.sort_by, current_state.sort_order)
+                .with_pagination(current_state.current_page, current_state.page_size);
+            
+            // La requête s'exécute en arrière-plan, le modèle est mis à jour à son retour
+            background::spawn_latest(
+                &products_handle,
+                LoadingState::Products,
+                move || queries::get_products_paginated(params),
+                |ui, result| match result {
                     Ok(paginated_result) => {
                         log::info!("Produits chargés: {} / {}", paginated_result.products.len(), paginated_result.total_count);
                         
@@ -90,8 +95,8 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
                         ui.set_product_current_page(1);
                         ui.set_product_total_pages(1);
                     }
-                }
-            }
+                },
+            );
         }
     };
 
@@ -255,11 +260,15 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
                     let dialog_handle = dialog.as_weak();
                     let load_fn_clone = load_fn.clone();
 
-                    dialog.on_save_clicked(move |name, packaging, stock, price_str| {
+                    dialog.on_save_clicked(move |name, packaging, stock, price_str, cost_str| {
                         if let Some(d) = dialog_handle.upgrade() {
+                            let Ok(cost) = parse_cost_price(&cost_str) else {
+                                d.set_status_message("Le prix de revient est invalide.".into());
+                                return;
+                            };
                             match bigdecimal::BigDecimal::from_str_radix(&price_str, 10) {
                                 Ok(price) => {
-                                    match queries::create_product(name.to_string(), packaging.to_string(), stock, price) {
+                                    match queries::create_product(name.to_string(), packaging.to_string(), stock, price, cost, current_user_id) {
                                         Ok(_) => {
                                             load_fn_clone();
                                             let _ = d.hide();
@@ -298,14 +307,19 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
                             dialog.set_packaging_description(product.packaging_description.into());
                             dialog.set_current_stock(product.stock_in_sale_units);
                             dialog.set_price(product.price_per_sale_unit.to_string().into());
+                            dialog.set_cost_price(product.cost_price.map(|c| c.to_string()).unwrap_or_default().into());
 
                             let dialog_handle = dialog.as_weak();
                             let load_fn_clone = load_fn.clone();
 
-                            dialog.on_save_clicked(move |id, name, packaging, stock, price_str| {
+                            dialog.on_save_clicked(move |id, name, packaging, stock, price_str, cost_str| {
                                 if let Some(d) = dialog_handle.upgrade() {
+                                    let Ok(cost) = parse_cost_price(&cost_str) else {
+                                        d.set_status_message("Le prix de revient est invalide.".into());
+                                        return;
+                                    };
                                     if let (Ok(uuid), Ok(price)) = (Uuid::parse_str(&id), bigdecimal::BigDecimal::from_str_radix(&price_str, 10)) {
-                                        match queries::update_product(uuid, name.to_string(), packaging.to_string(), stock, price) {
+                                        match queries::update_product(uuid, name.to_string(), packaging.to_string(), stock, price, cost, current_user_id) {
                                             Ok(_) => {
                                                 load_fn_clone();
                                                 let _ = d.hide();
@@ -329,6 +343,50 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
         });
     }
 
+    // --- HISTORIQUE DES MOUVEMENTS ET CASSE ---
+    {
+        let load_fn = load_products.clone();
+
+        main_window_handle.upgrade().unwrap().on_stock_history_clicked(move |product_id_str| {
+            let Ok(product_id) = Uuid::parse_str(&product_id_str) else {
+                return;
+            };
+            let Ok(dialog) = ui::StockMovementsDialog::new() else {
+                return;
+            };
+            load_stock_movements(&dialog, product_id);
+
+            let dialog_handle = dialog.as_weak();
+            let load_fn_clone = load_fn.clone();
+            dialog.on_breakage_clicked(move |quantity_str, note| {
+                if let Some(d) = dialog_handle.upgrade() {
+                    let Ok(quantity) = quantity_str.trim().parse::<i32>() else {
+                        d.set_status_message("La quantité cassée est invalide.".into());
+                        return;
+                    };
+                    match queries::record_breakage(product_id, current_user_id, quantity, Some(note.to_string())) {
+                        Ok(_) => {
+                            d.set_breakage_quantity("".into());
+                            d.set_breakage_note("".into());
+                            d.set_status_message("".into());
+                            load_stock_movements(&d, product_id);
+                            load_fn_clone();
+                        }
+                        Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                    }
+                }
+            });
+
+            let dialog_handle_close = dialog.as_weak();
+            dialog.on_close_clicked(move || {
+                if let Some(d) = dialog_handle_close.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+            let _ = dialog.run();
+        });
+    }
+
     // --- SUPPRESSION D'UN PRODUIT ---
     {
         let load_fn = load_products.clone();
@@ -365,7 +423,7 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
                         Ok(false) => {
                             show_info_dialog(
                                 "Suppression impossible",
-                                &format!("Le produit '{}' ne peut pas être supprimé car il est lié à des ventes existantes.", product_name)
+                                &format!("Le produit '{}' ne peut pas être supprimé car il est lié à des ventes, des commandes fournisseur, des inventaires ou des mouvements de stock existants.", product_name)
                             );
                         },
                         Err(e) => {
@@ -396,4 +454,54 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
         // Charger les données
         load_products();
     }
-}
\ No newline at end of file
+}
+
+/// Nombre de mouvements affichés dans l'historique d'un produit.
+const STOCK_HISTORY_LIMIT: i64 = 200;
+
+/// Recharge le stock et les derniers mouvements d'un produit dans le dialogue d'historique.
+fn load_stock_movements(dialog: &ui::StockMovementsDialog, product_id: Uuid) {
+    match queries::get_product_by_id(product_id) {
+        Ok(product) => {
+            dialog.set_product_name(format!("{} ({})", product.name, product.packaging_description).into());
+            dialog.set_current_stock(product.stock_in_sale_units);
+        }
+        Err(e) => {
+            dialog.set_status_message(format!("Erreur: {}", e).into());
+            return;
+        }
+    }
+
+    let calendar = business_calendar();
+    match queries::get_stock_movements(product_id, STOCK_HISTORY_LIMIT) {
+        Ok(movements) => {
+            let model = movements
+                .iter()
+                .map(|(movement, user_name)| ui::StockMovementUI {
+                    date: calendar.format_local(movement.created_at, "%d/%m/%Y %H:%M").into(),
+                    reason: movement.reason().map(|r| r.label()).unwrap_or("?").into(),
+                    quantity: format!("{:+}", movement.quantity_delta).into(),
+                    stock_after: movement.stock_after,
+                    user_name: user_name.clone().unwrap_or_default().into(),
+                    reference: movement.reference.clone().unwrap_or_default().into(),
+                    note: movement.note.clone().unwrap_or_default().into(),
+                    is_outgoing: movement.quantity_delta < 0,
+                })
+                .collect::<Vec<_>>();
+            dialog.set_movements(ModelRc::new(VecModel::from(model)));
+        }
+        Err(e) => {
+            log::error!("Erreur lors du chargement des mouvements de stock: {}", e);
+            dialog.set_status_message(format!("Erreur: {}", e).into());
+        }
+    }
+}
+
+/// Prix de revient saisi dans les formulaires : un champ vide signifie « coût inconnu ».
+fn parse_cost_price(text: &str) -> Result<Option<bigdecimal::BigDecimal>, bigdecimal::ParseBigDecimalError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    bigdecimal::BigDecimal::from_str_radix(text, 10).map(Some)
+}
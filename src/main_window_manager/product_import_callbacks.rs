@@ -0,0 +1,213 @@
+// src/main_window_manager/product_import_callbacks.rs
+
+use super::background::{self, LoadingState};
+use crate::{
+    config::business_calendar::business_calendar,
+    models::ImportAction,
+    queries::{self, ImportPreview},
+    services::product_import_service::{self, ParsedImport},
+    ui,
+};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+/// Nombre d'imports affichés dans l'historique du dialogue.
+const HISTORY_LIMIT: i64 = 10;
+
+/// Lignes de l'aperçu : les erreurs d'abord, puis les créations et mises à jour, dans l'ordre du fichier.
+fn preview_rows(preview: &ImportPreview) -> Vec<ui::ImportRowUI> {
+    let errors = preview.errors.iter().map(|error| ui::ImportRowUI {
+        line: error.line as i32,
+        product: "".into(),
+        action: "Erreur".into(),
+        details: error.message.clone().into(),
+        is_error: true,
+    });
+    let rows = preview.rows.iter().map(|row| ui::ImportRowUI {
+        line: row.line as i32,
+        product: row.product_label().into(),
+        action: row.action.label().into(),
+        details: row.changes.join(" ; ").into(),
+        is_error: false,
+    });
+    errors.chain(rows).collect()
+}
+
+fn preview_summary(preview: &ImportPreview) -> String {
+    let mut summary = format!(
+        "{} : {} création(s), {} mise(s) à jour, {} inchangé(s)",
+        preview.file_name,
+        preview.count(ImportAction::Created),
+        preview.count(ImportAction::Updated),
+        preview.count(ImportAction::Unchanged)
+    );
+    if !preview.errors.is_empty() {
+        summary.push_str(&format!(
+            " — {} ligne(s) en erreur à corriger avant l'import",
+            preview.errors.len()
+        ));
+    }
+    summary
+}
+
+/// Recharge l'historique des imports dans le dialogue, en tâche de fond.
+fn load_imports(main_handle: &Weak<ui::MainWindow>, dialog: &ui::ProductImportDialog) {
+    let dialog_weak = dialog.as_weak();
+    background::spawn(
+        main_handle,
+        None,
+        || queries::get_product_imports(HISTORY_LIMIT),
+        move |_, result| {
+            let Some(dialog) = dialog_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(imports) => {
+                    let calendar = business_calendar();
+                    let model = imports
+                        .iter()
+                        .map(|summary| ui::ProductImportUI {
+                            date: calendar
+                                .format_local(summary.import.created_at, "%d/%m/%Y %H:%M")
+                                .into(),
+                            user_name: summary.user_name.clone().into(),
+                            file_name: summary.import.file_name.clone().into(),
+                            summary: format!(
+                                "{} créé(s), {} mis à jour",
+                                summary.import.rows_created, summary.import.rows_updated
+                            )
+                            .into(),
+                        })
+                        .collect::<Vec<_>>();
+                    dialog.set_imports(ModelRc::new(VecModel::from(model)));
+                }
+                Err(e) => log::error!("Erreur lors du chargement des imports: {}", e),
+            }
+        },
+    );
+}
+
+/// Configure l'import en masse du catalogue (réservé aux administrateurs).
+/// Le fichier est d'abord prévisualisé ; l'import n'est proposé que si toutes les lignes sont valides.
+/// La lecture du fichier, l'aperçu et l'import s'exécutent en tâche de fond.
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_import_products_clicked({
+        let main_handle = main_window_handle.clone();
+        move || {
+            let Ok(dialog) = ui::ProductImportDialog::new() else {
+                return;
+            };
+            load_imports(&main_handle, &dialog);
+
+            // Fichier lu lors de la dernière prévisualisation
+            let parsed: Arc<Mutex<Option<ParsedImport>>> = Arc::new(Mutex::new(None));
+
+            let dialog_handle = dialog.as_weak();
+            let parsed_preview = parsed.clone();
+            let preview_handle = main_handle.clone();
+            dialog.on_preview_clicked(move |path| {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let path = path.trim().trim_matches('"').to_string();
+                d.set_busy(true);
+                d.set_can_import(false);
+                d.set_status_message("".into());
+                *parsed_preview.lock().unwrap() = None;
+
+                let dialog_weak = d.as_weak();
+                let parsed_preview = parsed_preview.clone();
+                background::spawn(
+                    &preview_handle,
+                    Some(LoadingState::Products),
+                    move || {
+                        product_import_service::read_import_file(Path::new(&path)).and_then(
+                            |file| {
+                                queries::preview_product_import(&file)
+                                    .map(|preview| (file, preview))
+                            },
+                        )
+                    },
+                    move |_, result| {
+                        let Some(d) = dialog_weak.upgrade() else {
+                            return;
+                        };
+                        d.set_busy(false);
+                        match result {
+                            Ok((file, preview)) => {
+                                d.set_rows(ModelRc::new(VecModel::from(preview_rows(&preview))));
+                                d.set_summary(preview_summary(&preview).into());
+                                d.set_can_import(preview.can_apply());
+                                *parsed_preview.lock().unwrap() = Some(file);
+                            }
+                            Err(e) => {
+                                d.set_rows(ModelRc::new(VecModel::default()));
+                                d.set_summary("".into());
+                                d.set_status_message(format!("Erreur: {}", e).into());
+                            }
+                        }
+                    },
+                );
+            });
+
+            let dialog_handle = dialog.as_weak();
+            let import_handle = main_handle.clone();
+            dialog.on_import_clicked(move || {
+                let Some(d) = dialog_handle.upgrade() else {
+                    return;
+                };
+                let Some(file) = parsed.lock().unwrap().take() else {
+                    return;
+                };
+                d.set_can_import(false);
+                d.set_busy(true);
+
+                let dialog_weak = d.as_weak();
+                background::spawn(
+                    &import_handle,
+                    Some(LoadingState::Products),
+                    move || queries::apply_product_import(&file, current_user_id),
+                    move |main_ui, result| {
+                        let Some(d) = dialog_weak.upgrade() else {
+                            return;
+                        };
+                        d.set_busy(false);
+                        match result {
+                            Ok(import) => {
+                                d.set_rows(ModelRc::new(VecModel::default()));
+                                d.set_summary(
+                                    format!(
+                                        "Import terminé : {} produit(s) créé(s), {} mis à jour, {} inchangé(s).",
+                                        import.rows_created, import.rows_updated, import.rows_unchanged
+                                    )
+                                    .into(),
+                                );
+                                d.set_file_path("".into());
+                                d.set_status_message("".into());
+                                load_imports(&main_ui.as_weak(), &d);
+                                main_ui.invoke_request_products();
+                            }
+                            // Le catalogue a pu changer depuis l'aperçu : il faut prévisualiser à nouveau
+                            Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                        }
+                    },
+                );
+            });
+
+            let dialog_handle_close = dialog.as_weak();
+            dialog.on_close_clicked(move || {
+                if let Some(d) = dialog_handle_close.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+
+            let _ = dialog.run();
+        }
+    });
+}
@@ -0,0 +1,647 @@
+// src/main_window_manager/purchase_callbacks.rs
+
+use crate::{
+    config::business_calendar::business_calendar,
+    models::{
+        CreatePurchaseOrderData, CreatePurchaseOrderLineData, Product, PurchaseOrderStatus,
+        ReceiveLineData, Supplier,
+    },
+    queries, ui,
+};
+use bigdecimal::{BigDecimal, Num};
+use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use super::{
+    background::{self, LoadingState},
+    show_error_dialog, show_info_dialog,
+};
+
+/// Ligne saisie dans le dialogue de nouvelle commande.
+#[derive(Debug, Clone)]
+struct DraftLine {
+    product: Product,
+    quantity: i32,
+    unit_cost: BigDecimal,
+}
+
+/// Saisie de réception pour une ligne de commande.
+#[derive(Debug, Clone)]
+struct ReceiptInput {
+    line_id: Uuid,
+    quantity: String,
+    unit_cost: String,
+}
+
+/// Convertit un bon de commande en modèle UI.
+fn order_to_ui(summary: &queries::PurchaseOrderSummary) -> ui::PurchaseOrderUI {
+    let status = summary.order.status();
+    ui::PurchaseOrderUI {
+        id: summary.order.id.to_string().into(),
+        po_number: summary.order.po_number.clone().into(),
+        supplier_name: summary.supplier_name.clone().into(),
+        date: business_calendar()
+            .format_local(summary.order.order_date, "%d/%m/%Y")
+            .into(),
+        status: status
+            .map(|s| s.label().to_string())
+            .unwrap_or_else(|| summary.order.status.clone())
+            .into(),
+        lines_count: summary.lines_count as i32,
+        total_cost: format!("{:.0} XAF", summary.total_cost).into(),
+        can_send: status == Some(PurchaseOrderStatus::Draft),
+        can_receive: status.is_some_and(|s| s.can_receive()),
+        can_delete: status == Some(PurchaseOrderStatus::Draft),
+    }
+}
+
+fn supplier_to_ui(supplier: &Supplier) -> ui::SupplierUI {
+    ui::SupplierUI {
+        id: supplier.id.to_string().into(),
+        name: supplier.name.clone().into(),
+        phone: supplier.phone.clone().unwrap_or_default().into(),
+        address: supplier.address.clone().unwrap_or_default().into(),
+    }
+}
+
+fn draft_lines_to_ui(lines: &[DraftLine]) -> Vec<ui::PurchaseOrderLineUI> {
+    lines
+        .iter()
+        .map(|line| ui::PurchaseOrderLineUI {
+            id: line.product.id.to_string().into(),
+            product_name: format!(
+                "{} ({})",
+                line.product.name, line.product.packaging_description
+            )
+            .into(),
+            quantity_ordered: line.quantity,
+            quantity_received: 0,
+            quantity_remaining: line.quantity,
+            unit_cost: format!("{:.0}", line.unit_cost).into(),
+            line_total: format!("{:.0}", &line.unit_cost * BigDecimal::from(line.quantity)).into(),
+            quantity_to_receive: "".into(),
+            received_cost: "".into(),
+        })
+        .collect()
+}
+
+/// Configure les callbacks de la vue des achats (réservée aux administrateurs).
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
+    let status_filter: Arc<Mutex<Option<PurchaseOrderStatus>>> = Arc::new(Mutex::new(None));
+
+    // Fonction pour charger les commandes et les fournisseurs
+    let load_purchases = {
+        let handle = main_window_handle.clone();
+        let filter = status_filter.clone();
+        move || {
+            let current_filter = *filter.lock().unwrap();
+            background::spawn_latest(
+                &handle,
+                LoadingState::Purchases,
+                move || {
+                    (
+                        queries::get_purchase_orders(current_filter),
+                        queries::get_suppliers(),
+                    )
+                },
+                |ui, (orders, suppliers)| {
+                    match orders {
+                        Ok(orders) => {
+                            let model = orders.iter().map(order_to_ui).collect::<Vec<_>>();
+                            ui.set_purchase_orders_model(ModelRc::new(VecModel::from(model)));
+                        }
+                        Err(e) => {
+                            log::error!("Erreur lors du chargement des commandes: {}", e);
+                            ui.set_purchase_orders_model(ModelRc::new(VecModel::default()));
+                        }
+                    }
+                    match suppliers {
+                        Ok(suppliers) => {
+                            let model = suppliers.iter().map(supplier_to_ui).collect::<Vec<_>>();
+                            ui.set_suppliers_model(ModelRc::new(VecModel::from(model)));
+                        }
+                        Err(e) => {
+                            log::error!("Erreur lors du chargement des fournisseurs: {}", e);
+                            ui.set_suppliers_model(ModelRc::new(VecModel::default()));
+                        }
+                    }
+                },
+            );
+        }
+    };
+
+    let ui = main_window_handle.upgrade().unwrap();
+
+    ui.on_request_purchases({
+        let load_fn = load_purchases.clone();
+        move || load_fn()
+    });
+
+    ui.on_filter_purchase_orders({
+        let filter = status_filter.clone();
+        let load_fn = load_purchases.clone();
+        move |index| {
+            *filter.lock().unwrap() = usize::try_from(index - 1)
+                .ok()
+                .and_then(|i| PurchaseOrderStatus::ALL.get(i).copied());
+            load_fn();
+        }
+    });
+
+    // --- AJOUT D'UN FOURNISSEUR ---
+    ui.on_add_supplier_clicked({
+        let load_fn = load_purchases.clone();
+        move || {
+            if let Ok(dialog) = ui::SupplierDialog::new() {
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+                dialog.on_save_clicked(move |name, phone, address| {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        match queries::create_supplier(
+                            name.to_string(),
+                            Some(phone.to_string()),
+                            Some(address.to_string()),
+                        ) {
+                            Ok(_) => {
+                                load_fn_clone();
+                                let _ = d.hide();
+                            }
+                            Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // --- ÉDITION D'UN FOURNISSEUR ---
+    ui.on_edit_supplier_clicked({
+        let load_fn = load_purchases.clone();
+        move |supplier_id_str| {
+            let Ok(supplier_id) = Uuid::parse_str(&supplier_id_str) else {
+                return;
+            };
+            let supplier = match queries::get_suppliers()
+                .map(|list| list.into_iter().find(|s| s.id == supplier_id))
+            {
+                Ok(Some(s)) => s,
+                Ok(None) => return,
+                Err(e) => {
+                    show_error_dialog("Erreur", &format!("Fournisseur introuvable: {}", e));
+                    return;
+                }
+            };
+            if let Ok(dialog) = ui::SupplierDialog::new() {
+                dialog.set_dialog_title("Modifier le Fournisseur".into());
+                dialog.set_supplier_name(supplier.name.into());
+                dialog.set_phone(supplier.phone.unwrap_or_default().into());
+                dialog.set_address(supplier.address.unwrap_or_default().into());
+
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+                dialog.on_save_clicked(move |name, phone, address| {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        match queries::update_supplier(
+                            supplier_id,
+                            name.to_string(),
+                            Some(phone.to_string()),
+                            Some(address.to_string()),
+                        ) {
+                            Ok(_) => {
+                                load_fn_clone();
+                                let _ = d.hide();
+                            }
+                            Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // --- NOUVELLE COMMANDE FOURNISSEUR ---
+    ui.on_new_purchase_order_clicked({
+        let load_fn = load_purchases.clone();
+        move || {
+            let suppliers = match queries::get_suppliers() {
+                Ok(s) => s,
+                Err(e) => {
+                    show_error_dialog("Erreur", &format!("{}", e));
+                    return;
+                }
+            };
+            let products = match queries::get_all_products() {
+                Ok(p) => p,
+                Err(e) => {
+                    show_error_dialog("Erreur", &format!("{}", e));
+                    return;
+                }
+            };
+            let Ok(dialog) = ui::PurchaseOrderDialog::new() else {
+                return;
+            };
+
+            let supplier_names = suppliers
+                .iter()
+                .map(|s| s.name.clone().into())
+                .collect::<Vec<slint::SharedString>>();
+            let product_names = products
+                .iter()
+                .map(|p| format!("{} ({})", p.name, p.packaging_description).into())
+                .collect::<Vec<slint::SharedString>>();
+            dialog.set_supplier_names(ModelRc::new(VecModel::from(supplier_names)));
+            dialog.set_product_names(ModelRc::new(VecModel::from(product_names)));
+
+            let lines: Arc<Mutex<Vec<DraftLine>>> = Arc::new(Mutex::new(Vec::new()));
+            let refresh_lines = {
+                let lines = lines.clone();
+                move |d: &ui::PurchaseOrderDialog| {
+                    let lines = lines.lock().unwrap();
+                    let total: BigDecimal = lines
+                        .iter()
+                        .map(|l| &l.unit_cost * BigDecimal::from(l.quantity))
+                        .sum();
+                    d.set_lines(ModelRc::new(VecModel::from(draft_lines_to_ui(&lines))));
+                    d.set_total_cost(format!("{:.0}", total).into());
+                }
+            };
+
+            dialog.on_add_line({
+                let dialog_weak = dialog.as_weak();
+                let lines = lines.clone();
+                let refresh_lines = refresh_lines.clone();
+                move |product_index, quantity_str, cost_str| {
+                    let Some(d) = dialog_weak.upgrade() else {
+                        return;
+                    };
+                    let Some(product) = usize::try_from(product_index)
+                        .ok()
+                        .and_then(|i| products.get(i))
+                    else {
+                        d.set_status_message("Sélectionnez un produit.".into());
+                        return;
+                    };
+                    let quantity = match quantity_str.trim().parse::<i32>() {
+                        Ok(q) if q > 0 => q,
+                        _ => {
+                            d.set_status_message("La quantité est invalide.".into());
+                            return;
+                        }
+                    };
+                    let unit_cost = match BigDecimal::from_str_radix(cost_str.trim(), 10) {
+                        Ok(c) if c >= BigDecimal::from(0) => c,
+                        _ => {
+                            d.set_status_message("Le prix d'achat est invalide.".into());
+                            return;
+                        }
+                    };
+                    {
+                        let mut lines = lines.lock().unwrap();
+                        // Un même produit n'apparaît qu'une fois : la quantité est cumulée
+                        match lines.iter_mut().find(|l| l.product.id == product.id) {
+                            Some(existing) => {
+                                existing.quantity += quantity;
+                                existing.unit_cost = unit_cost;
+                            }
+                            None => lines.push(DraftLine {
+                                product: product.clone(),
+                                quantity,
+                                unit_cost,
+                            }),
+                        }
+                    }
+                    d.set_status_message("".into());
+                    refresh_lines(&d);
+                }
+            });
+
+            dialog.on_remove_line({
+                let dialog_weak = dialog.as_weak();
+                let lines = lines.clone();
+                let refresh_lines = refresh_lines.clone();
+                move |index| {
+                    if let Some(d) = dialog_weak.upgrade() {
+                        {
+                            let mut lines = lines.lock().unwrap();
+                            if let Ok(index) = usize::try_from(index)
+                                && index < lines.len()
+                            {
+                                lines.remove(index);
+                            }
+                        }
+                        refresh_lines(&d);
+                    }
+                }
+            });
+
+            dialog.on_save_clicked({
+                let dialog_weak = dialog.as_weak();
+                let lines = lines.clone();
+                let load_fn = load_fn.clone();
+                move |supplier_index, note| {
+                    let Some(d) = dialog_weak.upgrade() else {
+                        return;
+                    };
+                    let Some(supplier) = usize::try_from(supplier_index)
+                        .ok()
+                        .and_then(|i| suppliers.get(i))
+                    else {
+                        d.set_status_message("Sélectionnez un fournisseur.".into());
+                        return;
+                    };
+                    let data = CreatePurchaseOrderData {
+                        supplier_id: supplier.id,
+                        user_id: current_user_id,
+                        note: Some(note.to_string()),
+                        lines: lines
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .map(|l| CreatePurchaseOrderLineData {
+                                product_id: l.product.id,
+                                quantity: l.quantity,
+                                unit_cost: l.unit_cost.clone(),
+                            })
+                            .collect(),
+                    };
+                    match queries::create_purchase_order(data) {
+                        Ok(order) => {
+                            load_fn();
+                            let _ = d.hide();
+                            show_info_dialog(
+                                "Commande enregistrée",
+                                &format!(
+                                    "Le bon de commande {} a été créé en brouillon.",
+                                    order.po_number
+                                ),
+                            );
+                        }
+                        Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                    }
+                }
+            });
+
+            let dialog_handle_cancel = dialog.as_weak();
+            dialog.on_cancel_clicked(move || {
+                if let Some(d) = dialog_handle_cancel.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+
+            let _ = dialog.run();
+        }
+    });
+
+    // --- ENVOI AU FOURNISSEUR ---
+    ui.on_send_purchase_order_clicked({
+        let load_fn = load_purchases.clone();
+        move |order_id_str| {
+            let Ok(order_id) = Uuid::parse_str(&order_id_str) else {
+                return;
+            };
+            match queries::mark_purchase_order_sent(order_id) {
+                Ok(_) => load_fn(),
+                Err(e) => show_error_dialog("Erreur", &format!("{}", e)),
+            }
+        }
+    });
+
+    // --- SUPPRESSION D'UN BROUILLON ---
+    ui.on_delete_purchase_order_clicked({
+        let load_fn = load_purchases.clone();
+        move |order_id_str| {
+            let Ok(order_id) = Uuid::parse_str(&order_id_str) else {
+                return;
+            };
+            if let Ok(dialog) = ui::ConfirmDialog::new() {
+                dialog.set_dialog_title("Supprimer la Commande".into());
+                dialog.set_message(
+                    "Êtes-vous sûr de vouloir supprimer ce bon de commande en brouillon ?".into(),
+                );
+                let dialog_handle = dialog.as_weak();
+                let load_fn_clone = load_fn.clone();
+                dialog.on_ok_clicked(move || {
+                    if let Some(d) = dialog_handle.upgrade() {
+                        let _ = d.hide();
+                        match queries::delete_purchase_order(order_id) {
+                            Ok(_) => load_fn_clone(),
+                            Err(e) => show_error_dialog("Erreur", &format!("{}", e)),
+                        }
+                    }
+                });
+                let dialog_handle_cancel = dialog.as_weak();
+                dialog.on_cancel_clicked(move || {
+                    if let Some(d) = dialog_handle_cancel.upgrade() {
+                        let _ = d.hide();
+                    }
+                });
+                let _ = dialog.run();
+            }
+        }
+    });
+
+    // --- DÉTAILS ET RÉCEPTION D'UNE COMMANDE ---
+    ui.on_view_purchase_order_clicked({
+        let load_fn = load_purchases.clone();
+        let main_handle = main_window_handle.clone();
+        move |order_id_str| {
+            let Ok(order_id) = Uuid::parse_str(&order_id_str) else {
+                return;
+            };
+            let Ok(dialog) = ui::PurchaseOrderDetailsDialog::new() else {
+                return;
+            };
+            let inputs: Arc<Mutex<Vec<ReceiptInput>>> = Arc::new(Mutex::new(Vec::new()));
+
+            // Charge (ou recharge après réception) la commande dans le dialogue
+            let load_details = {
+                let inputs = inputs.clone();
+                move |d: &ui::PurchaseOrderDetailsDialog| -> bool {
+                    let details = match queries::get_purchase_order_details(order_id) {
+                        Ok(details) => details,
+                        Err(e) => {
+                            show_error_dialog("Erreur", &format!("{}", e));
+                            return false;
+                        }
+                    };
+                    let total_cost: BigDecimal = details
+                        .lines
+                        .iter()
+                        .map(|(l, _)| &l.unit_cost * BigDecimal::from(l.quantity_ordered))
+                        .sum();
+                    let summary = queries::PurchaseOrderSummary {
+                        order: details.order.clone(),
+                        supplier_name: details.supplier.name.clone(),
+                        lines_count: details.lines.len() as i64,
+                        total_cost,
+                    };
+
+                    let mut new_inputs = Vec::new();
+                    let lines_ui = details
+                        .lines
+                        .iter()
+                        .map(|(line, product)| {
+                            // Par défaut, on propose de réceptionner tout le reste au prix commandé
+                            let input = ReceiptInput {
+                                line_id: line.id,
+                                quantity: line.quantity_remaining().to_string(),
+                                unit_cost: format!("{:.0}", line.unit_cost),
+                            };
+                            let line_ui = ui::PurchaseOrderLineUI {
+                                id: line.id.to_string().into(),
+                                product_name: format!(
+                                    "{} ({})",
+                                    product.name, product.packaging_description
+                                )
+                                .into(),
+                                quantity_ordered: line.quantity_ordered,
+                                quantity_received: line.quantity_received,
+                                quantity_remaining: line.quantity_remaining(),
+                                unit_cost: format!("{:.0} XAF", line.unit_cost).into(),
+                                line_total: format!(
+                                    "{:.0} XAF",
+                                    &line.unit_cost * BigDecimal::from(line.quantity_ordered)
+                                )
+                                .into(),
+                                quantity_to_receive: input.quantity.clone().into(),
+                                received_cost: input.unit_cost.clone().into(),
+                            };
+                            new_inputs.push(input);
+                            line_ui
+                        })
+                        .collect::<Vec<_>>();
+                    *inputs.lock().unwrap() = new_inputs;
+
+                    d.set_order(order_to_ui(&summary));
+                    d.set_supplier_info(
+                        [details.supplier.phone, details.supplier.address]
+                            .into_iter()
+                            .flatten()
+                            .collect::<Vec<_>>()
+                            .join(" — ")
+                            .into(),
+                    );
+                    d.set_note(details.order.note.unwrap_or_default().into());
+                    d.set_lines(ModelRc::new(VecModel::from(lines_ui)));
+                    true
+                }
+            };
+
+            if !load_details(&dialog) {
+                return;
+            }
+
+            dialog.on_quantity_edited({
+                let inputs = inputs.clone();
+                move |index, text| {
+                    if let Ok(i) = usize::try_from(index)
+                        && let Some(input) = inputs.lock().unwrap().get_mut(i)
+                    {
+                        input.quantity = text.to_string();
+                    }
+                }
+            });
+
+            dialog.on_cost_edited({
+                let inputs = inputs.clone();
+                move |index, text| {
+                    if let Ok(i) = usize::try_from(index)
+                        && let Some(input) = inputs.lock().unwrap().get_mut(i)
+                    {
+                        input.unit_cost = text.to_string();
+                    }
+                }
+            });
+
+            dialog.on_receive_clicked({
+                let dialog_weak = dialog.as_weak();
+                let inputs = inputs.clone();
+                let load_fn = load_fn.clone();
+                let main_handle = main_handle.clone();
+                move || {
+                    let Some(d) = dialog_weak.upgrade() else {
+                        return;
+                    };
+                    let mut received = Vec::new();
+                    for input in inputs.lock().unwrap().iter() {
+                        let quantity = if input.quantity.trim().is_empty() {
+                            0
+                        } else {
+                            match input.quantity.trim().parse::<i32>() {
+                                Ok(q) => q,
+                                Err(_) => {
+                                    d.set_status_message("Une quantité est invalide.".into());
+                                    return;
+                                }
+                            }
+                        };
+                        let unit_cost = if input.unit_cost.trim().is_empty() {
+                            None
+                        } else {
+                            match BigDecimal::from_str_radix(input.unit_cost.trim(), 10) {
+                                Ok(c) => Some(c),
+                                Err(_) => {
+                                    d.set_status_message("Un prix d'achat est invalide.".into());
+                                    return;
+                                }
+                            }
+                        };
+                        received.push(ReceiveLineData {
+                            line_id: input.line_id,
+                            quantity,
+                            unit_cost,
+                        });
+                    }
+
+                    match queries::receive_purchase_order(order_id, received, current_user_id) {
+                        Ok(order) => {
+                            d.set_status_message("".into());
+                            load_details(&d);
+                            load_fn();
+                            if let Some(main_ui) = main_handle.upgrade() {
+                                main_ui.invoke_request_products();
+                                main_ui.invoke_refresh_dashboard();
+                            }
+                            show_info_dialog(
+                                "Réception enregistrée",
+                                &format!(
+                                    "Commande {} : {}",
+                                    order.po_number,
+                                    order.status().map(|s| s.label()).unwrap_or_default()
+                                ),
+                            );
+                        }
+                        Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+                    }
+                }
+            });
+
+            let dialog_handle_close = dialog.as_weak();
+            dialog.on_close_clicked(move || {
+                if let Some(d) = dialog_handle_close.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+
+            let _ = dialog.run();
+        }
+    });
+
+    // Chargement initial
+    load_purchases();
+}
@@ -1,98 +1,231 @@
 // src/main_window_manager/reporting_callbacks.rs
 
+use crate::config::business_calendar::{DateRange, business_calendar};
+use crate::models::CommissionKind;
 use crate::{queries, services::report_generator_service, ui};
-use chrono::{Datelike, Duration, TimeZone, Utc};
-use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel, Weak};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use uuid::Uuid;
 
-use super::{show_error_dialog, show_info_dialog};
+use super::{
+    background::{self, LoadingState},
+    show_error_dialog, show_info_dialog,
+};
 
 // État pour garder en mémoire les dernières données du rapport généré,
 // afin de pouvoir les exporter sans refaire de requête.
 #[derive(Debug, Clone, Default)]
 struct ReportState {
     data: queries::ReportData,
+    /// Dernière période demandée, rechargée après une vente.
+    range: Option<DateRange>,
+    comparison: queries::ComparisonMode,
 }
 
 /// Configure tous les callbacks pour la vue des rapports.
 /// Cette fonction n'est appelée que si l'utilisateur est un administrateur.
-pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
+pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
     // L'état est partagé entre tous les callbacks de ce module.
     let report_state = Arc::new(Mutex::new(ReportState::default()));
     let ui = main_window_handle.upgrade().unwrap();
 
-    // --- Callback principal pour charger les données ---
-    ui.on_request_report_data({
+    // --- Chargement des données pour une plage de dates ---
+    let load_report = {
         let handle = main_window_handle.clone();
         let state = report_state.clone();
-        move |period| {
-            if let Some(ui) = handle.upgrade() {
-                ui.set_report_is_loading(true);
-                // Il est bon de forcer un redessinage pour que l'indicateur de chargement apparaisse immédiatement.
-                ui.window().request_redraw();
-
-                // Calculer la plage de dates en fonction de la période demandée
-                let end_date = Utc::now();
-                let start_date = match period.as_str() {
-                    "7d" => end_date - Duration::days(7),
-                    "30d" => end_date - Duration::days(30),
-                    _ => {
-                        // Par défaut, "year"
-                        let year_start_date = Utc::now()
-                            .date_naive()
-                            .with_day(1)
-                            .unwrap()
-                            .with_month(1)
-                            .unwrap();
-                        // Convertir en NaiveDateTime avec minuit comme heure
-                        let year_start_datetime = year_start_date.and_hms_opt(0, 0, 0).unwrap();
-
-                        // Convertir en DateTime<Utc>
-                        Utc.from_utc_datetime(&year_start_datetime)
-                        // year_start.and_hms_opt(0, 0, 0).unwrap()
-                    }
-                };
-
-                log::info!(
-                    "Chargement du rapport pour la période: {} -> {}",
-                    start_date,
-                    end_date
-                );
+        move |range: DateRange| {
+            log::info!(
+                "Chargement du rapport pour la période: {} -> {}",
+                range.start,
+                range.end
+            );
+            let comparison = {
+                let mut state = state.lock().unwrap();
+                state.range = Some(range);
+                state.comparison
+            };
 
-                // Appeler la requête pour obtenir les données
-                match queries::get_report_data(start_date, end_date) {
+            // La requête s'exécute en arrière-plan ; l'indicateur de chargement est géré par l'exécuteur
+            let state = state.clone();
+            background::spawn_latest(
+                &handle,
+                LoadingState::Reports,
+                move || queries::get_report_data(range, comparison),
+                move |ui, result| match result {
                     Ok(data) => {
                         // Mettre à jour l'état partagé avec les nouvelles données
                         state.lock().unwrap().data = data.clone();
 
+                        let calendar = business_calendar();
+                        let totals = &data.totals;
+                        let previous = data.comparison.as_ref().map(|c| &c.totals);
+                        let period_label = match &data.comparison {
+                            Some(comparison) => format!(
+                                "Période {} — {} : {}",
+                                calendar.format_range(&data.range),
+                                comparison.mode.label(),
+                                calendar.format_range(&comparison.range)
+                            ),
+                            None => format!("Période {}", calendar.format_range(&data.range)),
+                        };
+
                         // Transformer les données pour l'UI
                         let kpis = vec![
                             ui::ReportKPI {
                                 title: "Chiffre d'Affaires".into(),
-                                value: format!("{} XAF", data.total_revenue).into(),
+                                value: format!("{} XAF", totals.total_revenue).into(),
                                 icon: "💰".into(),
+                                comparison: comparison_line(
+                                    &totals.total_revenue,
+                                    previous.map(|p| p.total_revenue.clone()),
+                                    " XAF",
+                                ),
                             },
                             ui::ReportKPI {
                                 title: "Ventes Totales".into(),
-                                value: data.total_sales.to_string().into(),
+                                value: totals.total_sales.to_string().into(),
                                 icon: "📈".into(),
+                                comparison: comparison_line(
+                                    &BigDecimal::from(totals.total_sales),
+                                    previous.map(|p| BigDecimal::from(p.total_sales)),
+                                    "",
+                                ),
                             },
                             ui::ReportKPI {
                                 title: "Panier Moyen".into(),
-                                value: if data.total_sales > 0 {
-                                    format!(
-                                        "{:.0} XAF",
-                                        &data.total_revenue
-                                            / bigdecimal::BigDecimal::from(data.total_sales)
-                                    )
-                                    .into()
-                                } else {
-                                    "0 XAF".into()
-                                },
+                                value: format!("{:.0} XAF", totals.average_basket()).into(),
                                 icon: "🛒".into(),
+                                comparison: comparison_line(
+                                    &totals.average_basket(),
+                                    previous.map(|p| p.average_basket()),
+                                    " XAF",
+                                ),
+                            },
+                            ui::ReportKPI {
+                                title: "Retours & Annulations".into(),
+                                value: format!(
+                                    "{:.0} XAF — {} annulée(s)",
+                                    totals.total_returns, totals.voided_sales
+                                )
+                                .into(),
+                                icon: "↩️".into(),
+                                comparison: comparison_line(
+                                    &totals.total_returns,
+                                    previous.map(|p| p.total_returns.clone()),
+                                    " XAF",
+                                ),
+                            },
+                            ui::ReportKPI {
+                                title: "Marge Brute".into(),
+                                value: format!(
+                                    "{:.0} XAF — {}",
+                                    totals.margin.margin(),
+                                    format_rate(&totals.margin)
+                                )
+                                .into(),
+                                icon: "📊".into(),
+                                comparison: comparison_line(
+                                    &totals.margin.margin(),
+                                    previous.map(|p| p.margin.margin()),
+                                    " XAF",
+                                ),
                             },
                         ];
 
+                        let margin_rows = |lines: &[queries::MarginLine]| {
+                            let rows = lines
+                                .iter()
+                                .map(|m| ui::MarginLineUI {
+                                    label: m.label.clone().into(),
+                                    revenue: format!("{:.0} XAF", &m.revenue + &m.uncosted_revenue)
+                                        .into(),
+                                    margin: format!("{:.0} XAF", m.margin()).into(),
+                                    rate: format_rate(m).into(),
+                                })
+                                .collect::<Vec<_>>();
+                            ModelRc::new(VecModel::from(rows))
+                        };
+                        ui.set_report_margins_by_product(margin_rows(&data.margins.by_product));
+                        ui.set_report_margins_by_packaging(margin_rows(&data.margins.by_packaging));
+                        ui.set_report_margins_by_seller(margin_rows(&data.margins.by_seller));
+
+                        let sellers = data
+                            .sellers
+                            .iter()
+                            .map(|s| ui::SellerPerformanceUI {
+                                seller_id: s.seller_id.to_string().into(),
+                                name: s.seller_name.clone().into(),
+                                revenue: format!("{:.0} XAF", s.revenue).into(),
+                                activity: format!(
+                                    "{} vente(s), panier moyen {:.0} XAF, {} unité(s), {} jour(s) d'activité",
+                                    s.sales_count,
+                                    s.average_basket(),
+                                    s.units,
+                                    s.active_days
+                                )
+                                .into(),
+                                rule: s
+                                    .rule
+                                    .as_ref()
+                                    .map(|rule| rule.describe())
+                                    .unwrap_or_else(|| "Aucune règle de commission".to_string())
+                                    .into(),
+                                commission: format!("{:.0} XAF", s.commission).into(),
+                            })
+                            .collect::<Vec<_>>();
+                        ui.set_report_sellers(ModelRc::new(VecModel::from(sellers)));
+
+                        let deposits = data
+                            .deposits
+                            .iter()
+                            .map(|d| ui::DepositReportUI {
+                                packaging: d.packaging_description.clone().into(),
+                                movements: format!(
+                                    "{} sortis / {} rendus",
+                                    d.quantity_out, d.quantity_returned
+                                )
+                                .into(),
+                                net_amount: format!("{:.0} XAF", d.net_amount).into(),
+                                empties: format!(
+                                    "{} en stock — {} non rendus",
+                                    d.empties_in_stock, d.empties_outstanding
+                                )
+                                .into(),
+                            })
+                            .collect::<Vec<_>>();
+
+                        // Les barres sont proportionnelles au meilleur regroupement de la période
+                        let max_revenue = data
+                            .timeseries
+                            .iter()
+                            .map(|p| to_f64(&p.revenue))
+                            .fold(0.0, f64::max);
+                        let timeseries = data
+                            .timeseries
+                            .iter()
+                            .map(|p| ui::TimeseriesPointUI {
+                                label: data.granularity.bucket_label(p.bucket_start).into(),
+                                value: compact_amount(&p.revenue).into(),
+                                details: format!(
+                                    "{:.0} XAF — {} vente(s), {} unité(s), panier moyen {:.0} XAF, marge {:.0} XAF",
+                                    p.revenue,
+                                    p.sales_count,
+                                    p.units,
+                                    p.average_basket(),
+                                    p.margin
+                                )
+                                .into(),
+                                ratio: if max_revenue > 0.0 {
+                                    (to_f64(&p.revenue) / max_revenue).max(0.0) as f32
+                                } else {
+                                    0.0
+                                },
+                            })
+                            .collect::<Vec<_>>();
+
                         let top_products = data
                             .top_products
                             .into_iter()
@@ -107,62 +240,338 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>) {
                         // Mettre à jour les propriétés de l'UI
                         ui.set_report_kpis(ModelRc::new(VecModel::from(kpis)));
                         ui.set_report_top_products(ModelRc::new(VecModel::from(top_products)));
+                        ui.set_report_deposits(ModelRc::new(VecModel::from(deposits)));
+                        ui.set_report_period_label(period_label.into());
+                        ui.set_report_timeseries(ModelRc::new(VecModel::from(timeseries)));
+                        ui.set_report_timeseries_title(
+                            format!("Chiffre d'affaires par {}", data.granularity.label()).into(),
+                        );
                     }
                     Err(e) => {
                         show_error_dialog("Erreur de Rapport", &e.to_string());
                     }
-                }
-                ui.set_report_is_loading(false);
+                },
+            );
+        }
+    };
+
+    // --- Périodes prédéfinies, évaluées dans le calendrier commercial du dépôt ---
+    ui.on_request_report_data({
+        let load_report = load_report.clone();
+        move |period| {
+            let calendar = business_calendar();
+            let today = calendar.today();
+            let range = match period.as_str() {
+                "today" => calendar.day(today),
+                "week" => calendar.iso_week(today),
+                "month" => calendar.month(today),
+                _ => calendar.year(today), // Par défaut, "year"
+            };
+            load_report(range);
+        }
+    });
+
+    // --- Rechargement de la période affichée (après une vente par exemple) ---
+    ui.on_refresh_report({
+        let load_report = load_report.clone();
+        let state = report_state.clone();
+        move || {
+            let range = state.lock().unwrap().range;
+            if let Some(range) = range {
+                load_report(range);
             }
         }
     });
 
+    // --- Période de comparaison : recharge la période affichée ---
+    ui.on_report_comparison_changed({
+        let load_report = load_report.clone();
+        let state = report_state.clone();
+        move |mode| {
+            let range = {
+                let mut state = state.lock().unwrap();
+                state.comparison = match mode.as_str() {
+                    "previous" => queries::ComparisonMode::PreviousPeriod,
+                    "last_year" => queries::ComparisonMode::SamePeriodLastYear,
+                    _ => queries::ComparisonMode::None,
+                };
+                state.range
+            };
+            if let Some(range) = range {
+                load_report(range);
+            }
+        }
+    });
+
+    // --- Période personnalisée, du premier au dernier jour inclus (JJ/MM/AAAA) ---
+    ui.on_request_custom_report(move |first, last| {
+        let parse = |s: &str| NaiveDate::parse_from_str(s.trim(), "%d/%m/%Y");
+        match (parse(&first), parse(&last)) {
+            (Ok(first), Ok(last)) => load_report(business_calendar().days(first, last)),
+            _ => show_error_dialog(
+                "Période invalide",
+                "Saisissez les dates de début et de fin au format JJ/MM/AAAA.",
+            ),
+        }
+    });
+
     // --- Callbacks d'exportation ---
 
     ui.on_export_pdf_clicked({
+        let handle = main_window_handle.clone();
         let state = report_state.clone();
         move || {
             log::info!("Demande d'export PDF...");
-            let data = &state.lock().unwrap().data;
-            if data.total_sales == 0 {
+            let data = state.lock().unwrap().data.clone();
+            if data.totals.total_sales == 0 {
                 show_info_dialog(
                     "Export PDF",
                     "Aucune donnée à exporter pour la période sélectionnée.",
                 );
                 return;
             }
-            match report_generator_service::generate_pdf_report(data) {
-                Ok(path) => show_info_dialog(
-                    "Export PDF Réussi",
-                    &format!("Rapport sauvegardé : {}", path),
-                ),
-                Err(e) => show_error_dialog("Erreur d'Export PDF", &e.to_string()),
-            }
+            // Le rapport reste affiché pendant l'export : seul l'indicateur global est actif
+            background::spawn(
+                &handle,
+                None,
+                move || report_generator_service::generate_pdf_report(&data),
+                |_, result| match result {
+                    Ok(path) => show_info_dialog(
+                        "Export PDF Réussi",
+                        &format!("Rapport sauvegardé : {}", path),
+                    ),
+                    Err(e) => show_error_dialog("Erreur d'Export PDF", &e.to_string()),
+                },
+            );
         }
     });
 
     ui.on_export_excel_clicked({
+        let handle = main_window_handle.clone();
         let state = report_state.clone();
         move || {
             log::info!("Demande d'export Excel...");
-            let data = &state.lock().unwrap().data;
-            if data.total_sales == 0 {
+            let data = state.lock().unwrap().data.clone();
+            if data.totals.total_sales == 0 {
                 show_info_dialog(
                     "Export Excel",
                     "Aucune donnée à exporter pour la période sélectionnée.",
                 );
                 return;
             }
-            match report_generator_service::generate_excel_report(data) {
-                Ok(path) => show_info_dialog(
-                    "Export Excel Réussi",
-                    &format!("Rapport sauvegardé : {}", path),
-                ),
-                Err(e) => show_error_dialog("Erreur d'Export Excel", &e.to_string()),
+            // Le rapport reste affiché pendant l'export : seul l'indicateur global est actif
+            background::spawn(
+                &handle,
+                None,
+                move || report_generator_service::generate_excel_report(&data),
+                |_, result| match result {
+                    Ok(path) => show_info_dialog(
+                        "Export Excel Réussi",
+                        &format!("Rapport sauvegardé : {}", path),
+                    ),
+                    Err(e) => show_error_dialog("Erreur d'Export Excel", &e.to_string()),
+                },
+            );
+        }
+    });
+
+    // --- Commissions des vendeurs ---
+
+    ui.on_edit_commission_rule({
+        let handle = main_window_handle.clone();
+        let state = report_state.clone();
+        move |seller_id| {
+            // Identifiant vide : règle par défaut, commune à tous les vendeurs
+            let seller = Uuid::parse_str(&seller_id).ok().map(|id| {
+                let name = state
+                    .lock()
+                    .unwrap()
+                    .data
+                    .sellers
+                    .iter()
+                    .find(|s| s.seller_id == id)
+                    .map(|s| s.seller_name.clone())
+                    .unwrap_or_default();
+                (id, name)
+            });
+            show_commission_rule_dialog(current_user_id, seller, handle.clone());
+        }
+    });
+
+    ui.on_export_commission_statement({
+        let handle = main_window_handle.clone();
+        let state = report_state.clone();
+        move |seller_id| {
+            let Ok(seller_id) = Uuid::parse_str(&seller_id) else {
+                return;
+            };
+            log::info!(
+                "Demande de relevé de commissions pour le vendeur {}",
+                seller_id
+            );
+            let data = state.lock().unwrap().data.clone();
+            background::spawn(
+                &handle,
+                None,
+                move || {
+                    report_generator_service::generate_commission_statement_pdf(&data, seller_id)
+                },
+                |_, result| match result {
+                    Ok(path) => show_info_dialog(
+                        "Relevé de Commissions",
+                        &format!("Relevé sauvegardé : {}", path),
+                    ),
+                    Err(e) => show_error_dialog("Erreur d'Export PDF", &e.to_string()),
+                },
+            );
+        }
+    });
+
+    // Déclenche le chargement initial avec la période par défaut ("Cette semaine")
+    ui.invoke_request_report_data("week".into());
+}
+
+/// Affiche le dialogue de règle de commission d'un vendeur, ou de la règle par défaut si `seller` est `None`.
+/// Le rapport affiché est rechargé après chaque modification.
+fn show_commission_rule_dialog(
+    current_user_id: Uuid,
+    seller: Option<(Uuid, String)>,
+    main_ui_weak: Weak<ui::MainWindow>,
+) {
+    let rules = match queries::get_commission_rules() {
+        Ok(rules) => rules,
+        Err(e) => {
+            show_error_dialog("Erreur de Chargement", &e.to_string());
+            return;
+        }
+    };
+    let Ok(dialog) = ui::CommissionRuleDialog::new() else {
+        return;
+    };
+    let seller_id = seller.as_ref().map(|(id, _)| *id);
+    let own_rule = rules.iter().find(|rule| rule.user_id == seller_id);
+    let applied_rule = match seller_id {
+        Some(id) => queries::commission_rule_for(&rules, id),
+        None => own_rule,
+    };
+
+    dialog.set_seller_name(seller.map(|(_, name)| name).unwrap_or_default().into());
+    dialog.set_current_rule(
+        match (applied_rule, own_rule) {
+            (Some(rule), Some(_)) => rule.describe(),
+            (Some(rule), None) => format!("{} (règle par défaut)", rule.describe()),
+            (None, _) => "aucune".to_string(),
+        }
+        .into(),
+    );
+    dialog.set_can_remove(seller_id.is_some() && own_rule.is_some());
+    if let Some(rule) = applied_rule {
+        let kind = rule.kind().unwrap_or(CommissionKind::Percent);
+        let index = CommissionKind::ALL
+            .iter()
+            .position(|k| *k == kind)
+            .unwrap_or(0);
+        dialog.set_kind_index(index as i32);
+        dialog.set_rate(rule.rate.normalized().to_string().into());
+    }
+
+    let refresh_report = {
+        let main_ui_weak = main_ui_weak.clone();
+        move || {
+            if let Some(ui) = main_ui_weak.upgrade() {
+                ui.invoke_refresh_report();
+            }
+        }
+    };
+
+    dialog.on_save_clicked({
+        let dialog_weak = dialog.as_weak();
+        let refresh_report = refresh_report.clone();
+        move |kind_index, rate| {
+            let Some(d) = dialog_weak.upgrade() else {
+                return;
+            };
+            let kind = usize::try_from(kind_index)
+                .ok()
+                .and_then(|i| CommissionKind::ALL.get(i).copied())
+                .unwrap_or(CommissionKind::Percent);
+            let Ok(rate) = BigDecimal::from_str(rate.trim().replace(',', ".").as_str()) else {
+                d.set_status_message("Le taux est invalide.".into());
+                return;
+            };
+            match queries::set_commission_rule(current_user_id, seller_id, kind, rate) {
+                Ok(_) => {
+                    let _ = d.hide();
+                    refresh_report();
+                }
+                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+            }
+        }
+    });
+
+    dialog.on_remove_clicked({
+        let dialog_weak = dialog.as_weak();
+        move || {
+            let (Some(d), Some(seller_id)) = (dialog_weak.upgrade(), seller_id) else {
+                return;
+            };
+            match queries::remove_commission_rule(current_user_id, seller_id) {
+                Ok(()) => {
+                    let _ = d.hide();
+                    refresh_report();
+                }
+                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
             }
         }
     });
 
-    // Déclenche le chargement initial avec la période par défaut ("7 derniers jours")
-    ui.invoke_request_report_data("7d".into());
+    let dialog_handle_cancel = dialog.as_weak();
+    dialog.on_cancel_clicked(move || {
+        if let Some(d) = dialog_handle_cancel.upgrade() {
+            let _ = d.hide();
+        }
+    });
+
+    let _ = dialog.run();
+}
+
+/// Ligne de comparaison d'une carte KPI : variation et valeur de la période de référence.
+fn comparison_line(current: &BigDecimal, previous: Option<BigDecimal>, unit: &str) -> SharedString {
+    match previous {
+        Some(previous) => format!(
+            "{} (réf. {:.0}{})",
+            queries::format_percent_change(current, &previous),
+            previous,
+            unit
+        )
+        .into(),
+        None => SharedString::new(),
+    }
+}
+
+/// Taux de marge affiché ; les ventes sans prix de revient sont signalées à part.
+fn format_rate(line: &queries::MarginLine) -> String {
+    let rate = match line.margin_rate() {
+        Some(rate) => format!("{:.1} %", rate),
+        None => "n.d.".to_string(),
+    };
+    if line.uncosted_revenue > BigDecimal::from(0) {
+        format!("{} (hors {:.0} XAF sans coût)", rate, line.uncosted_revenue)
+    } else {
+        rate
+    }
+}
+
+fn to_f64(value: &BigDecimal) -> f64 {
+    value.to_string().parse().unwrap_or(0.0)
+}
+
+/// Montant abrégé pour les étiquettes du graphique (« 1.2 M », « 850 k »).
+fn compact_amount(value: &BigDecimal) -> String {
+    let amount = to_f64(value);
+    match amount.abs() {
+        a if a >= 1_000_000.0 => format!("{:.1} M", amount / 1_000_000.0),
+        a if a >= 1_000.0 => format!("{:.0} k", amount / 1_000.0),
+        _ => format!("{:.0}", amount),
+    }
 }
@@ -1,9 +1,14 @@
 use crate::{
-    models::{CreateSaleData, CreateSaleItemData, Receipt},
+    config::{business_calendar::business_calendar, printer_config},
+    helpers::printer_error_handler::PrinterError,
+    models::{
+        CreateCreditNoteData, CreatePaymentData, CreateSaleData, CreateSaleItemData, Payment,
+        PaymentMethod, PaymentSummary, Receipt, ReturnItemData, SaleStatus, SaleWithItems,
+    },
     queries::{self},
     ui,
 };
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Num};
 use slint::{ComponentHandle, ModelRc, Weak};
 use std::{
     collections::HashMap,
@@ -11,7 +16,10 @@ use std::{
 };
 use uuid::Uuid;
 
-use super::{show_error_dialog, show_info_dialog};
+use super::{
+    background::{self, LoadingState},
+    printer_callbacks, show_error_dialog, show_info_dialog,
+};
 
 /// Maintient l'état des filtres et pagination pour les ventes
 #[derive(Debug, Clone)]
@@ -46,6 +54,14 @@ impl SalesState {
 pub struct NewSaleState {
     pub cart_items: HashMap<String, CartItemData>,
     pub total_amount: BigDecimal,
+    /// Consignes nettes du panier (négatif si le client rapporte plus de vides qu'il n'en emporte).
+    pub deposit_total: BigDecimal,
+    /// Client sélectionné pour une vente à crédit (`None` = vente comptant).
+    pub customer_id: Option<Uuid>,
+    /// Paiements saisis (plusieurs moyens de paiement possibles).
+    pub payments: Vec<CreatePaymentData>,
+    /// Enregistrement en cours en arrière-plan : empêche une double validation.
+    pub is_saving: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +72,20 @@ pub struct CartItemData {
     pub quantity: i32,
     pub total_price: BigDecimal,
     pub packaging_description: String,
+    /// Montant unitaire de la consigne (`None` si le conditionnement n'est pas consigné).
+    pub deposit_unit_amount: Option<BigDecimal>,
+    /// Vides rapportés par le client sur cette ligne.
+    pub deposit_returned: i32,
+}
+
+impl CartItemData {
+    /// Consigne nette de la ligne : emballages sortis moins vides rendus.
+    pub fn deposit_amount(&self) -> BigDecimal {
+        self.deposit_unit_amount
+            .as_ref()
+            .map(|unit| unit * BigDecimal::from(self.quantity - self.deposit_returned))
+            .unwrap_or_else(|| BigDecimal::from(0))
+    }
 }
 
 impl NewSaleState {
@@ -63,6 +93,10 @@ impl NewSaleState {
         Self {
             cart_items: HashMap::new(),
             total_amount: BigDecimal::from(0),
+            deposit_total: BigDecimal::from(0),
+            customer_id: None,
+            payments: Vec::new(),
+            is_saving: false,
         }
     }
 
@@ -73,6 +107,7 @@ impl NewSaleState {
         unit_price: BigDecimal,
         quantity: i32,
         packaging_description: String,
+        deposit_unit_amount: Option<BigDecimal>,
     ) {
         let total_price = unit_price.clone() * BigDecimal::from(quantity);
 
@@ -92,6 +127,8 @@ impl NewSaleState {
                     quantity,
                     total_price,
                     packaging_description,
+                    deposit_unit_amount,
+                    deposit_returned: 0,
                 },
             );
         }
@@ -112,6 +149,13 @@ impl NewSaleState {
         }
     }
 
+    pub fn update_deposit_returned(&mut self, product_id: &str, returned: i32) {
+        if let Some(item) = self.cart_items.get_mut(product_id) {
+            item.deposit_returned = returned.max(0);
+            self.update_total();
+        }
+    }
+
     pub fn clear_cart(&mut self) {
         self.cart_items.clear();
         self.update_total();
@@ -123,6 +167,52 @@ impl NewSaleState {
             .values()
             .map(|item| item.total_price.clone())
             .sum();
+        self.deposit_total = self.cart_items.values().map(|i| i.deposit_amount()).sum();
+    }
+
+    /// Montant à régler : marchandises + consignes nettes.
+    pub fn amount_due(&self) -> BigDecimal {
+        &self.total_amount + &self.deposit_total
+    }
+
+    /// Aperçu des paiements saisis : lignes UI, montant payé, reste et monnaie à rendre.
+    /// Applique les mêmes règles que `create_sale` (l'excédent en espèces est rendu).
+    pub fn payment_preview(&self) -> (Vec<ui::PaymentUI>, BigDecimal, BigDecimal, BigDecimal) {
+        let zero = BigDecimal::from(0);
+        let amount_due = self.amount_due();
+        let mut remaining = if amount_due > zero {
+            amount_due
+        } else {
+            zero.clone()
+        };
+        let mut paid = zero.clone();
+        let mut change = zero.clone();
+        let mut lines = Vec::new();
+
+        for payment in &self.payments {
+            let applied = if payment.amount > remaining {
+                remaining.clone()
+            } else {
+                payment.amount.clone()
+            };
+            let mut detail = payment.reference.clone().unwrap_or_default();
+            if payment.method == PaymentMethod::Cash && payment.amount > applied {
+                let line_change = &payment.amount - &applied;
+                detail = format!("Remis: {:.0} — Rendu: {:.0}", payment.amount, line_change);
+                change += line_change;
+            }
+            if payment.method != PaymentMethod::Credit {
+                paid += &applied;
+            }
+            remaining -= &applied;
+            lines.push(ui::PaymentUI {
+                method: payment.method.label().into(),
+                amount: format!("{:.0}", applied).into(),
+                detail: detail.into(),
+            });
+        }
+
+        (lines, paid, remaining, change)
     }
 
     pub fn to_cart_ui_items(&self) -> Vec<ui::CartItem> {
@@ -135,6 +225,15 @@ impl NewSaleState {
                 quantity: item.quantity,
                 total_price: format!("{:.0}", item.total_price).into(),
                 packaging_description: item.packaging_description.clone().into(),
+                has_deposit: item.deposit_unit_amount.is_some(),
+                deposit_unit_amount: item
+                    .deposit_unit_amount
+                    .as_ref()
+                    .map(|a| format!("{:.0}", a))
+                    .unwrap_or_default()
+                    .into(),
+                deposit_returned: item.deposit_returned,
+                deposit_amount: format!("{:.0}", item.deposit_amount()).into(),
             })
             .collect()
     }
@@ -150,41 +249,50 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
         let state_clone = sales_state.clone();
 
         move || {
-            if let Some(ui) = handle.upgrade() {
-                let current_state = state_clone.lock().unwrap().clone();
-
-                let params = queries::SaleSearchParams {
-                    user_id_filter: if current_state.is_admin {
-                        None
-                    } else {
-                        Some(current_state.current_user_id)
-                    },
-                    search_query: if current_state.search_query.is_empty() {
-                        None
-                    } else {
-                        Some(current_state.search_query.clone())
-                    },
-                    date_filter: current_state.date_filter,
-                    sort_by: current_state.sort_by,
-                    sort_order: current_state.sort_order,
-                    page: current_state.current_page,
-                    page_size: current_state.page_size,
-                };
+            let current_state = state_clone.lock().unwrap().clone();
+
+            let params = queries::SaleSearchParams {
+                user_id_filter: if current_state.is_admin {
+                    None
+                } else {
+                    Some(current_state.current_user_id)
+                },
+                search_query: if current_state.search_query.is_empty() {
+                    None
+                } else {
+                    Some(current_state.search_query.clone())
+                },
+                date_filter: current_state.date_filter,
+                sort_by: current_state.sort_by,
+                sort_order: current_state.sort_order,
+                page: current_state.current_page,
+                page_size: current_state.page_size,
+            };
 
-                match queries::get_sales_paginated(params) {
+            let is_admin = current_state.is_admin;
+            background::spawn_latest(
+                &handle,
+                LoadingState::Sales,
+                move || queries::get_sales_paginated(params),
+                move |ui, result| match result {
                     Ok(result) => {
                         let model = result
                             .sales
                             .into_iter()
                             .map(|s| ui::SaleUI {
                                 id: s.sale.id.to_string().into(),
-                                sale_number: s.sale.sale_number.into(),
-                                date: s.sale.date.format("%d/%m/%Y %H:%M").to_string().into(),
+                                sale_number: s.sale.sale_number.as_str().into(),
+                                date: business_calendar()
+                                    .format_local(s.sale.date, "%d/%m/%Y %H:%M")
+                                    .into(),
                                 total_amount: format!("{} XAF", s.sale.total_amount).into(),
                                 seller_name: s.seller_name.into(),
                                 items_count: s.items_count as i32,
                                 items: ModelRc::new(slint::VecModel::default()),
                                 show_details: false,
+                                payment_status: s.payment_status.label().into(),
+                                sale_status: sale_status_label(s.sale.status()).into(),
+                                is_voided: s.sale.is_voided(),
                             })
                             .collect::<Vec<_>>();
 
@@ -192,7 +300,7 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
                         ui.set_sale_current_page(result.page as i32);
                         ui.set_sale_total_pages(result.total_pages as i32);
                         ui.set_total_sales(result.total_count as i32);
-                        ui.set_is_admin(current_state.is_admin);
+                        ui.set_is_admin(is_admin);
                     }
                     Err(e) => {
                         show_error_dialog(
@@ -201,8 +309,8 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
                         );
                         ui.set_sales_model(ModelRc::new(slint::VecModel::default()));
                     }
-                }
-            }
+                },
+            );
         }
     };
 
@@ -317,33 +425,83 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
                             current_state.is_admin,
                         ) {
                             Ok(sale_details) => {
-                                let items_ui = sale_details
-                                    .items
-                                    .into_iter()
-                                    .map(|(item, product)| ui::SaleItemUI {
-                                        id: item.id.to_string().into(),
-                                        product_name: product.name.into(),
-                                        packaging_description: product.packaging_description.into(),
-                                        quantity: item.quantity,
-                                        unit_price: format!("{} XAF", item.unit_price).into(),
-                                        total_price: format!("{} XAF", item.total_price).into(),
-                                    })
-                                    .collect::<Vec<_>>();
-
                                 let details_dialog = ui::SaleDetailsDialog::new().unwrap();
-                                details_dialog.set_sale_details(ui::SaleDetailsUI {
-                                    id: sale_details.sale.id.to_string().into(),
-                                    sale_number: sale_details.sale.sale_number.into(),
-                                    date: sale_details
-                                        .sale
-                                        .date
-                                        .format("%d/%m/%Y %H:%M")
-                                        .to_string()
-                                        .into(),
-                                    total_amount: format!("{} XAF", sale_details.sale.total_amount)
-                                        .into(),
-                                    seller_name: sale_details.seller_name.into(),
-                                    items: ModelRc::new(slint::VecModel::from(items_ui)),
+                                details_dialog.set_sale_details(sale_details_to_ui(
+                                    sale_details,
+                                    current_state.is_admin,
+                                ));
+
+                                // Encaissement complémentaire (vente partiellement réglée)
+                                let details_weak = details_dialog.as_weak();
+                                let main_ui_weak = ui.as_weak();
+                                details_dialog.on_add_payment(
+                                    move |method_index, amount_str, reference| {
+                                        let Some(dd) = details_weak.upgrade() else {
+                                            return;
+                                        };
+                                        let tender = match parse_payment(
+                                            method_index,
+                                            &amount_str,
+                                            &reference,
+                                        ) {
+                                            Ok(tender) => tender,
+                                            Err(msg) => {
+                                                dd.set_payment_message(msg.into());
+                                                return;
+                                            }
+                                        };
+                                        let result = queries::add_sale_payment(sale_id, tender)
+                                            .and_then(|_| {
+                                                queries::get_sale_details(
+                                                    sale_id,
+                                                    current_state.current_user_id,
+                                                    current_state.is_admin,
+                                                )
+                                            });
+                                        match result {
+                                            Ok(updated) => {
+                                                dd.set_sale_details(sale_details_to_ui(
+                                                    updated,
+                                                    current_state.is_admin,
+                                                ));
+                                                dd.set_payment_message("".into());
+                                                if let Some(main_ui) = main_ui_weak.upgrade() {
+                                                    main_ui.invoke_request_sales();
+                                                    main_ui.invoke_refresh_dashboard();
+                                                }
+                                            }
+                                            Err(e) => {
+                                                dd.set_payment_message(
+                                                    format!("Erreur: {}", e).into(),
+                                                );
+                                            }
+                                        }
+                                    },
+                                );
+
+                                // Retour de marchandise (avoir)
+                                let details_weak = details_dialog.as_weak();
+                                let main_ui_weak = ui.as_weak();
+                                details_dialog.on_return_clicked(move || {
+                                    show_return_dialog(
+                                        sale_id,
+                                        current_state.current_user_id,
+                                        current_state.is_admin,
+                                        details_weak.clone(),
+                                        main_ui_weak.clone(),
+                                    );
+                                });
+
+                                // Annulation de la vente (administrateurs)
+                                let details_weak = details_dialog.as_weak();
+                                let main_ui_weak = ui.as_weak();
+                                details_dialog.on_void_clicked(move || {
+                                    show_void_sale_dialog(
+                                        sale_id,
+                                        current_state.current_user_id,
+                                        details_weak.clone(),
+                                        main_ui_weak.clone(),
+                                    );
                                 });
 
                                 // Gestion de l'impression
@@ -352,7 +510,7 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
                                     if let Some(_ui) = ui_weak.upgrade() {
                                         match queries::generate_receipt(sale_id) {
                                             Ok(receipt) => {
-                                                show_receipt_dialog(receipt);
+                                                show_receipt_dialog(&ui_weak, receipt, None);
                                             }
                                             Err(e) => {
                                                 show_error_dialog(
@@ -367,6 +525,40 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
                                     }
                                 });
 
+                                // Réimpression directe sur l'imprimante par défaut
+                                let main_ui_weak = ui.as_weak();
+                                details_dialog.on_reprint_clicked(move || {
+                                    background::spawn(
+                                        &main_ui_weak,
+                                        None,
+                                        move || {
+                                            let receipt = queries::generate_receipt(sale_id)
+                                                .map_err(|e| {
+                                                    PrinterError::SystemError(format!(
+                                                        "Impossible de générer le reçu: {}",
+                                                        e
+                                                    ))
+                                                })?;
+                                            printer_callbacks::print_receipt_on_default_printer(
+                                                &receipt,
+                                            )
+                                        },
+                                        |_, result| match result {
+                                            Ok(printer_name) => show_info_dialog(
+                                                "Impression",
+                                                &format!(
+                                                    "Le ticket a été réimprimé sur {}",
+                                                    printer_name
+                                                ),
+                                            ),
+                                            Err(error) => show_error_dialog(
+                                                "Erreur d'impression",
+                                                &error.message(),
+                                            ),
+                                        },
+                                    );
+                                });
+
                                 let details_dialog_weak = details_dialog.as_weak();
                                 details_dialog.on_close_clicked(move || {
                                     if let Some(dd) = details_dialog_weak.upgrade() {
@@ -388,42 +580,468 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid, i
     load_sales();
 }
 
-// Fonction utilitaire pour afficher le ticket de caisse
-fn show_receipt_dialog(receipt: Receipt) {
+/// Convertit un paiement enregistré en ligne UI.
+fn payment_to_ui(payment: &Payment) -> ui::PaymentUI {
+    let method = payment
+        .method()
+        .map(|m| m.label().to_string())
+        .unwrap_or_else(|| payment.method.clone());
+    let detail = match (&payment.cash_tendered, &payment.reference) {
+        (Some(tendered), _) if payment.change_due > BigDecimal::from(0) => format!(
+            "Remis: {:.0} XAF — Rendu: {:.0} XAF",
+            tendered, payment.change_due
+        ),
+        (_, Some(reference)) => format!("Réf. {}", reference),
+        _ => String::new(),
+    };
+    ui::PaymentUI {
+        method: method.into(),
+        amount: format!("{:.0} XAF", payment.amount).into(),
+        detail: detail.into(),
+    }
+}
+
+/// Libellé de l'état d'une vente pour l'affichage ; vide pour une vente validée sans retour.
+fn sale_status_label(status: Option<SaleStatus>) -> &'static str {
+    match status {
+        Some(SaleStatus::Completed) | None => "",
+        Some(status) => status.label(),
+    }
+}
+
+/// Construit le modèle UI des détails d'une vente, paiements et avoirs compris.
+fn sale_details_to_ui(sale_details: SaleWithItems, is_admin: bool) -> ui::SaleDetailsUI {
+    let summary =
+        PaymentSummary::from_payments(&sale_details.sale.amount_due(), &sale_details.payments);
+    let is_voided = sale_details.sale.is_voided();
+    let can_return = is_admin
+        && !is_voided
+        && sale_details
+            .items
+            .iter()
+            .any(|(item, _)| item.quantity_returnable() > 0);
+    let void_info = match (&sale_details.sale.voided_at, &sale_details.sale.void_reason) {
+        (Some(voided_at), reason) if is_voided => format!(
+            "Annulée le {} — Motif : {}",
+            business_calendar().format_local(*voided_at, "%d/%m/%Y %H:%M"),
+            reason.clone().unwrap_or_default()
+        ),
+        _ => String::new(),
+    };
+    let credit_notes_ui = sale_details
+        .credit_notes
+        .iter()
+        .map(|cn| {
+            format!(
+                "Avoir {} du {} : {:.0} XAF — {}",
+                cn.credit_note_number,
+                business_calendar().format_local(cn.date, "%d/%m/%Y %H:%M"),
+                cn.total_amount,
+                cn.reason
+            )
+            .into()
+        })
+        .collect::<Vec<slint::SharedString>>();
+    let deposit_total = if sale_details.sale.deposit_total != BigDecimal::from(0) {
+        format!("{:.0} XAF", sale_details.sale.deposit_total)
+    } else {
+        String::new()
+    };
+    let payments_ui = sale_details
+        .payments
+        .iter()
+        .map(payment_to_ui)
+        .collect::<Vec<_>>();
+    let items_ui = sale_details
+        .items
+        .into_iter()
+        .map(|(item, product)| ui::SaleItemUI {
+            id: item.id.to_string().into(),
+            product_name: product.name.into(),
+            packaging_description: product.packaging_description.into(),
+            quantity: item.quantity,
+            unit_price: format!("{} XAF", item.unit_price).into(),
+            total_price: format!("{} XAF", item.total_price).into(),
+            quantity_returned: item.quantity_returned,
+        })
+        .collect::<Vec<_>>();
+
+    ui::SaleDetailsUI {
+        id: sale_details.sale.id.to_string().into(),
+        sale_number: sale_details.sale.sale_number.as_str().into(),
+        date: business_calendar()
+            .format_local(sale_details.sale.date, "%d/%m/%Y %H:%M")
+            .into(),
+        total_amount: format!("{} XAF", sale_details.sale.total_amount).into(),
+        seller_name: sale_details.seller_name.into(),
+        items: ModelRc::new(slint::VecModel::from(items_ui)),
+        payments: ModelRc::new(slint::VecModel::from(payments_ui)),
+        payment_status: summary.status.label().into(),
+        amount_paid: format!("{:.0} XAF", summary.settled).into(),
+        balance_due: format!("{:.0} XAF", &summary.on_account + &summary.remaining).into(),
+        can_add_payment: !is_voided && summary.remaining > BigDecimal::from(0),
+        deposit_total: deposit_total.into(),
+        sale_status: sale_status_label(sale_details.sale.status()).into(),
+        void_info: void_info.into(),
+        credit_notes: ModelRc::new(slint::VecModel::from(credit_notes_ui)),
+        can_void: is_admin && !is_voided,
+        can_return,
+    }
+}
+
+/// Recharge les détails d'une vente dans le dialogue ouvert et rafraîchit la fenêtre principale.
+fn refresh_sale_views(
+    sale_id: Uuid,
+    current_user_id: Uuid,
+    is_admin: bool,
+    details_weak: &Weak<ui::SaleDetailsDialog>,
+    main_ui_weak: &Weak<ui::MainWindow>,
+) {
+    if let Some(dd) = details_weak.upgrade() {
+        match queries::get_sale_details(sale_id, current_user_id, is_admin) {
+            Ok(updated) => dd.set_sale_details(sale_details_to_ui(updated, is_admin)),
+            Err(e) => log::error!("Erreur lors du rechargement de la vente: {}", e),
+        }
+    }
+    if let Some(main_ui) = main_ui_weak.upgrade() {
+        main_ui.invoke_request_sales();
+        main_ui.invoke_request_products();
+        main_ui.invoke_refresh_dashboard();
+    }
+}
+
+/// Affiche le dialogue de retour de marchandise et émet l'avoir correspondant.
+fn show_return_dialog(
+    sale_id: Uuid,
+    current_user_id: Uuid,
+    is_admin: bool,
+    details_weak: Weak<ui::SaleDetailsDialog>,
+    main_ui_weak: Weak<ui::MainWindow>,
+) {
+    let sale_details = match queries::get_sale_details(sale_id, current_user_id, is_admin) {
+        Ok(details) => details,
+        Err(e) => {
+            show_error_dialog("Erreur", &format!("{}", e));
+            return;
+        }
+    };
+    let Ok(dialog) = ui::ReturnDialog::new() else {
+        return;
+    };
+
+    // (ligne de vente, prix unitaire, quantité saisie)
+    let lines: Arc<Mutex<Vec<(Uuid, BigDecimal, String)>>> = Arc::new(Mutex::new(
+        sale_details
+            .items
+            .iter()
+            .map(|(item, _)| (item.id, item.unit_price.clone(), String::new()))
+            .collect(),
+    ));
+    let items_ui = sale_details
+        .items
+        .iter()
+        .map(|(item, product)| ui::ReturnItemUI {
+            product_name: format!("{} ({})", product.name, product.packaging_description).into(),
+            quantity_sold: item.quantity,
+            quantity_returnable: item.quantity_returnable(),
+            unit_price: format!("{:.0} XAF", item.unit_price).into(),
+            quantity_to_return: "".into(),
+        })
+        .collect::<Vec<_>>();
+
+    dialog.set_sale_number(sale_details.sale.sale_number.clone().into());
+    dialog.set_items(ModelRc::new(slint::VecModel::from(items_ui)));
+    dialog.set_refund_info(
+        if sale_details.sale.customer_id.is_some() {
+            "Le montant de l'avoir sera crédité sur le compte du client."
+        } else {
+            "Le montant de l'avoir est à rembourser au client."
+        }
+        .into(),
+    );
+
+    dialog.on_quantity_edited({
+        let dialog_weak = dialog.as_weak();
+        let lines = lines.clone();
+        move |index, text| {
+            let mut lines = lines.lock().unwrap();
+            if let Some(line) = usize::try_from(index).ok().and_then(|i| lines.get_mut(i)) {
+                line.2 = text.to_string();
+            }
+            // Total indicatif de l'avoir, les quantités invalides sont ignorées
+            let total: BigDecimal = lines
+                .iter()
+                .filter_map(|(_, unit_price, qty)| {
+                    qty.trim()
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|q| *q > 0)
+                        .map(|q| unit_price * BigDecimal::from(q))
+                })
+                .sum();
+            if let Some(d) = dialog_weak.upgrade() {
+                d.set_credit_total(format!("{:.0} XAF", total).into());
+            }
+        }
+    });
+
+    dialog.on_confirm_clicked({
+        let dialog_weak = dialog.as_weak();
+        let lines = lines.clone();
+        move |reason| {
+            let Some(d) = dialog_weak.upgrade() else {
+                return;
+            };
+            let mut items = Vec::new();
+            for (sale_item_id, _, qty) in lines.lock().unwrap().iter() {
+                if qty.trim().is_empty() {
+                    continue;
+                }
+                match qty.trim().parse::<i32>() {
+                    Ok(quantity) => items.push(ReturnItemData {
+                        sale_item_id: *sale_item_id,
+                        quantity,
+                    }),
+                    Err(_) => {
+                        d.set_status_message("Une quantité est invalide.".into());
+                        return;
+                    }
+                }
+            }
+
+            let data = CreateCreditNoteData {
+                sale_id,
+                user_id: current_user_id,
+                reason: reason.to_string(),
+                items,
+            };
+            match queries::create_credit_note(data) {
+                Ok(credit_note) => {
+                    let _ = d.hide();
+                    refresh_sale_views(
+                        sale_id,
+                        current_user_id,
+                        is_admin,
+                        &details_weak,
+                        &main_ui_weak,
+                    );
+                    show_info_dialog(
+                        "Avoir émis",
+                        &format!(
+                            "L'avoir {} de {:.0} XAF a été enregistré et les articles remis en stock.",
+                            credit_note.credit_note_number, credit_note.total_amount
+                        ),
+                    );
+                }
+                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+            }
+        }
+    });
+
+    let dialog_handle_cancel = dialog.as_weak();
+    dialog.on_cancel_clicked(move || {
+        if let Some(d) = dialog_handle_cancel.upgrade() {
+            let _ = d.hide();
+        }
+    });
+
+    let _ = dialog.run();
+}
+
+/// Affiche le dialogue d'annulation d'une vente (réservé aux administrateurs).
+fn show_void_sale_dialog(
+    sale_id: Uuid,
+    current_user_id: Uuid,
+    details_weak: Weak<ui::SaleDetailsDialog>,
+    main_ui_weak: Weak<ui::MainWindow>,
+) {
+    let Ok(dialog) = ui::VoidSaleDialog::new() else {
+        return;
+    };
+    if let Some(dd) = details_weak.upgrade() {
+        let details = dd.get_sale_details();
+        dialog.set_sale_number(details.sale_number);
+        dialog.set_total_amount(details.total_amount);
+    }
+
+    dialog.on_confirm_clicked({
+        let dialog_weak = dialog.as_weak();
+        move |reason| {
+            let Some(d) = dialog_weak.upgrade() else {
+                return;
+            };
+            match queries::void_sale(sale_id, current_user_id, reason.to_string()) {
+                Ok(_) => {
+                    let _ = d.hide();
+                    refresh_sale_views(
+                        sale_id,
+                        current_user_id,
+                        true,
+                        &details_weak,
+                        &main_ui_weak,
+                    );
+                }
+                Err(e) => d.set_status_message(format!("Erreur: {}", e).into()),
+            }
+        }
+    });
+
+    let dialog_handle_cancel = dialog.as_weak();
+    dialog.on_cancel_clicked(move || {
+        if let Some(d) = dialog_handle_cancel.upgrade() {
+            let _ = d.hide();
+        }
+    });
+
+    let _ = dialog.run();
+}
+
+/// Valide la saisie d'un paiement (moyen de paiement, montant, référence).
+fn parse_payment(
+    method_index: i32,
+    amount_str: &str,
+    reference: &str,
+) -> Result<CreatePaymentData, String> {
+    let method = usize::try_from(method_index)
+        .ok()
+        .and_then(|i| PaymentMethod::ALL.get(i).copied())
+        .ok_or_else(|| "Moyen de paiement invalide.".to_string())?;
+    let amount = BigDecimal::from_str_radix(amount_str.trim(), 10)
+        .map_err(|_| "Le montant est invalide.".to_string())?;
+    if amount <= BigDecimal::from(0) {
+        return Err("Le montant doit être positif.".to_string());
+    }
+    Ok(CreatePaymentData {
+        method,
+        amount,
+        reference: Some(reference.trim().to_string()).filter(|r| !r.is_empty()),
+    })
+}
+
+/// Met à jour les consignes et la section "Règlement" du dialogue de nouvelle vente.
+fn refresh_payment_ui(dialog: &ui::NewSaleDialog, state: &Arc<Mutex<NewSaleState>>) {
+    let state_guard = state.lock().unwrap();
+    let (lines, paid, remaining, change) = state_guard.payment_preview();
+    dialog.set_deposit_total(format!("{:.0}", state_guard.deposit_total).into());
+    dialog.set_amount_due(format!("{:.0}", state_guard.amount_due()).into());
+    drop(state_guard);
+    dialog.set_payments(ModelRc::new(slint::VecModel::from(lines)));
+    dialog.set_amount_paid(format!("{:.0}", paid).into());
+    dialog.set_remaining_amount(format!("{:.0}", remaining).into());
+    dialog.set_change_due(format!("{:.0}", change).into());
+}
+
+// Fonction utilitaire pour afficher le ticket de caisse.
+// `printed` porte le résultat de l'impression automatique faite après la vente, le cas échéant.
+fn show_receipt_dialog(
+    main_ui_weak: &Weak<ui::MainWindow>,
+    receipt: Receipt,
+    printed: Option<Result<String, PrinterError>>,
+) {
     let receipt_dialog = ui::ReceiptDialog::new().unwrap();
-    receipt_dialog.set_sale_number(receipt.sale_number.into());
-    receipt_dialog.set_date(receipt.date.into());
-    receipt_dialog.set_seller_name(receipt.seller_name.into());
+    receipt_dialog.set_sale_number(receipt.sale_number.clone().into());
+    receipt_dialog.set_date(receipt.date.clone().into());
+    receipt_dialog.set_seller_name(receipt.seller_name.clone().into());
+    receipt_dialog.set_customer_name(receipt.customer_name.clone().unwrap_or_default().into());
     receipt_dialog.set_total_amount(receipt.total_amount.to_string().into());
+    receipt_dialog.set_change_due(format!("{:.0}", receipt.change_due).into());
+    receipt_dialog.set_balance_due(format!("{:.0}", receipt.balance_due).into());
+    receipt_dialog.set_deposit_total(format!("{:.0}", receipt.deposit_total).into());
+    receipt_dialog.set_amount_due(format!("{:.0}", receipt.amount_due).into());
+
+    let empties = receipt
+        .empties_owed
+        .iter()
+        .map(|e| format!("{} : {}", e.packaging_description, e.quantity).into())
+        .collect::<Vec<slint::SharedString>>();
+    receipt_dialog.set_empties_owed(ModelRc::new(slint::VecModel::from(empties)));
+
+    let payments = receipt
+        .payments
+        .iter()
+        .map(|p| ui::PaymentUI {
+            method: p.method.clone().into(),
+            amount: format!("{:.0}", p.amount).into(),
+            detail: p.reference.clone().unwrap_or_default().into(),
+        })
+        .collect::<Vec<_>>();
+    receipt_dialog.set_payments(ModelRc::new(slint::VecModel::from(payments)));
 
     let items = receipt
         .items
-        .into_iter()
+        .iter()
         .map(|item| ui::ReceiptItemUI {
-            product_name: item.product_name.into(),
+            product_name: item.product_name.clone().into(),
             quantity: item.quantity.to_string().into(),
             unit_price: item.unit_price.to_string().into(),
             total_price: item.total_price.to_string().into(),
+            deposit: if item.deposit_quantity_out > 0 || item.deposit_quantity_returned > 0 {
+                format!(
+                    "Consigne : {} sortis, {} rendus = {:.0}",
+                    item.deposit_quantity_out, item.deposit_quantity_returned, item.deposit_amount
+                )
+            } else {
+                String::new()
+            }
+            .into(),
         })
         .collect::<Vec<_>>();
 
     receipt_dialog.set_items(ModelRc::new(slint::VecModel::from(items)));
 
-    // Callback pour l'impression
+    // Impression automatique après la vente : un échec est signalé sans bloquer le caissier
+    match printed {
+        Some(Ok(printer_name)) => {
+            receipt_dialog.set_print_status(format!("Ticket imprimé sur {}", printer_name).into());
+        }
+        Some(Err(error)) => {
+            log::error!(
+                "Impression automatique du ticket impossible: {}",
+                error.message()
+            );
+            receipt_dialog.set_print_status(error.message().into());
+            receipt_dialog.set_print_failed(true);
+        }
+        None => {}
+    }
+
+    // Callback pour l'impression, hors du thread de l'interface (l'imprimante peut tarder à répondre)
+    let receipt = Arc::new(receipt);
     receipt_dialog.on_print_clicked({
         let receipt_weak = receipt_dialog.as_weak();
+        let main_ui_weak = main_ui_weak.clone();
         move || {
             if let Some(rd) = receipt_weak.upgrade() {
-                // la logique d'impression
-                // Par exemple, envoyer vers une imprimante, exporter en PDF, etc.
-                println!("Impression du ticket de caisse...");
-
-                // Afficher un message de succès
-                show_info_dialog("Impression", "Le ticket a été envoyé à l'imprimante");
-
-                // Fermer le dialogue du reçu
-                let _ = rd.hide();
+                rd.set_print_status("Impression en cours...".into());
+                rd.set_print_failed(false);
             }
+            let receipt = receipt.clone();
+            let receipt_weak = receipt_weak.clone();
+            background::spawn(
+                &main_ui_weak,
+                None,
+                move || printer_callbacks::print_receipt_on_default_printer(&receipt),
+                move |_, result| {
+                    let Some(rd) = receipt_weak.upgrade() else {
+                        return;
+                    };
+                    match result {
+                        Ok(printer_name) => {
+                            show_info_dialog(
+                                "Impression",
+                                &format!("Le ticket a été envoyé à l'imprimante {}", printer_name),
+                            );
+                            let _ = rd.hide();
+                        }
+                        // Le ticket reste affiché ; la file d'impression le réimprimera
+                        Err(error) => {
+                            rd.set_print_status(error.message().into());
+                            rd.set_print_failed(true);
+                            show_error_dialog("Erreur d'impression", &error.message());
+                        }
+                    }
+                },
+            );
         }
     });
 
@@ -469,6 +1087,58 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
         }
     }
 
+    // Charger les clients pour la vente à crédit (index 0 = vente comptant)
+    let customers = match queries::get_customers(None) {
+        Ok(customers) => customers,
+        Err(e) => {
+            log::error!("Erreur lors du chargement des clients: {}", e);
+            Vec::new()
+        }
+    };
+    let mut customer_names: Vec<slint::SharedString> = vec!["Client comptant".into()];
+    customer_names.extend(customers.iter().map(|c| c.customer.name.clone().into()));
+    dialog.set_customer_names(ModelRc::new(slint::VecModel::from(customer_names)));
+
+    // Callback pour la sélection du client
+    dialog.on_customer_selected({
+        let state = new_sale_state.clone();
+        let dialog_weak = dialog.as_weak();
+        move |index| {
+            if let Some(d) = dialog_weak.upgrade() {
+                let selected = if index > 0 {
+                    customers.get(index as usize - 1)
+                } else {
+                    None
+                };
+                {
+                    let mut state_guard = state.lock().unwrap();
+                    state_guard.customer_id = selected.map(|c| c.customer.id);
+                    // Sans client, les paiements à crédit ne sont plus possibles
+                    if state_guard.customer_id.is_none() {
+                        state_guard
+                            .payments
+                            .retain(|p| p.method != PaymentMethod::Credit);
+                    }
+                }
+                refresh_payment_ui(&d, &state);
+                d.set_customer_info(
+                    selected
+                        .map(|c| {
+                            format!(
+                                "Encours: {:.0} XAF — Plafond: {:.0} XAF — Disponible: {:.0} XAF",
+                                c.balance,
+                                c.customer.credit_limit,
+                                c.available_credit()
+                            )
+                        })
+                        .unwrap_or_default()
+                        .into(),
+                );
+                d.set_status_message("".into());
+            }
+        }
+    });
+
     // Callback pour ajouter un article au panier
     dialog.on_add_to_cart({
         let state = new_sale_state.clone();
@@ -480,6 +1150,19 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                     Ok(product) => {
                         // Vérifier le stock disponible
                         if quantity <= product.stock_in_sale_units as i32 {
+                            // Consigne du conditionnement, s'il y en a une
+                            let deposit_unit_amount = match product.deposit_item_id {
+                                Some(deposit_id) => match queries::get_deposit_item(deposit_id) {
+                                    Ok(deposit) => Some(deposit.deposit_amount),
+                                    Err(e) => {
+                                        d.set_status_message(
+                                            format!("Erreur lors de l'ajout: {}", e).into(),
+                                        );
+                                        return;
+                                    }
+                                },
+                                None => None,
+                            };
                             let mut state_guard = state.lock().unwrap();
                             state_guard.add_item(
                                 product_id_str.to_string(),
@@ -487,6 +1170,7 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                                 product.price_per_sale_unit,
                                 quantity,
                                 product.packaging_description,
+                                deposit_unit_amount,
                             );
 
                             // Mettre à jour l'UI
@@ -498,6 +1182,7 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                             d.set_cart_items(ModelRc::new(slint::VecModel::from(cart_items)));
                             d.set_total_amount(total.into());
                             d.set_status_message("".into());
+                            refresh_payment_ui(&d, &state);
                         } else {
                             d.set_status_message(
                                 format!(
@@ -533,6 +1218,7 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                 d.set_cart_items(ModelRc::new(slint::VecModel::from(cart_items)));
                 d.set_total_amount(total.into());
                 d.set_status_message("".into());
+                refresh_payment_ui(&d, &state);
             }
         }
     });
@@ -555,11 +1241,32 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                     d.set_cart_items(ModelRc::new(slint::VecModel::from(cart_items)));
                     d.set_total_amount(total.into());
                     d.set_status_message("".into());
+                    refresh_payment_ui(&d, &state);
                 }
             }
         }
     });
 
+    // Callback pour mettre à jour les vides rendus
+    dialog.on_update_cart_deposit_returned({
+        let state = new_sale_state.clone();
+        let dialog_weak = dialog.as_weak();
+        move |product_id_str, returned| {
+            if let Some(d) = dialog_weak.upgrade() {
+                let mut state_guard = state.lock().unwrap();
+                state_guard.update_deposit_returned(&product_id_str, returned);
+
+                let cart_items = state_guard.to_cart_ui_items();
+
+                drop(state_guard);
+
+                d.set_cart_items(ModelRc::new(slint::VecModel::from(cart_items)));
+                d.set_status_message("".into());
+                refresh_payment_ui(&d, &state);
+            }
+        }
+    });
+
     // Callback pour vider le panier
     dialog.on_clear_cart({
         let state = new_sale_state.clone();
@@ -574,6 +1281,55 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                 d.set_cart_items(ModelRc::new(slint::VecModel::default()));
                 d.set_total_amount("0".into());
                 d.set_status_message("".into());
+                refresh_payment_ui(&d, &state);
+            }
+        }
+    });
+
+    // Callback pour ajouter un paiement
+    dialog.on_add_payment({
+        let state = new_sale_state.clone();
+        let dialog_weak = dialog.as_weak();
+        move |method_index, amount_str, reference| {
+            if let Some(d) = dialog_weak.upgrade() {
+                match parse_payment(method_index, &amount_str, &reference) {
+                    Ok(payment) => {
+                        let mut state_guard = state.lock().unwrap();
+                        if payment.method == PaymentMethod::Credit
+                            && state_guard.customer_id.is_none()
+                        {
+                            drop(state_guard);
+                            d.set_status_message(
+                                "Sélectionnez un client pour un paiement à crédit".into(),
+                            );
+                            return;
+                        }
+                        state_guard.payments.push(payment);
+                        drop(state_guard);
+                        d.set_status_message("".into());
+                        refresh_payment_ui(&d, &state);
+                    }
+                    Err(msg) => d.set_status_message(msg.into()),
+                }
+            }
+        }
+    });
+
+    // Callback pour retirer un paiement
+    dialog.on_remove_payment({
+        let state = new_sale_state.clone();
+        let dialog_weak = dialog.as_weak();
+        move |index| {
+            if let Some(d) = dialog_weak.upgrade() {
+                {
+                    let mut state_guard = state.lock().unwrap();
+                    if let Ok(index) = usize::try_from(index)
+                        && index < state_guard.payments.len()
+                    {
+                        state_guard.payments.remove(index);
+                    }
+                }
+                refresh_payment_ui(&d, &state);
             }
         }
     });
@@ -592,8 +1348,11 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
         let main_ui_weak = main_ui.as_weak();
         move || {
             if let Some(d) = dialog_weak.upgrade() {
-                let state_guard = state.lock().unwrap();
+                let mut state_guard = state.lock().unwrap();
 
+                if state_guard.is_saving {
+                    return;
+                }
                 if state_guard.cart_items.is_empty() {
                     d.set_status_message("Veuillez ajouter au moins un article".into());
                     return;
@@ -602,41 +1361,67 @@ fn show_new_sale_dialog(main_ui: &ui::MainWindow, current_user_id: Uuid) {
                 // Préparer les données pour la sauvegarde
                 let sale_data = CreateSaleData {
                     user_id: current_user_id,
+                    customer_id: state_guard.customer_id,
                     items: state_guard
                         .cart_items
                         .values()
                         .map(|item| CreateSaleItemData {
                             product_id: Uuid::parse_str(&item.product_id).unwrap(),
                             quantity: item.quantity,
+                            deposit_returned: item.deposit_returned,
                         })
                         .collect(),
+                    payments: state_guard.payments.clone(),
                 };
 
+                state_guard.is_saving = true;
                 drop(state_guard);
+                d.set_status_message("Enregistrement de la vente...".into());
+
+                // Sauvegarder la vente en arrière-plan : le dialogue reste réactif pendant la transaction
+                let state = state.clone();
+                let dialog_weak = dialog_weak.clone();
+                background::spawn(
+                    &main_ui_weak,
+                    None,
+                    move || {
+                        queries::create_sale(sale_data).map(|receipt| {
+                            let printed = printer_config::auto_print_receipts().then(|| {
+                                printer_callbacks::print_receipt_on_default_printer(&receipt)
+                            });
+                            (receipt, printed)
+                        })
+                    },
+                    move |main_ui, result| {
+                        state.lock().unwrap().is_saving = false;
+                        match result {
+                            Ok((receipt, printed)) => {
+                                // Succès - fermer le dialogue et rafraîchir la liste
+                                if let Some(d) = dialog_weak.upgrade() {
+                                    let _ = d.hide();
+                                }
 
-                // Sauvegarder la vente
-                match queries::create_sale(sale_data) {
-                    Ok(receipt) => {
-                        // Succès - fermer le dialogue et rafraîchir la liste
-                        let _ = d.hide();
-
-                        if let Some(main_ui) = main_ui_weak.upgrade() {
-                            main_ui.invoke_request_sales();
+                                main_ui.invoke_request_sales();
 
-                            // Rafraîchir automatiquement le dashboard
-                            main_ui.invoke_refresh_dashboard();
+                                // Rafraîchir automatiquement le dashboard
+                                main_ui.invoke_refresh_dashboard();
 
-                            // Rafraîchir automatiquement du rapport de vente
-                            main_ui.invoke_request_report_data("7d".into());
+                                // Rafraîchir automatiquement du rapport de vente
+                                main_ui.invoke_refresh_report();
 
-                            // Afficher automatiquement le ticket de caisse
-                            show_receipt_dialog(receipt);
+                                // Afficher automatiquement le ticket de caisse (et l'imprimer si l'option est activée)
+                                show_receipt_dialog(&main_ui.as_weak(), receipt, printed);
+                            }
+                            Err(e) => {
+                                if let Some(d) = dialog_weak.upgrade() {
+                                    d.set_status_message(
+                                        format!("Erreur lors de la sauvegarde: {}", e).into(),
+                                    );
+                                }
+                            }
                         }
-                    }
-                    Err(e) => {
-                        d.set_status_message(format!("Erreur lors de la sauvegarde: {}", e).into());
-                    }
-                }
+                    },
+                );
             }
         }
     });
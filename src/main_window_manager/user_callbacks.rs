@@ -6,6 +6,7 @@ use uuid::Uuid;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use super::background::{self, LoadingState};
 use super::{show_info_dialog, show_error_dialog};
 
 /// Configure tous les callbacks liés à la gestion des utilisateurs pour la fenêtre principale.
@@ -34,40 +35,47 @@ pub fn setup(main_window_handle: &Weak<ui::MainWindow>, current_user_id: Uuid) {
                 state_borrow.1 = pagination.clone();
             }
 
-            match queries::user_queries::get_users_paginated(current_user_id, filter.clone(), pagination.clone()) {
-                Ok(result) => {
-                    let users_count = result.users.len();
-                    log::info!("Résultat de la requête: {} utilisateurs trouvés, page {}/{}, total: {}", 
-                              users_count, result.current_page, result.total_pages, result.total_count);
-                    
-                    let model = result.users
-                        .into_iter()
-                        .map(|u| {
-                            log::debug!("Utilisateur trouvé: {} ({})", u.name, u.role);
-                            ui::UserUI {
-                                id: u.id.to_string().into(),
-                                name: u.name.into(),
-                                role: u.role.into(),
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                    
-                    ui.set_users_model(std::rc::Rc::new(slint::VecModel::from(model)).into());
-                    
-                    // Mettre à jour les propriétés de la vue
-                    ui.set_user_current_page(result.current_page as i32);
-                    ui.set_user_total_pages(result.total_pages as i32);
-                    ui.set_user_total_users(result.total_count as i32);
-                    ui.set_users_per_page(result.per_page as i32);
-                    
-                    log::info!("UI mise à jour avec {} utilisateurs (page {}/{})", 
-                              users_count, result.current_page, result.total_pages);
-                }
-                Err(e) => {
-                    log::error!("Erreur lors du chargement des utilisateurs: {}", e);
-                    ui.set_users_model(std::rc::Rc::new(slint::VecModel::default()).into());
-                }
-            }
+            // La requête s'exécute en arrière-plan, la liste est mise à jour à son retour
+            let (filter, pagination) = (filter.clone(), pagination.clone());
+            background::spawn_latest(
+                &ui.as_weak(),
+                LoadingState::Users,
+                move || queries::user_queries::get_users_paginated(current_user_id, filter, pagination),
+                |ui, result| match result {
+                    Ok(result) => {
+                        let users_count = result.users.len();
+                        log::info!("Résultat de la requête: {} utilisateurs trouvés, page {}/{}, total: {}", 
+                                  users_count, result.current_page, result.total_pages, result.total_count);
+                        
+                        let model = result.users
+                            .into_iter()
+                            .map(|u| {
+                                log::debug!("Utilisateur trouvé: {} ({})", u.name, u.role);
+                                ui::UserUI {
+                                    id: u.id.to_string().into(),
+                                    name: u.name.into(),
+                                    role: u.role.into(),
+                                }
+                            })
+                            .collect::<Vec<_>>();
+                        
+                        ui.set_users_model(std::rc::Rc::new(slint::VecModel::from(model)).into());
+                        
+                        // Mettre à jour les propriétés de la vue
+                        ui.set_user_current_page(result.current_page as i32);
+                        ui.set_user_total_pages(result.total_pages as i32);
+                        ui.set_user_total_users(result.total_count as i32);
+                        ui.set_users_per_page(result.per_page as i32);
+                        
+                        log::info!("UI mise à jour avec {} utilisateurs (page {}/{})", 
+                                  users_count, result.current_page, result.total_pages);
+                    }
+                    Err(e) => {
+                        log::error!("Erreur lors du chargement des utilisateurs: {}", e);
+                        ui.set_users_model(std::rc::Rc::new(slint::VecModel::default()).into());
+                    }
+                },
+            );
         }
     };
 
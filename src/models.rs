@@ -1,6 +1,11 @@
 // src/models.rs
 
-use crate::schema::{products, sale_items, sales, users};
+use crate::schema::{
+    cash_movements, cash_sessions, commission_rules, credit_note_items, credit_notes,
+    customer_payments, customers, deposit_items, inventory_lines, inventory_sessions, payments,
+    product_import_lines, product_imports, products, purchase_order_lines, purchase_orders,
+    sale_items, sales, stock_movements, suppliers, users,
+};
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
@@ -22,6 +27,21 @@ pub struct Product {
     pub price_per_sale_unit: BigDecimal,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deposit_item_id: Option<Uuid>,
+    /// Prix de revient unitaire : saisi sur la fiche produit et mis à jour
+    /// au dernier prix d'achat à la réception d'une commande fournisseur.
+    pub cost_price: Option<BigDecimal>,
+}
+
+impl Product {
+    /// Taux de marge brute sur le prix de vente, en pourcentage (`None` si le coût est inconnu).
+    pub fn margin_rate(&self) -> Option<BigDecimal> {
+        let cost = self.cost_price.as_ref()?;
+        if self.price_per_sale_unit == BigDecimal::from(0) {
+            return None;
+        }
+        Some((&self.price_per_sale_unit - cost) * BigDecimal::from(100) / &self.price_per_sale_unit)
+    }
 }
 
 /// Pour insérer un nouveau produit.
@@ -34,6 +54,34 @@ pub struct NewProduct {
     pub sku: Option<String>,
     pub stock_in_sale_units: i32,
     pub price_per_sale_unit: BigDecimal,
+    pub deposit_item_id: Option<Uuid>,
+    pub cost_price: Option<BigDecimal>,
+}
+
+//===================//
+//   DEPOSIT ITEMS   //
+//===================//
+// Représente la consigne d'un conditionnement (casier, palette...).
+#[derive(Queryable, Selectable, Identifiable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = deposit_items)]
+pub struct DepositItem {
+    pub id: Uuid,
+    pub packaging_description: String,
+    pub deposit_amount: BigDecimal,
+    /// Emballages vides physiquement présents au dépôt.
+    pub empties_in_stock: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Pour insérer une nouvelle consigne.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = deposit_items)]
+pub struct NewDepositItem {
+    pub id: Uuid,
+    pub packaging_description: String,
+    pub deposit_amount: BigDecimal,
+    pub empties_in_stock: i32,
 }
 
 //============//
@@ -51,6 +99,75 @@ pub struct Sale {
     pub date: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub customer_id: Option<Uuid>,
+    /// Total net des consignes (facturées - remboursées), hors chiffre d'affaires.
+    pub deposit_total: BigDecimal,
+    pub status: String,
+    /// Motif de l'annulation, pour une vente annulée.
+    pub void_reason: Option<String>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub voided_by: Option<Uuid>,
+    /// Session de caisse qui a rendu les espèces de la vente annulée.
+    pub void_cash_session_id: Option<Uuid>,
+}
+
+impl Sale {
+    /// Montant à régler par le client : marchandises + consignes nettes.
+    pub fn amount_due(&self) -> BigDecimal {
+        &self.total_amount + &self.deposit_total
+    }
+
+    pub fn status(&self) -> Option<SaleStatus> {
+        SaleStatus::from_db(&self.status)
+    }
+
+    pub fn is_voided(&self) -> bool {
+        self.status() == Some(SaleStatus::Voided)
+    }
+}
+
+/// État d'une vente. Une vente n'est jamais supprimée : elle est annulée ou reprise par avoir.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SaleStatus {
+    Completed,
+    /// Une partie des articles a été reprise par avoir.
+    PartiallyReturned,
+    /// Tous les articles ont été repris par avoir.
+    Returned,
+    /// Vente annulée par un administrateur, exclue du chiffre d'affaires.
+    Voided,
+}
+
+impl SaleStatus {
+    pub const ALL: [SaleStatus; 4] = [
+        SaleStatus::Completed,
+        SaleStatus::PartiallyReturned,
+        SaleStatus::Returned,
+        SaleStatus::Voided,
+    ];
+
+    /// Valeur stockée dans la colonne `sales.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SaleStatus::Completed => "completed",
+            SaleStatus::PartiallyReturned => "partially_returned",
+            SaleStatus::Returned => "returned",
+            SaleStatus::Voided => "voided",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SaleStatus::Completed => "Validée",
+            SaleStatus::PartiallyReturned => "Retour partiel",
+            SaleStatus::Returned => "Retournée",
+            SaleStatus::Voided => "Annulée",
+        }
+    }
 }
 
 /// Pour insérer une nouvelle vente.
@@ -62,6 +179,8 @@ pub struct NewSale {
     pub sale_number: String,
     pub total_amount: BigDecimal,
     pub date: DateTime<Utc>,
+    pub customer_id: Option<Uuid>,
+    pub deposit_total: BigDecimal,
 }
 
 //================//
@@ -81,6 +200,25 @@ pub struct SaleItem {
     pub total_price: BigDecimal,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deposit_item_id: Option<Uuid>,
+    pub deposit_unit_amount: BigDecimal,
+    /// Emballages consignés sortis avec la marchandise.
+    pub deposit_quantity_out: i32,
+    /// Emballages vides rendus par l'acheteur.
+    pub deposit_quantity_returned: i32,
+    /// Consigne nette de la ligne : (sortis - rendus) x consigne unitaire.
+    pub deposit_amount: BigDecimal,
+    /// Quantité déjà reprise par avoir.
+    pub quantity_returned: i32,
+    /// Coût d'achat unitaire au moment de la vente (`None` si inconnu).
+    pub unit_cost: Option<BigDecimal>,
+}
+
+impl SaleItem {
+    /// Quantité pouvant encore être reprise par avoir.
+    pub fn quantity_returnable(&self) -> i32 {
+        self.quantity - self.quantity_returned
+    }
 }
 
 /// Pour insérer un nouvel article de vente.
@@ -93,6 +231,566 @@ pub struct NewSaleItem {
     pub quantity: i32,
     pub unit_price: BigDecimal,
     pub total_price: BigDecimal,
+    pub deposit_item_id: Option<Uuid>,
+    pub deposit_unit_amount: BigDecimal,
+    pub deposit_quantity_out: i32,
+    pub deposit_quantity_returned: i32,
+    pub deposit_amount: BigDecimal,
+    pub unit_cost: Option<BigDecimal>,
+}
+
+//==================//
+//   CREDIT NOTES   //
+//==================//
+// Représente un avoir : reprise partielle ou totale des articles d'une vente.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Sale))]
+#[diesel(table_name = credit_notes)]
+pub struct CreditNote {
+    pub id: Uuid,
+    pub credit_note_number: String,
+    pub sale_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    /// Montant des marchandises reprises, remboursé ou crédité sur le compte du client.
+    pub total_amount: BigDecimal,
+    pub date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Session de caisse qui a remboursé la part de l'avoir rendue en espèces.
+    pub cash_session_id: Option<Uuid>,
+}
+
+/// Pour insérer un nouvel avoir.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = credit_notes)]
+pub struct NewCreditNote {
+    pub id: Uuid,
+    pub credit_note_number: String,
+    pub sale_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub total_amount: BigDecimal,
+    pub date: DateTime<Utc>,
+    pub cash_session_id: Option<Uuid>,
+}
+
+// Représente un article repris sur un avoir.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(CreditNote))]
+#[diesel(belongs_to(SaleItem))]
+#[diesel(table_name = credit_note_items)]
+pub struct CreditNoteItem {
+    pub id: Uuid,
+    pub credit_note_id: Uuid,
+    pub sale_item_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    pub total_price: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Pour insérer un nouvel article d'avoir.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = credit_note_items)]
+pub struct NewCreditNoteItem {
+    pub id: Uuid,
+    pub credit_note_id: Uuid,
+    pub sale_item_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    pub total_price: BigDecimal,
+}
+
+//===============//
+//   CUSTOMERS   //
+//===============//
+// Représente un client (bar, boutique...) pouvant acheter à crédit.
+#[derive(Queryable, Selectable, Identifiable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = customers)]
+pub struct Customer {
+    pub id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub credit_limit: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Pour insérer un nouveau client.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = customers)]
+pub struct NewCustomer {
+    pub id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub credit_limit: BigDecimal,
+}
+
+// Représente un règlement effectué par un client sur son encours.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Customer))]
+#[diesel(table_name = customer_payments)]
+pub struct CustomerPayment {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: BigDecimal,
+    pub note: Option<String>,
+    pub date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Moyen de paiement (`payments.method`), jamais "crédit client".
+    pub method: String,
+}
+
+/// Pour insérer un nouveau règlement client.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = customer_payments)]
+pub struct NewCustomerPayment {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: BigDecimal,
+    pub note: Option<String>,
+    pub date: DateTime<Utc>,
+    pub method: String,
+}
+
+//==============//
+//   PAYMENTS   //
+//==============//
+/// Moyen de paiement accepté à la caisse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    Cash,
+    MobileMoney,
+    Cheque,
+    /// Montant porté au compte du client (vente à crédit).
+    Credit,
+}
+
+impl PaymentMethod {
+    /// Ordre d'affichage des moyens de paiement dans l'interface.
+    pub const ALL: [PaymentMethod; 4] = [
+        PaymentMethod::Cash,
+        PaymentMethod::MobileMoney,
+        PaymentMethod::Cheque,
+        PaymentMethod::Credit,
+    ];
+
+    /// Valeur stockée dans la colonne `payments.method`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "cash",
+            PaymentMethod::MobileMoney => "mobile_money",
+            PaymentMethod::Cheque => "cheque",
+            PaymentMethod::Credit => "credit",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == value)
+    }
+
+    /// Libellé affiché à l'écran et sur le ticket.
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "Espèces",
+            PaymentMethod::MobileMoney => "Mobile Money",
+            PaymentMethod::Cheque => "Chèque",
+            PaymentMethod::Credit => "Crédit client",
+        }
+    }
+}
+
+/// État de règlement d'une vente, déduit des paiements enregistrés.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PaymentStatus {
+    /// Entièrement réglée (espèces, Mobile Money, chèque).
+    Paid,
+    /// Couverte, mais tout ou partie est porté au compte du client.
+    OnAccount,
+    /// Un acompte a été versé, il reste un montant à encaisser.
+    Partial,
+    /// Aucun paiement enregistré.
+    Unpaid,
+}
+
+impl PaymentStatus {
+    /// Déduit l'état à partir du total de la vente, des montants encaissés et du montant en compte.
+    pub fn derive(total: &BigDecimal, settled: &BigDecimal, on_account: &BigDecimal) -> Self {
+        let covered = settled + on_account;
+        if settled >= total {
+            PaymentStatus::Paid
+        } else if &covered >= total {
+            PaymentStatus::OnAccount
+        } else if covered > BigDecimal::from(0) {
+            PaymentStatus::Partial
+        } else {
+            PaymentStatus::Unpaid
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentStatus::Paid => "Payée",
+            PaymentStatus::OnAccount => "En compte",
+            PaymentStatus::Partial => "Partielle",
+            PaymentStatus::Unpaid => "Impayée",
+        }
+    }
+}
+
+// Représente un paiement (une "tenue") imputé sur une vente.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Sale))]
+#[diesel(table_name = payments)]
+pub struct Payment {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub method: String,
+    pub amount: BigDecimal,
+    pub cash_tendered: Option<BigDecimal>,
+    pub change_due: BigDecimal,
+    pub reference: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Payment {
+    pub fn method(&self) -> Option<PaymentMethod> {
+        PaymentMethod::from_db(&self.method)
+    }
+}
+
+/// Pour insérer un nouveau paiement.
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = payments)]
+pub struct NewPayment {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub method: String,
+    pub amount: BigDecimal,
+    pub cash_tendered: Option<BigDecimal>,
+    pub change_due: BigDecimal,
+    pub reference: Option<String>,
+}
+
+/// Récapitulatif des paiements d'une vente.
+#[derive(Debug, Clone)]
+pub struct PaymentSummary {
+    /// Montant effectivement encaissé (hors crédit client).
+    pub settled: BigDecimal,
+    /// Montant porté au compte du client.
+    pub on_account: BigDecimal,
+    /// Monnaie totale rendue au client.
+    pub change_due: BigDecimal,
+    /// Montant non couvert restant à encaisser.
+    pub remaining: BigDecimal,
+    pub status: PaymentStatus,
+}
+
+impl PaymentSummary {
+    pub fn from_payments(total: &BigDecimal, payments: &[Payment]) -> Self {
+        let zero = BigDecimal::from(0);
+        let mut settled = zero.clone();
+        let mut on_account = zero.clone();
+        let mut change_due = zero.clone();
+        for payment in payments {
+            if payment.method() == Some(PaymentMethod::Credit) {
+                on_account += &payment.amount;
+            } else {
+                settled += &payment.amount;
+            }
+            change_due += &payment.change_due;
+        }
+        let remaining = total - &settled - &on_account;
+        let remaining = if remaining < zero { zero } else { remaining };
+        let status = PaymentStatus::derive(total, &settled, &on_account);
+        PaymentSummary {
+            settled,
+            on_account,
+            change_due,
+            remaining,
+            status,
+        }
+    }
+}
+
+//===============//
+//   SUPPLIERS   //
+//===============//
+// Représente un fournisseur (brasserie, distributeur...).
+#[derive(Queryable, Selectable, Identifiable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = suppliers)]
+pub struct Supplier {
+    pub id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Pour insérer un nouveau fournisseur.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = suppliers)]
+pub struct NewSupplier {
+    pub id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+}
+
+//=====================//
+//   PURCHASE ORDERS   //
+//=====================//
+/// État d'un bon de commande fournisseur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PurchaseOrderStatus {
+    /// En préparation, modifiable.
+    Draft,
+    /// Transmis au fournisseur, en attente de livraison.
+    Sent,
+    /// Une partie des quantités a été réceptionnée.
+    PartiallyReceived,
+    /// Toutes les quantités ont été réceptionnées.
+    Received,
+}
+
+impl PurchaseOrderStatus {
+    pub const ALL: [PurchaseOrderStatus; 4] = [
+        PurchaseOrderStatus::Draft,
+        PurchaseOrderStatus::Sent,
+        PurchaseOrderStatus::PartiallyReceived,
+        PurchaseOrderStatus::Received,
+    ];
+
+    /// Valeur stockée dans la colonne `purchase_orders.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PurchaseOrderStatus::Draft => "draft",
+            PurchaseOrderStatus::Sent => "sent",
+            PurchaseOrderStatus::PartiallyReceived => "partially_received",
+            PurchaseOrderStatus::Received => "received",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PurchaseOrderStatus::Draft => "Brouillon",
+            PurchaseOrderStatus::Sent => "Envoyée",
+            PurchaseOrderStatus::PartiallyReceived => "Reçue partiellement",
+            PurchaseOrderStatus::Received => "Reçue",
+        }
+    }
+
+    /// Une livraison peut être réceptionnée sur la commande.
+    pub fn can_receive(&self) -> bool {
+        matches!(
+            self,
+            PurchaseOrderStatus::Sent | PurchaseOrderStatus::PartiallyReceived
+        )
+    }
+}
+
+// Représente un bon de commande fournisseur.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Supplier))]
+#[diesel(table_name = purchase_orders)]
+pub struct PurchaseOrder {
+    pub id: Uuid,
+    pub po_number: String,
+    pub supplier_id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub order_date: DateTime<Utc>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PurchaseOrder {
+    pub fn status(&self) -> Option<PurchaseOrderStatus> {
+        PurchaseOrderStatus::from_db(&self.status)
+    }
+}
+
+/// Pour insérer un nouveau bon de commande.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = purchase_orders)]
+pub struct NewPurchaseOrder {
+    pub id: Uuid,
+    pub po_number: String,
+    pub supplier_id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub order_date: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+// Représente une ligne de bon de commande.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(PurchaseOrder))]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = purchase_order_lines)]
+pub struct PurchaseOrderLine {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity_ordered: i32,
+    pub quantity_received: i32,
+    pub unit_cost: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PurchaseOrderLine {
+    /// Quantité restant à livrer.
+    pub fn quantity_remaining(&self) -> i32 {
+        self.quantity_ordered - self.quantity_received
+    }
+}
+
+/// Pour insérer une nouvelle ligne de bon de commande.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = purchase_order_lines)]
+pub struct NewPurchaseOrderLine {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity_ordered: i32,
+    pub unit_cost: BigDecimal,
+}
+
+/// Données pour créer un bon de commande.
+#[derive(Debug, Clone)]
+pub struct CreatePurchaseOrderData {
+    pub supplier_id: Uuid,
+    pub user_id: Uuid,
+    pub note: Option<String>,
+    pub lines: Vec<CreatePurchaseOrderLineData>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatePurchaseOrderLineData {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_cost: BigDecimal,
+}
+
+/// Quantité livrée sur une ligne lors d'une réception.
+#[derive(Debug, Clone)]
+pub struct ReceiveLineData {
+    pub line_id: Uuid,
+    pub quantity: i32,
+    /// Prix d'achat facturé, s'il diffère de celui de la commande.
+    pub unit_cost: Option<BigDecimal>,
+}
+
+//=====================//
+//   STOCK MOVEMENTS   //
+//=====================//
+
+/// Motif d'un mouvement de stock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StockMovementReason {
+    /// Sortie de marchandise vendue.
+    Sale,
+    /// Entrée d'une livraison fournisseur.
+    Receipt,
+    /// Correction manuelle (saisie produit, import, solde d'ouverture).
+    Adjustment,
+    /// Retour de marchandise par un client.
+    Return,
+    /// Casse ou perte.
+    Breakage,
+    /// Écart constaté lors d'un inventaire physique.
+    Inventory,
+}
+
+impl StockMovementReason {
+    pub const ALL: [StockMovementReason; 6] = [
+        StockMovementReason::Sale,
+        StockMovementReason::Receipt,
+        StockMovementReason::Adjustment,
+        StockMovementReason::Return,
+        StockMovementReason::Breakage,
+        StockMovementReason::Inventory,
+    ];
+
+    /// Valeur stockée dans la colonne `stock_movements.reason`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMovementReason::Sale => "sale",
+            StockMovementReason::Receipt => "receipt",
+            StockMovementReason::Adjustment => "adjustment",
+            StockMovementReason::Return => "return",
+            StockMovementReason::Breakage => "breakage",
+            StockMovementReason::Inventory => "inventory",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StockMovementReason::Sale => "Vente",
+            StockMovementReason::Receipt => "Réception",
+            StockMovementReason::Adjustment => "Ajustement",
+            StockMovementReason::Return => "Retour",
+            StockMovementReason::Breakage => "Casse",
+            StockMovementReason::Inventory => "Inventaire",
+        }
+    }
+}
+
+// Représente une ligne du journal des mouvements de stock (jamais modifiée après insertion).
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = stock_movements)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub reason: String,
+    pub quantity_delta: i32,
+    pub stock_after: i32,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl StockMovement {
+    pub fn reason(&self) -> Option<StockMovementReason> {
+        StockMovementReason::from_db(&self.reason)
+    }
+}
+
+/// Pour insérer un nouveau mouvement de stock.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = stock_movements)]
+pub struct NewStockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub reason: String,
+    pub quantity_delta: i32,
+    pub stock_after: i32,
+    pub reference: Option<String>,
+    pub note: Option<String>,
 }
 
 //==============================//
@@ -106,6 +804,8 @@ pub struct SaleWithItems {
     pub sale: Sale,
     pub items: Vec<(SaleItem, Product)>,
     pub seller_name: String,
+    pub payments: Vec<Payment>,
+    pub credit_notes: Vec<CreditNote>,
 }
 
 /// Contient toutes les informations nécessaires pour créer une nouvelle vente en base de données.
@@ -113,7 +813,12 @@ pub struct SaleWithItems {
 #[derive(Debug, Clone)]
 pub struct CreateSaleData {
     pub user_id: Uuid,
+    /// Client auquel la vente est portée en compte (vente à crédit). `None` pour une vente comptant.
+    pub customer_id: Option<Uuid>,
     pub items: Vec<CreateSaleItemData>,
+    /// Paiements saisis à la caisse. Si la liste est vide, la vente est réglée intégralement
+    /// en espèces (ou portée en compte si un client est sélectionné).
+    pub payments: Vec<CreatePaymentData>,
 }
 
 /// Représente un article à insérer dans le cadre d'une nouvelle vente.
@@ -121,6 +826,35 @@ pub struct CreateSaleData {
 pub struct CreateSaleItemData {
     pub product_id: Uuid,
     pub quantity: i32,
+    /// Emballages vides rendus par l'acheteur sur cette ligne (remboursés ou déduits de sa dette).
+    pub deposit_returned: i32,
+}
+
+/// Données pour enregistrer un retour de marchandise (avoir) sur une vente.
+#[derive(Debug, Clone)]
+pub struct CreateCreditNoteData {
+    pub sale_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub items: Vec<ReturnItemData>,
+}
+
+/// Quantité reprise sur une ligne de vente.
+#[derive(Debug, Clone)]
+pub struct ReturnItemData {
+    pub sale_item_id: Uuid,
+    pub quantity: i32,
+}
+
+/// Représente un paiement saisi à la caisse.
+#[derive(Debug, Clone)]
+pub struct CreatePaymentData {
+    pub method: PaymentMethod,
+    /// Montant remis par le client. Pour les espèces, l'excédent par rapport
+    /// au reste à payer est rendu en monnaie.
+    pub amount: BigDecimal,
+    /// N° de transaction Mobile Money, n° de chèque...
+    pub reference: Option<String>,
 }
 
 //==============================//
@@ -133,8 +867,21 @@ pub struct Receipt {
     pub sale_number: String,
     pub date: String,
     pub seller_name: String,
+    pub customer_name: Option<String>,
     pub items: Vec<ReceiptItem>,
     pub total_amount: BigDecimal,
+    /// Consignes nettes de la vente (négatif si les vides rendus dépassent les emballages sortis).
+    pub deposit_total: BigDecimal,
+    /// Total à payer : marchandises + consignes.
+    pub amount_due: BigDecimal,
+    /// Emballages encore dus par le client après cette vente (clients nommés uniquement).
+    pub empties_owed: Vec<ReceiptEmpties>,
+    pub payments: Vec<ReceiptPayment>,
+    pub amount_paid: BigDecimal,
+    pub change_due: BigDecimal,
+    /// Reste dû (montant en compte client ou non encore encaissé).
+    pub balance_due: BigDecimal,
+    pub payment_status: String,
 }
 
 /// Représente un article sur le ticket de caisse.
@@ -145,6 +892,26 @@ pub struct ReceiptItem {
     pub quantity: i32,
     pub unit_price: BigDecimal,
     pub total_price: BigDecimal,
+    pub deposit_quantity_out: i32,
+    pub deposit_quantity_returned: i32,
+    pub deposit_amount: BigDecimal,
+}
+
+/// Emballages vides dus par un client, par conditionnement.
+#[derive(Debug, Serialize)]
+pub struct ReceiptEmpties {
+    pub packaging_description: String,
+    pub quantity: i64,
+}
+
+/// Représente un paiement sur le ticket de caisse.
+#[derive(Debug, Serialize)]
+pub struct ReceiptPayment {
+    pub method: String,
+    pub amount: BigDecimal,
+    pub cash_tendered: Option<BigDecimal>,
+    pub change_due: BigDecimal,
+    pub reference: Option<String>,
 }
 
 //===========//
@@ -173,3 +940,423 @@ pub struct NewUser<'a> {
     pub role: &'a str,
     pub must_change_password: bool,
 }
+
+//======================//
+//   COMMISSION RULES   //
+//======================//
+
+/// Mode de calcul de la commission d'un vendeur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommissionKind {
+    /// Pourcentage du chiffre d'affaires net des retours.
+    Percent,
+    /// Montant fixe par unité de vente (casier, palette...) vendue.
+    PerUnit,
+}
+
+impl CommissionKind {
+    pub const ALL: [CommissionKind; 2] = [CommissionKind::Percent, CommissionKind::PerUnit];
+
+    /// Valeur stockée dans la colonne `commission_rules.kind`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommissionKind::Percent => "percent",
+            CommissionKind::PerUnit => "per_unit",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CommissionKind::Percent => "% du chiffre d'affaires",
+            CommissionKind::PerUnit => "XAF par unité vendue",
+        }
+    }
+}
+
+// Représente une règle de commission, propre à un vendeur ou par défaut (`user_id` NULL).
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = commission_rules)]
+pub struct CommissionRule {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub kind: String,
+    pub rate: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CommissionRule {
+    pub fn kind(&self) -> Option<CommissionKind> {
+        CommissionKind::from_db(&self.kind)
+    }
+
+    /// Commission due pour un chiffre d'affaires et un nombre d'unités vendues.
+    pub fn commission(&self, revenue: &BigDecimal, units: i64) -> BigDecimal {
+        match self.kind() {
+            Some(CommissionKind::Percent) => revenue * &self.rate / BigDecimal::from(100),
+            Some(CommissionKind::PerUnit) => &self.rate * BigDecimal::from(units),
+            None => BigDecimal::from(0),
+        }
+    }
+
+    /// Description de la règle, par exemple « 2 % du chiffre d'affaires ».
+    pub fn describe(&self) -> String {
+        match self.kind() {
+            Some(kind) => format!("{} {}", self.rate.normalized(), kind.label()),
+            None => format!("Règle inconnue ({})", self.kind),
+        }
+    }
+}
+
+/// Pour insérer une nouvelle règle de commission.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = commission_rules)]
+pub struct NewCommissionRule {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub kind: String,
+    pub rate: BigDecimal,
+}
+
+//===================//
+//   CASH SESSIONS   //
+//===================//
+
+// Représente une session de caisse, de l'ouverture avec un fond de caisse à la clôture.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = cash_sessions)]
+pub struct CashSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub opened_at: DateTime<Utc>,
+    pub opening_float: BigDecimal,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closed_by: Option<Uuid>,
+    /// Espèces attendues, figées à la clôture.
+    pub expected_cash: Option<BigDecimal>,
+    /// Espèces comptées par le caissier à la clôture.
+    pub counted_cash: Option<BigDecimal>,
+    pub closing_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CashSession {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+
+    /// Écart de caisse (compté - attendu) d'une session clôturée : négatif s'il manque des espèces.
+    pub fn variance(&self) -> Option<BigDecimal> {
+        match (&self.counted_cash, &self.expected_cash) {
+            (Some(counted), Some(expected)) => Some(counted - expected),
+            _ => None,
+        }
+    }
+}
+
+/// Pour ouvrir une nouvelle session de caisse.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = cash_sessions)]
+pub struct NewCashSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub opened_at: DateTime<Utc>,
+    pub opening_float: BigDecimal,
+}
+
+/// Sens d'un mouvement d'espèces hors ventes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CashMovementKind {
+    /// Apport d'espèces (monnaie, règlement d'un client...).
+    In,
+    /// Retrait d'espèces (dépense, remboursement, dépôt en banque...).
+    Out,
+}
+
+impl CashMovementKind {
+    pub const ALL: [CashMovementKind; 2] = [CashMovementKind::In, CashMovementKind::Out];
+
+    /// Valeur stockée dans la colonne `cash_movements.kind`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CashMovementKind::In => "in",
+            CashMovementKind::Out => "out",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CashMovementKind::In => "Entrée",
+            CashMovementKind::Out => "Sortie",
+        }
+    }
+}
+
+// Représente une entrée ou une sortie d'espèces hors ventes (jamais modifiée après insertion).
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(CashSession))]
+#[diesel(table_name = cash_movements)]
+pub struct CashMovement {
+    pub id: Uuid,
+    pub cash_session_id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub amount: BigDecimal,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CashMovement {
+    pub fn kind(&self) -> Option<CashMovementKind> {
+        CashMovementKind::from_db(&self.kind)
+    }
+
+    /// Montant signé : positif pour une entrée, négatif pour une sortie.
+    pub fn signed_amount(&self) -> BigDecimal {
+        match self.kind() {
+            Some(CashMovementKind::Out) => -self.amount.clone(),
+            _ => self.amount.clone(),
+        }
+    }
+}
+
+/// Pour enregistrer un mouvement d'espèces.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = cash_movements)]
+pub struct NewCashMovement {
+    pub id: Uuid,
+    pub cash_session_id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub amount: BigDecimal,
+    pub reason: String,
+}
+
+//===================//
+//  PRODUCT IMPORTS  //
+//===================//
+
+// Représente un import en masse du catalogue depuis un fichier CSV ou XLSX.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = product_imports)]
+pub struct ProductImport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub file_name: String,
+    pub rows_created: i32,
+    pub rows_updated: i32,
+    pub rows_unchanged: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pour journaliser un import.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = product_imports)]
+pub struct NewProductImport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub file_name: String,
+    pub rows_created: i32,
+    pub rows_updated: i32,
+    pub rows_unchanged: i32,
+}
+
+/// Effet d'une ligne du fichier importé sur le catalogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportAction {
+    /// Le produit n'existait pas et a été créé.
+    Created,
+    /// Le produit existant a été modifié.
+    Updated,
+    /// La ligne correspond déjà au produit existant.
+    Unchanged,
+}
+
+impl ImportAction {
+    pub const ALL: [ImportAction; 3] = [
+        ImportAction::Created,
+        ImportAction::Updated,
+        ImportAction::Unchanged,
+    ];
+
+    /// Valeur stockée dans la colonne `product_import_lines.action`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportAction::Created => "created",
+            ImportAction::Updated => "updated",
+            ImportAction::Unchanged => "unchanged",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportAction::Created => "Création",
+            ImportAction::Updated => "Mise à jour",
+            ImportAction::Unchanged => "Inchangé",
+        }
+    }
+}
+
+// Représente un produit créé ou modifié par un import, avec le détail des changements.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(ProductImport))]
+#[diesel(table_name = product_import_lines)]
+pub struct ProductImportLine {
+    pub id: Uuid,
+    pub product_import_id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub line_number: i32,
+    pub action: String,
+    pub changes: String,
+}
+
+/// Pour journaliser une ligne d'import.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = product_import_lines)]
+pub struct NewProductImportLine {
+    pub id: Uuid,
+    pub product_import_id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub line_number: i32,
+    pub action: String,
+    pub changes: String,
+}
+
+//===================//
+//    INVENTORIES    //
+//===================//
+
+/// État d'un inventaire physique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InventoryStatus {
+    /// Comptage en cours : les quantités peuvent encore être saisies.
+    Open,
+    /// Inventaire validé : les écarts ont été passés au journal des mouvements.
+    Approved,
+    /// Inventaire abandonné, sans effet sur le stock.
+    Cancelled,
+}
+
+impl InventoryStatus {
+    pub const ALL: [InventoryStatus; 3] = [
+        InventoryStatus::Open,
+        InventoryStatus::Approved,
+        InventoryStatus::Cancelled,
+    ];
+
+    /// Valeur stockée dans la colonne `inventory_sessions.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InventoryStatus::Open => "open",
+            InventoryStatus::Approved => "approved",
+            InventoryStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InventoryStatus::Open => "En cours",
+            InventoryStatus::Approved => "Validé",
+            InventoryStatus::Cancelled => "Abandonné",
+        }
+    }
+}
+
+// Représente un inventaire physique, de l'ouverture (stock théorique figé) à la validation.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = inventory_sessions)]
+pub struct InventorySession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub note: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl InventorySession {
+    pub fn status(&self) -> Option<InventoryStatus> {
+        InventoryStatus::from_db(&self.status)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status() == Some(InventoryStatus::Open)
+    }
+}
+
+/// Pour ouvrir un inventaire.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = inventory_sessions)]
+pub struct NewInventorySession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub note: Option<String>,
+}
+
+// Représente le comptage d'un produit lors d'un inventaire.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(InventorySession))]
+#[diesel(table_name = inventory_lines)]
+pub struct InventoryLine {
+    pub id: Uuid,
+    pub inventory_session_id: Uuid,
+    pub product_id: Uuid,
+    /// Stock théorique figé à l'ouverture de l'inventaire.
+    pub expected_stock: i32,
+    /// Quantité comptée, `None` tant que le produit n'a pas été compté.
+    pub counted_stock: Option<i32>,
+    pub unit_cost: Option<BigDecimal>,
+    pub unit_price: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl InventoryLine {
+    /// Écart (compté - théorique) : négatif pour une perte, `None` si le produit n'est pas compté.
+    pub fn variance(&self) -> Option<i32> {
+        self.counted_stock
+            .map(|counted| counted - self.expected_stock)
+    }
+
+    /// Valeur de l'écart au prix de revient (`None` si non compté ou coût inconnu).
+    pub fn variance_cost(&self) -> Option<BigDecimal> {
+        Some(BigDecimal::from(self.variance()?) * self.unit_cost.as_ref()?)
+    }
+
+    /// Valeur de l'écart au prix de vente.
+    pub fn variance_value(&self) -> Option<BigDecimal> {
+        Some(BigDecimal::from(self.variance()?) * &self.unit_price)
+    }
+}
+
+/// Pour figer le stock théorique d'un produit à l'ouverture.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = inventory_lines)]
+pub struct NewInventoryLine {
+    pub id: Uuid,
+    pub inventory_session_id: Uuid,
+    pub product_id: Uuid,
+    pub expected_stock: i32,
+    pub unit_cost: Option<BigDecimal>,
+    pub unit_price: BigDecimal,
+}
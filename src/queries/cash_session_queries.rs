@@ -0,0 +1,620 @@
+// src/queries/cash_session_queries.rs
+
+use super::credit_note_queries::load_credit_note_refunds;
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{
+        CashMovement, CashMovementKind, CashSession, NewCashMovement, NewCashSession,
+        PaymentMethod, SaleStatus,
+    },
+    schema::{
+        cash_movements, cash_sessions, credit_notes, customer_payments, payments, sales, users,
+    },
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Encaissements d'un moyen de paiement sur une session de caisse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenderLine {
+    pub label: String,
+    pub count: i64,
+    pub amount: BigDecimal,
+}
+
+/// Rapport Z d'une session de caisse : ventes, encaissements, mouvements d'espèces et écart.
+/// Pour une session encore ouverte, il est calculé à l'instant `period_end` (rapport X).
+#[derive(Debug, Clone)]
+pub struct ZReport {
+    pub session: CashSession,
+    pub cashier_name: String,
+    pub closed_by_name: Option<String>,
+    pub period_end: DateTime<Utc>,
+    pub sales_count: i64,
+    pub sales_total: BigDecimal, // Montant des ventes non annulées, hors consignes
+    pub tenders: Vec<TenderLine>, // Encaissements par moyen de paiement
+    pub cash_sales: BigDecimal,  // Espèces encaissées sur les ventes
+    pub customer_cash_payments: BigDecimal, // Règlements d'encours clients en espèces
+    pub cash_refunds: BigDecimal, // Avoirs remboursés en espèces par ce tiroir
+    pub voided_sales: i64,       // Ventes annulées dont ce tiroir a rendu les espèces
+    pub void_refunds: BigDecimal, // Espèces rendues sur ces ventes annulées
+    pub movements: Vec<CashMovement>,
+    pub expected_cash: BigDecimal,
+}
+
+impl ZReport {
+    pub fn cash_in(&self) -> BigDecimal {
+        self.movements_total(CashMovementKind::In)
+    }
+
+    pub fn cash_out(&self) -> BigDecimal {
+        self.movements_total(CashMovementKind::Out)
+    }
+
+    fn movements_total(&self, kind: CashMovementKind) -> BigDecimal {
+        self.movements
+            .iter()
+            .filter(|m| m.kind() == Some(kind))
+            .map(|m| &m.amount)
+            .sum()
+    }
+
+    /// Écart entre les espèces comptées et attendues, une fois la session clôturée.
+    pub fn variance(&self) -> Option<BigDecimal> {
+        self.session
+            .counted_cash
+            .as_ref()
+            .map(|counted| counted - &self.expected_cash)
+    }
+}
+
+/// Espèces attendues dans le tiroir : fond de caisse + ventes et règlements clients en espèces
+/// - avoirs et ventes annulées remboursés en espèces + entrées - sorties.
+pub fn compute_expected_cash(
+    opening_float: &BigDecimal,
+    cash_sales: &BigDecimal,
+    customer_cash_payments: &BigDecimal,
+    cash_refunds: &BigDecimal,
+    void_refunds: &BigDecimal,
+    movements: &[CashMovement],
+) -> BigDecimal {
+    let movements: BigDecimal = movements.iter().map(|m| m.signed_amount()).sum();
+    opening_float + cash_sales + customer_cash_payments - cash_refunds - void_refunds + movements
+}
+
+/// Session de caisse qui rend les espèces d'un retour ou d'une annulation : celle de l'utilisateur
+/// qui l'enregistre s'il tient une caisse, sinon celle du vendeur de la vente.
+/// `None` si aucune des deux n'est ouverte. Utilisable à l'intérieur d'une transaction existante.
+pub(crate) fn find_paying_cash_session(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    seller_id: Uuid,
+) -> QueryResult<Option<Uuid>> {
+    let open_sessions: Vec<(Uuid, Uuid)> = cash_sessions::table
+        .filter(cash_sessions::user_id.eq_any([user_id, seller_id]))
+        .filter(cash_sessions::closed_at.is_null())
+        .select((cash_sessions::id, cash_sessions::user_id))
+        .load(conn)?;
+    Ok(open_sessions
+        .iter()
+        .find(|(_, cashier_id)| *cashier_id == user_id)
+        .or_else(|| open_sessions.first())
+        .map(|(session_id, _)| *session_id))
+}
+
+/// Session de caisse ouverte par un caissier, s'il en a une.
+pub fn get_open_cash_session(user_id: Uuid) -> AppResult<Option<CashSession>> {
+    let mut conn = db::get_conn()?;
+    cash_sessions::table
+        .filter(cash_sessions::user_id.eq(user_id))
+        .filter(cash_sessions::closed_at.is_null())
+        .first(&mut conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Ouvre une session de caisse avec le fond de caisse remis au caissier.
+pub fn open_cash_session(user_id: Uuid, opening_float: BigDecimal) -> AppResult<CashSession> {
+    if opening_float < BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le fond de caisse ne peut pas être négatif".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let already_open = cash_sessions::table
+            .filter(cash_sessions::user_id.eq(user_id))
+            .filter(cash_sessions::closed_at.is_null())
+            .count()
+            .get_result::<i64>(conn)?;
+        if already_open > 0 {
+            return Err(AppError::ValidationError(
+                "Une session de caisse est déjà ouverte pour ce caissier".to_string(),
+            ));
+        }
+
+        let session = diesel::insert_into(cash_sessions::table)
+            .values(NewCashSession {
+                id: Uuid::new_v4(),
+                user_id,
+                opened_at: Utc::now(),
+                opening_float,
+            })
+            .get_result(conn)?;
+        Ok(session)
+    })
+}
+
+/// Enregistre une entrée ou une sortie d'espèces sur une session ouverte.
+pub fn record_cash_movement(
+    session_id: Uuid,
+    user_id: Uuid,
+    kind: CashMovementKind,
+    amount: BigDecimal,
+    reason: String,
+) -> AppResult<CashMovement> {
+    if amount <= BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le montant doit être positif".to_string(),
+        ));
+    }
+    if reason.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le motif du mouvement est obligatoire".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let session: CashSession = cash_sessions::table
+            .find(session_id)
+            .for_update()
+            .first(conn)?;
+        if !session.is_open() {
+            return Err(AppError::ValidationError(
+                "La session de caisse est déjà clôturée".to_string(),
+            ));
+        }
+
+        let movement = diesel::insert_into(cash_movements::table)
+            .values(NewCashMovement {
+                id: Uuid::new_v4(),
+                cash_session_id: session.id,
+                user_id,
+                kind: kind.as_str().to_string(),
+                amount,
+                reason: reason.trim().to_string(),
+            })
+            .get_result(conn)?;
+        Ok(movement)
+    })
+}
+
+/// Clôture une session avec le montant compté : les espèces attendues sont calculées et figées.
+/// Seul le caissier de la session ou un administrateur peut la clôturer.
+pub fn close_cash_session(
+    session_id: Uuid,
+    user_id: Uuid,
+    counted_cash: BigDecimal,
+    note: Option<String>,
+) -> AppResult<ZReport> {
+    if counted_cash < BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le montant compté ne peut pas être négatif".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let session: CashSession = cash_sessions::table
+            .find(session_id)
+            .for_update()
+            .first(conn)?;
+        if !session.is_open() {
+            return Err(AppError::ValidationError(
+                "La session de caisse est déjà clôturée".to_string(),
+            ));
+        }
+        if session.user_id != user_id {
+            let role: String = users::table.find(user_id).select(users::role).first(conn)?;
+            if role != "Admin" {
+                return Err(AppError::Unauthorized(
+                    "Seul le caissier ou un administrateur peut clôturer cette caisse".to_string(),
+                ));
+            }
+        }
+
+        let closed_at = Utc::now();
+        let report = build_z_report(conn, session, closed_at)?;
+        let session: CashSession = diesel::update(cash_sessions::table.find(session_id))
+            .set((
+                cash_sessions::closed_at.eq(closed_at),
+                cash_sessions::closed_by.eq(user_id),
+                cash_sessions::expected_cash.eq(&report.expected_cash),
+                cash_sessions::counted_cash.eq(&counted_cash),
+                cash_sessions::closing_note.eq(note.filter(|n| !n.trim().is_empty())),
+            ))
+            .get_result(conn)?;
+        let closed_by_name = users::table.find(user_id).select(users::name).first(conn)?;
+
+        Ok(ZReport {
+            session,
+            closed_by_name: Some(closed_by_name),
+            ..report
+        })
+    })
+}
+
+/// Rapport d'une session : à sa clôture si elle est clôturée, à l'instant présent sinon.
+pub fn get_cash_session_report(session_id: Uuid) -> AppResult<ZReport> {
+    let mut conn = db::get_conn()?;
+    let session: CashSession = cash_sessions::table.find(session_id).first(&mut conn)?;
+    let until = session.closed_at.unwrap_or_else(Utc::now);
+    build_z_report(&mut conn, session, until).map_err(Into::into)
+}
+
+/// Historique des sessions de caisse, les plus récentes en premier, avec le nom du caissier.
+/// `user_id` restreint la liste aux sessions d'un caissier.
+pub fn get_cash_sessions(
+    user_id: Option<Uuid>,
+    limit: i64,
+) -> AppResult<Vec<(CashSession, String)>> {
+    let mut conn = db::get_conn()?;
+    let mut query = cash_sessions::table
+        .inner_join(users::table.on(cash_sessions::user_id.eq(users::id)))
+        .select((CashSession::as_select(), users::name))
+        .order(cash_sessions::opened_at.desc())
+        .limit(limit)
+        .into_boxed();
+    if let Some(user_id) = user_id {
+        query = query.filter(cash_sessions::user_id.eq(user_id));
+    }
+    query.load(&mut conn).map_err(Into::into)
+}
+
+/// Calcule le rapport d'une session sur l'intervalle `[ouverture, until)`.
+/// Les encaissements des ventes annulées depuis restent comptés : leurs espèces sont sorties
+/// du tiroir qui a enregistré l'annulation, comme celles des avoirs.
+fn build_z_report(
+    conn: &mut PgConnection,
+    session: CashSession,
+    until: DateTime<Utc>,
+) -> QueryResult<ZReport> {
+    let (sales_total, sales_count) = sales::table
+        .filter(sales::user_id.eq(session.user_id))
+        .filter(sales::date.ge(session.opened_at))
+        .filter(sales::date.lt(until))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((
+            diesel::dsl::sum(sales::total_amount),
+            diesel::dsl::count(sales::id),
+        ))
+        .first::<(Option<BigDecimal>, i64)>(conn)?;
+
+    let mut by_method: Vec<(String, i64, Option<BigDecimal>)> = payments::table
+        .inner_join(sales::table)
+        .filter(sales::user_id.eq(session.user_id))
+        .filter(payments::created_at.ge(session.opened_at))
+        .filter(payments::created_at.lt(until))
+        .group_by(payments::method)
+        .select((
+            payments::method,
+            diesel::dsl::count(payments::id),
+            diesel::dsl::sum(payments::amount),
+        ))
+        .load(conn)?;
+    // Dans l'ordre d'affichage des moyens de paiement
+    by_method.sort_by_key(|(method, _, _)| {
+        PaymentMethod::ALL
+            .iter()
+            .position(|m| m.as_str() == method)
+            .unwrap_or(PaymentMethod::ALL.len())
+    });
+
+    let zero = || BigDecimal::from(0);
+    let cash_sales = by_method
+        .iter()
+        .find(|(method, _, _)| method == PaymentMethod::Cash.as_str())
+        .and_then(|(_, _, amount)| amount.clone())
+        .unwrap_or_else(zero);
+    let tenders = by_method
+        .into_iter()
+        .map(|(method, count, amount)| TenderLine {
+            label: PaymentMethod::from_db(&method)
+                .map(|m| m.label().to_string())
+                .unwrap_or(method),
+            count,
+            amount: amount.unwrap_or_else(zero),
+        })
+        .collect();
+
+    // Règlements d'encours encaissés en espèces par le caissier pendant la session
+    let customer_cash_payments = customer_payments::table
+        .filter(customer_payments::user_id.eq(session.user_id))
+        .filter(customer_payments::method.eq(PaymentMethod::Cash.as_str()))
+        .filter(customer_payments::date.ge(session.opened_at))
+        .filter(customer_payments::date.lt(until))
+        .select(diesel::dsl::sum(customer_payments::amount))
+        .first::<Option<BigDecimal>>(conn)?
+        .unwrap_or_else(zero);
+
+    // Avoirs remboursés par ce tiroir : seule la part non imputée sur un compte client en sort
+    let session_credit_notes: Vec<(Uuid, Uuid)> = credit_notes::table
+        .filter(credit_notes::cash_session_id.eq(session.id))
+        .filter(credit_notes::date.lt(until))
+        .select((credit_notes::id, credit_notes::sale_id))
+        .load(conn)?;
+    let sale_ids: Vec<Uuid> = session_credit_notes
+        .iter()
+        .map(|(_, sale_id)| *sale_id)
+        .collect();
+    let cash_refunds: BigDecimal = load_credit_note_refunds(conn, &sale_ids)?
+        .into_iter()
+        .filter(|refund| {
+            session_credit_notes
+                .iter()
+                .any(|(id, _)| *id == refund.credit_note_id)
+        })
+        .map(|refund| refund.cash)
+        .sum();
+
+    // Ventes annulées par ce tiroir : les espèces encaissées, moins celles déjà rendues par avoir
+    let voided_sale_ids: Vec<Uuid> = sales::table
+        .filter(sales::void_cash_session_id.eq(session.id))
+        .filter(sales::voided_at.lt(until))
+        .select(sales::id)
+        .load(conn)?;
+    let voided_cash_paid = payments::table
+        .filter(payments::sale_id.eq_any(&voided_sale_ids))
+        .filter(payments::method.eq(PaymentMethod::Cash.as_str()))
+        .select(diesel::dsl::sum(payments::amount))
+        .first::<Option<BigDecimal>>(conn)?
+        .unwrap_or_else(zero);
+    let voided_cash_refunded: BigDecimal = load_credit_note_refunds(conn, &voided_sale_ids)?
+        .into_iter()
+        .map(|refund| refund.cash)
+        .sum();
+    let void_refunds = voided_cash_paid - voided_cash_refunded;
+
+    let movements: Vec<CashMovement> = CashMovement::belonging_to(&session)
+        .order(cash_movements::created_at.asc())
+        .load(conn)?;
+    let expected_cash = match &session.expected_cash {
+        // Session clôturée : le montant figé à la clôture fait foi
+        Some(expected) => expected.clone(),
+        None => compute_expected_cash(
+            &session.opening_float,
+            &cash_sales,
+            &customer_cash_payments,
+            &cash_refunds,
+            &void_refunds,
+            &movements,
+        ),
+    };
+
+    let cashier_name = users::table
+        .find(session.user_id)
+        .select(users::name)
+        .first(conn)?;
+    let closed_by_name = session
+        .closed_by
+        .map(|id| users::table.find(id).select(users::name).first(conn))
+        .transpose()?;
+
+    Ok(ZReport {
+        session,
+        cashier_name,
+        closed_by_name,
+        period_end: until,
+        sales_count,
+        sales_total: sales_total.unwrap_or_else(zero),
+        tenders,
+        cash_sales,
+        customer_cash_payments,
+        cash_refunds,
+        voided_sales: voided_sale_ids.len() as i64,
+        void_refunds,
+        movements,
+        expected_cash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            CreateCreditNoteData, CreateSaleData, CreateSaleItemData, NewProduct, NewUser,
+            ReturnItemData, StockMovementReason,
+        },
+        queries::{
+            create_credit_note, create_sale, stock_queries::record_stock_movement, void_sale,
+        },
+        schema::{credit_note_items, products, sale_items},
+    };
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> BigDecimal {
+        BigDecimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_expected_cash_and_variance() {
+        let movement = |kind: CashMovementKind, amount: &str| CashMovement {
+            id: Uuid::new_v4(),
+            cash_session_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            kind: kind.as_str().to_string(),
+            amount: dec(amount),
+            reason: "Test".to_string(),
+            created_at: Utc::now(),
+        };
+        let movements = vec![
+            movement(CashMovementKind::In, "5000"),
+            movement(CashMovementKind::Out, "12000"),
+        ];
+        // 20 000 de fond + 150 000 de ventes en espèces + 10 000 de règlements clients
+        // - 6 000 d'avoirs remboursés - 4 000 rendus sur une vente annulée + 5 000 - 12 000
+        let expected = compute_expected_cash(
+            &dec("20000"),
+            &dec("150000"),
+            &dec("10000"),
+            &dec("6000"),
+            &dec("4000"),
+            &movements,
+        );
+        assert_eq!(expected, dec("163000"));
+
+        let session = CashSession {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            opened_at: Utc::now(),
+            opening_float: dec("20000"),
+            closed_at: Some(Utc::now()),
+            closed_by: Some(Uuid::nil()),
+            expected_cash: Some(expected.clone()),
+            counted_cash: Some(dec("162500")),
+            closing_note: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(session.variance(), Some(dec("-500")));
+    }
+
+    /// Un administrateur qui ne tient pas de caisse reprend puis annule la vente d'un caissier :
+    /// les espèces rendues sortent du tiroir du caissier et son rapport les déduit.
+    #[test]
+    fn test_admin_return_and_void_are_paid_from_the_cashier_session() {
+        if !db::init_test().expect("initialisation de la base de test") {
+            eprintln!("TEST_DATABASE_URL n'est pas définie : test ignoré");
+            return;
+        }
+        let mut conn = db::get_conn().unwrap();
+
+        let cashier_id = Uuid::new_v4();
+        let admin_id = Uuid::new_v4();
+        for (id, role) in [(cashier_id, "User"), (admin_id, "Admin")] {
+            diesel::insert_into(users::table)
+                .values(&NewUser {
+                    id,
+                    password: "test",
+                    name: &format!("test-{}-{}", role, id),
+                    role,
+                    must_change_password: false,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+        let product_id = Uuid::new_v4();
+        diesel::insert_into(products::table)
+            .values(&NewProduct {
+                id: product_id,
+                name: format!("Produit test {}", product_id),
+                packaging_description: "Casier test".to_string(),
+                sku: None,
+                stock_in_sale_units: 0,
+                price_per_sale_unit: BigDecimal::from(1000),
+                deposit_item_id: None,
+                cost_price: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        conn.transaction(|conn| {
+            record_stock_movement(
+                conn,
+                product_id,
+                10,
+                StockMovementReason::Adjustment,
+                Some(admin_id),
+                None,
+                None,
+            )
+        })
+        .unwrap();
+
+        let session = open_cash_session(cashier_id, dec("10000")).unwrap();
+        // Deux articles à 1 000, réglés en espèces
+        create_sale(CreateSaleData {
+            user_id: cashier_id,
+            customer_id: None,
+            items: vec![CreateSaleItemData {
+                product_id,
+                quantity: 2,
+                deposit_returned: 0,
+            }],
+            payments: vec![],
+        })
+        .unwrap();
+        let (sale_id, sale_item_id): (Uuid, Uuid) = sale_items::table
+            .inner_join(sales::table)
+            .filter(sales::user_id.eq(cashier_id))
+            .select((sales::id, sale_items::id))
+            .first(&mut conn)
+            .unwrap();
+
+        let credit_note = create_credit_note(CreateCreditNoteData {
+            sale_id,
+            user_id: admin_id,
+            reason: "Bouteille cassée".to_string(),
+            items: vec![ReturnItemData {
+                sale_item_id,
+                quantity: 1,
+            }],
+        });
+        let after_return = get_cash_session_report(session.id);
+        let voided = void_sale(sale_id, admin_id, "Erreur de saisie".to_string());
+        let after_void = get_cash_session_report(session.id);
+
+        // Nettoyage avant les assertions pour ne pas laisser de données en cas d'échec
+        let credit_note_ids: Vec<Uuid> = credit_notes::table
+            .filter(credit_notes::sale_id.eq(sale_id))
+            .select(credit_notes::id)
+            .load(&mut conn)
+            .unwrap();
+        diesel::delete(
+            credit_note_items::table
+                .filter(credit_note_items::credit_note_id.eq_any(&credit_note_ids)),
+        )
+        .execute(&mut conn)
+        .unwrap();
+        diesel::delete(credit_notes::table.filter(credit_notes::sale_id.eq(sale_id)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(payments::table.filter(payments::sale_id.eq(sale_id)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(sale_items::table.filter(sale_items::sale_id.eq(sale_id)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(sales::table.find(sale_id))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(cash_sessions::table.find(session.id))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(products::table.find(product_id))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(users::table.filter(users::id.eq_any([cashier_id, admin_id])))
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(credit_note.unwrap().cash_session_id, Some(session.id));
+        let after_return = after_return.unwrap();
+        assert_eq!(after_return.cash_sales, dec("2000"));
+        assert_eq!(after_return.cash_refunds, dec("1000"));
+        assert_eq!(after_return.expected_cash, dec("11000"));
+
+        // L'annulation rend la part encore encaissée ; l'avoir déjà remboursé reste au rapport
+        assert_eq!(voided.unwrap().void_cash_session_id, Some(session.id));
+        let after_void = after_void.unwrap();
+        assert_eq!(after_void.voided_sales, 1);
+        assert_eq!(after_void.cash_refunds, dec("1000"));
+        assert_eq!(after_void.void_refunds, dec("1000"));
+        assert_eq!(after_void.expected_cash, dec("10000"));
+    }
+}
@@ -0,0 +1,108 @@
+// src/queries/commission_queries.rs
+
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{CommissionKind, CommissionRule, NewCommissionRule},
+    schema::{commission_rules, users},
+};
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Toutes les règles de commission : la règle par défaut et celles propres à un vendeur.
+pub fn get_commission_rules() -> AppResult<Vec<CommissionRule>> {
+    let mut conn = db::get_conn()?;
+    load_commission_rules(&mut conn).map_err(Into::into)
+}
+
+pub(crate) fn load_commission_rules(conn: &mut PgConnection) -> QueryResult<Vec<CommissionRule>> {
+    commission_rules::table
+        .order(commission_rules::created_at.asc())
+        .load(conn)
+}
+
+/// Règle applicable à un vendeur : la sienne, sinon la règle par défaut.
+pub fn commission_rule_for(rules: &[CommissionRule], seller_id: Uuid) -> Option<&CommissionRule> {
+    rules
+        .iter()
+        .find(|rule| rule.user_id == Some(seller_id))
+        .or_else(|| rules.iter().find(|rule| rule.user_id.is_none()))
+}
+
+/// Définit la règle de commission d'un vendeur (ou la règle par défaut si `seller_id` est `None`),
+/// en remplaçant la règle existante. Réservé aux administrateurs.
+pub fn set_commission_rule(
+    admin_id: Uuid,
+    seller_id: Option<Uuid>,
+    kind: CommissionKind,
+    rate: BigDecimal,
+) -> AppResult<CommissionRule> {
+    if rate < BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le taux de commission ne peut pas être négatif".to_string(),
+        ));
+    }
+    if kind == CommissionKind::Percent && rate > BigDecimal::from(100) {
+        return Err(AppError::ValidationError(
+            "Une commission ne peut pas dépasser 100 % du chiffre d'affaires".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        ensure_admin(conn, admin_id)?;
+
+        let existing: Option<CommissionRule> = match seller_id {
+            Some(seller_id) => commission_rules::table
+                .filter(commission_rules::user_id.eq(seller_id))
+                .for_update()
+                .first(conn)
+                .optional()?,
+            None => commission_rules::table
+                .filter(commission_rules::user_id.is_null())
+                .for_update()
+                .first(conn)
+                .optional()?,
+        };
+
+        let rule = match existing {
+            Some(rule) => diesel::update(commission_rules::table.find(rule.id))
+                .set((
+                    commission_rules::kind.eq(kind.as_str()),
+                    commission_rules::rate.eq(&rate),
+                ))
+                .get_result(conn)?,
+            None => diesel::insert_into(commission_rules::table)
+                .values(NewCommissionRule {
+                    id: Uuid::new_v4(),
+                    user_id: seller_id,
+                    kind: kind.as_str().to_string(),
+                    rate,
+                })
+                .get_result(conn)?,
+        };
+        Ok(rule)
+    })
+}
+
+/// Supprime la règle propre à un vendeur : la règle par défaut s'applique de nouveau.
+pub fn remove_commission_rule(admin_id: Uuid, seller_id: Uuid) -> AppResult<()> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        ensure_admin(conn, admin_id)?;
+        diesel::delete(commission_rules::table.filter(commission_rules::user_id.eq(seller_id)))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+fn ensure_admin(conn: &mut PgConnection, user_id: Uuid) -> AppResult<()> {
+    let role: String = users::table.find(user_id).select(users::role).first(conn)?;
+    if role != "Admin" {
+        return Err(AppError::Unauthorized(
+            "Seul un administrateur peut modifier les commissions".to_string(),
+        ));
+    }
+    Ok(())
+}
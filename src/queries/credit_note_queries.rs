@@ -0,0 +1,295 @@
+// src/queries/credit_note_queries.rs
+
+use super::{
+    cash_session_queries::find_paying_cash_session, numbering_queries::next_credit_note_number,
+    stock_queries::record_stock_movement,
+};
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{
+        CreateCreditNoteData, CreditNote, NewCreditNote, NewCreditNoteItem, PaymentMethod, Sale,
+        SaleItem, SaleStatus, StockMovementReason,
+    },
+    schema::{credit_note_items, credit_notes, payments, sale_items, sales, users},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Enregistre un retour de marchandise sur une vente : émet un avoir, remet les articles en stock
+/// et passe la vente à l'état "retour partiel" ou "retournée".
+/// Les consignes ne sont pas concernées : les emballages se règlent par les vides rendus.
+pub fn create_credit_note(data: CreateCreditNoteData) -> AppResult<CreditNote> {
+    if data.reason.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le motif du retour est obligatoire".to_string(),
+        ));
+    }
+    let returned_items: Vec<_> = data.items.iter().filter(|i| i.quantity != 0).collect();
+    if returned_items.is_empty() {
+        return Err(AppError::ValidationError(
+            "Aucun article à reprendre".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let role: String = users::table
+            .find(data.user_id)
+            .select(users::role)
+            .first(conn)?;
+        if role != "Admin" {
+            return Err(AppError::Unauthorized(
+                "Seul un administrateur peut enregistrer un retour".to_string(),
+            ));
+        }
+
+        // Verrouille la vente pour sérialiser les retours concurrents
+        let sale: Sale = sales::table.find(data.sale_id).for_update().first(conn)?;
+        if sale.is_voided() {
+            return Err(AppError::ValidationError(format!(
+                "La vente {} est annulée",
+                sale.sale_number
+            )));
+        }
+
+        let items: HashMap<Uuid, SaleItem> = SaleItem::belonging_to(&sale)
+            .load::<SaleItem>(conn)?
+            .into_iter()
+            .map(|item| (item.id, item))
+            .collect();
+
+        let cash_session_id = find_paying_cash_session(conn, data.user_id, sale.user_id)?;
+        let credit_note_id = Uuid::new_v4();
+        let credit_note_number = next_credit_note_number(conn)?;
+        let mut total_amount = BigDecimal::from(0);
+        let mut new_items = Vec::new();
+        for returned in &returned_items {
+            let item = items.get(&returned.sale_item_id).ok_or_else(|| {
+                AppError::ValidationError("Article de vente introuvable".to_string())
+            })?;
+            if returned.quantity < 0 || returned.quantity > item.quantity_returnable() {
+                return Err(AppError::ValidationError(format!(
+                    "Quantité reprise invalide ({}) : {} au plus peuvent être reprises sur cette ligne",
+                    returned.quantity,
+                    item.quantity_returnable()
+                )));
+            }
+            let total_price = &item.unit_price * BigDecimal::from(returned.quantity);
+            total_amount += &total_price;
+            new_items.push(NewCreditNoteItem {
+                id: Uuid::new_v4(),
+                credit_note_id,
+                sale_item_id: item.id,
+                product_id: item.product_id,
+                quantity: returned.quantity,
+                unit_price: item.unit_price.clone(),
+                total_price,
+            });
+        }
+
+        let new_credit_note = NewCreditNote {
+            id: credit_note_id,
+            credit_note_number: credit_note_number.clone(),
+            sale_id: sale.id,
+            user_id: data.user_id,
+            reason: data.reason.trim().to_string(),
+            total_amount,
+            date: Utc::now(),
+            cash_session_id,
+        };
+        let credit_note: CreditNote = diesel::insert_into(credit_notes::table)
+            .values(&new_credit_note)
+            .get_result(conn)?;
+        diesel::insert_into(credit_note_items::table)
+            .values(&new_items)
+            .execute(conn)?;
+
+        for new_item in &new_items {
+            diesel::update(sale_items::table.find(new_item.sale_item_id))
+                .set(
+                    sale_items::quantity_returned
+                        .eq(sale_items::quantity_returned + new_item.quantity),
+                )
+                .execute(conn)?;
+            record_stock_movement(
+                conn,
+                new_item.product_id,
+                new_item.quantity,
+                StockMovementReason::Return,
+                Some(data.user_id),
+                Some(credit_note_number.clone()),
+                None,
+            )?;
+        }
+
+        // L'état de la vente est déduit des quantités restant à reprendre
+        let outstanding: i64 = SaleItem::belonging_to(&sale)
+            .filter(sale_items::quantity_returned.lt(sale_items::quantity))
+            .count()
+            .get_result(conn)?;
+        let new_status = if outstanding == 0 {
+            SaleStatus::Returned
+        } else {
+            SaleStatus::PartiallyReturned
+        };
+        diesel::update(sales::table.find(sale.id))
+            .set(sales::status.eq(new_status.as_str()))
+            .execute(conn)?;
+
+        Ok(credit_note)
+    })
+}
+
+/// Répartition d'un avoir entre le compte du client et la caisse.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CreditNoteRefund {
+    pub credit_note_id: Uuid,
+    pub sale_id: Uuid,
+    /// Part déduite de l'encours du client.
+    pub on_account: BigDecimal,
+    /// Part rendue en espèces.
+    pub cash: BigDecimal,
+}
+
+/// Les avoirs d'une vente s'imputent d'abord, dans l'ordre chronologique, sur la part de la vente
+/// portée au compte du client (`credit_portions`, par vente) ; le reste est remboursé en espèces.
+fn split_refunds(
+    credit_portions: &HashMap<Uuid, BigDecimal>,
+    credit_notes: &[CreditNote],
+) -> Vec<CreditNoteRefund> {
+    let mut remaining = credit_portions.clone();
+    credit_notes
+        .iter()
+        .map(|credit_note| {
+            let available = remaining
+                .entry(credit_note.sale_id)
+                .or_insert_with(|| BigDecimal::from(0));
+            let on_account = if credit_note.total_amount < *available {
+                credit_note.total_amount.clone()
+            } else {
+                available.clone()
+            };
+            *available -= &on_account;
+            CreditNoteRefund {
+                credit_note_id: credit_note.id,
+                sale_id: credit_note.sale_id,
+                cash: &credit_note.total_amount - &on_account,
+                on_account,
+            }
+        })
+        .collect()
+}
+
+/// Répartition des avoirs émis sur les ventes données, annulées ou non : à l'appelant d'écarter
+/// les ventes annulées si elles ne le concernent pas.
+/// Utilisable à l'intérieur d'une transaction existante.
+pub(crate) fn load_credit_note_refunds(
+    conn: &mut PgConnection,
+    sale_ids: &[Uuid],
+) -> QueryResult<Vec<CreditNoteRefund>> {
+    let credit_notes: Vec<CreditNote> = credit_notes::table
+        .filter(credit_notes::sale_id.eq_any(sale_ids))
+        .order((credit_notes::date.asc(), credit_notes::created_at.asc()))
+        .load(conn)?;
+    if credit_notes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let credit_portions: HashMap<Uuid, BigDecimal> = payments::table
+        .filter(payments::sale_id.eq_any(sale_ids))
+        .filter(payments::method.eq(PaymentMethod::Credit.as_str()))
+        .group_by(payments::sale_id)
+        .select((payments::sale_id, diesel::dsl::sum(payments::amount)))
+        .load::<(Uuid, Option<BigDecimal>)>(conn)?
+        .into_iter()
+        .map(|(id, total)| (id, total.unwrap_or_else(|| BigDecimal::from(0))))
+        .collect();
+
+    Ok(split_refunds(&credit_portions, &credit_notes))
+}
+
+/// Montant total des avoirs émis sur la période (hors ventes annulées).
+/// Utilisable à l'intérieur d'une transaction existante.
+pub(crate) fn compute_returns_total(
+    conn: &mut PgConnection,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> QueryResult<BigDecimal> {
+    let total: Option<BigDecimal> = credit_notes::table
+        .inner_join(sales::table)
+        .filter(credit_notes::date.between(start_date, end_date))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select(diesel::dsl::sum(credit_notes::total_amount))
+        .first(conn)?;
+    Ok(total.unwrap_or_else(|| BigDecimal::from(0)))
+}
+
+/// Quantités reprises par produit sur la période (hors ventes annulées).
+pub(crate) fn compute_returned_quantities(
+    conn: &mut PgConnection,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> QueryResult<HashMap<Uuid, i64>> {
+    Ok(credit_note_items::table
+        .inner_join(credit_notes::table.inner_join(sales::table))
+        .filter(credit_notes::date.between(start_date, end_date))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .group_by(credit_note_items::product_id)
+        .select((
+            credit_note_items::product_id,
+            diesel::dsl::sum(credit_note_items::quantity),
+        ))
+        .load::<(Uuid, Option<i64>)>(conn)?
+        .into_iter()
+        .map(|(id, qty)| (id, qty.unwrap_or(0)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> BigDecimal {
+        BigDecimal::from_str(s).unwrap()
+    }
+
+    fn credit_note(sale_id: Uuid, amount: &str) -> CreditNote {
+        CreditNote {
+            id: Uuid::new_v4(),
+            credit_note_number: "AV-2025-000001".to_string(),
+            sale_id,
+            user_id: Uuid::nil(),
+            reason: "Casse".to_string(),
+            total_amount: dec(amount),
+            date: Utc::now(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            cash_session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_refunds_use_the_credit_portion_first_then_cash() {
+        let cash_sale = Uuid::new_v4();
+        let mixed_sale = Uuid::new_v4();
+        // Vente mixte : 30 000 en espèces et 20 000 portés au compte du client
+        let credit_portions = HashMap::from([(mixed_sale, dec("20000"))]);
+        let notes = vec![
+            credit_note(cash_sale, "6000"),
+            credit_note(mixed_sale, "15000"),
+            credit_note(mixed_sale, "9000"),
+        ];
+
+        let refunds = split_refunds(&credit_portions, &notes);
+        let split = |i: usize| (refunds[i].on_account.clone(), refunds[i].cash.clone());
+        assert_eq!(split(0), (dec("0"), dec("6000")));
+        assert_eq!(split(1), (dec("15000"), dec("0")));
+        assert_eq!(split(2), (dec("5000"), dec("4000")));
+    }
+}
@@ -0,0 +1,303 @@
+// src/queries/customer_queries.rs
+
+use super::{
+    credit_note_queries::load_credit_note_refunds,
+    deposit_queries::compute_empties_owed_by_customer,
+};
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{Customer, NewCustomer, NewCustomerPayment, PaymentMethod, SaleStatus},
+    schema::{credit_notes, customer_payments, customers, payments, sales},
+};
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Un client accompagné de son encours (montant restant dû).
+#[derive(Debug, Clone)]
+pub struct CustomerWithBalance {
+    pub customer: Customer,
+    pub balance: BigDecimal,
+    /// Emballages consignés sortis et pas encore rendus.
+    pub empties_owed: i64,
+}
+
+impl CustomerWithBalance {
+    /// Crédit encore disponible avant d'atteindre le plafond autorisé.
+    pub fn available_credit(&self) -> BigDecimal {
+        let available = &self.customer.credit_limit - &self.balance;
+        if available < BigDecimal::from(0) {
+            BigDecimal::from(0)
+        } else {
+            available
+        }
+    }
+}
+
+/// Calcule l'encours d'un client : total des paiements "crédit client" de ses ventes moins les règlements
+/// et la part des avoirs imputée sur ce crédit. Les ventes annulées ne comptent pas.
+/// Utilisable à l'intérieur d'une transaction existante.
+pub(crate) fn compute_customer_balance(
+    conn: &mut PgConnection,
+    customer_id: Uuid,
+) -> QueryResult<BigDecimal> {
+    let total_sales: Option<BigDecimal> = payments::table
+        .inner_join(sales::table)
+        .filter(sales::customer_id.eq(customer_id))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .filter(payments::method.eq(PaymentMethod::Credit.as_str()))
+        .select(diesel::dsl::sum(payments::amount))
+        .first(conn)?;
+
+    let total_paid: Option<BigDecimal> = customer_payments::table
+        .filter(customer_payments::customer_id.eq(customer_id))
+        .select(diesel::dsl::sum(customer_payments::amount))
+        .first(conn)?;
+
+    // Seule la part des avoirs imputée sur le crédit des ventes réduit l'encours
+    let sale_ids: Vec<Uuid> = sales::table
+        .filter(sales::customer_id.eq(customer_id))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .filter(diesel::dsl::exists(
+            credit_notes::table.filter(credit_notes::sale_id.eq(sales::id)),
+        ))
+        .select(sales::id)
+        .load(conn)?;
+    let total_returns: BigDecimal = load_credit_note_refunds(conn, &sale_ids)?
+        .iter()
+        .map(|refund| &refund.on_account)
+        .sum();
+
+    Ok(total_sales.unwrap_or_else(|| BigDecimal::from(0))
+        - total_paid.unwrap_or_else(|| BigDecimal::from(0))
+        - total_returns)
+}
+
+/// Récupère la liste des clients avec leur encours, filtrée par nom ou téléphone.
+pub fn get_customers(search_query: Option<String>) -> AppResult<Vec<CustomerWithBalance>> {
+    let mut conn = db::get_conn()?;
+
+    let mut query = customers::table.into_boxed();
+    if let Some(search) = search_query.filter(|s| !s.trim().is_empty()) {
+        let pattern = format!("%{}%", search.trim());
+        query = query.filter(
+            customers::name
+                .ilike(pattern.clone())
+                .or(customers::phone.ilike(pattern)),
+        );
+    }
+    let customer_list = query
+        .order(customers::name.asc())
+        .load::<Customer>(&mut conn)?;
+
+    let customer_ids: Vec<Uuid> = customer_list.iter().map(|c| c.id).collect();
+
+    // Totaux portés en compte (paiements "crédit client") par client
+    let sales_totals: HashMap<Uuid, BigDecimal> = payments::table
+        .inner_join(sales::table)
+        .filter(sales::customer_id.eq_any(&customer_ids))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .filter(payments::method.eq(PaymentMethod::Credit.as_str()))
+        .group_by(sales::customer_id)
+        .select((sales::customer_id, diesel::dsl::sum(payments::amount)))
+        .load::<(Option<Uuid>, Option<BigDecimal>)>(&mut conn)?
+        .into_iter()
+        .filter_map(|(id, total)| id.map(|id| (id, total.unwrap_or_else(|| BigDecimal::from(0)))))
+        .collect();
+
+    // Part des avoirs imputée sur le compte, par client
+    let sale_customers: HashMap<Uuid, Uuid> = sales::table
+        .filter(sales::customer_id.eq_any(&customer_ids))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .filter(diesel::dsl::exists(
+            credit_notes::table.filter(credit_notes::sale_id.eq(sales::id)),
+        ))
+        .select((sales::id, sales::customer_id))
+        .load::<(Uuid, Option<Uuid>)>(&mut conn)?
+        .into_iter()
+        .filter_map(|(sale_id, customer_id)| customer_id.map(|id| (sale_id, id)))
+        .collect();
+    let sale_ids: Vec<Uuid> = sale_customers.keys().copied().collect();
+    let mut returns_totals: HashMap<Uuid, BigDecimal> = HashMap::new();
+    for refund in load_credit_note_refunds(&mut conn, &sale_ids)? {
+        if let Some(customer_id) = sale_customers.get(&refund.sale_id) {
+            *returns_totals
+                .entry(*customer_id)
+                .or_insert_with(|| BigDecimal::from(0)) += refund.on_account;
+        }
+    }
+
+    // Totaux des règlements par client
+    let payment_totals: HashMap<Uuid, BigDecimal> = customer_payments::table
+        .filter(customer_payments::customer_id.eq_any(&customer_ids))
+        .group_by(customer_payments::customer_id)
+        .select((
+            customer_payments::customer_id,
+            diesel::dsl::sum(customer_payments::amount),
+        ))
+        .load::<(Uuid, Option<BigDecimal>)>(&mut conn)?
+        .into_iter()
+        .map(|(id, total)| (id, total.unwrap_or_else(|| BigDecimal::from(0))))
+        .collect();
+
+    // Emballages dus par client
+    let empties_totals = compute_empties_owed_by_customer(&mut conn, &customer_ids)?;
+
+    Ok(customer_list
+        .into_iter()
+        .map(|customer| {
+            let sold = sales_totals
+                .get(&customer.id)
+                .cloned()
+                .unwrap_or_else(|| BigDecimal::from(0));
+            let paid = payment_totals
+                .get(&customer.id)
+                .cloned()
+                .unwrap_or_else(|| BigDecimal::from(0));
+            let returned = returns_totals
+                .get(&customer.id)
+                .cloned()
+                .unwrap_or_else(|| BigDecimal::from(0));
+            let empties_owed = empties_totals.get(&customer.id).copied().unwrap_or(0);
+            CustomerWithBalance {
+                customer,
+                balance: sold - paid - returned,
+                empties_owed,
+            }
+        })
+        .collect())
+}
+
+/// Récupère un client par son ID avec son encours.
+pub fn get_customer_with_balance(customer_id: Uuid) -> AppResult<CustomerWithBalance> {
+    let mut conn = db::get_conn()?;
+    let customer = customers::table
+        .find(customer_id)
+        .first::<Customer>(&mut conn)?;
+    let balance = compute_customer_balance(&mut conn, customer_id)?;
+    let empties_owed = compute_empties_owed_by_customer(&mut conn, &[customer_id])?
+        .get(&customer_id)
+        .copied()
+        .unwrap_or(0);
+    Ok(CustomerWithBalance {
+        customer,
+        balance,
+        empties_owed,
+    })
+}
+
+/// Crée un nouveau client.
+pub fn create_customer(
+    c_name: String,
+    c_phone: Option<String>,
+    c_address: Option<String>,
+    c_credit_limit: BigDecimal,
+) -> AppResult<Customer> {
+    validate_customer_fields(&c_name, &c_credit_limit)?;
+    let mut conn = db::get_conn()?;
+
+    let new_customer = NewCustomer {
+        id: Uuid::new_v4(),
+        name: c_name.trim().to_string(),
+        phone: c_phone.filter(|p| !p.trim().is_empty()),
+        address: c_address.filter(|a| !a.trim().is_empty()),
+        credit_limit: c_credit_limit,
+    };
+
+    diesel::insert_into(customers::table)
+        .values(&new_customer)
+        .get_result(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Met à jour les informations et le plafond de crédit d'un client.
+pub fn update_customer(
+    customer_id: Uuid,
+    c_name: String,
+    c_phone: Option<String>,
+    c_address: Option<String>,
+    c_credit_limit: BigDecimal,
+) -> AppResult<Customer> {
+    validate_customer_fields(&c_name, &c_credit_limit)?;
+    let mut conn = db::get_conn()?;
+
+    diesel::update(customers::table.find(customer_id))
+        .set((
+            customers::name.eq(c_name.trim()),
+            customers::phone.eq(c_phone.filter(|p| !p.trim().is_empty())),
+            customers::address.eq(c_address.filter(|a| !a.trim().is_empty())),
+            customers::credit_limit.eq(c_credit_limit),
+        ))
+        .get_result(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Enregistre un règlement client. Le montant ne peut pas dépasser l'encours.
+pub fn record_customer_payment(
+    customer_id: Uuid,
+    user_id: Uuid,
+    amount: BigDecimal,
+    method: PaymentMethod,
+    note: Option<String>,
+) -> AppResult<BigDecimal> {
+    if amount <= BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le montant du règlement doit être positif".to_string(),
+        ));
+    }
+    if method == PaymentMethod::Credit {
+        return Err(AppError::ValidationError(
+            "Un encours ne peut pas être réglé par crédit client".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        // Verrouille le client pour sérialiser les règlements concurrents
+        customers::table
+            .find(customer_id)
+            .for_update()
+            .first::<Customer>(conn)?;
+
+        let balance = compute_customer_balance(conn, customer_id)?;
+        if amount > balance {
+            return Err(AppError::ValidationError(format!(
+                "Le règlement ({} XAF) dépasse l'encours du client ({} XAF)",
+                amount, balance
+            )));
+        }
+
+        let new_payment = NewCustomerPayment {
+            id: Uuid::new_v4(),
+            customer_id,
+            user_id,
+            amount: amount.clone(),
+            note: note.filter(|n| !n.trim().is_empty()),
+            date: Utc::now(),
+            method: method.as_str().to_string(),
+        };
+        diesel::insert_into(customer_payments::table)
+            .values(&new_payment)
+            .execute(conn)?;
+
+        Ok(balance - amount)
+    })
+}
+
+fn validate_customer_fields(c_name: &str, c_credit_limit: &BigDecimal) -> AppResult<()> {
+    if c_name.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le nom du client est obligatoire".to_string(),
+        ));
+    }
+    if *c_credit_limit < BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le plafond de crédit ne peut pas être négatif".to_string(),
+        ));
+    }
+    Ok(())
+}
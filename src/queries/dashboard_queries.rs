@@ -1,34 +1,43 @@
 // src/queries/dashboard_queries.rs
-use crate::{db, error::AppResult, models::Product};
+use super::credit_note_queries::compute_returns_total;
+use crate::{
+    config::business_calendar::business_calendar,
+    db,
+    error::AppResult,
+    models::{Product, SaleStatus},
+};
 use bigdecimal::BigDecimal;
-use chrono::Utc;
 use diesel::{
     dsl::{count, sum},
     prelude::*,
 };
 
-/// Calcule le chiffre d'affaires total et le nombre de ventes pour la journée en cours.
+/// Calcule le chiffre d'affaires net (ventes - avoirs) et le nombre de ventes pour la journée en cours.
+/// La journée est celle du calendrier commercial (fuseau du dépôt, heure de début de journée).
+/// Les ventes annulées sont exclues.
 pub fn get_today_sales_summary() -> AppResult<(BigDecimal, i64)> {
     use crate::schema::sales::dsl::*;
     let mut conn = db::get_conn()?;
 
-    let now_utc = Utc::now();
-    let today_utc_naive = now_utc.date_naive();
-    let start_of_day_utc = today_utc_naive.and_hms_opt(0, 0, 0).unwrap();
-    let end_of_day_utc = today_utc_naive.and_hms_opt(23, 59, 59).unwrap();
+    let calendar = business_calendar();
+    let today = calendar.day(calendar.today());
 
     log::info!(
         "Calcul du résumé des ventes entre {} et {}",
-        start_of_day_utc,
-        end_of_day_utc
+        today.start,
+        today.end
     );
 
     let summary = sales
-        .filter(date.between(start_of_day_utc, end_of_day_utc))
+        .filter(date.ge(today.start))
+        .filter(date.lt(today.end))
+        .filter(status.ne(SaleStatus::Voided.as_str()))
         .select((sum(total_amount), count(id)))
         .first::<(Option<BigDecimal>, i64)>(&mut conn)?;
 
-    let total_revenue = summary.0.unwrap_or_else(|| BigDecimal::from(0));
+    let returns = compute_returns_total(&mut conn, today.start, today.last_instant())?;
+
+    let total_revenue = summary.0.unwrap_or_else(|| BigDecimal::from(0)) - returns;
     let sales_count = summary.1;
 
     Ok((total_revenue, sales_count))
@@ -46,3 +55,25 @@ pub fn get_low_stock_products(threshold: i32) -> AppResult<Vec<Product>> {
 
     Ok(low_stock_items)
 }
+
+/// Récupère les produits dont le taux de marge brute (prix de vente face au prix de revient)
+/// est inférieur au seuil donné en pourcentage, les ventes à perte en premier.
+/// Les produits sans prix de revient sont ignorés.
+pub fn get_low_margin_products(threshold_percent: i64) -> AppResult<Vec<(Product, BigDecimal)>> {
+    use crate::schema::products::dsl::*;
+    let mut conn = db::get_conn()?;
+
+    let threshold = BigDecimal::from(threshold_percent);
+    let mut low_margin_items = products
+        .filter(cost_price.is_not_null())
+        .load::<Product>(&mut conn)?
+        .into_iter()
+        .filter_map(|product| {
+            let rate = product.margin_rate()?;
+            (rate < threshold).then_some((product, rate))
+        })
+        .collect::<Vec<_>>();
+    low_margin_items.sort_by(|a, b| a.1.cmp(&b.1));
+
+    Ok(low_margin_items)
+}
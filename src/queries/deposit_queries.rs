@@ -0,0 +1,320 @@
+// src/queries/deposit_queries.rs
+
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{DepositItem, NewDepositItem, SaleStatus},
+    schema::{deposit_items, products, sale_items, sales},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Une consigne avec la situation de ses emballages.
+#[derive(Debug, Clone)]
+pub struct DepositItemStatus {
+    pub item: DepositItem,
+    /// Emballages sortis et non rendus, tous acheteurs confondus (consigne encaissée à rembourser).
+    pub empties_outstanding: i64,
+    /// Emballages dus par les clients nommés.
+    pub empties_with_customers: i64,
+}
+
+/// Emballages vides dus par un client pour un conditionnement.
+#[derive(Debug, Clone)]
+pub struct CustomerEmpties {
+    pub deposit_item_id: Uuid,
+    pub packaging_description: String,
+    pub quantity: i64,
+}
+
+/// Ligne de rapport sur les consignes pour une période.
+#[derive(Debug, Clone)]
+pub struct DepositReportLine {
+    pub packaging_description: String,
+    pub deposit_amount: BigDecimal,
+    pub empties_in_stock: i32,
+    pub empties_outstanding: i64,
+    pub empties_with_customers: i64,
+    /// Emballages sortis sur la période.
+    pub quantity_out: i64,
+    /// Vides rendus sur la période.
+    pub quantity_returned: i64,
+    /// Consignes nettes encaissées sur la période.
+    pub net_amount: BigDecimal,
+}
+
+/// Retrouve la consigne associée à un conditionnement. Utilisable dans une transaction existante.
+pub(crate) fn find_deposit_item_id_for_packaging(
+    conn: &mut PgConnection,
+    packaging: &str,
+) -> QueryResult<Option<Uuid>> {
+    deposit_items::table
+        .filter(deposit_items::packaging_description.eq(packaging.trim()))
+        .select(deposit_items::id)
+        .first(conn)
+        .optional()
+}
+
+/// Acheteurs pris en compte dans le calcul des emballages dus.
+#[derive(Debug, Clone, Copy)]
+enum EmptiesScope {
+    AllBuyers,
+    NamedCustomers,
+    Customer(Uuid),
+}
+
+/// Solde net (sortis - rendus) par consigne pour les acheteurs demandés.
+fn empties_balance_by_item(
+    conn: &mut PgConnection,
+    scope: EmptiesScope,
+) -> QueryResult<HashMap<Uuid, i64>> {
+    let mut query = sale_items::table
+        .inner_join(sales::table)
+        .filter(sale_items::deposit_item_id.is_not_null())
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .group_by(sale_items::deposit_item_id)
+        .select((
+            sale_items::deposit_item_id,
+            diesel::dsl::sum(
+                sale_items::deposit_quantity_out - sale_items::deposit_quantity_returned,
+            ),
+        ))
+        .into_boxed();
+    match scope {
+        EmptiesScope::AllBuyers => {}
+        EmptiesScope::NamedCustomers => query = query.filter(sales::customer_id.is_not_null()),
+        EmptiesScope::Customer(customer_id) => {
+            query = query.filter(sales::customer_id.eq(customer_id))
+        }
+    }
+
+    Ok(query
+        .load::<(Option<Uuid>, Option<i64>)>(conn)?
+        .into_iter()
+        .filter_map(|(id, qty)| id.map(|id| (id, qty.unwrap_or(0))))
+        .collect())
+}
+
+/// Emballages vides dus par un client, par conditionnement (uniquement les soldes positifs).
+/// Utilisable à l'intérieur d'une transaction existante.
+pub(crate) fn compute_customer_empties(
+    conn: &mut PgConnection,
+    customer_id: Uuid,
+) -> QueryResult<Vec<CustomerEmpties>> {
+    let balances = empties_balance_by_item(conn, EmptiesScope::Customer(customer_id))?;
+    let ids: Vec<Uuid> = balances.keys().copied().collect();
+    let items = deposit_items::table
+        .filter(deposit_items::id.eq_any(&ids))
+        .order(deposit_items::packaging_description.asc())
+        .load::<DepositItem>(conn)?;
+
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let quantity = balances.get(&item.id).copied().unwrap_or(0);
+            (quantity > 0).then_some(CustomerEmpties {
+                deposit_item_id: item.id,
+                packaging_description: item.packaging_description,
+                quantity,
+            })
+        })
+        .collect())
+}
+
+/// Nombre total d'emballages dus par client (tous conditionnements confondus).
+pub(crate) fn compute_empties_owed_by_customer(
+    conn: &mut PgConnection,
+    customer_ids: &[Uuid],
+) -> QueryResult<HashMap<Uuid, i64>> {
+    Ok(sale_items::table
+        .inner_join(sales::table)
+        .filter(sales::customer_id.eq_any(customer_ids))
+        .filter(sale_items::deposit_item_id.is_not_null())
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .group_by(sales::customer_id)
+        .select((
+            sales::customer_id,
+            diesel::dsl::sum(
+                sale_items::deposit_quantity_out - sale_items::deposit_quantity_returned,
+            ),
+        ))
+        .load::<(Option<Uuid>, Option<i64>)>(conn)?
+        .into_iter()
+        .filter_map(|(id, qty)| id.map(|id| (id, qty.unwrap_or(0))))
+        .collect())
+}
+
+/// Récupère toutes les consignes avec la situation de leurs emballages.
+pub fn get_deposit_items() -> AppResult<Vec<DepositItemStatus>> {
+    let mut conn = db::get_conn()?;
+
+    let items = deposit_items::table
+        .order(deposit_items::packaging_description.asc())
+        .load::<DepositItem>(&mut conn)?;
+    let outstanding = empties_balance_by_item(&mut conn, EmptiesScope::AllBuyers)?;
+    let with_customers = empties_balance_by_item(&mut conn, EmptiesScope::NamedCustomers)?;
+
+    Ok(items
+        .into_iter()
+        .map(|item| DepositItemStatus {
+            empties_outstanding: outstanding.get(&item.id).copied().unwrap_or(0),
+            empties_with_customers: with_customers.get(&item.id).copied().unwrap_or(0),
+            item,
+        })
+        .collect())
+}
+
+/// Récupère une consigne par son ID.
+pub fn get_deposit_item(deposit_item_id: Uuid) -> AppResult<DepositItem> {
+    let mut conn = db::get_conn()?;
+    deposit_items::table
+        .find(deposit_item_id)
+        .first::<DepositItem>(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Emballages vides dus par un client.
+pub fn get_customer_empties(customer_id: Uuid) -> AppResult<Vec<CustomerEmpties>> {
+    let mut conn = db::get_conn()?;
+    compute_customer_empties(&mut conn, customer_id).map_err(Into::into)
+}
+
+/// Crée la consigne d'un conditionnement et y rattache les produits existants de ce conditionnement.
+pub fn create_deposit_item(
+    d_packaging: String,
+    d_amount: BigDecimal,
+    d_empties_in_stock: i32,
+) -> AppResult<DepositItem> {
+    validate_deposit_fields(&d_packaging, &d_amount, d_empties_in_stock)?;
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let new_item = NewDepositItem {
+            id: Uuid::new_v4(),
+            packaging_description: d_packaging.trim().to_string(),
+            deposit_amount: d_amount,
+            empties_in_stock: d_empties_in_stock,
+        };
+        let item: DepositItem = diesel::insert_into(deposit_items::table)
+            .values(&new_item)
+            .get_result(conn)?;
+
+        diesel::update(
+            products::table
+                .filter(products::packaging_description.eq(&item.packaging_description))
+                .filter(products::deposit_item_id.is_null()),
+        )
+        .set(products::deposit_item_id.eq(item.id))
+        .execute(conn)?;
+
+        Ok(item)
+    })
+}
+
+/// Met à jour le montant d'une consigne et le stock physique de vides (après comptage).
+pub fn update_deposit_item(
+    deposit_item_id: Uuid,
+    d_amount: BigDecimal,
+    d_empties_in_stock: i32,
+) -> AppResult<DepositItem> {
+    let mut conn = db::get_conn()?;
+    let item = deposit_items::table
+        .find(deposit_item_id)
+        .first::<DepositItem>(&mut conn)?;
+    validate_deposit_fields(&item.packaging_description, &d_amount, d_empties_in_stock)?;
+
+    diesel::update(deposit_items::table.find(deposit_item_id))
+        .set((
+            deposit_items::deposit_amount.eq(d_amount),
+            deposit_items::empties_in_stock.eq(d_empties_in_stock),
+        ))
+        .get_result(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Situation des consignes sur une période : mouvements de la période et soldes actuels.
+pub(crate) fn compute_deposit_report(
+    conn: &mut PgConnection,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> QueryResult<Vec<DepositReportLine>> {
+    let items = deposit_items::table
+        .order(deposit_items::packaging_description.asc())
+        .load::<DepositItem>(conn)?;
+    let outstanding = empties_balance_by_item(conn, EmptiesScope::AllBuyers)?;
+    let with_customers = empties_balance_by_item(conn, EmptiesScope::NamedCustomers)?;
+
+    let period_moves: HashMap<Uuid, (i64, i64, BigDecimal)> = sale_items::table
+        .inner_join(sales::table)
+        .filter(sales::date.between(start_date, end_date))
+        .filter(sale_items::deposit_item_id.is_not_null())
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .group_by(sale_items::deposit_item_id)
+        .select((
+            sale_items::deposit_item_id,
+            diesel::dsl::sum(sale_items::deposit_quantity_out),
+            diesel::dsl::sum(sale_items::deposit_quantity_returned),
+            diesel::dsl::sum(sale_items::deposit_amount),
+        ))
+        .load::<(Option<Uuid>, Option<i64>, Option<i64>, Option<BigDecimal>)>(conn)?
+        .into_iter()
+        .filter_map(|(id, out, returned, amount)| {
+            id.map(|id| {
+                (
+                    id,
+                    (
+                        out.unwrap_or(0),
+                        returned.unwrap_or(0),
+                        amount.unwrap_or_else(|| BigDecimal::from(0)),
+                    ),
+                )
+            })
+        })
+        .collect();
+
+    Ok(items
+        .into_iter()
+        .map(|item| {
+            let (quantity_out, quantity_returned, net_amount) = period_moves
+                .get(&item.id)
+                .cloned()
+                .unwrap_or_else(|| (0, 0, BigDecimal::from(0)));
+            DepositReportLine {
+                empties_outstanding: outstanding.get(&item.id).copied().unwrap_or(0),
+                empties_with_customers: with_customers.get(&item.id).copied().unwrap_or(0),
+                packaging_description: item.packaging_description,
+                deposit_amount: item.deposit_amount,
+                empties_in_stock: item.empties_in_stock,
+                quantity_out,
+                quantity_returned,
+                net_amount,
+            }
+        })
+        .collect())
+}
+
+fn validate_deposit_fields(
+    d_packaging: &str,
+    d_amount: &BigDecimal,
+    d_empties_in_stock: i32,
+) -> AppResult<()> {
+    if d_packaging.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le conditionnement est obligatoire".to_string(),
+        ));
+    }
+    if *d_amount < BigDecimal::from(0) {
+        return Err(AppError::ValidationError(
+            "Le montant de la consigne ne peut pas être négatif".to_string(),
+        ));
+    }
+    if d_empties_in_stock < 0 {
+        return Err(AppError::ValidationError(
+            "Le stock de vides ne peut pas être négatif".to_string(),
+        ));
+    }
+    Ok(())
+}
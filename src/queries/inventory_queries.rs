@@ -0,0 +1,481 @@
+// src/queries/inventory_queries.rs
+
+use crate::{
+    config::business_calendar::business_calendar,
+    db,
+    error::{AppError, AppResult},
+    models::{
+        InventoryLine, InventorySession, InventoryStatus, NewInventoryLine, NewInventorySession,
+        Product, StockMovementReason,
+    },
+    schema::{inventory_lines, inventory_sessions, products, users},
+    services::product_import_service::{ImportRowError, ParsedImport},
+};
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{
+    product_import_queries::{product_key, sku_key},
+    stock_queries::record_stock_movement,
+};
+
+/// Ligne d'inventaire accompagnée de la désignation du produit.
+#[derive(Debug, Clone)]
+pub struct InventoryLineDetail {
+    pub line: InventoryLine,
+    pub product_name: String,
+    pub packaging: String,
+    pub sku: Option<String>,
+}
+
+impl InventoryLineDetail {
+    pub fn product_label(&self) -> String {
+        format!("{} — {}", self.product_name, self.packaging)
+    }
+}
+
+/// Inventaire complet : en-tête, auteurs et lignes triées par produit.
+#[derive(Debug, Clone)]
+pub struct InventoryReport {
+    pub session: InventorySession,
+    pub opened_by: String,
+    pub closed_by: Option<String>,
+    pub lines: Vec<InventoryLineDetail>,
+}
+
+impl InventoryReport {
+    pub fn counted_count(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|l| l.line.counted_stock.is_some())
+            .count()
+    }
+
+    pub fn uncounted_count(&self) -> usize {
+        self.lines.len() - self.counted_count()
+    }
+
+    /// Lignes comptées dont la quantité diffère du stock théorique.
+    pub fn discrepancies(&self) -> impl Iterator<Item = &InventoryLineDetail> {
+        self.lines
+            .iter()
+            .filter(|l| l.line.variance().is_some_and(|v| v != 0))
+    }
+
+    /// Écart total valorisé au prix de revient (les produits sans coût connu n'y figurent pas).
+    pub fn total_variance_cost(&self) -> BigDecimal {
+        self.lines
+            .iter()
+            .filter_map(|l| l.line.variance_cost())
+            .sum()
+    }
+
+    /// Écart total valorisé au prix de vente.
+    pub fn total_variance_value(&self) -> BigDecimal {
+        self.lines
+            .iter()
+            .filter_map(|l| l.line.variance_value())
+            .sum()
+    }
+}
+
+/// Ouvre un inventaire en figeant le stock théorique et les prix de tous les produits.
+pub fn start_inventory(user_id: Uuid, note: Option<String>) -> AppResult<InventorySession> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let open: i64 = inventory_sessions::table
+            .filter(inventory_sessions::status.eq(InventoryStatus::Open.as_str()))
+            .count()
+            .get_result(conn)?;
+        if open > 0 {
+            return Err(AppError::ValidationError(
+                "Un inventaire est déjà en cours".to_string(),
+            ));
+        }
+
+        let session: InventorySession = diesel::insert_into(inventory_sessions::table)
+            .values(&NewInventorySession {
+                id: Uuid::new_v4(),
+                user_id,
+                note: note.filter(|n| !n.trim().is_empty()),
+            })
+            .get_result(conn)?;
+
+        let catalogue = products::table.for_share().load::<Product>(conn)?;
+        let lines = catalogue
+            .into_iter()
+            .map(|p| NewInventoryLine {
+                id: Uuid::new_v4(),
+                inventory_session_id: session.id,
+                product_id: p.id,
+                expected_stock: p.stock_in_sale_units,
+                unit_cost: p.cost_price,
+                unit_price: p.price_per_sale_unit,
+            })
+            .collect::<Vec<_>>();
+        diesel::insert_into(inventory_lines::table)
+            .values(&lines)
+            .execute(conn)?;
+
+        Ok(session)
+    })
+}
+
+/// Inventaire en cours, s'il y en a un.
+pub fn get_open_inventory() -> AppResult<Option<InventorySession>> {
+    let mut conn = db::get_conn()?;
+    inventory_sessions::table
+        .filter(inventory_sessions::status.eq(InventoryStatus::Open.as_str()))
+        .first::<InventorySession>(&mut conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Derniers inventaires, du plus récent au plus ancien, avec le nom de l'utilisateur qui les a ouverts.
+pub fn get_inventory_sessions(limit: i64) -> AppResult<Vec<(InventorySession, String)>> {
+    let mut conn = db::get_conn()?;
+    inventory_sessions::table
+        .inner_join(users::table.on(inventory_sessions::user_id.eq(users::id)))
+        .select((InventorySession::as_select(), users::name))
+        .order(inventory_sessions::opened_at.desc())
+        .limit(limit)
+        .load(&mut conn)
+        .map_err(Into::into)
+}
+
+fn load_report(conn: &mut PgConnection, session: InventorySession) -> QueryResult<InventoryReport> {
+    let opened_by = users::table
+        .find(session.user_id)
+        .select(users::name)
+        .first::<String>(conn)?;
+    let closed_by = session
+        .closed_by
+        .map(|id| {
+            users::table
+                .find(id)
+                .select(users::name)
+                .first::<String>(conn)
+        })
+        .transpose()?;
+    let lines = inventory_lines::table
+        .inner_join(products::table)
+        .filter(inventory_lines::inventory_session_id.eq(session.id))
+        .order((products::name.asc(), products::packaging_description.asc()))
+        .select((
+            InventoryLine::as_select(),
+            products::name,
+            products::packaging_description,
+            products::sku,
+        ))
+        .load::<(InventoryLine, String, String, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(line, product_name, packaging, sku)| InventoryLineDetail {
+            line,
+            product_name,
+            packaging,
+            sku,
+        })
+        .collect();
+    Ok(InventoryReport {
+        session,
+        opened_by,
+        closed_by,
+        lines,
+    })
+}
+
+/// Récupère un inventaire avec toutes ses lignes.
+pub fn get_inventory_report(session_id: Uuid) -> AppResult<InventoryReport> {
+    let mut conn = db::get_conn()?;
+    let session = inventory_sessions::table
+        .find(session_id)
+        .first::<InventorySession>(&mut conn)?;
+    load_report(&mut conn, session).map_err(Into::into)
+}
+
+/// Verrouille un inventaire et vérifie qu'il est encore en cours.
+fn lock_open_session(conn: &mut PgConnection, session_id: Uuid) -> AppResult<InventorySession> {
+    let session: InventorySession = inventory_sessions::table
+        .find(session_id)
+        .for_update()
+        .first(conn)?;
+    if !session.is_open() {
+        return Err(AppError::ValidationError(
+            "Cet inventaire est déjà clôturé".to_string(),
+        ));
+    }
+    Ok(session)
+}
+
+/// Enregistre la quantité comptée d'une ligne ; `None` remet le produit à compter.
+pub fn set_inventory_count(line_id: Uuid, counted: Option<i32>) -> AppResult<InventoryLine> {
+    if counted.is_some_and(|c| c < 0) {
+        return Err(AppError::ValidationError(
+            "La quantité comptée ne peut pas être négative".to_string(),
+        ));
+    }
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let line: InventoryLine = inventory_lines::table.find(line_id).first(conn)?;
+        lock_open_session(conn, line.inventory_session_id)?;
+        diesel::update(inventory_lines::table.find(line_id))
+            .set(inventory_lines::counted_stock.eq(counted))
+            .get_result(conn)
+            .map_err(Into::into)
+    })
+}
+
+/// Rapproche les lignes d'un fichier de comptage des lignes de l'inventaire, par SKU ou par nom et conditionnement.
+/// Renvoie les quantités à enregistrer par ligne d'inventaire, ou toutes les erreurs du fichier.
+pub(crate) fn match_counts(
+    lines: &[InventoryLineDetail],
+    parsed: &ParsedImport,
+) -> Result<Vec<(Uuid, i32)>, Vec<ImportRowError>> {
+    let by_sku = lines
+        .iter()
+        .filter_map(|l| l.sku.as_deref().map(|sku| (sku_key(sku), l.line.id)))
+        .collect::<HashMap<_, _>>();
+    let by_name = lines
+        .iter()
+        .map(|l| (product_key(&l.product_name, &l.packaging), l.line.id))
+        .collect::<HashMap<_, _>>();
+
+    let mut errors = parsed.errors.clone();
+    let mut counts = Vec::new();
+    let mut seen = HashMap::<Uuid, usize>::new();
+    for row in &parsed.rows {
+        let line_id = row
+            .sku
+            .as_deref()
+            .and_then(|sku| by_sku.get(&sku_key(sku)))
+            .or_else(|| match (&row.name, &row.packaging) {
+                (Some(name), Some(packaging)) => by_name.get(&product_key(name, packaging)),
+                _ => None,
+            });
+        let message = match (line_id, row.stock) {
+            (None, _) => Some("produit absent de l'inventaire".to_string()),
+            (Some(_), None) => Some("quantité comptée manquante".to_string()),
+            (Some(id), Some(_)) => seen
+                .get(id)
+                .map(|line| format!("produit déjà compté à la ligne {}", line)),
+        };
+        match (message, line_id, row.stock) {
+            (None, Some(id), Some(stock)) => {
+                seen.insert(*id, row.line);
+                counts.push((*id, stock));
+            }
+            (message, _, _) => errors.push(ImportRowError {
+                line: row.line,
+                message: message.unwrap_or_default(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(counts)
+    } else {
+        errors.sort_by_key(|e| e.line);
+        Err(errors)
+    }
+}
+
+/// Importe les quantités comptées depuis un fichier CSV ou XLSX (colonnes sku ou nom et conditionnement, et stock).
+/// Rien n'est enregistré si une seule ligne est en erreur. Renvoie le nombre de produits comptés.
+pub fn import_inventory_counts(session_id: Uuid, parsed: &ParsedImport) -> AppResult<usize> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let session = lock_open_session(conn, session_id)?;
+        let report = load_report(conn, session)?;
+        let counts = match_counts(&report.lines, parsed).map_err(|errors| {
+            let details = errors
+                .iter()
+                .take(5)
+                .map(|e| format!("ligne {} : {}", e.line, e.message))
+                .collect::<Vec<_>>()
+                .join(" ; ");
+            AppError::ValidationError(format!(
+                "{} ligne(s) en erreur, aucun comptage importé ({})",
+                errors.len(),
+                details
+            ))
+        })?;
+
+        for (line_id, counted) in &counts {
+            diesel::update(inventory_lines::table.find(line_id))
+                .set(inventory_lines::counted_stock.eq(counted))
+                .execute(conn)?;
+        }
+        Ok(counts.len())
+    })
+}
+
+/// Valide l'inventaire : chaque écart est passé au journal des mouvements avec le motif « inventaire ».
+/// L'écart est appliqué au stock actuel, si bien que les ventes enregistrées pendant le comptage sont conservées.
+/// Les produits non comptés gardent leur stock.
+pub fn approve_inventory(session_id: Uuid, user_id: Uuid) -> AppResult<InventoryReport> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let session = lock_open_session(conn, session_id)?;
+        let note = format!(
+            "Inventaire du {}",
+            business_calendar().format_local(session.opened_at, "%d/%m/%Y")
+        );
+        let report = load_report(conn, session)?;
+
+        for detail in report.discrepancies() {
+            let variance = detail.line.variance().unwrap_or_default();
+            let current: i32 = products::table
+                .find(detail.line.product_id)
+                .select(products::stock_in_sale_units)
+                .for_update()
+                .first(conn)?;
+            if current + variance < 0 {
+                return Err(AppError::ValidationError(format!(
+                    "L'écart de {} sur « {} » rendrait le stock négatif (stock actuel : {})",
+                    variance,
+                    detail.product_label(),
+                    current
+                )));
+            }
+            record_stock_movement(
+                conn,
+                detail.line.product_id,
+                variance,
+                StockMovementReason::Inventory,
+                Some(user_id),
+                None,
+                Some(note.clone()),
+            )?;
+        }
+
+        let session = diesel::update(inventory_sessions::table.find(session_id))
+            .set((
+                inventory_sessions::status.eq(InventoryStatus::Approved.as_str()),
+                inventory_sessions::closed_at.eq(Utc::now()),
+                inventory_sessions::closed_by.eq(user_id),
+            ))
+            .get_result::<InventorySession>(conn)?;
+        load_report(conn, session).map_err(Into::into)
+    })
+}
+
+/// Abandonne un inventaire en cours, sans effet sur le stock.
+pub fn cancel_inventory(session_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        lock_open_session(conn, session_id)?;
+        diesel::update(inventory_sessions::table.find(session_id))
+            .set((
+                inventory_sessions::status.eq(InventoryStatus::Cancelled.as_str()),
+                inventory_sessions::closed_at.eq(Utc::now()),
+                inventory_sessions::closed_by.eq(user_id),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::product_import_service::ImportRow;
+
+    fn detail(name: &str, sku: &str, expected: i32, counted: Option<i32>) -> InventoryLineDetail {
+        InventoryLineDetail {
+            line: InventoryLine {
+                id: Uuid::new_v4(),
+                inventory_session_id: Uuid::nil(),
+                product_id: Uuid::new_v4(),
+                expected_stock: expected,
+                counted_stock: counted,
+                unit_cost: Some(BigDecimal::from(4000)),
+                unit_price: BigDecimal::from(5000),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            product_name: name.to_string(),
+            packaging: "Casier 65cl de 12".to_string(),
+            sku: Some(sku.to_string()),
+        }
+    }
+
+    fn row(line: usize, sku: Option<&str>, name: Option<&str>, stock: Option<i32>) -> ImportRow {
+        ImportRow {
+            line,
+            sku: sku.map(str::to_string),
+            name: name.map(str::to_string),
+            packaging: name.map(|_| "casier 65cl de 12".to_string()),
+            stock,
+            price: None,
+            cost: None,
+        }
+    }
+
+    #[test]
+    fn test_variance_valuation() {
+        let session = InventorySession {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            status: "open".to_string(),
+            note: None,
+            opened_at: Utc::now(),
+            closed_at: None,
+            closed_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let report = InventoryReport {
+            session,
+            opened_by: "admin".to_string(),
+            closed_by: None,
+            lines: vec![
+                detail("Isenbeck", "ISEN", 10, Some(8)),
+                detail("Guinness", "GUIN", 5, Some(6)),
+                detail("Castel", "CAST", 3, Some(3)),
+                detail("Supermont", "SUPE", 7, None),
+            ],
+        };
+
+        assert_eq!(report.counted_count(), 3);
+        assert_eq!(report.uncounted_count(), 1);
+        assert_eq!(report.discrepancies().count(), 2);
+        assert_eq!(report.total_variance_cost(), BigDecimal::from(-4000));
+        assert_eq!(report.total_variance_value(), BigDecimal::from(-5000));
+    }
+
+    #[test]
+    fn test_match_counts_by_sku_or_name() {
+        let lines = vec![
+            detail("Isenbeck", "ISEN", 10, None),
+            detail("Guinness", "GUIN", 5, None),
+        ];
+        let parsed = ParsedImport {
+            file_name: "comptage.csv".to_string(),
+            rows: vec![
+                row(2, Some("isen"), None, Some(9)),
+                row(3, None, Some("guinness"), Some(5)),
+            ],
+            errors: Vec::new(),
+        };
+        let counts = match_counts(&lines, &parsed).unwrap();
+        assert_eq!(counts, vec![(lines[0].line.id, 9), (lines[1].line.id, 5)]);
+
+        let parsed = ParsedImport {
+            file_name: "comptage.csv".to_string(),
+            rows: vec![
+                row(2, Some("ISEN"), None, Some(9)),
+                row(3, Some("ISEN"), None, Some(4)),
+                row(4, Some("INCONNU"), None, Some(1)),
+                row(5, Some("GUIN"), None, None),
+            ],
+            errors: Vec::new(),
+        };
+        let errors = match_counts(&lines, &parsed).unwrap_err();
+        let lines = errors.iter().map(|e| e.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert!(errors[0].message.contains("ligne 2"));
+    }
+}
@@ -1,19 +1,38 @@
 // src/queries/mod.rs
 
 // Déclarer les sous-modules
+pub mod cash_session_queries;
+pub mod commission_queries;
+pub mod credit_note_queries;
+pub mod customer_queries;
 pub mod dashboard_queries;
+pub mod deposit_queries;
+pub mod inventory_queries;
+pub mod numbering_queries;
 pub mod password_queries;
+pub mod product_import_queries;
 pub mod product_queries;
+pub mod purchase_queries;
 pub mod reporting_queries;
 pub mod sale_queries;
+pub mod stock_queries;
 pub mod user_queries;
 
 // Rendre toutes les fonctions publiques accessibles directement via `queries::...`
+pub use cash_session_queries::*;
+pub use commission_queries::*;
+pub use credit_note_queries::*;
+pub use customer_queries::*;
 pub use dashboard_queries::*;
+pub use deposit_queries::*;
+pub use inventory_queries::*;
 pub use password_queries::*;
+pub use product_import_queries::*;
 pub use product_queries::*;
+pub use purchase_queries::*;
 pub use reporting_queries::*;
 pub use sale_queries::*;
+pub use stock_queries::*;
 pub use user_queries::*;
 
 // Types communs pour la pagination et le tri
@@ -0,0 +1,54 @@
+// src/queries/numbering_queries.rs
+
+use crate::{
+    config::{business_calendar::business_calendar, numbering_config::SaleNumberingConfig},
+    schema::sale_number_sequences,
+};
+use chrono::Datelike;
+use diesel::prelude::*;
+
+/// Attribue le prochain numéro de la série de vente configurée pour cette caisse.
+/// Doit être appelée dans la transaction de la vente : le compteur reste verrouillé jusqu'au commit
+/// et un rollback rend le numéro, ce qui garantit une suite continue sans doublon.
+pub(crate) fn next_sale_number(conn: &mut PgConnection) -> QueryResult<String> {
+    let config = SaleNumberingConfig::from_env();
+    // L'année suit le calendrier commercial : une vente de la nuit du 31 décembre reste sur l'exercice qui se termine
+    let year = business_calendar().today().year();
+    let value = next_sequence_value(conn, &config.series(), config.period(year))?;
+    Ok(config.format_number(year, value))
+}
+
+/// Attribue le prochain numéro d'avoir, ex. `AV-2025-000007`, sur une série annuelle commune à
+/// toutes les caisses. À appeler dans la transaction du retour, comme pour les ventes.
+pub(crate) fn next_credit_note_number(conn: &mut PgConnection) -> QueryResult<String> {
+    next_document_number(conn, "AV")
+}
+
+/// Attribue le prochain numéro de bon de commande, ex. `BC-2025-000012`.
+/// À appeler dans la transaction de création de la commande.
+pub(crate) fn next_purchase_order_number(conn: &mut PgConnection) -> QueryResult<String> {
+    next_document_number(conn, "BC")
+}
+
+/// Numéro suivant d'une série annuelle de documents désignée par son préfixe.
+/// Les séries de vente contiennent toujours un tiret (`VTE-01`) et ne peuvent pas se confondre avec elles.
+fn next_document_number(conn: &mut PgConnection, prefix: &str) -> QueryResult<String> {
+    let year = business_calendar().today().year();
+    let value = next_sequence_value(conn, prefix, year)?;
+    Ok(format!("{}-{}-{:06}", prefix, year, value))
+}
+
+/// Incrémente (ou initialise à 1) le compteur d'une série pour une période et renvoie sa nouvelle valeur.
+fn next_sequence_value(conn: &mut PgConnection, series: &str, period: i32) -> QueryResult<i64> {
+    diesel::insert_into(sale_number_sequences::table)
+        .values((
+            sale_number_sequences::series.eq(series),
+            sale_number_sequences::period.eq(period),
+            sale_number_sequences::last_value.eq(1i64),
+        ))
+        .on_conflict((sale_number_sequences::series, sale_number_sequences::period))
+        .do_update()
+        .set(sale_number_sequences::last_value.eq(sale_number_sequences::last_value + 1i64))
+        .returning(sale_number_sequences::last_value)
+        .get_result(conn)
+}
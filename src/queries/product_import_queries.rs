@@ -0,0 +1,587 @@
+// src/queries/product_import_queries.rs
+
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{
+        ImportAction, NewProduct, NewProductImport, NewProductImportLine, Product, ProductImport,
+        StockMovementReason,
+    },
+    schema::{product_import_lines, product_imports, products, users},
+    services::product_import_service::{ImportRow, ImportRowError, ParsedImport},
+};
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{
+    deposit_queries::find_deposit_item_id_for_packaging, product_queries::generate_sku,
+    stock_queries::record_stock_movement,
+};
+
+/// Ce que l'import fera d'une ligne du fichier : les valeurs finales du produit et le détail des changements.
+#[derive(Debug, Clone)]
+pub struct PlannedImportRow {
+    pub line: usize,
+    pub action: ImportAction,
+    /// Produit existant mis à jour, `None` pour une création.
+    pub product_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub name: String,
+    pub packaging: String,
+    pub stock: i32,
+    pub price: BigDecimal,
+    pub cost: Option<BigDecimal>,
+    /// Changements lisibles, par exemple « Prix : 5000 XAF → 5500 XAF ».
+    pub changes: Vec<String>,
+}
+
+impl PlannedImportRow {
+    pub fn product_label(&self) -> String {
+        format!("{} — {}", self.name, self.packaging)
+    }
+}
+
+/// Aperçu d'un import (« dry-run ») : rien n'est écrit tant qu'il reste des erreurs.
+#[derive(Debug, Clone, Default)]
+pub struct ImportPreview {
+    pub file_name: String,
+    pub rows: Vec<PlannedImportRow>,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportPreview {
+    pub fn count(&self, action: ImportAction) -> usize {
+        self.rows.iter().filter(|row| row.action == action).count()
+    }
+
+    /// L'import n'est appliqué que si toutes les lignes sont valides et qu'il y a quelque chose à changer.
+    pub fn can_apply(&self) -> bool {
+        self.errors.is_empty()
+            && self
+                .rows
+                .iter()
+                .any(|row| row.action != ImportAction::Unchanged)
+    }
+}
+
+/// Import enregistré, avec le nom de l'utilisateur qui l'a lancé.
+#[derive(Debug, Clone)]
+pub struct ProductImportSummary {
+    pub import: ProductImport,
+    pub user_name: String,
+}
+
+/// Clé de rapprochement par nom et conditionnement, insensible à la casse.
+pub(super) fn product_key(name: &str, packaging: &str) -> (String, String) {
+    (name.trim().to_lowercase(), packaging.trim().to_lowercase())
+}
+
+pub(super) fn sku_key(sku: &str) -> String {
+    sku.trim().to_uppercase()
+}
+
+/// Occupant d'un nom ou d'un SKU, qui doivent rester uniques dans le catalogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    Product(Uuid),
+    Line(usize),
+}
+
+fn describe(owner: Owner) -> String {
+    match owner {
+        Owner::Product(_) => "un autre produit du catalogue".to_string(),
+        Owner::Line(line) => format!("la ligne {}", line),
+    }
+}
+
+fn money(amount: &BigDecimal) -> String {
+    format!("{:.0} XAF", amount)
+}
+
+fn optional_money(amount: Option<&BigDecimal>) -> String {
+    amount.map(money).unwrap_or_else(|| "—".to_string())
+}
+
+/// Rapproche les lignes du fichier du catalogue : par SKU d'abord, sinon par nom et conditionnement.
+/// Les lignes sont traitées dans l'ordre du fichier, comme elles le seront à l'application.
+pub(crate) fn plan_import(catalogue: &[Product], parsed: &ParsedImport) -> ImportPreview {
+    let mut preview = ImportPreview {
+        file_name: parsed.file_name.clone(),
+        rows: Vec::new(),
+        errors: parsed.errors.clone(),
+    };
+
+    let by_id = catalogue
+        .iter()
+        .map(|p| (p.id, p))
+        .collect::<HashMap<_, _>>();
+    let mut skus = catalogue
+        .iter()
+        .filter_map(|p| {
+            p.sku
+                .as_deref()
+                .map(|sku| (sku_key(sku), Owner::Product(p.id)))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut names = catalogue
+        .iter()
+        .map(|p| {
+            (
+                product_key(&p.name, &p.packaging_description),
+                Owner::Product(p.id),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let mut seen = HashMap::<Uuid, usize>::new();
+
+    for row in &parsed.rows {
+        match plan_row(row, &by_id, &skus, &names, &seen) {
+            Ok(planned) => {
+                let owner = match planned.product_id {
+                    Some(id) => {
+                        seen.insert(id, row.line);
+                        // Un produit renommé libère son ancien nom et son ancien SKU
+                        let current = by_id[&id];
+                        names.remove(&product_key(&current.name, &current.packaging_description));
+                        if let Some(sku) = &current.sku {
+                            skus.remove(&sku_key(sku));
+                        }
+                        Owner::Product(id)
+                    }
+                    None => Owner::Line(row.line),
+                };
+                names.insert(product_key(&planned.name, &planned.packaging), owner);
+                if let Some(sku) = &planned.sku {
+                    skus.insert(sku_key(sku), owner);
+                }
+                preview.rows.push(planned);
+            }
+            Err(message) => preview.errors.push(ImportRowError {
+                line: row.line,
+                message,
+            }),
+        }
+    }
+
+    preview.errors.sort_by_key(|e| e.line);
+    preview
+}
+
+fn plan_row(
+    row: &ImportRow,
+    by_id: &HashMap<Uuid, &Product>,
+    skus: &HashMap<String, Owner>,
+    names: &HashMap<(String, String), Owner>,
+    seen: &HashMap<Uuid, usize>,
+) -> Result<PlannedImportRow, String> {
+    let find = |owner: Option<&Owner>| match owner {
+        Some(Owner::Product(id)) => by_id.get(id).copied(),
+        _ => None,
+    };
+    let by_sku = row
+        .sku
+        .as_deref()
+        .and_then(|sku| find(skus.get(&sku_key(sku))));
+    let by_name = match (&row.name, &row.packaging) {
+        (Some(name), Some(packaging)) => find(names.get(&product_key(name, packaging))),
+        _ => None,
+    };
+
+    let planned = match by_sku.or(by_name) {
+        Some(product) => {
+            if let Some(line) = seen.get(&product.id) {
+                return Err(format!("produit déjà importé à la ligne {}", line));
+            }
+            plan_update(row, product)
+        }
+        None => plan_creation(row)?,
+    };
+
+    let me = match planned.product_id {
+        Some(id) => Owner::Product(id),
+        None => Owner::Line(row.line),
+    };
+    if let Some(owner) = names
+        .get(&product_key(&planned.name, &planned.packaging))
+        .filter(|owner| **owner != me)
+    {
+        return Err(format!(
+            "« {} » est déjà utilisé par {}",
+            planned.product_label(),
+            describe(*owner)
+        ));
+    }
+    if let Some((sku, owner)) = planned.sku.as_deref().and_then(|sku| {
+        skus.get(&sku_key(sku))
+            .filter(|owner| **owner != me)
+            .map(|owner| (sku, *owner))
+    }) {
+        return Err(format!(
+            "le SKU « {} » est déjà utilisé par {}",
+            sku,
+            describe(owner)
+        ));
+    }
+    Ok(planned)
+}
+
+fn plan_creation(row: &ImportRow) -> Result<PlannedImportRow, String> {
+    let (Some(name), Some(packaging), Some(price)) = (&row.name, &row.packaging, &row.price) else {
+        return Err(match &row.sku {
+            Some(sku) => format!(
+                "aucun produit avec le SKU « {} » ; nom, conditionnement et prix requis pour le créer",
+                sku
+            ),
+            None => "nom, conditionnement et prix requis pour créer un produit".to_string(),
+        });
+    };
+    let stock = row.stock.unwrap_or(0);
+
+    let mut changes = vec![
+        format!("Prix : {}", money(price)),
+        format!("Stock : {}", stock),
+    ];
+    if let Some(cost) = &row.cost {
+        changes.push(format!("Prix de revient : {}", money(cost)));
+    }
+    if let Some(sku) = &row.sku {
+        changes.insert(0, format!("SKU : {}", sku));
+    }
+
+    Ok(PlannedImportRow {
+        line: row.line,
+        action: ImportAction::Created,
+        product_id: None,
+        sku: row.sku.clone(),
+        name: name.clone(),
+        packaging: packaging.clone(),
+        stock,
+        price: price.clone(),
+        cost: row.cost.clone(),
+        changes,
+    })
+}
+
+fn keep_spelling(value: Option<&String>, current: &str) -> String {
+    match value {
+        Some(value) if value.trim().to_lowercase() != current.trim().to_lowercase() => {
+            value.clone()
+        }
+        _ => current.to_string(),
+    }
+}
+
+fn plan_update(row: &ImportRow, product: &Product) -> PlannedImportRow {
+    let sku = match (&row.sku, &product.sku) {
+        (Some(sku), Some(current)) if sku_key(sku) == sku_key(current) => Some(current.clone()),
+        (Some(sku), _) => Some(sku.clone()),
+        (None, current) => current.clone(),
+    };
+    // Une simple différence de casse ne renomme pas le produit
+    let name = keep_spelling(row.name.as_ref(), &product.name);
+    let packaging = keep_spelling(row.packaging.as_ref(), &product.packaging_description);
+    let stock = row.stock.unwrap_or(product.stock_in_sale_units);
+    let price = row
+        .price
+        .clone()
+        .unwrap_or_else(|| product.price_per_sale_unit.clone());
+    let cost = row.cost.clone().or_else(|| product.cost_price.clone());
+
+    let mut changes = Vec::new();
+    if sku != product.sku {
+        changes.push(format!(
+            "SKU : {} → {}",
+            product.sku.as_deref().unwrap_or("—"),
+            sku.as_deref().unwrap_or("—")
+        ));
+    }
+    if name != product.name {
+        changes.push(format!("Nom : {} → {}", product.name, name));
+    }
+    if packaging != product.packaging_description {
+        changes.push(format!(
+            "Conditionnement : {} → {}",
+            product.packaging_description, packaging
+        ));
+    }
+    if price != product.price_per_sale_unit {
+        changes.push(format!(
+            "Prix : {} → {}",
+            money(&product.price_per_sale_unit),
+            money(&price)
+        ));
+    }
+    if cost != product.cost_price {
+        changes.push(format!(
+            "Prix de revient : {} → {}",
+            optional_money(product.cost_price.as_ref()),
+            optional_money(cost.as_ref())
+        ));
+    }
+    if stock != product.stock_in_sale_units {
+        changes.push(format!(
+            "Stock : {} → {}",
+            product.stock_in_sale_units, stock
+        ));
+    }
+
+    PlannedImportRow {
+        line: row.line,
+        action: if changes.is_empty() {
+            ImportAction::Unchanged
+        } else {
+            ImportAction::Updated
+        },
+        product_id: Some(product.id),
+        sku,
+        name,
+        packaging,
+        stock,
+        price,
+        cost,
+        changes,
+    }
+}
+
+/// Aperçu de l'import sur le catalogue actuel, sans rien écrire.
+pub fn preview_product_import(parsed: &ParsedImport) -> AppResult<ImportPreview> {
+    let mut conn = db::get_conn()?;
+    let catalogue = products::table.load::<Product>(&mut conn)?;
+    Ok(plan_import(&catalogue, parsed))
+}
+
+/// Applique l'import dans une seule transaction : toutes les lignes passent, ou aucune.
+/// Les écarts de stock sont inscrits au journal comme des ajustements, et chaque produit
+/// créé ou modifié est consigné dans le journal des imports. Réservé aux administrateurs.
+pub fn apply_product_import(parsed: &ParsedImport, user_id: Uuid) -> AppResult<ProductImport> {
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let role: String = users::table.find(user_id).select(users::role).first(conn)?;
+        if role != "Admin" {
+            return Err(AppError::Unauthorized(
+                "Seul un administrateur peut importer des produits".to_string(),
+            ));
+        }
+
+        // Verrouille le catalogue : l'aperçu est recalculé sur l'état réel au moment de l'écriture
+        let catalogue = products::table.for_update().load::<Product>(conn)?;
+        let preview = plan_import(&catalogue, parsed);
+        if !preview.errors.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "{} ligne(s) en erreur : l'import est annulé",
+                preview.errors.len()
+            )));
+        }
+
+        let import: ProductImport = diesel::insert_into(product_imports::table)
+            .values(&NewProductImport {
+                id: Uuid::new_v4(),
+                user_id,
+                file_name: parsed.file_name.clone(),
+                rows_created: preview.count(ImportAction::Created) as i32,
+                rows_updated: preview.count(ImportAction::Updated) as i32,
+                rows_unchanged: preview.count(ImportAction::Unchanged) as i32,
+            })
+            .get_result(conn)?;
+        let note = format!("Import {}", parsed.file_name);
+
+        for row in preview
+            .rows
+            .iter()
+            .filter(|row| row.action != ImportAction::Unchanged)
+        {
+            let linked_deposit = find_deposit_item_id_for_packaging(conn, &row.packaging)?;
+            let (product_id, current_stock) = match row.product_id {
+                Some(id) => {
+                    // Un conditionnement sans consigne connue ne détache pas la consigne du produit
+                    let deposit_item_id = linked_deposit.or_else(|| {
+                        catalogue
+                            .iter()
+                            .find(|product| product.id == id)
+                            .and_then(|product| product.deposit_item_id)
+                    });
+                    let current: Product = diesel::update(products::table.find(id))
+                        .set((
+                            products::sku.eq(&row.sku),
+                            products::name.eq(&row.name),
+                            products::packaging_description.eq(&row.packaging),
+                            products::price_per_sale_unit.eq(&row.price),
+                            products::cost_price.eq(&row.cost),
+                            products::deposit_item_id.eq(deposit_item_id),
+                        ))
+                        .get_result(conn)?;
+                    (id, current.stock_in_sale_units)
+                }
+                None => {
+                    let created: Product = diesel::insert_into(products::table)
+                        .values(&NewProduct {
+                            id: Uuid::new_v4(),
+                            name: row.name.clone(),
+                            packaging_description: row.packaging.clone(),
+                            sku: Some(
+                                row.sku
+                                    .clone()
+                                    .unwrap_or_else(|| generate_sku(&row.name, &row.packaging)),
+                            ),
+                            stock_in_sale_units: 0,
+                            price_per_sale_unit: row.price.clone(),
+                            deposit_item_id: linked_deposit,
+                            cost_price: row.cost.clone(),
+                        })
+                        .get_result(conn)?;
+                    (created.id, 0)
+                }
+            };
+
+            let delta = row.stock - current_stock;
+            if delta != 0 {
+                record_stock_movement(
+                    conn,
+                    product_id,
+                    delta,
+                    StockMovementReason::Adjustment,
+                    Some(user_id),
+                    None,
+                    Some(note.clone()),
+                )?;
+            }
+
+            diesel::insert_into(product_import_lines::table)
+                .values(&NewProductImportLine {
+                    id: Uuid::new_v4(),
+                    product_import_id: import.id,
+                    product_id: Some(product_id),
+                    line_number: row.line as i32,
+                    action: row.action.as_str().to_string(),
+                    changes: row.changes.join(" ; "),
+                })
+                .execute(conn)?;
+        }
+
+        Ok(import)
+    })
+}
+
+/// Derniers imports du catalogue, du plus récent au plus ancien.
+pub fn get_product_imports(limit: i64) -> AppResult<Vec<ProductImportSummary>> {
+    let mut conn = db::get_conn()?;
+    product_imports::table
+        .inner_join(users::table)
+        .order(product_imports::created_at.desc())
+        .limit(limit)
+        .select((ProductImport::as_select(), users::name))
+        .load::<(ProductImport, String)>(&mut conn)
+        .map(|rows| {
+            rows.into_iter()
+                .map(|(import, user_name)| ProductImportSummary { import, user_name })
+                .collect()
+        })
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::str::FromStr;
+
+    fn product(name: &str, packaging: &str, sku: &str, stock: i32, price: i64) -> Product {
+        Product {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            packaging_description: packaging.to_string(),
+            sku: Some(sku.to_string()),
+            stock_in_sale_units: stock,
+            price_per_sale_unit: BigDecimal::from(price),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deposit_item_id: None,
+            cost_price: None,
+        }
+    }
+
+    fn row(
+        line: usize,
+        sku: Option<&str>,
+        name: Option<&str>,
+        packaging: Option<&str>,
+    ) -> ImportRow {
+        ImportRow {
+            line,
+            sku: sku.map(str::to_string),
+            name: name.map(str::to_string),
+            packaging: packaging.map(str::to_string),
+            stock: None,
+            price: None,
+            cost: None,
+        }
+    }
+
+    fn parsed(rows: Vec<ImportRow>) -> ParsedImport {
+        ParsedImport {
+            file_name: "catalogue.csv".to_string(),
+            rows,
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_matches_by_sku_then_by_name() {
+        let isenbeck = product("Isenbeck", "Casier 65cl de 12", "ISEN-C12", 10, 5000);
+        let guinness = product("Guinness", "Casier 33cl de 24", "GUIN-C24", 4, 9000);
+        let catalogue = vec![isenbeck.clone(), guinness.clone()];
+
+        let mut by_sku = row(2, Some("isen-c12"), None, None);
+        by_sku.stock = Some(18);
+        let mut by_name = row(3, None, Some("guinness"), Some("casier 33cl de 24"));
+        by_name.price = BigDecimal::from_str("9000.00").ok();
+        let mut new = row(4, None, Some("Supermont"), Some("Palette 1.5L de 12"));
+        new.price = Some(BigDecimal::from(3000));
+
+        let preview = plan_import(&catalogue, &parsed(vec![by_sku, by_name, new]));
+
+        assert!(preview.errors.is_empty());
+        assert_eq!(preview.rows[0].product_id, Some(isenbeck.id));
+        assert_eq!(preview.rows[0].action, ImportAction::Updated);
+        assert_eq!(preview.rows[0].changes, vec!["Stock : 10 → 18".to_string()]);
+        assert_eq!(preview.rows[1].product_id, Some(guinness.id));
+        assert_eq!(preview.rows[1].action, ImportAction::Unchanged);
+        assert_eq!(preview.rows[2].action, ImportAction::Created);
+        assert_eq!(preview.rows[2].stock, 0);
+        assert!(preview.can_apply());
+    }
+
+    #[test]
+    fn test_plan_reports_conflicts_and_incomplete_rows() {
+        let isenbeck = product("Isenbeck", "Casier 65cl de 12", "ISEN-C12", 10, 5000);
+        let guinness = product("Guinness", "Casier 33cl de 24", "GUIN-C24", 4, 9000);
+        let catalogue = vec![isenbeck, guinness];
+
+        let twice = row(3, None, Some("Isenbeck"), Some("Casier 65cl de 12"));
+        let renamed = row(
+            4,
+            Some("GUIN-C24"),
+            Some("Isenbeck"),
+            Some("Casier 65cl de 12"),
+        );
+        let unknown = row(5, Some("INCONNU"), None, None);
+        let preview = plan_import(
+            &catalogue,
+            &parsed(vec![
+                row(2, Some("ISEN-C12"), None, None),
+                twice,
+                renamed,
+                unknown,
+            ]),
+        );
+
+        let lines = preview.errors.iter().map(|e| e.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert!(preview.errors[0].message.contains("ligne 2"));
+        assert!(preview.errors[1].message.contains("déjà utilisé"));
+        assert!(preview.errors[2].message.contains("INCONNU"));
+        assert!(!preview.can_apply());
+    }
+}
@@ -2,15 +2,18 @@
 
 use crate::{
     db,
-    error::AppResult,
-    models::{NewProduct, Product},
+    error::{AppError, AppResult},
+    models::{NewProduct, Product, StockMovementReason},
     schema,
 };
 use bigdecimal::BigDecimal;
 use diesel::prelude::*;
 use uuid::Uuid;
 
-use super::SortOrder;
+use super::{
+    SortOrder, deposit_queries::find_deposit_item_id_for_packaging,
+    stock_queries::record_stock_movement,
+};
 
 #[derive(Debug, Clone)]
 pub struct ProductSearchParams {
@@ -173,56 +176,125 @@ pub fn get_products_paginated(params: ProductSearchParams) -> AppResult<Paginate
 }
 
 /// Crée un nouveau produit fini (SKU).
+/// Le stock initial est inscrit au journal comme un ajustement.
 pub fn create_product(
     p_name: String,
     p_packaging: String,
     p_stock: i32,
     p_price: BigDecimal,
+    p_cost: Option<BigDecimal>,
+    user_id: Uuid,
 ) -> AppResult<Product> {
     use crate::schema::products::dsl::*;
 
-    let mut conn = db::get_conn()?;
-
-    let new_sku = generate_sku(&p_name, &p_packaging);
+    if p_stock < 0 {
+        return Err(AppError::ValidationError(
+            "Le stock ne peut pas être négatif".to_string(),
+        ));
+    }
+    validate_cost_price(p_cost.as_ref())?;
 
-    let new_product = NewProduct {
-        id: Uuid::new_v4(),
-        name: p_name,
-        packaging_description: p_packaging,
-        sku: Some(new_sku),
-        stock_in_sale_units: p_stock,
-        price_per_sale_unit: p_price,
-    };
+    let mut conn = db::get_conn()?;
 
-    diesel::insert_into(products)
-        .values(&new_product)
-        .get_result(&mut conn)
-        .map_err(Into::into)
+    conn.transaction(|conn| {
+        let new_sku = generate_sku(&p_name, &p_packaging);
+        // Rattache le produit à la consigne de son conditionnement, si elle existe
+        let linked_deposit = find_deposit_item_id_for_packaging(conn, &p_packaging)?;
+
+        let new_product = NewProduct {
+            id: Uuid::new_v4(),
+            name: p_name,
+            packaging_description: p_packaging,
+            sku: Some(new_sku),
+            stock_in_sale_units: 0,
+            price_per_sale_unit: p_price,
+            deposit_item_id: linked_deposit,
+            cost_price: p_cost,
+        };
+
+        let created: Product = diesel::insert_into(products)
+            .values(&new_product)
+            .get_result(conn)?;
+
+        if p_stock == 0 {
+            return Ok(created);
+        }
+        record_stock_movement(
+            conn,
+            created.id,
+            p_stock,
+            StockMovementReason::Adjustment,
+            Some(user_id),
+            None,
+            Some("Stock initial".to_string()),
+        )?;
+        products.find(created.id).first(conn)
+    })
+    .map_err(Into::into)
 }
 
 /// Met à jour un produit existant.
+/// Un changement de stock est inscrit au journal comme un ajustement, par différence avec le stock actuel.
 pub fn update_product(
     product_id: Uuid,
     new_name: String,
     new_packaging: String,
     new_stock: i32,
     new_price: BigDecimal,
+    new_cost: Option<BigDecimal>,
+    user_id: Uuid,
 ) -> AppResult<Product> {
     use crate::schema::products::dsl::*;
+    if new_stock < 0 {
+        return Err(AppError::ValidationError(
+            "Le stock ne peut pas être négatif".to_string(),
+        ));
+    }
+    validate_cost_price(new_cost.as_ref())?;
+
     let mut conn = db::get_conn()?;
 
-    let new_sku = generate_sku(&new_name, &new_packaging);
-
-    diesel::update(products.find(product_id))
-        .set((
-            name.eq(new_name),
-            packaging_description.eq(new_packaging),
-            sku.eq(Some(new_sku)),
-            stock_in_sale_units.eq(new_stock),
-            price_per_sale_unit.eq(new_price),
-        ))
-        .get_result(&mut conn)
-        .map_err(Into::into)
+    conn.transaction(|conn| {
+        let new_sku = generate_sku(&new_name, &new_packaging);
+        let linked_deposit = find_deposit_item_id_for_packaging(conn, &new_packaging)?;
+
+        // Verrouille le produit pour que l'écart soit calculé sur le stock réel
+        let current: Product = products.find(product_id).for_update().first(conn)?;
+        let delta = new_stock - current.stock_in_sale_units;
+        if delta != 0 {
+            record_stock_movement(
+                conn,
+                product_id,
+                delta,
+                StockMovementReason::Adjustment,
+                Some(user_id),
+                None,
+                Some("Modification du produit".to_string()),
+            )?;
+        }
+
+        diesel::update(products.find(product_id))
+            .set((
+                name.eq(new_name),
+                packaging_description.eq(new_packaging),
+                sku.eq(Some(new_sku)),
+                price_per_sale_unit.eq(new_price),
+                deposit_item_id.eq(linked_deposit),
+                cost_price.eq(new_cost),
+            ))
+            .get_result(conn)
+    })
+    .map_err(Into::into)
+}
+
+/// Le prix de revient est facultatif, mais ne peut pas être négatif.
+fn validate_cost_price(cost: Option<&BigDecimal>) -> AppResult<()> {
+    match cost {
+        Some(cost) if *cost < BigDecimal::from(0) => Err(AppError::ValidationError(
+            "Le prix de revient ne peut pas être négatif".to_string(),
+        )),
+        _ => Ok(()),
+    }
 }
 
 /// Supprime un produit. La suppression échouera si des ventes y sont liées (contrainte FK).
@@ -245,7 +317,7 @@ pub fn get_product_by_id(product_id: Uuid) -> AppResult<Product> {
 }
 
 /// Fonction d'aide pour générer un SKU standardisé.
-fn generate_sku(product_name: &str, packaging: &str) -> String {
+pub(super) fn generate_sku(product_name: &str, packaging: &str) -> String {
     let name_part = product_name
         .chars()
         .filter(|c| c.is_alphanumeric())
@@ -261,7 +333,8 @@ fn generate_sku(product_name: &str, packaging: &str) -> String {
     format!("{}-{}-{}", name_part, packaging_part, unique_part)
 }
 
-/// Vérifie si un produit peut être supprimé (pas de ventes associées)
+/// Vérifie si un produit peut être supprimé (pas de ventes, de commandes fournisseur, d'inventaires
+/// ni de mouvements de stock associés : le journal des mouvements ne doit pas disparaître avec le produit)
 pub fn can_delete_product(p_id: Uuid) -> AppResult<bool> {
     use crate::schema::sale_items::dsl::*;
     let mut conn = db::get_conn()?;
@@ -271,7 +344,35 @@ pub fn can_delete_product(p_id: Uuid) -> AppResult<bool> {
         .count()
         .get_result::<i64>(&mut conn)?;
 
-    Ok(sales_count == 0)
+    let purchase_lines_count = schema::purchase_order_lines::table
+        .filter(schema::purchase_order_lines::product_id.eq(p_id))
+        .count()
+        .get_result::<i64>(&mut conn)?;
+
+    let inventory_lines_count = schema::inventory_lines::table
+        .filter(schema::inventory_lines::product_id.eq(p_id))
+        .count()
+        .get_result::<i64>(&mut conn)?;
+
+    let movements_count = schema::stock_movements::table
+        .filter(schema::stock_movements::product_id.eq(p_id))
+        .count()
+        .get_result::<i64>(&mut conn)?;
+
+    Ok(sales_count == 0
+        && purchase_lines_count == 0
+        && inventory_lines_count == 0
+        && movements_count == 0)
+}
+
+/// Récupère tous les produits du catalogue, y compris ceux en rupture (pour les commandes fournisseur).
+pub fn get_all_products() -> AppResult<Vec<Product>> {
+    let mut conn = db::get_conn()?;
+
+    schema::products::table
+        .order(schema::products::name.asc())
+        .load::<Product>(&mut conn)
+        .map_err(Into::into)
 }
 
 pub fn get_available_products() -> AppResult<Vec<Product>> {
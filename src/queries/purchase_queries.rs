@@ -0,0 +1,364 @@
+// src/queries/purchase_queries.rs
+
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{
+        CreatePurchaseOrderData, NewPurchaseOrder, NewPurchaseOrderLine, NewSupplier, Product,
+        PurchaseOrder, PurchaseOrderLine, PurchaseOrderStatus, ReceiveLineData,
+        StockMovementReason, Supplier,
+    },
+    schema::{products, purchase_order_lines, purchase_orders, suppliers},
+};
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{numbering_queries::next_purchase_order_number, stock_queries::record_stock_movement};
+
+/// Un bon de commande avec son fournisseur et ses totaux, pour les listes.
+#[derive(Debug, Clone)]
+pub struct PurchaseOrderSummary {
+    pub order: PurchaseOrder,
+    pub supplier_name: String,
+    pub lines_count: i64,
+    /// Montant total commandé (quantités commandées x prix d'achat).
+    pub total_cost: BigDecimal,
+}
+
+/// Un bon de commande avec son fournisseur et ses lignes.
+#[derive(Debug, Clone)]
+pub struct PurchaseOrderWithLines {
+    pub order: PurchaseOrder,
+    pub supplier: Supplier,
+    pub lines: Vec<(PurchaseOrderLine, Product)>,
+}
+
+/// Récupère tous les fournisseurs, triés par nom.
+pub fn get_suppliers() -> AppResult<Vec<Supplier>> {
+    let mut conn = db::get_conn()?;
+    suppliers::table
+        .order(suppliers::name.asc())
+        .load::<Supplier>(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Crée un nouveau fournisseur.
+pub fn create_supplier(
+    s_name: String,
+    s_phone: Option<String>,
+    s_address: Option<String>,
+) -> AppResult<Supplier> {
+    validate_supplier_name(&s_name)?;
+    let mut conn = db::get_conn()?;
+
+    let new_supplier = NewSupplier {
+        id: Uuid::new_v4(),
+        name: s_name.trim().to_string(),
+        phone: s_phone.filter(|p| !p.trim().is_empty()),
+        address: s_address.filter(|a| !a.trim().is_empty()),
+    };
+
+    diesel::insert_into(suppliers::table)
+        .values(&new_supplier)
+        .get_result(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Met à jour les informations d'un fournisseur.
+pub fn update_supplier(
+    supplier_id: Uuid,
+    s_name: String,
+    s_phone: Option<String>,
+    s_address: Option<String>,
+) -> AppResult<Supplier> {
+    validate_supplier_name(&s_name)?;
+    let mut conn = db::get_conn()?;
+
+    diesel::update(suppliers::table.find(supplier_id))
+        .set((
+            suppliers::name.eq(s_name.trim()),
+            suppliers::phone.eq(s_phone.filter(|p| !p.trim().is_empty())),
+            suppliers::address.eq(s_address.filter(|a| !a.trim().is_empty())),
+        ))
+        .get_result(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Récupère les bons de commande, les plus récents en premier, éventuellement filtrés par état.
+pub fn get_purchase_orders(
+    status_filter: Option<PurchaseOrderStatus>,
+) -> AppResult<Vec<PurchaseOrderSummary>> {
+    let mut conn = db::get_conn()?;
+
+    let mut query = purchase_orders::table
+        .inner_join(suppliers::table)
+        .select((PurchaseOrder::as_select(), suppliers::name))
+        .into_boxed();
+    if let Some(status) = status_filter {
+        query = query.filter(purchase_orders::status.eq(status.as_str()));
+    }
+    let orders: Vec<(PurchaseOrder, String)> = query
+        .order(purchase_orders::order_date.desc())
+        .load(&mut conn)?;
+
+    let order_ids: Vec<Uuid> = orders.iter().map(|(o, _)| o.id).collect();
+    let lines: Vec<PurchaseOrderLine> = purchase_order_lines::table
+        .filter(purchase_order_lines::purchase_order_id.eq_any(&order_ids))
+        .load(&mut conn)?;
+
+    let mut totals: HashMap<Uuid, (i64, BigDecimal)> = HashMap::new();
+    for line in &lines {
+        let entry = totals
+            .entry(line.purchase_order_id)
+            .or_insert_with(|| (0, BigDecimal::from(0)));
+        entry.0 += 1;
+        entry.1 += &line.unit_cost * BigDecimal::from(line.quantity_ordered);
+    }
+
+    Ok(orders
+        .into_iter()
+        .map(|(order, supplier_name)| {
+            let (lines_count, total_cost) = totals
+                .remove(&order.id)
+                .unwrap_or_else(|| (0, BigDecimal::from(0)));
+            PurchaseOrderSummary {
+                order,
+                supplier_name,
+                lines_count,
+                total_cost,
+            }
+        })
+        .collect())
+}
+
+/// Récupère un bon de commande avec son fournisseur et ses lignes.
+pub fn get_purchase_order_details(order_id: Uuid) -> AppResult<PurchaseOrderWithLines> {
+    let mut conn = db::get_conn()?;
+
+    let (order, supplier): (PurchaseOrder, Supplier) = purchase_orders::table
+        .find(order_id)
+        .inner_join(suppliers::table)
+        .select((PurchaseOrder::as_select(), Supplier::as_select()))
+        .first(&mut conn)?;
+
+    let lines = PurchaseOrderLine::belonging_to(&order)
+        .inner_join(products::table)
+        .select((PurchaseOrderLine::as_select(), Product::as_select()))
+        .order(products::name.asc())
+        .load::<(PurchaseOrderLine, Product)>(&mut conn)?;
+
+    Ok(PurchaseOrderWithLines {
+        order,
+        supplier,
+        lines,
+    })
+}
+
+/// Crée un bon de commande à l'état brouillon.
+pub fn create_purchase_order(data: CreatePurchaseOrderData) -> AppResult<PurchaseOrder> {
+    if data.lines.is_empty() {
+        return Err(AppError::ValidationError(
+            "La commande doit contenir au moins une ligne".to_string(),
+        ));
+    }
+    for line in &data.lines {
+        if line.quantity <= 0 {
+            return Err(AppError::ValidationError(
+                "Les quantités commandées doivent être positives".to_string(),
+            ));
+        }
+        if line.unit_cost < BigDecimal::from(0) {
+            return Err(AppError::ValidationError(
+                "Le prix d'achat ne peut pas être négatif".to_string(),
+            ));
+        }
+    }
+
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let new_order = NewPurchaseOrder {
+            id: Uuid::new_v4(),
+            po_number: next_purchase_order_number(conn)?,
+            supplier_id: data.supplier_id,
+            user_id: data.user_id,
+            status: PurchaseOrderStatus::Draft.as_str().to_string(),
+            order_date: Utc::now(),
+            note: data.note.filter(|n| !n.trim().is_empty()),
+        };
+        let order: PurchaseOrder = diesel::insert_into(purchase_orders::table)
+            .values(&new_order)
+            .get_result(conn)?;
+
+        let new_lines: Vec<NewPurchaseOrderLine> = data
+            .lines
+            .iter()
+            .map(|line| NewPurchaseOrderLine {
+                id: Uuid::new_v4(),
+                purchase_order_id: order.id,
+                product_id: line.product_id,
+                quantity_ordered: line.quantity,
+                unit_cost: line.unit_cost.clone(),
+            })
+            .collect();
+        diesel::insert_into(purchase_order_lines::table)
+            .values(&new_lines)
+            .execute(conn)?;
+
+        Ok(order)
+    })
+}
+
+/// Marque un bon de commande brouillon comme envoyé au fournisseur.
+pub fn mark_purchase_order_sent(order_id: Uuid) -> AppResult<PurchaseOrder> {
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let order: PurchaseOrder = purchase_orders::table
+            .find(order_id)
+            .for_update()
+            .first(conn)?;
+        if order.status() != Some(PurchaseOrderStatus::Draft) {
+            return Err(AppError::ValidationError(format!(
+                "La commande {} a déjà été envoyée",
+                order.po_number
+            )));
+        }
+
+        diesel::update(purchase_orders::table.find(order_id))
+            .set(purchase_orders::status.eq(PurchaseOrderStatus::Sent.as_str()))
+            .get_result(conn)
+            .map_err(Into::into)
+    })
+}
+
+/// Supprime un bon de commande encore à l'état brouillon (ses lignes sont supprimées en cascade).
+pub fn delete_purchase_order(order_id: Uuid) -> AppResult<usize> {
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let order: PurchaseOrder = purchase_orders::table
+            .find(order_id)
+            .for_update()
+            .first(conn)?;
+        if order.status() != Some(PurchaseOrderStatus::Draft) {
+            return Err(AppError::ValidationError(
+                "Seule une commande en brouillon peut être supprimée".to_string(),
+            ));
+        }
+
+        diesel::delete(purchase_orders::table.find(order_id))
+            .execute(conn)
+            .map_err(Into::into)
+    })
+}
+
+/// Réceptionne une livraison : augmente le stock des produits, enregistre le prix d'achat
+/// et passe la commande à l'état "reçue partiellement" ou "reçue".
+pub fn receive_purchase_order(
+    order_id: Uuid,
+    received: Vec<ReceiveLineData>,
+    user_id: Uuid,
+) -> AppResult<PurchaseOrder> {
+    let received: Vec<ReceiveLineData> = received.into_iter().filter(|r| r.quantity != 0).collect();
+    if received.is_empty() {
+        return Err(AppError::ValidationError(
+            "Aucune quantité réceptionnée".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        // Verrouille la commande pour sérialiser les réceptions concurrentes
+        let order: PurchaseOrder = purchase_orders::table
+            .find(order_id)
+            .for_update()
+            .first(conn)?;
+        if !order.status().is_some_and(|s| s.can_receive()) {
+            return Err(AppError::ValidationError(format!(
+                "La commande {} ne peut pas être réceptionnée dans son état actuel",
+                order.po_number
+            )));
+        }
+
+        let lines: HashMap<Uuid, PurchaseOrderLine> = PurchaseOrderLine::belonging_to(&order)
+            .load::<PurchaseOrderLine>(conn)?
+            .into_iter()
+            .map(|line| (line.id, line))
+            .collect();
+
+        for receipt in &received {
+            let line = lines.get(&receipt.line_id).ok_or_else(|| {
+                AppError::ValidationError("Ligne de commande introuvable".to_string())
+            })?;
+            if receipt.quantity < 0 || receipt.quantity > line.quantity_remaining() {
+                return Err(AppError::ValidationError(format!(
+                    "Quantité réceptionnée invalide ({}) : il reste {} à livrer sur cette ligne",
+                    receipt.quantity,
+                    line.quantity_remaining()
+                )));
+            }
+            let unit_cost = receipt
+                .unit_cost
+                .clone()
+                .unwrap_or_else(|| line.unit_cost.clone());
+            if unit_cost < BigDecimal::from(0) {
+                return Err(AppError::ValidationError(
+                    "Le prix d'achat ne peut pas être négatif".to_string(),
+                ));
+            }
+
+            diesel::update(purchase_order_lines::table.find(line.id))
+                .set((
+                    purchase_order_lines::quantity_received
+                        .eq(purchase_order_lines::quantity_received + receipt.quantity),
+                    purchase_order_lines::unit_cost.eq(&unit_cost),
+                ))
+                .execute(conn)?;
+
+            record_stock_movement(
+                conn,
+                line.product_id,
+                receipt.quantity,
+                StockMovementReason::Receipt,
+                Some(user_id),
+                Some(order.po_number.clone()),
+                None,
+            )?;
+            diesel::update(products::table.find(line.product_id))
+                .set(products::cost_price.eq(Some(unit_cost.clone())))
+                .execute(conn)?;
+        }
+
+        // L'état de la commande est déduit des quantités restant à livrer
+        let outstanding: i64 = PurchaseOrderLine::belonging_to(&order)
+            .filter(
+                purchase_order_lines::quantity_received.lt(purchase_order_lines::quantity_ordered),
+            )
+            .count()
+            .get_result(conn)?;
+        let new_status = if outstanding == 0 {
+            PurchaseOrderStatus::Received
+        } else {
+            PurchaseOrderStatus::PartiallyReceived
+        };
+
+        diesel::update(purchase_orders::table.find(order_id))
+            .set(purchase_orders::status.eq(new_status.as_str()))
+            .get_result(conn)
+            .map_err(Into::into)
+    })
+}
+
+fn validate_supplier_name(s_name: &str) -> AppResult<()> {
+    if s_name.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le nom du fournisseur est obligatoire".to_string(),
+        ));
+    }
+    Ok(())
+}
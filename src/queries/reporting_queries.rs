@@ -1,17 +1,347 @@
 // src/queries/reporting_queries.rs
 
-use crate::{db, error::AppResult, models::Product};
+use super::{
+    commission_queries::{commission_rule_for, load_commission_rules},
+    credit_note_queries::{compute_returned_quantities, compute_returns_total},
+    deposit_queries::{DepositReportLine, compute_deposit_report},
+};
+use crate::{
+    config::business_calendar::{BusinessCalendar, DateRange, business_calendar},
+    db,
+    error::AppResult,
+    models::{CommissionRule, PaymentMethod, Product, SaleStatus},
+};
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
 use diesel::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use uuid::Uuid;
 
+/// Période de référence à laquelle comparer le rapport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComparisonMode {
+    #[default]
+    None,
+    /// Période de même durée qui précède immédiatement.
+    PreviousPeriod,
+    /// Mêmes journées un an plus tôt.
+    SamePeriodLastYear,
+}
+
+impl ComparisonMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ComparisonMode::None => "Sans comparaison",
+            ComparisonMode::PreviousPeriod => "Période précédente",
+            ComparisonMode::SamePeriodLastYear => "Même période l'an dernier",
+        }
+    }
+}
+
+// Indicateurs clés d'une période
+#[derive(Debug, Clone, Default)]
+pub struct ReportTotals {
+    pub total_revenue: BigDecimal, // Chiffre d'affaires net des avoirs
+    pub total_sales: i64,
+    pub total_returns: BigDecimal, // Avoirs émis sur la période
+    pub voided_sales: i64,         // Ventes annulées sur la période
+    pub margin: MarginLine,        // Marge brute des ventes de la période
+}
+
+impl ReportTotals {
+    /// Chiffre d'affaires moyen par vente.
+    pub fn average_basket(&self) -> BigDecimal {
+        if self.total_sales > 0 {
+            &self.total_revenue / BigDecimal::from(self.total_sales)
+        } else {
+            BigDecimal::from(0)
+        }
+    }
+}
+
+// Marge brute d'un regroupement de lignes vendues (produit, conditionnement, vendeur, période)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarginLine {
+    pub label: String,
+    pub quantity: i64,                // Unités vendues, nettes des retours
+    pub revenue: BigDecimal,          // Chiffre d'affaires des lignes dont le coût est connu
+    pub cost: BigDecimal,             // Coût d'achat de ces mêmes lignes
+    pub uncosted_revenue: BigDecimal, // Chiffre d'affaires sans coût connu, exclu de la marge
+}
+
+impl MarginLine {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, line: &SoldLine) {
+        let quantity = BigDecimal::from(line.net_quantity);
+        let revenue = &line.unit_price * &quantity;
+        self.quantity += line.net_quantity;
+        match &line.unit_cost {
+            Some(unit_cost) => {
+                self.cost += unit_cost * &quantity;
+                self.revenue += revenue;
+            }
+            None => self.uncosted_revenue += revenue,
+        }
+    }
+
+    /// Marge brute : chiffre d'affaires moins coût d'achat, sur les lignes au coût connu.
+    pub fn margin(&self) -> BigDecimal {
+        &self.revenue - &self.cost
+    }
+
+    /// Taux de marge brute en pourcentage du chiffre d'affaires (`None` sans vente au coût connu).
+    pub fn margin_rate(&self) -> Option<BigDecimal> {
+        if self.revenue == BigDecimal::from(0) {
+            return None;
+        }
+        Some(self.margin() * BigDecimal::from(100) / &self.revenue)
+    }
+}
+
+// Marges de la période, par produit, par conditionnement et par vendeur (marge décroissante)
+#[derive(Debug, Clone, Default)]
+pub struct MarginReport {
+    pub by_product: Vec<MarginLine>,
+    pub by_packaging: Vec<MarginLine>,
+    pub by_seller: Vec<MarginLine>,
+}
+
+// Ligne de vente chargée pour le calcul des marges et des performances des vendeurs
+struct SoldLine {
+    sale_id: Uuid,
+    sold_at: DateTime<Utc>,
+    product: String,
+    packaging: String,
+    seller_id: Uuid,
+    seller: String,
+    net_quantity: i64,
+    unit_price: BigDecimal,
+    unit_cost: Option<BigDecimal>,
+}
+
+// Période de référence et ses indicateurs
+#[derive(Debug, Clone)]
+pub struct ReportComparison {
+    pub mode: ComparisonMode,
+    pub range: DateRange,
+    pub totals: ReportTotals,
+}
+
 // Structure pour contenir toutes les données agrégées pour un rapport
 #[derive(Debug, Clone, Default)]
 pub struct ReportData {
-    pub total_revenue: BigDecimal,
-    pub total_sales: i64,
+    pub range: DateRange,
+    pub totals: ReportTotals,
     pub top_products: Vec<(Product, i64)>, // (Produit, Quantité totale vendue)
+    pub deposits: Vec<DepositReportLine>,  // Situation des consignes (emballages)
+    pub comparison: Option<ReportComparison>,
+    pub margins: MarginReport,
+    pub granularity: Granularity,
+    pub timeseries: Vec<TimeseriesPoint>, // Évolution des ventes sur la période
+    pub sellers: Vec<SellerPerformance>,  // Performances et commissions par vendeur
+    pub sales: Vec<SaleListingLine>,      // Détail des ventes de la période, annulées comprises
+    pub daily: Vec<TimeseriesPoint>,      // Totaux par journée commerciale
+    pub stock: Vec<Product>,              // Stock actuel, pour sa valorisation
+}
+
+// Vente de la période, pour le détail des ventes du rapport
+#[derive(Debug, Clone)]
+pub struct SaleListingLine {
+    pub sale_number: String,
+    pub date: DateTime<Utc>,
+    pub seller: String,
+    pub customer: Option<String>,
+    pub status: SaleStatus,
+    pub units: i64, // Unités vendues, nettes des retours
+    pub total_amount: BigDecimal,
+    pub payment_methods: Vec<String>, // Libellés des moyens de paiement utilisés
+    pub items: Vec<SaleItemLine>,
+}
+
+// Article d'une vente du détail des ventes
+#[derive(Debug, Clone)]
+pub struct SaleItemLine {
+    pub product: String,
+    pub packaging: String,
+    pub quantity: i32,
+    pub quantity_returned: i32,
+    pub unit_price: BigDecimal,
+    pub unit_cost: Option<BigDecimal>, // Coût d'achat figé au moment de la vente
+}
+
+impl SaleItemLine {
+    /// Quantité vendue, nette des articles repris par avoir.
+    pub fn net_quantity(&self) -> i64 {
+        (self.quantity - self.quantity_returned) as i64
+    }
+}
+
+// Activité d'un vendeur sur une journée commerciale, pour le relevé de commissions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellerDay {
+    pub date: NaiveDate,
+    pub revenue: BigDecimal,
+    pub sales_count: i64,
+    pub units: i64,
+    pub commission: BigDecimal,
+}
+
+// Performance d'un vendeur sur la période et commission due selon sa règle
+#[derive(Debug, Clone)]
+pub struct SellerPerformance {
+    pub seller_id: Uuid,
+    pub seller_name: String,
+    pub revenue: BigDecimal, // Chiffre d'affaires net des retours
+    pub sales_count: i64,
+    pub units: i64,       // Unités vendues, nettes des retours
+    pub active_days: i64, // Journées commerciales avec au moins une vente
+    pub rule: Option<CommissionRule>,
+    pub commission: BigDecimal,
+    pub days: Vec<SellerDay>, // Détail par journée, dans l'ordre chronologique
+}
+
+impl SellerPerformance {
+    /// Chiffre d'affaires moyen par vente.
+    pub fn average_basket(&self) -> BigDecimal {
+        if self.sales_count > 0 {
+            &self.revenue / BigDecimal::from(self.sales_count)
+        } else {
+            BigDecimal::from(0)
+        }
+    }
+}
+
+/// Taille des regroupements d'une série temporelle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Granularity {
+    #[default]
+    Day,
+    /// Semaine ISO, du lundi au dimanche.
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// Granularité adaptée à la longueur de la période :
+    /// par jour jusqu'à un mois, par semaine jusqu'à six mois, par mois au-delà.
+    pub fn for_range(range: &DateRange) -> Self {
+        let (first, last) = business_calendar().bounds(range);
+        match (last - first).num_days() + 1 {
+            ..=31 => Granularity::Day,
+            32..=186 => Granularity::Week,
+            _ => Granularity::Month,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Granularity::Day => "jour",
+            Granularity::Week => "semaine",
+            Granularity::Month => "mois",
+        }
+    }
+
+    /// Premier jour du regroupement contenant `date`.
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            Granularity::Month => date.with_day(1).unwrap(),
+        }
+    }
+
+    fn next_bucket(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => start + Duration::days(1),
+            Granularity::Week => start + Duration::days(7),
+            Granularity::Month => start + Months::new(1),
+        }
+    }
+
+    /// Libellé court d'un regroupement, pour l'axe des graphiques.
+    pub fn bucket_label(&self, start: NaiveDate) -> String {
+        match self {
+            Granularity::Day => start.format("%d/%m").to_string(),
+            Granularity::Week => format!("S{:02}", start.iso_week().week()),
+            Granularity::Month => start.format("%m/%Y").to_string(),
+        }
+    }
+}
+
+// Ventes d'un regroupement de la série temporelle
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeseriesPoint {
+    pub bucket_start: NaiveDate,
+    pub revenue: BigDecimal, // Chiffre d'affaires net des avoirs
+    pub sales_count: i64,
+    pub units: i64,         // Unités vendues, nettes des retours
+    pub margin: BigDecimal, // Marge brute des ventes du regroupement
+}
+
+impl TimeseriesPoint {
+    fn empty(bucket_start: NaiveDate) -> Self {
+        Self {
+            bucket_start,
+            revenue: BigDecimal::from(0),
+            sales_count: 0,
+            units: 0,
+            margin: BigDecimal::from(0),
+        }
+    }
+
+    /// Chiffre d'affaires moyen par vente.
+    pub fn average_basket(&self) -> BigDecimal {
+        if self.sales_count > 0 {
+            &self.revenue / BigDecimal::from(self.sales_count)
+        } else {
+            BigDecimal::from(0)
+        }
+    }
+}
+
+// Mouvement élémentaire ventilé dans la série : vente, ligne vendue, avoir ou article repris
+struct TimeseriesEntry {
+    at: DateTime<Utc>,
+    revenue: BigDecimal,
+    sales_count: i64,
+    units: i64,
+    margin: BigDecimal,
+}
+
+// Ligne vendue chargée pour la série, avec ses prix unitaires figés à la vente
+#[derive(Queryable)]
+struct TimeseriesSoldUnits {
+    at: DateTime<Utc>,
+    quantity: i32,
+    quantity_returned: i32,
+    unit_price: BigDecimal,
+    unit_cost: Option<BigDecimal>,
+}
+
+/// Variation en pourcentage de `current` par rapport à `previous`.
+/// `None` si la valeur de référence est nulle (variation non définie).
+pub fn percent_change(current: &BigDecimal, previous: &BigDecimal) -> Option<BigDecimal> {
+    if *previous == BigDecimal::from(0) {
+        return None;
+    }
+    Some((current - previous) * BigDecimal::from(100) / previous.abs())
+}
+
+/// Variation formatée pour l'affichage, par exemple « +12.5 % » ou « n.d. ».
+pub fn format_percent_change(current: &BigDecimal, previous: &BigDecimal) -> String {
+    match percent_change(current, previous) {
+        Some(change) if change > BigDecimal::from(0) => format!("+{:.1} %", change),
+        Some(change) => format!("{:.1} %", change),
+        None => "n.d.".to_string(),
+    }
 }
 
 // Structure pour le résultat de la requête d'agrégation des produits
@@ -21,43 +351,91 @@ struct TopProductResult {
     total_quantity: Option<i64>, // sum(Int4) -> BigInt (i64), et il peut être NULL
 }
 
-/// Génère des données de rapport pour une période donnée.
-pub fn get_report_data(
-    start_date: DateTime<Utc>,
-    end_date: DateTime<Utc>,
-) -> AppResult<ReportData> {
+/// Génère des données de rapport pour une période donnée,
+/// accompagnées des indicateurs de la période de comparaison demandée.
+pub fn get_report_data(range: DateRange, comparison: ComparisonMode) -> AppResult<ReportData> {
     use crate::schema::{products, sale_items, sales};
     let mut conn = db::get_conn()?;
+    let (start_date, end_date) = (range.start, range.last_instant());
 
-    // --- 1. KPI principaux (ne change pas) ---
-    let (total_revenue, total_sales) = sales::table
-        .filter(sales::date.between(start_date, end_date))
-        .select((
-            diesel::dsl::sum(sales::total_amount),
-            diesel::dsl::count(sales::id),
-        ))
-        .first::<(Option<BigDecimal>, i64)>(&mut conn)?;
+    // --- 1. KPI principaux : ventes annulées exclues, avoirs déduits ---
+    let sold_lines = load_sold_lines(&mut conn, &range)?;
+    let totals = compute_totals(&mut conn, &range, &sold_lines)?;
+
+    let calendar = business_calendar();
+    let comparison = match comparison {
+        ComparisonMode::None => None,
+        ComparisonMode::PreviousPeriod => Some(calendar.previous_period(&range)),
+        ComparisonMode::SamePeriodLastYear => Some(calendar.same_period_last_year(&range)),
+    }
+    .map(|previous| {
+        let previous_lines = load_sold_lines(&mut conn, &previous)?;
+        compute_totals(&mut conn, &previous, &previous_lines).map(|totals| ReportComparison {
+            mode: comparison,
+            range: previous,
+            totals,
+        })
+    })
+    .transpose()?;
+
+    // --- Marges brutes par produit, conditionnement et vendeur ---
+    let margins = MarginReport {
+        by_product: group_margins(&sold_lines, |l| format!("{} — {}", l.product, l.packaging)),
+        by_packaging: group_margins(&sold_lines, |l| l.packaging.clone()),
+        by_seller: group_margins(&sold_lines, |l| l.seller.clone()),
+    };
+
+    // --- Performances des vendeurs et commissions dues ---
+    let rules = load_commission_rules(&mut conn)?;
+    let sellers = compute_seller_performance(calendar, &sold_lines, &rules);
 
-    // --- 2. Top 5 des produits vendus (par quantité) ---
-    let top_products_query = sale_items::table
+    // --- Consignes : mouvements de la période et emballages dus ---
+    let deposits = compute_deposit_report(&mut conn, start_date, end_date)?;
+
+    // --- Évolution des ventes, regroupées selon la longueur de la période ---
+    let granularity = Granularity::for_range(&range);
+    let timeseries = compute_timeseries(&mut conn, &range, granularity)?;
+    let daily = if granularity == Granularity::Day {
+        timeseries.clone()
+    } else {
+        compute_timeseries(&mut conn, &range, Granularity::Day)?
+    };
+
+    // --- Détail des ventes de la période ---
+    let sales = load_sale_listing(&mut conn, &range)?;
+
+    // --- Stock actuel, valorisé au prix de revient et au prix de vente ---
+    let stock = products::table
+        .order((products::name.asc(), products::packaging_description.asc()))
+        .load::<Product>(&mut conn)?;
+
+    // --- 2. Top 5 des produits vendus (par quantité nette des retours) ---
+    let sold_quantities_query = sale_items::table
         .inner_join(sales::table.on(sale_items::sale_id.eq(sales::id)))
-        .filter(sales::date.between(start_date, end_date))
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
         .group_by(sale_items::product_id)
         .select((
             sale_items::product_id,
             diesel::dsl::sum(sale_items::quantity),
-        ))
-        .order(diesel::dsl::sum(sale_items::quantity).desc().nulls_last()) // Trier avant de limiter
-        .limit(5);
+        ));
 
     // On charge le résultat dans notre struct explicite
-    let top_product_results: Vec<TopProductResult> = top_products_query.load(&mut conn)?;
+    let sold_quantities: Vec<TopProductResult> = sold_quantities_query.load(&mut conn)?;
+    let returned_quantities = compute_returned_quantities(&mut conn, start_date, end_date)?;
 
-    // On convertit le résultat en une structure plus simple pour la suite
-    let top_products_with_quantities: Vec<(Uuid, i64)> = top_product_results
+    // Les retours sont déduits avant de trier et de limiter
+    let mut top_products_with_quantities: Vec<(Uuid, i64)> = sold_quantities
         .into_iter()
-        .map(|r| (r.product_id, r.total_quantity.unwrap_or(0)))
+        .map(|r| {
+            let returned = returned_quantities.get(&r.product_id).copied().unwrap_or(0);
+            (r.product_id, r.total_quantity.unwrap_or(0) - returned)
+        })
+        .filter(|(_, quantity)| *quantity > 0)
         .collect();
+    top_products_with_quantities.sort_by_key(|(_, quantity)| std::cmp::Reverse(*quantity));
+    top_products_with_quantities.truncate(5);
 
     let top_product_ids: Vec<Uuid> = top_products_with_quantities
         .iter()
@@ -66,9 +444,18 @@ pub fn get_report_data(
 
     if top_product_ids.is_empty() {
         return Ok(ReportData {
-            total_revenue: total_revenue.unwrap_or_else(|| BigDecimal::from(0)),
-            total_sales,
+            range,
+            totals,
             top_products: vec![],
+            deposits,
+            comparison,
+            margins,
+            granularity,
+            timeseries,
+            sellers,
+            sales,
+            daily,
+            stock,
         });
     }
 
@@ -88,8 +475,593 @@ pub fn get_report_data(
         .collect();
 
     Ok(ReportData {
-        total_revenue: total_revenue.unwrap_or_else(|| BigDecimal::from(0)),
-        total_sales,
+        range,
+        totals,
         top_products: final_top_products,
+        deposits,
+        comparison,
+        margins,
+        granularity,
+        timeseries,
+        sellers,
+        sales,
+        daily,
+        stock,
     })
 }
+
+/// Calcule les indicateurs clés d'une période : ventes annulées exclues, avoirs déduits.
+/// La marge est calculée sur les lignes vendues de la période, chargées par l'appelant.
+fn compute_totals(
+    conn: &mut PgConnection,
+    range: &DateRange,
+    sold_lines: &[SoldLine],
+) -> QueryResult<ReportTotals> {
+    use crate::schema::sales;
+
+    let (gross_revenue, total_sales) = sales::table
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((
+            diesel::dsl::sum(sales::total_amount),
+            diesel::dsl::count(sales::id),
+        ))
+        .first::<(Option<BigDecimal>, i64)>(conn)?;
+    let total_returns = compute_returns_total(conn, range.start, range.last_instant())?;
+    let total_revenue = gross_revenue.unwrap_or_else(|| BigDecimal::from(0)) - &total_returns;
+
+    let voided_sales = sales::table
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.eq(SaleStatus::Voided.as_str()))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    let mut margin = MarginLine::new("Total".to_string());
+    sold_lines.iter().for_each(|line| margin.add(line));
+
+    Ok(ReportTotals {
+        total_revenue,
+        total_sales,
+        total_returns,
+        voided_sales,
+        margin,
+    })
+}
+
+/// Lignes vendues sur la période (ventes annulées exclues), nettes des quantités reprises par avoir.
+/// Chaque ligne porte le coût d'achat figé au moment de la vente.
+fn load_sold_lines(conn: &mut PgConnection, range: &DateRange) -> QueryResult<Vec<SoldLine>> {
+    use crate::schema::{products, sale_items, sales, users};
+
+    let rows = sale_items::table
+        .inner_join(sales::table.on(sale_items::sale_id.eq(sales::id)))
+        .inner_join(products::table.on(sale_items::product_id.eq(products::id)))
+        .inner_join(users::table.on(sales::user_id.eq(users::id)))
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((
+            sales::id,
+            sales::date,
+            products::name,
+            products::packaging_description,
+            users::id,
+            users::name,
+            sale_items::quantity,
+            sale_items::quantity_returned,
+            sale_items::unit_price,
+            sale_items::unit_cost,
+        ))
+        .load::<(
+            Uuid,
+            DateTime<Utc>,
+            String,
+            String,
+            Uuid,
+            String,
+            i32,
+            i32,
+            BigDecimal,
+            Option<BigDecimal>,
+        )>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                sale_id,
+                sold_at,
+                product,
+                packaging,
+                seller_id,
+                seller,
+                quantity,
+                returned,
+                unit_price,
+                unit_cost,
+            )| SoldLine {
+                sale_id,
+                sold_at,
+                product,
+                packaging,
+                seller_id,
+                seller,
+                net_quantity: (quantity - returned) as i64,
+                unit_price,
+                unit_cost,
+            },
+        )
+        .collect())
+}
+
+/// Ventes de la période par ordre chronologique, annulées comprises, avec le client,
+/// le vendeur, les moyens de paiement et le détail des articles.
+fn load_sale_listing(
+    conn: &mut PgConnection,
+    range: &DateRange,
+) -> QueryResult<Vec<SaleListingLine>> {
+    use crate::schema::{customers, payments, products, sale_items, sales, users};
+
+    let rows = sales::table
+        .inner_join(users::table)
+        .left_join(customers::table)
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .order((sales::date.asc(), sales::sale_number.asc()))
+        .select((
+            sales::id,
+            sales::sale_number,
+            sales::date,
+            users::name,
+            customers::name.nullable(),
+            sales::status,
+            sales::total_amount,
+        ))
+        .load::<(
+            Uuid,
+            String,
+            DateTime<Utc>,
+            String,
+            Option<String>,
+            String,
+            BigDecimal,
+        )>(conn)?;
+    let sale_ids = rows.iter().map(|row| row.0).collect::<Vec<_>>();
+
+    let mut items: HashMap<Uuid, Vec<SaleItemLine>> = HashMap::new();
+    sale_items::table
+        .inner_join(products::table)
+        .filter(sale_items::sale_id.eq_any(&sale_ids))
+        .order(sale_items::created_at.asc())
+        .select((
+            sale_items::sale_id,
+            products::name,
+            products::packaging_description,
+            sale_items::quantity,
+            sale_items::quantity_returned,
+            sale_items::unit_price,
+            sale_items::unit_cost,
+        ))
+        .load::<(
+            Uuid,
+            String,
+            String,
+            i32,
+            i32,
+            BigDecimal,
+            Option<BigDecimal>,
+        )>(conn)?
+        .into_iter()
+        .for_each(
+            |(sale_id, product, packaging, quantity, quantity_returned, unit_price, unit_cost)| {
+                items.entry(sale_id).or_default().push(SaleItemLine {
+                    product,
+                    packaging,
+                    quantity,
+                    quantity_returned,
+                    unit_price,
+                    unit_cost,
+                });
+            },
+        );
+
+    let mut methods: HashMap<Uuid, Vec<String>> = HashMap::new();
+    payments::table
+        .filter(payments::sale_id.eq_any(&sale_ids))
+        .order(payments::created_at.asc())
+        .select((payments::sale_id, payments::method))
+        .load::<(Uuid, String)>(conn)?
+        .into_iter()
+        .for_each(|(sale_id, method)| {
+            let label = PaymentMethod::from_db(&method)
+                .map(|m| m.label().to_string())
+                .unwrap_or(method);
+            let labels = methods.entry(sale_id).or_default();
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        });
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, sale_number, date, seller, customer, status, total_amount)| {
+                let items = items.remove(&id).unwrap_or_default();
+                SaleListingLine {
+                    sale_number,
+                    date,
+                    seller,
+                    customer,
+                    status: SaleStatus::from_db(&status).unwrap_or(SaleStatus::Completed),
+                    units: items.iter().map(SaleItemLine::net_quantity).sum(),
+                    total_amount,
+                    payment_methods: methods.remove(&id).unwrap_or_default(),
+                    items,
+                }
+            },
+        )
+        .collect())
+}
+
+/// Performances par vendeur, par chiffre d'affaires décroissant.
+/// La commission suit la règle du vendeur, ou à défaut la règle par défaut ; sans règle, elle est nulle.
+fn compute_seller_performance(
+    calendar: &BusinessCalendar,
+    lines: &[SoldLine],
+    rules: &[CommissionRule],
+) -> Vec<SellerPerformance> {
+    // Par vendeur puis par journée : chiffre d'affaires, ventes distinctes et unités
+    type DayTotals = (BigDecimal, HashSet<Uuid>, i64);
+    let mut by_seller: HashMap<Uuid, (String, BTreeMap<NaiveDate, DayTotals>)> = HashMap::new();
+    for line in lines {
+        let (_, days) = by_seller
+            .entry(line.seller_id)
+            .or_insert_with(|| (line.seller.clone(), BTreeMap::new()));
+        let (revenue, sales, units) = days
+            .entry(calendar.business_date(line.sold_at))
+            .or_insert_with(|| (BigDecimal::from(0), HashSet::new(), 0));
+        *revenue += &line.unit_price * BigDecimal::from(line.net_quantity);
+        sales.insert(line.sale_id);
+        *units += line.net_quantity;
+    }
+
+    let mut sellers = by_seller
+        .into_iter()
+        .map(|(seller_id, (seller_name, days))| {
+            let rule = commission_rule_for(rules, seller_id).cloned();
+            let days = days
+                .into_iter()
+                .map(|(date, (revenue, sales, units))| SellerDay {
+                    commission: rule
+                        .as_ref()
+                        .map(|rule| rule.commission(&revenue, units))
+                        .unwrap_or_else(|| BigDecimal::from(0)),
+                    date,
+                    revenue,
+                    sales_count: sales.len() as i64,
+                    units,
+                })
+                .collect::<Vec<_>>();
+            SellerPerformance {
+                seller_id,
+                seller_name,
+                revenue: days.iter().map(|d| &d.revenue).sum(),
+                sales_count: days.iter().map(|d| d.sales_count).sum(),
+                units: days.iter().map(|d| d.units).sum(),
+                active_days: days.len() as i64,
+                commission: days.iter().map(|d| &d.commission).sum(),
+                rule,
+                days,
+            }
+        })
+        .collect::<Vec<_>>();
+    sellers.sort_by(|a, b| {
+        b.revenue
+            .cmp(&a.revenue)
+            .then_with(|| a.seller_name.cmp(&b.seller_name))
+    });
+    sellers
+}
+
+/// Regroupe les lignes vendues selon `key`, par marge brute décroissante.
+fn group_margins(lines: &[SoldLine], key: impl Fn(&SoldLine) -> String) -> Vec<MarginLine> {
+    let mut groups: HashMap<String, MarginLine> = HashMap::new();
+    for line in lines {
+        let label = key(line);
+        groups
+            .entry(label.clone())
+            .or_insert_with(|| MarginLine::new(label))
+            .add(line);
+    }
+    let mut margins = groups.into_values().collect::<Vec<_>>();
+    margins.sort_by(|a, b| {
+        b.margin()
+            .cmp(&a.margin())
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    margins
+}
+
+/// Évolution des ventes sur une période : chiffre d'affaires, nombre de ventes, unités vendues
+/// et panier moyen par jour, semaine ou mois. Les regroupements sans vente valent zéro.
+pub fn get_sales_timeseries(
+    range: DateRange,
+    granularity: Granularity,
+) -> AppResult<Vec<TimeseriesPoint>> {
+    let mut conn = db::get_conn()?;
+    compute_timeseries(&mut conn, &range, granularity).map_err(Into::into)
+}
+
+fn compute_timeseries(
+    conn: &mut PgConnection,
+    range: &DateRange,
+    granularity: Granularity,
+) -> QueryResult<Vec<TimeseriesPoint>> {
+    use crate::schema::{credit_note_items, credit_notes, sale_items, sales};
+
+    let sold: Vec<(DateTime<Utc>, BigDecimal)> = sales::table
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((sales::date, sales::total_amount))
+        .load(conn)?;
+    let sold_units: Vec<TimeseriesSoldUnits> = sale_items::table
+        .inner_join(sales::table.on(sale_items::sale_id.eq(sales::id)))
+        .filter(sales::date.ge(range.start))
+        .filter(sales::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((
+            sales::date,
+            sale_items::quantity,
+            sale_items::quantity_returned,
+            sale_items::unit_price,
+            sale_items::unit_cost,
+        ))
+        .load(conn)?;
+
+    // Les avoirs sont déduits à leur date d'émission, comme dans les totaux du rapport
+    let returned: Vec<(DateTime<Utc>, BigDecimal)> = credit_notes::table
+        .inner_join(sales::table)
+        .filter(credit_notes::date.ge(range.start))
+        .filter(credit_notes::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((credit_notes::date, credit_notes::total_amount))
+        .load(conn)?;
+    let returned_units: Vec<(DateTime<Utc>, i32)> = credit_note_items::table
+        .inner_join(credit_notes::table.inner_join(sales::table))
+        .filter(credit_notes::date.ge(range.start))
+        .filter(credit_notes::date.lt(range.end))
+        .filter(sales::status.ne(SaleStatus::Voided.as_str()))
+        .select((credit_notes::date, credit_note_items::quantity))
+        .load(conn)?;
+
+    let zero = || BigDecimal::from(0);
+    let entries = sold
+        .into_iter()
+        .map(|(at, amount)| TimeseriesEntry {
+            at,
+            revenue: amount,
+            sales_count: 1,
+            units: 0,
+            margin: zero(),
+        })
+        .chain(sold_units.into_iter().map(|line| {
+            TimeseriesEntry {
+                at: line.at,
+                revenue: zero(),
+                sales_count: 0,
+                units: line.quantity as i64,
+                // Marge rattachée à la date de vente, nette des quantités reprises
+                margin: line
+                    .unit_cost
+                    .map(|cost| {
+                        (line.unit_price - cost)
+                            * BigDecimal::from(line.quantity - line.quantity_returned)
+                    })
+                    .unwrap_or_else(zero),
+            }
+        }))
+        .chain(returned.into_iter().map(|(at, amount)| TimeseriesEntry {
+            at,
+            revenue: -amount,
+            sales_count: 0,
+            units: 0,
+            margin: zero(),
+        }))
+        .chain(
+            returned_units
+                .into_iter()
+                .map(|(at, quantity)| TimeseriesEntry {
+                    at,
+                    revenue: zero(),
+                    sales_count: 0,
+                    units: -(quantity as i64),
+                    margin: zero(),
+                }),
+        );
+
+    Ok(build_timeseries(
+        business_calendar(),
+        range,
+        granularity,
+        entries,
+    ))
+}
+
+/// Ventile les mouvements dans des regroupements couvrant toute la période, y compris ceux sans vente.
+fn build_timeseries(
+    calendar: &BusinessCalendar,
+    range: &DateRange,
+    granularity: Granularity,
+    entries: impl IntoIterator<Item = TimeseriesEntry>,
+) -> Vec<TimeseriesPoint> {
+    let (first, last) = calendar.bounds(range);
+    let mut points = Vec::new();
+    let mut positions = HashMap::new();
+    let mut bucket = granularity.bucket_start(first);
+    while bucket <= last {
+        positions.insert(bucket, points.len());
+        points.push(TimeseriesPoint::empty(bucket));
+        bucket = granularity.next_bucket(bucket);
+    }
+
+    for entry in entries {
+        let bucket = granularity.bucket_start(calendar.business_date(entry.at));
+        if let Some(&position) = positions.get(&bucket) {
+            let point = &mut points[position];
+            point.revenue += entry.revenue;
+            point.sales_count += entry.sales_count;
+            point.units += entry.units;
+            point.margin += entry.margin;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommissionKind;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> BigDecimal {
+        BigDecimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_percent_change() {
+        assert_eq!(percent_change(&dec("150"), &dec("100")), Some(dec("50")));
+        assert_eq!(percent_change(&dec("75"), &dec("100")), Some(dec("-25")));
+        assert_eq!(percent_change(&dec("10"), &dec("0")), None);
+        assert_eq!(format_percent_change(&dec("150"), &dec("100")), "+50.0 %");
+        assert_eq!(format_percent_change(&dec("0"), &dec("0")), "n.d.");
+    }
+
+    #[test]
+    fn test_margins_exclude_lines_without_cost() {
+        let line =
+            |product: &str, seller: &str, quantity, price: &str, cost: Option<&str>| SoldLine {
+                sale_id: Uuid::new_v4(),
+                sold_at: Utc::now(),
+                product: product.to_string(),
+                packaging: "Casier 65cl de 12".to_string(),
+                seller_id: Uuid::nil(),
+                seller: seller.to_string(),
+                net_quantity: quantity,
+                unit_price: dec(price),
+                unit_cost: cost.map(dec),
+            };
+        let lines = vec![
+            line("Castel", "Awa", 10, "8500", Some("7200")),
+            line("Castel", "Paul", 2, "8500", Some("7200")),
+            line("Coca", "Awa", 4, "11000", None),
+        ];
+
+        let by_product = group_margins(&lines, |l| l.product.clone());
+        assert_eq!(by_product[0].label, "Castel");
+        assert_eq!(by_product[0].margin(), dec("15600"));
+        assert_eq!(by_product[1].margin_rate(), None);
+        assert_eq!(by_product[1].uncosted_revenue, dec("44000"));
+
+        let by_seller = group_margins(&lines, |l| l.seller.clone());
+        assert_eq!(by_seller[0].label, "Awa");
+        assert_eq!(by_seller[0].quantity, 14);
+        assert_eq!(by_seller[1].margin(), dec("2600"));
+    }
+
+    #[test]
+    fn test_seller_performance_and_commissions() {
+        let calendar = BusinessCalendar::default();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let (awa, paul) = (Uuid::new_v4(), Uuid::new_v4());
+        let (first_sale, second_sale, third_sale) =
+            (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let line =
+            |sale_id, sold_at: &str, seller_id, seller: &str, quantity, price: &str| SoldLine {
+                sale_id,
+                sold_at: at(sold_at),
+                product: "Castel".to_string(),
+                packaging: "Casier 65cl de 12".to_string(),
+                seller_id,
+                seller: seller.to_string(),
+                net_quantity: quantity,
+                unit_price: dec(price),
+                unit_cost: None,
+            };
+        let lines = vec![
+            line(first_sale, "2025-07-14T10:00:00Z", awa, "Awa", 2, "8500"),
+            line(first_sale, "2025-07-14T10:00:00Z", awa, "Awa", 1, "11000"),
+            line(second_sale, "2025-07-15T09:00:00Z", awa, "Awa", 4, "8500"),
+            line(third_sale, "2025-07-15T11:00:00Z", paul, "Paul", 10, "8500"),
+        ];
+        let rule = |user_id, kind: CommissionKind, rate: &str| CommissionRule {
+            id: Uuid::new_v4(),
+            user_id,
+            kind: kind.as_str().to_string(),
+            rate: dec(rate),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        // Awa touche 2 % du chiffre d'affaires (règle par défaut), Paul 100 XAF par casier
+        let rules = vec![
+            rule(None, CommissionKind::Percent, "2"),
+            rule(Some(paul), CommissionKind::PerUnit, "100"),
+        ];
+
+        let sellers = compute_seller_performance(&calendar, &lines, &rules);
+        assert_eq!(sellers[0].seller_name, "Paul");
+        assert_eq!(sellers[0].commission, dec("1000"));
+        assert_eq!(sellers[1].revenue, dec("62000"));
+        assert_eq!(sellers[1].sales_count, 2);
+        assert_eq!(sellers[1].units, 7);
+        assert_eq!(sellers[1].active_days, 2);
+        assert_eq!(sellers[1].average_basket(), dec("31000"));
+        assert_eq!(sellers[1].commission, dec("1240"));
+        assert_eq!(sellers[1].days[0].revenue, dec("28000"));
+
+        // Sans aucune règle, la commission est nulle
+        let sellers = compute_seller_performance(&calendar, &lines, &[]);
+        assert_eq!(sellers[1].commission, dec("0"));
+    }
+
+    #[test]
+    fn test_timeseries_buckets_are_filled_with_zero() {
+        let calendar = BusinessCalendar::default();
+        let date = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let sale = |s, amount: &str, units| TimeseriesEntry {
+            at: at(s),
+            revenue: dec(amount),
+            sales_count: 1,
+            units,
+            margin: dec("0"),
+        };
+
+        // Du mercredi 9 au mardi 15 juillet ; 23:30 UTC le 9 est déjà le 10 à Douala
+        let range = calendar.days(date(9), date(15));
+        let entries = vec![
+            sale("2025-07-09T10:00:00Z", "1000", 2),
+            sale("2025-07-09T23:30:00Z", "3000", 5),
+            sale("2025-07-14T12:00:00Z", "500", 1),
+        ];
+        let days = build_timeseries(&calendar, &range, Granularity::Day, entries);
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[0].revenue, dec("1000"));
+        assert_eq!(days[1].units, 5);
+        assert_eq!(days[2], TimeseriesPoint::empty(date(11)));
+        assert_eq!(days[5].average_basket(), dec("500"));
+
+        // Par semaine ISO : la semaine du 7 et celle du 14
+        let entries = vec![
+            sale("2025-07-09T10:00:00Z", "1000", 2),
+            sale("2025-07-14T12:00:00Z", "500", 1),
+        ];
+        let weeks = build_timeseries(&calendar, &range, Granularity::Week, entries);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].bucket_start, date(7));
+        assert_eq!(weeks[1].revenue, dec("500"));
+        assert_eq!(Granularity::Week.bucket_label(date(14)), "S29");
+    }
+}
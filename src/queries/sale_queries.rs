@@ -1,17 +1,27 @@
 // src/queries/sale_queries.rs
-use super::SortOrder;
+use super::{
+    SortOrder, cash_session_queries::find_paying_cash_session,
+    customer_queries::compute_customer_balance, deposit_queries::compute_customer_empties,
+    numbering_queries::next_sale_number, stock_queries::record_stock_movement,
+};
 use crate::{
+    config::business_calendar::{DateRange, business_calendar},
     db,
-    error::AppResult,
+    error::{AppError, AppResult},
     models::{
-        CreateSaleData, NewSale, NewSaleItem, Product, Receipt, ReceiptItem, Sale, SaleItem,
-        SaleWithItems,
+        CreatePaymentData, CreateSaleData, CreditNote, Customer, DepositItem, NewPayment, NewSale,
+        NewSaleItem, Payment, PaymentMethod, PaymentStatus, PaymentSummary, Product, Receipt,
+        ReceiptEmpties, ReceiptItem, ReceiptPayment, Sale, SaleItem, SaleStatus, SaleWithItems,
+        StockMovementReason,
+    },
+    schema::{
+        credit_notes, customers, deposit_items, payments, products, sale_items, sales, users,
     },
-    schema::{products, sale_items, sales, users},
 };
 use bigdecimal::BigDecimal;
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use diesel::prelude::*;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 // Allow columns from sales and users tables to appear in the same GROUP BY clause
@@ -23,6 +33,13 @@ diesel::allow_columns_to_appear_in_same_group_by_clause!(
     sales::date,
     sales::created_at,
     sales::updated_at,
+    sales::customer_id,
+    sales::deposit_total,
+    sales::status,
+    sales::void_reason,
+    sales::voided_at,
+    sales::voided_by,
+    sales::void_cash_session_id,
     users::name
 );
 
@@ -41,12 +58,32 @@ pub struct SaleSearchParams {
     pub page_size: i64,
 }
 
+/// Période de recherche, évaluée dans le calendrier commercial du dépôt.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DateFilter {
     All,
+    /// Journée commerciale en cours.
     Today,
+    /// Semaine ISO en cours (du lundi au dimanche).
     Week,
+    /// Mois calendaire en cours.
     Month,
+    /// Journées commerciales du premier au dernier jour inclus.
+    Custom(NaiveDate, NaiveDate),
+}
+
+impl DateFilter {
+    /// Bornes de la période, `None` pour toutes les dates.
+    pub fn range(&self) -> Option<DateRange> {
+        let calendar = business_calendar();
+        match *self {
+            DateFilter::All => None,
+            DateFilter::Today => Some(calendar.day(calendar.today())),
+            DateFilter::Week => Some(calendar.iso_week(calendar.today())),
+            DateFilter::Month => Some(calendar.month(calendar.today())),
+            DateFilter::Custom(first, last) => Some(calendar.days(first, last)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -72,6 +109,7 @@ pub struct SaleWithSeller {
     pub sale: Sale,
     pub seller_name: String,
     pub items_count: i64,
+    pub payment_status: PaymentStatus,
 }
 
 impl Default for SaleSearchParams {
@@ -91,14 +129,14 @@ impl Default for SaleSearchParams {
 // --- Fonctions CRUD et de recherche ---
 
 /// Crée une nouvelle vente à partir des données fournies,
-/// calcule les totaux, vérifie les stocks et met à jour la base de données.
+/// calcule les totaux, vérifie les stocks, enregistre les paiements et met à jour la base de données.
 pub fn create_sale(data: CreateSaleData) -> AppResult<Receipt> {
-    use crate::error::AppError;
     let mut conn = db::get_conn()?;
 
     conn.transaction(|conn| {
         // --- 1. Validation et calculs préliminaires ---
         let mut total_amount = BigDecimal::from(0);
+        let mut deposit_total = BigDecimal::from(0);
         let mut validated_items = Vec::new();
 
         if data.items.is_empty() {
@@ -108,11 +146,37 @@ pub fn create_sale(data: CreateSaleData) -> AppResult<Receipt> {
             ));
         }
 
+        // Quantité totale demandée par produit (un produit peut figurer sur plusieurs lignes)
+        let mut requested: HashMap<Uuid, i32> = HashMap::new();
+        for item_data in &data.items {
+            if item_data.quantity <= 0 {
+                return Err(AppError::ValidationError(
+                    "La quantité vendue doit être positive".to_string(),
+                ));
+            }
+            *requested.entry(item_data.product_id).or_insert(0) += item_data.quantity;
+        }
+
+        // Verrouille les produits vendus jusqu'à la fin de la transaction : deux caisses vendant
+        // le même article sont sérialisées et la vérification du stock reste valable à l'écriture.
+        // L'ordre par identifiant évite les interblocages entre ventes concurrentes.
+        let product_ids: Vec<Uuid> = requested.keys().copied().collect();
+        let locked_products: HashMap<Uuid, Product> = products::table
+            .filter(products::id.eq_any(&product_ids))
+            .order(products::id.asc())
+            .for_update()
+            .load::<Product>(conn)?
+            .into_iter()
+            .map(|product| (product.id, product))
+            .collect();
+
         for item_data in &data.items {
-            let product: Product = products::table.find(item_data.product_id).first(conn)?;
+            let product = locked_products
+                .get(&item_data.product_id)
+                .cloned()
+                .ok_or(diesel::result::Error::NotFound)?;
 
-            if product.stock_in_sale_units < item_data.quantity {
-                // return Err(DieselError::RollbackTransaction); // Stock insuffisant
+            if product.stock_in_sale_units < requested[&product.id] {
                 return Err(AppError::ValidationError(format!(
                     "Stock insuffisant pour le produit {} (ID: {})",
                     product.name, product.id
@@ -123,28 +187,118 @@ pub fn create_sale(data: CreateSaleData) -> AppResult<Receipt> {
             let total_price = &product.price_per_sale_unit * quantity_bd;
             total_amount += &total_price;
 
-            validated_items.push((item_data.clone(), product, total_price));
+            // Consigne : chaque unité vendue sort avec son emballage, les vides rendus sont crédités
+            if item_data.deposit_returned < 0 {
+                return Err(AppError::ValidationError(format!(
+                    "Nombre de vides rendus invalide pour le produit {}",
+                    product.name
+                )));
+            }
+            let deposit_item: Option<DepositItem> = match product.deposit_item_id {
+                Some(deposit_item_id) => Some(deposit_items::table.find(deposit_item_id).first(conn)?),
+                None if item_data.deposit_returned > 0 => {
+                    return Err(AppError::ValidationError(format!(
+                        "Le produit {} n'a pas d'emballage consigné",
+                        product.name
+                    )));
+                }
+                None => None,
+            };
+            let deposit_amount = match &deposit_item {
+                Some(deposit) => {
+                    &deposit.deposit_amount
+                        * BigDecimal::from(item_data.quantity - item_data.deposit_returned)
+                }
+                None => BigDecimal::from(0),
+            };
+            deposit_total += &deposit_amount;
+
+            validated_items.push((
+                item_data.clone(),
+                product,
+                total_price,
+                deposit_item,
+                deposit_amount,
+            ));
+        }
+
+        // Total à payer : marchandises + consignes nettes
+        let amount_due = &total_amount + &deposit_total;
+        if amount_due < BigDecimal::from(0) {
+            return Err(AppError::ValidationError(format!(
+                "Le remboursement des consignes ({} XAF) dépasse le montant des marchandises ({} XAF)",
+                deposit_total.abs(),
+                total_amount
+            )));
+        }
+
+        // --- 2. Verrouillage du client pour une vente en compte ---
+        // Empêche deux ventes simultanées de dépasser le plafond de crédit
+        let customer: Option<Customer> = match data.customer_id {
+            Some(customer_id) => Some(
+                customers::table
+                    .find(customer_id)
+                    .for_update()
+                    .first(conn)?,
+            ),
+            None => None,
+        };
+
+        // --- 3. Validation des paiements ---
+        // Sans paiement saisi : vente comptant en espèces, ou entièrement en compte pour un client
+        let tenders = if data.payments.is_empty() && amount_due > BigDecimal::from(0) {
+            vec![CreatePaymentData {
+                method: if customer.is_some() {
+                    PaymentMethod::Credit
+                } else {
+                    PaymentMethod::Cash
+                },
+                amount: amount_due.clone(),
+                reference: None,
+            }]
+        } else {
+            data.payments.clone()
+        };
+
+        let sale_id = Uuid::new_v4();
+        let mut remaining = amount_due.clone();
+        let mut new_payments = Vec::new();
+        for tender in &tenders {
+            let new_payment = build_payment(sale_id, tender, &remaining, customer.is_some())?;
+            remaining -= &new_payment.amount;
+            new_payments.push(new_payment);
         }
 
-        // --- 2. Création de la vente principale ---
+        let credit_amount: BigDecimal = new_payments
+            .iter()
+            .filter(|p| p.method == PaymentMethod::Credit.as_str())
+            .map(|p| p.amount.clone())
+            .sum();
+        if let Some(customer) = &customer {
+            check_credit_limit(conn, customer, &credit_amount)?;
+        }
+
+        // --- 4. Création de la vente principale ---
         let seller_name: String = users::table
             .find(data.user_id)
             .select(users::name)
             .first(conn)?;
         let new_sale = NewSale {
-            id: Uuid::new_v4(),
+            id: sale_id,
             user_id: data.user_id,
-            sale_number: generate_sale_number(),
+            sale_number: next_sale_number(conn)?,
             total_amount: total_amount.clone(),
             date: Utc::now(),
+            customer_id: data.customer_id,
+            deposit_total: deposit_total.clone(),
         };
         let created_sale: Sale = diesel::insert_into(sales::table)
             .values(&new_sale)
             .get_result(conn)?;
 
-        // --- 3. Insertion des articles de vente et mise à jour des stocks ---
+        // --- 5. Insertion des articles de vente et mise à jour des stocks ---
         let mut receipt_items = Vec::new();
-        for (item_data, product, total_price) in validated_items {
+        for (item_data, product, total_price, deposit_item, deposit_amount) in validated_items {
             let new_sale_item = NewSaleItem {
                 id: Uuid::new_v4(),
                 sale_id: created_sale.id,
@@ -152,17 +306,46 @@ pub fn create_sale(data: CreateSaleData) -> AppResult<Receipt> {
                 quantity: item_data.quantity,
                 unit_price: product.price_per_sale_unit.clone(),
                 total_price,
+                deposit_item_id: deposit_item.as_ref().map(|d| d.id),
+                deposit_unit_amount: deposit_item
+                    .as_ref()
+                    .map(|d| d.deposit_amount.clone())
+                    .unwrap_or_else(|| BigDecimal::from(0)),
+                deposit_quantity_out: if deposit_item.is_some() {
+                    item_data.quantity
+                } else {
+                    0
+                },
+                deposit_quantity_returned: item_data.deposit_returned,
+                deposit_amount,
+                // Le coût est figé à la vente : les marges passées ne bougent plus si le prix d'achat change
+                unit_cost: product.cost_price.clone(),
             };
             diesel::insert_into(sale_items::table)
                 .values(&new_sale_item)
                 .execute(conn)?;
 
-            diesel::update(products::table.find(item_data.product_id))
-                .set(
-                    products::stock_in_sale_units
-                        .eq(product.stock_in_sale_units - item_data.quantity),
-                )
-                .execute(conn)?;
+            record_stock_movement(
+                conn,
+                item_data.product_id,
+                -item_data.quantity,
+                StockMovementReason::Sale,
+                Some(data.user_id),
+                Some(created_sale.sale_number.clone()),
+                None,
+            )?;
+
+            // Les vides rendus rejoignent le stock d'emballages du dépôt
+            if let Some(deposit) = &deposit_item
+                && item_data.deposit_returned > 0
+            {
+                diesel::update(deposit_items::table.find(deposit.id))
+                    .set(
+                        deposit_items::empties_in_stock
+                            .eq(deposit_items::empties_in_stock + item_data.deposit_returned),
+                    )
+                    .execute(conn)?;
+            }
 
             receipt_items.push(ReceiptItem {
                 product_name: product.name,
@@ -170,19 +353,267 @@ pub fn create_sale(data: CreateSaleData) -> AppResult<Receipt> {
                 quantity: new_sale_item.quantity,
                 unit_price: new_sale_item.unit_price,
                 total_price: new_sale_item.total_price,
+                deposit_quantity_out: new_sale_item.deposit_quantity_out,
+                deposit_quantity_returned: new_sale_item.deposit_quantity_returned,
+                deposit_amount: new_sale_item.deposit_amount,
             });
         }
 
-        // --- 4. Génération du reçu ---
+        // --- 6. Enregistrement des paiements ---
+        let created_payments: Vec<Payment> = diesel::insert_into(payments::table)
+            .values(&new_payments)
+            .get_results(conn)?;
+        let summary = PaymentSummary::from_payments(&amount_due, &created_payments);
+
+        // --- 7. Emballages encore dus par le client ---
+        let empties_owed = match &customer {
+            Some(customer) => receipt_empties(compute_customer_empties(conn, customer.id)?),
+            None => Vec::new(),
+        };
+
+        // --- 8. Génération du reçu ---
         Ok(Receipt {
             sale_number: created_sale.sale_number,
-            date: created_sale.date.format("%d/%m/%Y %H:%M").to_string(),
+            date: business_calendar().format_local(created_sale.date, "%d/%m/%Y %H:%M"),
             seller_name,
+            customer_name: customer.map(|c| c.name),
             items: receipt_items,
             total_amount,
+            deposit_total,
+            amount_due,
+            empties_owed,
+            payments: receipt_payments(&created_payments),
+            amount_paid: summary.settled,
+            change_due: summary.change_due,
+            balance_due: summary.on_account + summary.remaining,
+            payment_status: summary.status.label().to_string(),
         })
     })
-    .map_err(|e| AppError::Database(Box::new(e)))
+}
+
+/// Enregistre un paiement complémentaire sur une vente partiellement réglée.
+/// Retourne le nouveau récapitulatif des paiements de la vente.
+pub fn add_sale_payment(sale_id: Uuid, tender: CreatePaymentData) -> AppResult<PaymentSummary> {
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        // Verrouille la vente pour sérialiser les encaissements concurrents
+        let sale: Sale = sales::table.find(sale_id).for_update().first(conn)?;
+        if sale.is_voided() {
+            return Err(AppError::ValidationError(format!(
+                "La vente {} est annulée",
+                sale.sale_number
+            )));
+        }
+        let existing: Vec<Payment> = Payment::belonging_to(&sale).load(conn)?;
+        let summary = PaymentSummary::from_payments(&sale.amount_due(), &existing);
+
+        let customer: Option<Customer> = match sale.customer_id {
+            Some(customer_id) => Some(
+                customers::table
+                    .find(customer_id)
+                    .for_update()
+                    .first(conn)?,
+            ),
+            None => None,
+        };
+
+        let new_payment = build_payment(sale.id, &tender, &summary.remaining, customer.is_some())?;
+        if let Some(customer) = &customer
+            && new_payment.method == PaymentMethod::Credit.as_str()
+        {
+            check_credit_limit(conn, customer, &new_payment.amount)?;
+        }
+
+        diesel::insert_into(payments::table)
+            .values(&new_payment)
+            .execute(conn)?;
+
+        let all_payments: Vec<Payment> = Payment::belonging_to(&sale).load(conn)?;
+        Ok(PaymentSummary::from_payments(
+            &sale.amount_due(),
+            &all_payments,
+        ))
+    })
+}
+
+/// Annule une vente (réservé aux administrateurs). La vente est conservée avec son motif d'annulation,
+/// les articles non encore repris sont remis en stock et les vides rendus ressortent du stock d'emballages.
+pub fn void_sale(sale_id: Uuid, user_id: Uuid, reason: String) -> AppResult<Sale> {
+    if reason.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Le motif de l'annulation est obligatoire".to_string(),
+        ));
+    }
+
+    let mut conn = db::get_conn()?;
+
+    conn.transaction(|conn| {
+        let role: String = users::table.find(user_id).select(users::role).first(conn)?;
+        if role != "Admin" {
+            return Err(AppError::Unauthorized(
+                "Seul un administrateur peut annuler une vente".to_string(),
+            ));
+        }
+
+        let sale: Sale = sales::table.find(sale_id).for_update().first(conn)?;
+        if sale.is_voided() {
+            return Err(AppError::ValidationError(format!(
+                "La vente {} est déjà annulée",
+                sale.sale_number
+            )));
+        }
+
+        // Les espèces de la vente sont rendues par le tiroir ouvert de l'administrateur ou du vendeur
+        let void_cash_session_id = find_paying_cash_session(conn, user_id, sale.user_id)?;
+
+        let items: Vec<SaleItem> = SaleItem::belonging_to(&sale).load(conn)?;
+        for item in &items {
+            // Les quantités déjà reprises par avoir ont été remises en stock à ce moment-là
+            if item.quantity_returnable() > 0 {
+                record_stock_movement(
+                    conn,
+                    item.product_id,
+                    item.quantity_returnable(),
+                    StockMovementReason::Return,
+                    Some(user_id),
+                    Some(sale.sale_number.clone()),
+                    Some("Annulation de la vente".to_string()),
+                )?;
+            }
+
+            if let Some(deposit_item_id) = item.deposit_item_id
+                && item.deposit_quantity_returned > 0
+            {
+                let deposit: DepositItem = deposit_items::table
+                    .find(deposit_item_id)
+                    .for_update()
+                    .first(conn)?;
+                diesel::update(deposit_items::table.find(deposit_item_id))
+                    .set(
+                        deposit_items::empties_in_stock
+                            .eq((deposit.empties_in_stock - item.deposit_quantity_returned).max(0)),
+                    )
+                    .execute(conn)?;
+            }
+        }
+
+        diesel::update(sales::table.find(sale.id))
+            .set((
+                sales::status.eq(SaleStatus::Voided.as_str()),
+                sales::void_reason.eq(Some(reason.trim().to_string())),
+                sales::voided_at.eq(Some(Utc::now())),
+                sales::voided_by.eq(Some(user_id)),
+                sales::void_cash_session_id.eq(void_cash_session_id),
+            ))
+            .get_result(conn)
+            .map_err(Into::into)
+    })
+}
+
+/// Valide un paiement saisi et calcule le montant imputé sur la vente ainsi que la monnaie à rendre.
+/// `remaining` est le montant de la vente qui n'est pas encore couvert par un paiement.
+fn build_payment(
+    sale_id: Uuid,
+    tender: &CreatePaymentData,
+    remaining: &BigDecimal,
+    has_customer: bool,
+) -> AppResult<NewPayment> {
+    let zero = BigDecimal::from(0);
+    if tender.amount <= zero {
+        return Err(AppError::ValidationError(
+            "Le montant d'un paiement doit être positif".to_string(),
+        ));
+    }
+    if *remaining <= zero {
+        return Err(AppError::ValidationError(
+            "La vente est déjà entièrement réglée".to_string(),
+        ));
+    }
+
+    let (amount, cash_tendered, change_due) = match tender.method {
+        // En espèces, l'excédent est rendu en monnaie
+        PaymentMethod::Cash if tender.amount > *remaining => (
+            remaining.clone(),
+            Some(tender.amount.clone()),
+            &tender.amount - remaining,
+        ),
+        PaymentMethod::Cash => (tender.amount.clone(), Some(tender.amount.clone()), zero),
+        PaymentMethod::Credit if !has_customer => {
+            return Err(AppError::ValidationError(
+                "Un paiement à crédit nécessite de sélectionner un client".to_string(),
+            ));
+        }
+        method => {
+            if tender.amount > *remaining {
+                return Err(AppError::ValidationError(format!(
+                    "Le paiement {} ({} XAF) dépasse le reste à payer ({} XAF)",
+                    method.label(),
+                    tender.amount,
+                    remaining
+                )));
+            }
+            (tender.amount.clone(), None, zero)
+        }
+    };
+
+    Ok(NewPayment {
+        id: Uuid::new_v4(),
+        sale_id,
+        method: tender.method.as_str().to_string(),
+        amount,
+        cash_tendered,
+        change_due,
+        reference: tender.reference.clone().filter(|r| !r.trim().is_empty()),
+    })
+}
+
+/// Vérifie que le montant porté en compte ne fait pas dépasser le plafond de crédit du client.
+/// Le client doit avoir été verrouillé (`FOR UPDATE`) dans la transaction courante.
+fn check_credit_limit(
+    conn: &mut PgConnection,
+    customer: &Customer,
+    credit_amount: &BigDecimal,
+) -> AppResult<()> {
+    if *credit_amount <= BigDecimal::from(0) {
+        return Ok(());
+    }
+    let balance = compute_customer_balance(conn, customer.id)?;
+    if &balance + credit_amount > customer.credit_limit {
+        return Err(AppError::ValidationError(format!(
+            "Plafond de crédit dépassé pour {} : encours {} XAF + crédit {} XAF > plafond {} XAF",
+            customer.name, balance, credit_amount, customer.credit_limit
+        )));
+    }
+    Ok(())
+}
+
+/// Convertit les emballages dus par un client en lignes de ticket.
+fn receipt_empties(empties: Vec<super::CustomerEmpties>) -> Vec<ReceiptEmpties> {
+    empties
+        .into_iter()
+        .map(|e| ReceiptEmpties {
+            packaging_description: e.packaging_description,
+            quantity: e.quantity,
+        })
+        .collect()
+}
+
+/// Convertit les paiements d'une vente en lignes de ticket.
+fn receipt_payments(payments: &[Payment]) -> Vec<ReceiptPayment> {
+    payments
+        .iter()
+        .map(|p| ReceiptPayment {
+            method: p
+                .method()
+                .map(|m| m.label().to_string())
+                .unwrap_or_else(|| p.method.clone()),
+            amount: p.amount.clone(),
+            cash_tendered: p.cash_tendered.clone(),
+            change_due: p.change_due.clone(),
+            reference: p.reference.clone(),
+        })
+        .collect()
 }
 
 /// Récupère les ventes avec pagination, filtres et tri.
@@ -217,19 +648,10 @@ pub fn get_sales_paginated(params: SaleSearchParams) -> AppResult<PaginatedSales
         }
 
         // Filtre par date
-        match params.date_filter {
-            DateFilter::Today => {
-                let start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-                let end = Utc::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
-                query = query.filter(sales::date.between(start, end));
-            }
-            DateFilter::Week => {
-                query = query.filter(sales::date.ge(Utc::now() - chrono::Duration::days(7)));
-            }
-            DateFilter::Month => {
-                query = query.filter(sales::date.ge(Utc::now() - chrono::Duration::days(30)));
-            }
-            DateFilter::All => {}
+        if let Some(range) = params.date_filter.range() {
+            query = query
+                .filter(sales::date.ge(range.start))
+                .filter(sales::date.lt(range.end));
         }
 
         query
@@ -326,17 +748,33 @@ pub fn get_sales_paginated(params: SaleSearchParams) -> AppResult<PaginatedSales
         .load::<(Uuid, i64)>(&mut conn)?;
 
     // Create a map for quick lookup of item counts
-    let item_count_map: std::collections::HashMap<Uuid, i64> = item_counts.into_iter().collect();
+    let item_count_map: HashMap<Uuid, i64> = item_counts.into_iter().collect();
+
+    // Paiements de chaque vente pour en déduire l'état de règlement
+    let mut payments_map: HashMap<Uuid, Vec<Payment>> = HashMap::new();
+    for payment in payments::table
+        .filter(payments::sale_id.eq_any(&sale_ids))
+        .load::<Payment>(&mut conn)?
+    {
+        payments_map
+            .entry(payment.sale_id)
+            .or_default()
+            .push(payment);
+    }
 
     // Transformer en structure plus claire
     let sales_with_seller: Vec<SaleWithSeller> = sales_with_sellers
         .into_iter()
         .map(|(sale, seller_name)| {
             let items_count = item_count_map.get(&sale.id).copied().unwrap_or(0);
+            let sale_payments = payments_map.get(&sale.id).map(Vec::as_slice).unwrap_or(&[]);
+            let payment_status =
+                PaymentSummary::from_payments(&sale.amount_due(), sale_payments).status;
             SaleWithSeller {
                 sale,
                 seller_name,
                 items_count,
+                payment_status,
             }
         })
         .collect();
@@ -373,7 +811,7 @@ pub fn get_sale_details(
 
     // Vérification des permissions
     if !is_admin && sale.user_id != current_user_id {
-        return Err(crate::error::AppError::Unauthorized(
+        return Err(AppError::Unauthorized(
             "Vous n'avez pas l'autorisation de voir cette vente".to_string(),
         ));
     }
@@ -382,18 +820,23 @@ pub fn get_sale_details(
         .inner_join(products::table)
         .load(&mut conn)?;
 
+    let sale_payments: Vec<Payment> = Payment::belonging_to(&sale)
+        .order(payments::created_at.asc())
+        .load(&mut conn)?;
+
+    let sale_credit_notes: Vec<CreditNote> = CreditNote::belonging_to(&sale)
+        .order(credit_notes::date.asc())
+        .load(&mut conn)?;
+
     Ok(SaleWithItems {
         sale,
         items: items_with_products,
         seller_name,
+        payments: sale_payments,
+        credit_notes: sale_credit_notes,
     })
 }
 
-/// Génère un numéro de vente unique.
-pub fn generate_sale_number() -> String {
-    format!("VTE-{}", &Utc::now().format("%Y%m%d%H%M%S"))
-}
-
 /// Fonction utilitaire pour créer les paramètres de recherche selon les permissions utilisateur
 pub fn create_search_params_for_user(
     base_params: SaleSearchParams,
@@ -426,7 +869,31 @@ pub fn generate_receipt(sale_id: Uuid) -> AppResult<Receipt> {
         .inner_join(products::table)
         .load(&mut conn)?;
 
-    // 3. Transformer les données en format Receipt
+    // 3. Récupérer le nom du client pour une vente à crédit
+    let customer_name: Option<String> = match sale.customer_id {
+        Some(customer_id) => Some(
+            customers::table
+                .find(customer_id)
+                .select(customers::name)
+                .first(&mut conn)?,
+        ),
+        None => None,
+    };
+
+    // 4. Récupérer les paiements de la vente
+    let sale_payments: Vec<Payment> = Payment::belonging_to(&sale)
+        .order(payments::created_at.asc())
+        .load(&mut conn)?;
+    let amount_due = sale.amount_due();
+    let summary = PaymentSummary::from_payments(&amount_due, &sale_payments);
+
+    // 5. Emballages actuellement dus par le client
+    let empties_owed = match sale.customer_id {
+        Some(customer_id) => receipt_empties(compute_customer_empties(&mut conn, customer_id)?),
+        None => Vec::new(),
+    };
+
+    // 6. Transformer les données en format Receipt
     let receipt_items = items_with_products
         .into_iter()
         .map(|(item, product)| ReceiptItem {
@@ -435,15 +902,158 @@ pub fn generate_receipt(sale_id: Uuid) -> AppResult<Receipt> {
             quantity: item.quantity,
             unit_price: item.unit_price,
             total_price: item.total_price,
+            deposit_quantity_out: item.deposit_quantity_out,
+            deposit_quantity_returned: item.deposit_quantity_returned,
+            deposit_amount: item.deposit_amount,
         })
         .collect();
 
-    // 4. Construire le reçu final
+    // 7. Construire le reçu final
     Ok(Receipt {
         sale_number: sale.sale_number,
-        date: sale.date.format("%d/%m/%Y %H:%M").to_string(),
+        date: business_calendar().format_local(sale.date, "%d/%m/%Y %H:%M"),
         seller_name,
+        customer_name,
         items: receipt_items,
         total_amount: sale.total_amount,
+        deposit_total: sale.deposit_total,
+        amount_due,
+        empties_owed,
+        payments: receipt_payments(&sale_payments),
+        amount_paid: summary.settled,
+        change_due: summary.change_due,
+        balance_due: summary.on_account + summary.remaining,
+        payment_status: summary.status.label().to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateSaleItemData, NewProduct, NewUser};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    /// Plusieurs caisses vendent simultanément le même produit : le stock ne doit jamais
+    /// devenir négatif et chaque vente validée doit être décomptée exactement une fois.
+    #[test]
+    fn test_parallel_sales_never_oversell() {
+        const INITIAL_STOCK: i32 = 5;
+        const CASHIERS: usize = 12;
+
+        if !db::init_test().expect("initialisation de la base de test") {
+            eprintln!("TEST_DATABASE_URL n'est pas définie : test ignoré");
+            return;
+        }
+        let mut conn = db::get_conn().unwrap();
+
+        let user_id = Uuid::new_v4();
+        let user_name = format!("test-caisse-{}", user_id);
+        diesel::insert_into(users::table)
+            .values(&NewUser {
+                id: user_id,
+                password: "test",
+                name: &user_name,
+                role: "User",
+                must_change_password: false,
+            })
+            .execute(&mut conn)
+            .unwrap();
+
+        let product_id = Uuid::new_v4();
+        diesel::insert_into(products::table)
+            .values(&NewProduct {
+                id: product_id,
+                name: format!("Produit test {}", product_id),
+                packaging_description: "Casier test".to_string(),
+                sku: None,
+                stock_in_sale_units: 0,
+                price_per_sale_unit: BigDecimal::from(1000),
+                deposit_item_id: None,
+                cost_price: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        conn.transaction(|conn| {
+            record_stock_movement(
+                conn,
+                product_id,
+                INITIAL_STOCK,
+                StockMovementReason::Adjustment,
+                Some(user_id),
+                None,
+                None,
+            )
+        })
+        .unwrap();
+
+        let barrier = Arc::new(Barrier::new(CASHIERS));
+        let handles: Vec<_> = (0..CASHIERS)
+            .map(|_| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    create_sale(CreateSaleData {
+                        user_id,
+                        customer_id: None,
+                        items: vec![CreateSaleItemData {
+                            product_id,
+                            quantity: 1,
+                            deposit_returned: 0,
+                        }],
+                        payments: vec![],
+                    })
+                })
+            })
+            .collect();
+        let results: Vec<AppResult<Receipt>> =
+            handles.into_iter().map(|h| h.join().unwrap()).collect();
+        let sold = results.iter().filter(|r| r.is_ok()).count() as i32;
+
+        let final_stock: i32 = products::table
+            .find(product_id)
+            .select(products::stock_in_sale_units)
+            .first(&mut conn)
+            .unwrap();
+        let sold_in_db: i64 = sale_items::table
+            .filter(sale_items::product_id.eq(product_id))
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        let ledger_stock = crate::queries::get_stock_at(product_id, Utc::now()).unwrap();
+
+        // Nettoyage avant les assertions pour ne pas laisser de données en cas d'échec
+        let sale_ids: Vec<Uuid> = sales::table
+            .filter(sales::user_id.eq(user_id))
+            .select(sales::id)
+            .load(&mut conn)
+            .unwrap();
+        diesel::delete(payments::table.filter(payments::sale_id.eq_any(&sale_ids)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(sale_items::table.filter(sale_items::sale_id.eq_any(&sale_ids)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(sales::table.filter(sales::id.eq_any(&sale_ids)))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(products::table.find(product_id))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(users::table.find(user_id))
+            .execute(&mut conn)
+            .unwrap();
+
+        // Tout le stock est vendu, et seules les ventes excédentaires sont refusées
+        assert_eq!(sold, INITIAL_STOCK);
+        assert!(
+            results
+                .iter()
+                .filter_map(|r| r.as_ref().err())
+                .all(|e| matches!(e, AppError::ValidationError(_)))
+        );
+        assert_eq!(sold_in_db, sold as i64);
+        assert_eq!(final_stock, INITIAL_STOCK - sold);
+        assert_eq!(ledger_stock, final_stock);
+    }
+}
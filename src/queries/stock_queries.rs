@@ -0,0 +1,126 @@
+// src/queries/stock_queries.rs
+
+use crate::{
+    db,
+    error::{AppError, AppResult},
+    models::{NewStockMovement, StockMovement, StockMovementReason},
+    schema::{products, stock_movements, users},
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Applique une variation au stock d'un produit et l'inscrit au journal des mouvements.
+/// Toute écriture du stock doit passer par ici, à l'intérieur de la transaction appelante.
+/// `reference` porte le numéro du document d'origine, `note` un commentaire libre.
+pub(crate) fn record_stock_movement(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    quantity_delta: i32,
+    reason: StockMovementReason,
+    user_id: Option<Uuid>,
+    reference: Option<String>,
+    note: Option<String>,
+) -> QueryResult<StockMovement> {
+    // La variation est appliquée par la base : le stock résultant ne dépend pas d'une lecture préalable
+    let stock_after: i32 = diesel::update(products::table.find(product_id))
+        .set(products::stock_in_sale_units.eq(products::stock_in_sale_units + quantity_delta))
+        .returning(products::stock_in_sale_units)
+        .get_result(conn)?;
+
+    let new_movement = NewStockMovement {
+        id: Uuid::new_v4(),
+        product_id,
+        user_id,
+        reason: reason.as_str().to_string(),
+        quantity_delta,
+        stock_after,
+        reference,
+        note,
+    };
+
+    diesel::insert_into(stock_movements::table)
+        .values(&new_movement)
+        .get_result(conn)
+}
+
+/// Récupère les derniers mouvements d'un produit, du plus récent au plus ancien,
+/// avec le nom de l'utilisateur qui les a enregistrés.
+pub fn get_stock_movements(
+    product_id: Uuid,
+    limit: i64,
+) -> AppResult<Vec<(StockMovement, Option<String>)>> {
+    let mut conn = db::get_conn()?;
+    stock_movements::table
+        .left_join(users::table.on(stock_movements::user_id.eq(users::id.nullable())))
+        .filter(stock_movements::product_id.eq(product_id))
+        .select((StockMovement::as_select(), users::name.nullable()))
+        .order(stock_movements::created_at.desc())
+        .limit(limit)
+        .load(&mut conn)
+        .map_err(Into::into)
+}
+
+/// Sort du stock une quantité cassée ou perdue (en unités de vente) et l'inscrit au journal.
+pub fn record_breakage(
+    product_id: Uuid,
+    user_id: Uuid,
+    quantity: i32,
+    note: Option<String>,
+) -> AppResult<StockMovement> {
+    if quantity <= 0 {
+        return Err(AppError::ValidationError(
+            "La quantité cassée doit être positive".to_string(),
+        ));
+    }
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+    let mut conn = db::get_conn()?;
+    conn.transaction(|conn| {
+        let stock: i32 = products::table
+            .find(product_id)
+            .select(products::stock_in_sale_units)
+            .for_update()
+            .first(conn)?;
+        if quantity > stock {
+            return Err(AppError::ValidationError(format!(
+                "Quantité cassée ({}) supérieure au stock disponible ({})",
+                quantity, stock
+            )));
+        }
+        record_stock_movement(
+            conn,
+            product_id,
+            -quantity,
+            StockMovementReason::Breakage,
+            Some(user_id),
+            None,
+            note,
+        )
+        .map_err(Into::into)
+    })
+}
+
+/// Reconstitue le stock d'un produit à une date donnée en cumulant ses mouvements jusqu'à cette date.
+pub fn get_stock_at(product_id: Uuid, at: DateTime<Utc>) -> AppResult<i32> {
+    let mut conn = db::get_conn()?;
+    let total: Option<i64> = stock_movements::table
+        .filter(stock_movements::product_id.eq(product_id))
+        .filter(stock_movements::created_at.le(at))
+        .select(diesel::dsl::sum(stock_movements::quantity_delta))
+        .first(&mut conn)?;
+    Ok(total.unwrap_or(0) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakage_quantity_must_be_positive() {
+        for quantity in [0, -3] {
+            let result = record_breakage(Uuid::nil(), Uuid::nil(), quantity, None);
+            assert!(matches!(result, Err(AppError::ValidationError(_))));
+        }
+    }
+}
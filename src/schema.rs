@@ -1,5 +1,175 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    cash_movements (id) {
+        id -> Uuid,
+        cash_session_id -> Uuid,
+        user_id -> Uuid,
+        kind -> Text,
+        amount -> Numeric,
+        reason -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    cash_sessions (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        opened_at -> Timestamptz,
+        opening_float -> Numeric,
+        closed_at -> Nullable<Timestamptz>,
+        closed_by -> Nullable<Uuid>,
+        expected_cash -> Nullable<Numeric>,
+        counted_cash -> Nullable<Numeric>,
+        closing_note -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    commission_rules (id) {
+        id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        kind -> Text,
+        rate -> Numeric,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    credit_note_items (id) {
+        id -> Uuid,
+        credit_note_id -> Uuid,
+        sale_item_id -> Uuid,
+        product_id -> Uuid,
+        quantity -> Int4,
+        unit_price -> Numeric,
+        total_price -> Numeric,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    credit_notes (id) {
+        id -> Uuid,
+        credit_note_number -> Text,
+        sale_id -> Uuid,
+        user_id -> Uuid,
+        reason -> Text,
+        total_amount -> Numeric,
+        date -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        cash_session_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    customer_payments (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        user_id -> Uuid,
+        amount -> Numeric,
+        note -> Nullable<Text>,
+        date -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        method -> Text,
+    }
+}
+
+diesel::table! {
+    customers (id) {
+        id -> Uuid,
+        name -> Text,
+        phone -> Nullable<Text>,
+        address -> Nullable<Text>,
+        credit_limit -> Numeric,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    deposit_items (id) {
+        id -> Uuid,
+        packaging_description -> Text,
+        deposit_amount -> Numeric,
+        empties_in_stock -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    inventory_lines (id) {
+        id -> Uuid,
+        inventory_session_id -> Uuid,
+        product_id -> Uuid,
+        expected_stock -> Int4,
+        counted_stock -> Nullable<Int4>,
+        unit_cost -> Nullable<Numeric>,
+        unit_price -> Numeric,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    inventory_sessions (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        status -> Text,
+        note -> Nullable<Text>,
+        opened_at -> Timestamptz,
+        closed_at -> Nullable<Timestamptz>,
+        closed_by -> Nullable<Uuid>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    payments (id) {
+        id -> Uuid,
+        sale_id -> Uuid,
+        method -> Text,
+        amount -> Numeric,
+        cash_tendered -> Nullable<Numeric>,
+        change_due -> Numeric,
+        reference -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    product_import_lines (id) {
+        id -> Uuid,
+        product_import_id -> Uuid,
+        product_id -> Nullable<Uuid>,
+        line_number -> Int4,
+        action -> Text,
+        changes -> Text,
+    }
+}
+
+diesel::table! {
+    product_imports (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        file_name -> Text,
+        rows_created -> Int4,
+        rows_updated -> Int4,
+        rows_unchanged -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     products (id) {
         id -> Uuid,
@@ -10,6 +180,35 @@ diesel::table! {
         price_per_sale_unit -> Numeric,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        deposit_item_id -> Nullable<Uuid>,
+        cost_price -> Nullable<Numeric>,
+    }
+}
+
+diesel::table! {
+    purchase_order_lines (id) {
+        id -> Uuid,
+        purchase_order_id -> Uuid,
+        product_id -> Uuid,
+        quantity_ordered -> Int4,
+        quantity_received -> Int4,
+        unit_cost -> Numeric,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    purchase_orders (id) {
+        id -> Uuid,
+        po_number -> Text,
+        supplier_id -> Uuid,
+        user_id -> Uuid,
+        status -> Text,
+        order_date -> Timestamptz,
+        note -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -23,6 +222,23 @@ diesel::table! {
         total_price -> Numeric,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        deposit_item_id -> Nullable<Uuid>,
+        deposit_unit_amount -> Numeric,
+        deposit_quantity_out -> Int4,
+        deposit_quantity_returned -> Int4,
+        deposit_amount -> Numeric,
+        quantity_returned -> Int4,
+        unit_cost -> Nullable<Numeric>,
+    }
+}
+
+diesel::table! {
+    sale_number_sequences (series, period) {
+        series -> Text,
+        period -> Int4,
+        last_value -> Int8,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -35,6 +251,38 @@ diesel::table! {
         date -> Timestamptz,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        customer_id -> Nullable<Uuid>,
+        deposit_total -> Numeric,
+        status -> Text,
+        void_reason -> Nullable<Text>,
+        voided_at -> Nullable<Timestamptz>,
+        voided_by -> Nullable<Uuid>,
+        void_cash_session_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    stock_movements (id) {
+        id -> Uuid,
+        product_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        reason -> Text,
+        quantity_delta -> Int4,
+        stock_after -> Int4,
+        reference -> Nullable<Text>,
+        note -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    suppliers (id) {
+        id -> Uuid,
+        name -> Text,
+        phone -> Nullable<Text>,
+        address -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -50,13 +298,59 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(cash_movements -> cash_sessions (cash_session_id));
+diesel::joinable!(cash_movements -> users (user_id));
+diesel::joinable!(cash_sessions -> users (user_id));
+diesel::joinable!(commission_rules -> users (user_id));
+diesel::joinable!(credit_note_items -> credit_notes (credit_note_id));
+diesel::joinable!(credit_note_items -> products (product_id));
+diesel::joinable!(credit_note_items -> sale_items (sale_item_id));
+diesel::joinable!(credit_notes -> cash_sessions (cash_session_id));
+diesel::joinable!(credit_notes -> sales (sale_id));
+diesel::joinable!(credit_notes -> users (user_id));
+diesel::joinable!(customer_payments -> customers (customer_id));
+diesel::joinable!(customer_payments -> users (user_id));
+diesel::joinable!(inventory_lines -> inventory_sessions (inventory_session_id));
+diesel::joinable!(inventory_lines -> products (product_id));
+diesel::joinable!(payments -> sales (sale_id));
+diesel::joinable!(product_import_lines -> product_imports (product_import_id));
+diesel::joinable!(product_import_lines -> products (product_id));
+diesel::joinable!(product_imports -> users (user_id));
+diesel::joinable!(products -> deposit_items (deposit_item_id));
+diesel::joinable!(purchase_order_lines -> products (product_id));
+diesel::joinable!(purchase_order_lines -> purchase_orders (purchase_order_id));
+diesel::joinable!(purchase_orders -> suppliers (supplier_id));
+diesel::joinable!(purchase_orders -> users (user_id));
+diesel::joinable!(sale_items -> deposit_items (deposit_item_id));
 diesel::joinable!(sale_items -> products (product_id));
 diesel::joinable!(sale_items -> sales (sale_id));
+diesel::joinable!(sales -> cash_sessions (void_cash_session_id));
+diesel::joinable!(sales -> customers (customer_id));
 diesel::joinable!(sales -> users (user_id));
+diesel::joinable!(stock_movements -> products (product_id));
+diesel::joinable!(stock_movements -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    cash_movements,
+    cash_sessions,
+    commission_rules,
+    credit_note_items,
+    credit_notes,
+    customer_payments,
+    customers,
+    deposit_items,
+    inventory_lines,
+    inventory_sessions,
+    payments,
+    product_import_lines,
+    product_imports,
     products,
+    purchase_order_lines,
+    purchase_orders,
     sale_items,
+    sale_number_sequences,
     sales,
+    stock_movements,
+    suppliers,
     users,
 );
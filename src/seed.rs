@@ -3,7 +3,11 @@
 use crate::{
     error::AppResult,
     // On importe seulement les modèles dont on a besoin maintenant
-    models::{NewProduct, NewSale, NewSaleItem, NewUser, Product, Sale},
+    models::{
+        NewCustomer, NewDepositItem, NewPayment, NewProduct, NewSale, NewSaleItem,
+        NewStockMovement, NewUser, PaymentMethod, Product, Sale, StockMovementReason,
+    },
+    queries::{numbering_queries::next_sale_number, stock_queries::record_stock_movement},
 };
 use bcrypt::{DEFAULT_COST, hash};
 use bigdecimal::BigDecimal;
@@ -13,17 +17,46 @@ use rand::Rng;
 use std::str::FromStr;
 use uuid::Uuid;
 
+/// Remplace le contenu de la base par les données de démonstration.
+/// Tout se fait dans une transaction : en cas d'erreur, la base reste inchangée.
 pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
+    conn.transaction(seed_tables)
+}
+
+fn seed_tables(conn: &mut PgConnection) -> AppResult<()> {
     // Le schéma importé est maintenant plus simple
-    use crate::schema::{products, sale_items, sales, users};
+    use crate::schema::{
+        cash_movements, cash_sessions, credit_note_items, credit_notes, customer_payments,
+        customers, deposit_items, inventory_lines, inventory_sessions, payments,
+        product_import_lines, product_imports, products, purchase_order_lines, purchase_orders,
+        sale_items, sale_number_sequences, sales, stock_movements, suppliers, users,
+    };
 
     log::info!("--- Début du seeding de la base de données ---");
 
     // 1. Nettoyer les tables dans le bon ordre
     log::info!("Nettoyage des tables existantes...");
+    diesel::delete(payments::table).execute(conn)?;
+    diesel::delete(credit_note_items::table).execute(conn)?;
+    diesel::delete(credit_notes::table).execute(conn)?;
     diesel::delete(sale_items::table).execute(conn)?;
     diesel::delete(sales::table).execute(conn)?;
+    // La numérotation repart de 1 avec la base de démonstration
+    diesel::delete(sale_number_sequences::table).execute(conn)?;
+    diesel::delete(customer_payments::table).execute(conn)?;
+    diesel::delete(customers::table).execute(conn)?;
+    diesel::delete(purchase_order_lines::table).execute(conn)?;
+    diesel::delete(purchase_orders::table).execute(conn)?;
+    diesel::delete(suppliers::table).execute(conn)?;
+    diesel::delete(product_import_lines::table).execute(conn)?;
+    diesel::delete(product_imports::table).execute(conn)?;
+    diesel::delete(inventory_lines::table).execute(conn)?;
+    diesel::delete(inventory_sessions::table).execute(conn)?;
+    // Les mouvements de stock, immuables, disparaissent avec leurs produits
     diesel::delete(products::table).execute(conn)?;
+    diesel::delete(deposit_items::table).execute(conn)?;
+    diesel::delete(cash_movements::table).execute(conn)?;
+    diesel::delete(cash_sessions::table).execute(conn)?;
     diesel::delete(users::table).execute(conn)?;
     log::info!("Tables nettoyées.");
 
@@ -44,6 +77,33 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
     log::info!("Utilisateur admin créé.");
 
     // 3. Créer les produits finis (SKUs)
+    // Consignes des casiers en verre (les palettes d'eau ne sont pas consignées)
+    log::info!("Création des consignes...");
+    let deposits_data = vec![
+        NewDepositItem {
+            id: Uuid::new_v4(),
+            packaging_description: "Casier 65cl de 12".to_string(),
+            deposit_amount: BigDecimal::from_str("3000.00")?,
+            empties_in_stock: 40,
+        },
+        NewDepositItem {
+            id: Uuid::new_v4(),
+            packaging_description: "Casier 33cl de 24".to_string(),
+            deposit_amount: BigDecimal::from_str("3600.00")?,
+            empties_in_stock: 25,
+        },
+    ];
+    diesel::insert_into(deposit_items::table)
+        .values(&deposits_data)
+        .execute(conn)?;
+    let deposit_for = |packaging: &str| {
+        deposits_data
+            .iter()
+            .find(|d| d.packaging_description == packaging)
+            .map(|d| (d.id, d.deposit_amount.clone()))
+    };
+    log::info!("Consignes créées.");
+
     log::info!("Création des produits (SKUs)...");
     let products_data = vec![
         NewProduct {
@@ -53,6 +113,8 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sku: Some("CAS-65-CAS12".to_string()),
             stock_in_sale_units: 100,
             price_per_sale_unit: BigDecimal::from_str("8500.00")?,
+            deposit_item_id: deposit_for("Casier 65cl de 12").map(|(id, _)| id),
+            cost_price: Some(BigDecimal::from_str("7200.00")?),
         },
         NewProduct {
             id: Uuid::new_v4(),
@@ -61,6 +123,8 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sku: Some("33EXP-65-CAS12".to_string()),
             stock_in_sale_units: 150,
             price_per_sale_unit: BigDecimal::from_str("8500.00")?,
+            deposit_item_id: deposit_for("Casier 65cl de 12").map(|(id, _)| id),
+            cost_price: Some(BigDecimal::from_str("7400.00")?),
         },
         NewProduct {
             id: Uuid::new_v4(),
@@ -69,6 +133,8 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sku: Some("GUIN-33-CAS24".to_string()),
             stock_in_sale_units: 80,
             price_per_sale_unit: BigDecimal::from_str("14500.00")?,
+            deposit_item_id: deposit_for("Casier 33cl de 24").map(|(id, _)| id),
+            cost_price: Some(BigDecimal::from_str("12500.00")?),
         },
         NewProduct {
             id: Uuid::new_v4(),
@@ -77,6 +143,8 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sku: Some("SPMT-1.5-PAL12".to_string()),
             stock_in_sale_units: 50,
             price_per_sale_unit: BigDecimal::from_str("2500.00")?,
+            deposit_item_id: deposit_for("Palette 1.5L de 12").map(|(id, _)| id),
+            cost_price: Some(BigDecimal::from_str("2200.00")?),
         },
         // On ajoute un produit avec un stock faible pour tester le dashboard
         NewProduct {
@@ -86,24 +154,69 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sku: Some("COKE-33-CAS24".to_string()),
             stock_in_sale_units: 45, // <-- Stock faible
             price_per_sale_unit: BigDecimal::from_str("11000.00")?,
+            deposit_item_id: deposit_for("Casier 33cl de 24").map(|(id, _)| id),
+            cost_price: Some(BigDecimal::from_str("10500.00")?), // <-- Marge faible
         },
     ];
     let inserted_products = diesel::insert_into(products::table)
         .values(&products_data)
         .get_results::<Product>(conn)?;
+
+    // Le stock de départ est le premier mouvement du journal de chaque produit
+    let opening_movements = inserted_products
+        .iter()
+        .map(|p| NewStockMovement {
+            id: Uuid::new_v4(),
+            product_id: p.id,
+            user_id: Some(admin_id),
+            reason: StockMovementReason::Adjustment.as_str().to_string(),
+            quantity_delta: p.stock_in_sale_units,
+            stock_after: p.stock_in_sale_units,
+            reference: None,
+            note: Some("Stock initial".to_string()),
+        })
+        .collect::<Vec<_>>();
+    diesel::insert_into(stock_movements::table)
+        .values(&opening_movements)
+        .execute(conn)?;
     log::info!("Produits créés.");
 
-    // 4. Créer des ventes de test pour aujourd'hui
+    // 4. Créer quelques clients pouvant acheter à crédit
+    log::info!("Création des clients...");
+    let customers_data = vec![
+        NewCustomer {
+            id: Uuid::new_v4(),
+            name: "Bar Le Palmier".to_string(),
+            phone: Some("+237 677 00 00 01".to_string()),
+            address: Some("Mvog-Mbi, Yaoundé".to_string()),
+            credit_limit: BigDecimal::from_str("150000.00")?,
+        },
+        NewCustomer {
+            id: Uuid::new_v4(),
+            name: "Boutique Chez Marie".to_string(),
+            phone: Some("+237 699 00 00 02".to_string()),
+            address: None,
+            credit_limit: BigDecimal::from_str("50000.00")?,
+        },
+    ];
+    diesel::insert_into(customers::table)
+        .values(&customers_data)
+        .execute(conn)?;
+    log::info!("Clients créés.");
+
+    // 5. Créer des ventes de test pour aujourd'hui
     log::info!("Création des ventes de test pour aujourd'hui...");
     let mut rng = rand::rng();
 
-    for i in 1..=5 {
+    for _ in 1..=5 {
         let new_sale = NewSale {
             id: Uuid::new_v4(),
             user_id: admin_id,
-            sale_number: format!("VTE-{:05}", i),
+            sale_number: next_sale_number(conn)?,
             total_amount: BigDecimal::from(0),
             date: Utc::now(), // La vente a lieu "maintenant"
+            customer_id: None,
+            deposit_total: BigDecimal::from(0),
         };
         let sale = diesel::insert_into(sales::table)
             .values(&new_sale)
@@ -121,6 +234,10 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             let total_price =
                 &product_to_sell.price_per_sale_unit * BigDecimal::from(quantity_sold);
 
+            // Le client échange ses vides : autant d'emballages rendus que sortis, consigne nette nulle
+            let deposit = deposit_for(&product_to_sell.packaging_description);
+            let deposit_quantity = if deposit.is_some() { quantity_sold } else { 0 };
+
             let new_sale_item = NewSaleItem {
                 id: Uuid::new_v4(),
                 sale_id: sale.id,
@@ -128,6 +245,14 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
                 quantity: quantity_sold,
                 unit_price: product_to_sell.price_per_sale_unit.clone(),
                 total_price: total_price.clone(),
+                deposit_item_id: deposit.as_ref().map(|(id, _)| *id),
+                deposit_unit_amount: deposit
+                    .map(|(_, amount)| amount)
+                    .unwrap_or_else(|| BigDecimal::from(0)),
+                deposit_quantity_out: deposit_quantity,
+                deposit_quantity_returned: deposit_quantity,
+                deposit_amount: BigDecimal::from(0),
+                unit_cost: product_to_sell.cost_price.clone(),
             };
             diesel::insert_into(sale_items::table)
                 .values(&new_sale_item)
@@ -136,16 +261,34 @@ pub fn seed_database(conn: &mut PgConnection) -> AppResult<()> {
             sale_total += &total_price;
 
             // Mettre à jour le stock du produit
-            diesel::update(products::table.find(product_to_sell.id))
-                .set(
-                    products::stock_in_sale_units.eq(products::stock_in_sale_units - quantity_sold),
-                )
-                .execute(conn)?;
+            record_stock_movement(
+                conn,
+                product_to_sell.id,
+                -quantity_sold,
+                StockMovementReason::Sale,
+                Some(admin_id),
+                Some(sale.sale_number.clone()),
+                None,
+            )?;
         }
 
         // Mettre à jour le montant total de la vente
         diesel::update(sales::table.find(sale.id))
-            .set(sales::total_amount.eq(sale_total))
+            .set(sales::total_amount.eq(&sale_total))
+            .execute(conn)?;
+
+        // Vente comptant réglée en espèces
+        let new_payment = NewPayment {
+            id: Uuid::new_v4(),
+            sale_id: sale.id,
+            method: PaymentMethod::Cash.as_str().to_string(),
+            amount: sale_total.clone(),
+            cash_tendered: Some(sale_total.clone()),
+            change_due: BigDecimal::from(0),
+            reference: None,
+        };
+        diesel::insert_into(payments::table)
+            .values(&new_payment)
             .execute(conn)?;
     }
     log::info!("Ventes de test créées.");
@@ -0,0 +1,287 @@
+// src/services/escpos_document.rs
+
+//! Construction d'un document ESC/POS au niveau de l'octet.
+//! Le même flux d'octets est envoyé à toutes les imprimantes, qu'elles soient en USB,
+//! en réseau (TCP 9100), en série ou alimentées par un fichier.
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+/// Caractère imprimé à la place d'un caractère absent de la table de codes.
+const REPLACEMENT: u8 = b'?';
+
+/// Correspondance Latin-1 (U+00A0 à U+00FF) vers la page de codes PC850.
+const LATIN1_TO_PC850: [u8; 96] = [
+    0xFF, 0xAD, 0xBD, 0x9C, 0xCF, 0xBE, 0xDD, 0xF5, 0xF9, 0xB8, 0xA6, 0xAE, //
+    0xAA, 0xF0, 0xA9, 0xEE, 0xF8, 0xF1, 0xFD, 0xFC, 0xEF, 0xE6, 0xF4, 0xFA, //
+    0xF7, 0xFB, 0xA7, 0xAF, 0xAC, 0xAB, 0xF3, 0xA8, 0xB7, 0xB5, 0xB6, 0xC7, //
+    0x8E, 0x8F, 0x92, 0x80, 0xD4, 0x90, 0xD2, 0xD3, 0xDE, 0xD6, 0xD7, 0xD8, //
+    0xD1, 0xA5, 0xE3, 0xE0, 0xE2, 0xE5, 0x99, 0x9E, 0x9D, 0xEB, 0xE9, 0xEA, //
+    0x9A, 0xED, 0xE8, 0xE1, 0x85, 0xA0, 0x83, 0xC6, 0x84, 0x86, 0x91, 0x87, //
+    0x8A, 0x82, 0x88, 0x89, 0x8D, 0xA1, 0x8C, 0x8B, 0xD0, 0xA4, 0x95, 0xA2, //
+    0x93, 0xE4, 0x94, 0xF6, 0x9B, 0x97, 0xA3, 0x96, 0x81, 0xEC, 0xE7, 0x98, //
+];
+
+/// Page de codes sélectionnée sur l'imprimante (commande `ESC t n`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodePage {
+    /// PC850 (Europe de l'Ouest), présente sur presque toutes les imprimantes ticket.
+    Pc850,
+    /// PC858 : PC850 avec le symbole euro.
+    Pc858,
+    /// Windows-1252.
+    Wpc1252,
+}
+
+impl CodePage {
+    /// Numéro de la table dans le jeu de commandes Epson.
+    fn table(self) -> u8 {
+        match self {
+            CodePage::Pc850 => 2,
+            CodePage::Pc858 => 19,
+            CodePage::Wpc1252 => 16,
+        }
+    }
+
+    /// Encode un caractère ; les caractères absents de la table deviennent `?`.
+    fn encode(self, c: char) -> u8 {
+        if c.is_ascii() {
+            return c as u8;
+        }
+        let code = c as u32;
+        match self {
+            CodePage::Pc850 | CodePage::Pc858 => match c {
+                '\u{a0}'..='\u{ff}' => LATIN1_TO_PC850[(code - 0xA0) as usize],
+                '€' if self == CodePage::Pc858 => 0xD5,
+                '’' | '‘' => b'\'',
+                '“' | '”' => b'"',
+                '–' | '—' => b'-',
+                _ => REPLACEMENT,
+            },
+            CodePage::Wpc1252 => match c {
+                '\u{a0}'..='\u{ff}' => code as u8,
+                '€' => 0x80,
+                '…' => 0x85,
+                'Œ' => 0x8C,
+                '‘' => 0x91,
+                '’' => 0x92,
+                '“' => 0x93,
+                '”' => 0x94,
+                '–' => 0x96,
+                '—' => 0x97,
+                'œ' => 0x9C,
+                _ => REPLACEMENT,
+            },
+        }
+    }
+}
+
+/// Alignement des lignes suivantes (commande `ESC a n`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+    Left,
+    Center,
+    Right,
+}
+
+/// Police de caractères (commande `ESC M n`) ; la police B est plus étroite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    A,
+    B,
+}
+
+/// Mode de coupe du papier (commande `GS V m`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cut {
+    Full,
+    Partial,
+}
+
+/// Document ESC/POS en cours de construction. Les commandes de style s'appliquent
+/// aux lignes suivantes, jusqu'à la commande contraire.
+pub struct EscPosDocument {
+    bytes: Vec<u8>,
+    code_page: CodePage,
+    /// Nombre de caractères par ligne en police A, taille normale.
+    width: usize,
+    /// Facteur d'agrandissement horizontal en cours, pour le calcul des colonnes.
+    width_factor: usize,
+}
+
+impl EscPosDocument {
+    /// Nouveau document : réinitialise l'imprimante et sélectionne la page de codes.
+    pub fn new(width: usize, code_page: CodePage) -> Self {
+        let mut document = Self {
+            bytes: Vec::new(),
+            code_page,
+            width,
+            width_factor: 1,
+        };
+        document.bytes.extend_from_slice(&[ESC, b'@']);
+        document
+            .bytes
+            .extend_from_slice(&[ESC, b't', code_page.table()]);
+        document
+    }
+
+    /// Nombre de caractères disponibles sur une ligne avec la taille en cours.
+    pub fn line_width(&self) -> usize {
+        self.width / self.width_factor
+    }
+
+    pub fn justify(&mut self, justify: Justify) {
+        let n = match justify {
+            Justify::Left => 0,
+            Justify::Center => 1,
+            Justify::Right => 2,
+        };
+        self.bytes.extend_from_slice(&[ESC, b'a', n]);
+    }
+
+    pub fn bold(&mut self, enabled: bool) {
+        self.bytes.extend_from_slice(&[ESC, b'E', enabled as u8]);
+    }
+
+    pub fn underline(&mut self, enabled: bool) {
+        self.bytes.extend_from_slice(&[ESC, b'-', enabled as u8]);
+    }
+
+    pub fn font(&mut self, font: Font) {
+        let n = match font {
+            Font::A => 0,
+            Font::B => 1,
+        };
+        self.bytes.extend_from_slice(&[ESC, b'M', n]);
+    }
+
+    /// Agrandissement des caractères, de 1 à 8 fois en largeur et en hauteur.
+    pub fn size(&mut self, width: u8, height: u8) {
+        let width = width.clamp(1, 8);
+        let height = height.clamp(1, 8);
+        self.width_factor = width as usize;
+        self.bytes
+            .extend_from_slice(&[GS, b'!', ((width - 1) << 4) | (height - 1)]);
+    }
+
+    /// Texte sans retour à la ligne, encodé dans la page de codes du document.
+    pub fn text(&mut self, text: &str) {
+        let code_page = self.code_page;
+        self.bytes.extend(
+            text.chars()
+                .filter(|c| !c.is_control())
+                .map(|c| code_page.encode(c)),
+        );
+    }
+
+    pub fn line(&mut self, text: &str) {
+        self.text(text);
+        self.bytes.push(LF);
+    }
+
+    /// Ligne de séparation sur toute la largeur.
+    pub fn separator(&mut self) {
+        let separator = "-".repeat(self.line_width());
+        self.line(&separator);
+    }
+
+    /// Libellé à gauche et valeur alignée à droite ; le libellé est tronqué si la ligne déborde.
+    pub fn columns(&mut self, label: &str, value: &str) {
+        let width = self.line_width();
+        let value_len = value.chars().count();
+        let label_width = width.saturating_sub(value_len + 1);
+        let label = label.chars().take(label_width).collect::<String>();
+        let padding = width.saturating_sub(label.chars().count() + value_len);
+        self.line(&format!("{}{}{}", label, " ".repeat(padding), value));
+    }
+
+    /// Avance le papier de `lines` lignes.
+    pub fn feed(&mut self, lines: u8) {
+        self.bytes.extend_from_slice(&[ESC, b'd', lines]);
+    }
+
+    pub fn cut(&mut self, cut: Cut) {
+        let m = match cut {
+            Cut::Full => 0,
+            Cut::Partial => 1,
+        };
+        self.bytes.extend_from_slice(&[GS, b'V', m]);
+    }
+
+    /// Impulsion d'ouverture du tiroir-caisse branché sur la broche 2.
+    pub fn open_drawer(&mut self) {
+        self.bytes.extend_from_slice(&[ESC, b'p', 0, 25, 250]);
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_alignment_feed_cut_and_drawer() {
+        let mut document = EscPosDocument::new(32, CodePage::Pc858);
+        document.justify(Justify::Center);
+        document.bold(true);
+        document.size(2, 2);
+        document.line("TOTAL");
+        document.size(1, 1);
+        document.bold(false);
+        document.font(Font::B);
+        document.underline(true);
+        document.feed(3);
+        document.cut(Cut::Partial);
+        document.open_drawer();
+
+        let expected: Vec<u8> = [
+            &b"\x1B@\x1Bt\x13"[..],
+            b"\x1Ba\x01\x1BE\x01\x1D!\x11TOTAL\n",
+            b"\x1D!\x00\x1BE\x00\x1BM\x01\x1B-\x01",
+            b"\x1Bd\x03\x1DV\x01\x1Bp\x00\x19\xFA",
+        ]
+        .concat();
+        assert_eq!(document.as_bytes(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_accents_are_encoded_in_the_selected_code_page() {
+        let mut pc858 = EscPosDocument::new(32, CodePage::Pc858);
+        pc858.text("Bière à 5€ ✓");
+        assert_eq!(&pc858.as_bytes()[5..], b"Bi\x8Are \x85 5\xD5 ?");
+
+        let mut pc850 = EscPosDocument::new(32, CodePage::Pc850);
+        pc850.text("Reçu n° 12 – €");
+        assert_eq!(&pc850.as_bytes()[5..], b"Re\x87u n\xF8 12 - ?");
+
+        let mut wpc1252 = EscPosDocument::new(32, CodePage::Wpc1252);
+        wpc1252.text("Œuvre é€");
+        assert_eq!(&wpc1252.as_bytes()[5..], b"\x8Cuvre \xE9\x80");
+    }
+
+    #[test]
+    fn test_columns_fit_the_line_width() {
+        let mut document = EscPosDocument::new(20, CodePage::Pc858);
+        document.columns("ESPECES", "12500");
+        document.columns("Un libellé beaucoup trop long", "900");
+        document.size(2, 1);
+        document.columns("TOTAL", "900");
+
+        let expected: Vec<u8> = [
+            &b"\x1B@\x1Bt\x13"[..],
+            b"ESPECES        12500\n",
+            b"Un libell\x82 beauc 900\n",
+            b"\x1D!\x10TOTAL  900\n",
+        ]
+        .concat();
+        assert_eq!(document.as_bytes(), expected.as_slice());
+    }
+}
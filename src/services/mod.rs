@@ -1,3 +1,7 @@
 // src/services/mod.rs
+pub mod escpos_document;
+pub mod pdf_layout;
+pub mod print_queue;
 pub mod printing_service;
+pub mod product_import_service;
 pub mod report_generator_service;
@@ -0,0 +1,870 @@
+// src/services/pdf_layout.rs
+
+//! Mise en page des documents PDF (rapports, relevés) : pages A4 avec en-tête au nom du dépôt,
+//! pagination automatique, tableaux dont l'en-tête est répété sur chaque page et numéros de page.
+//! Le texte est écrit avec une police TrueType embarquée (DejaVu Sans), si bien que tous les
+//! caractères UTF-8 couverts par la police s'affichent, et pas seulement le Latin-1.
+
+use crate::{
+    config::{
+        business_calendar::business_calendar,
+        company_config::{CompanyConfig, company_config},
+    },
+    error::{AppError, AppResult},
+};
+use chrono::Utc;
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, ObjectId, Stream, StringFormat, dictionary};
+use std::collections::BTreeMap;
+use ttf_parser::{Face, GlyphId};
+
+// Format A4, en points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 40.0;
+const CONTENT_WIDTH: f64 = PAGE_WIDTH - 2.0 * MARGIN;
+// Zone réservée à l'en-tête (logo, nom du dépôt) et au pied de page (numéro de page)
+const HEADER_HEIGHT: f64 = 60.0;
+const FOOTER_HEIGHT: f64 = 30.0;
+const BODY_TOP: f64 = PAGE_HEIGHT - MARGIN - HEADER_HEIGHT;
+const BODY_BOTTOM: f64 = MARGIN + FOOTER_HEIGHT;
+
+const TEXT_SIZE: f64 = 10.0;
+const LINE_HEIGHT: f64 = 14.0;
+const TABLE_TEXT_SIZE: f64 = 9.0;
+const ROW_HEIGHT: f64 = 15.0;
+const HEADER_ROW_HEIGHT: f64 = 18.0;
+const CELL_PADDING: f64 = 4.0;
+const LOGO_HEIGHT: f64 = 40.0;
+const LOGO_MAX_WIDTH: f64 = 120.0;
+
+// Couleurs (RVB entre 0 et 1), assorties à l'en-tête des tableaux Excel
+const TABLE_HEADER_COLOR: (f64, f64, f64) = (0.867, 0.922, 0.969);
+const TABLE_STRIPE_COLOR: (f64, f64, f64) = (0.965, 0.965, 0.965);
+const MUTED_GRAY: f64 = 0.4;
+
+const REGULAR_RESOURCE: &str = "F1";
+const BOLD_RESOURCE: &str = "F2";
+const LOGO_RESOURCE: &str = "Logo";
+
+static REGULAR_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+static BOLD_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Regular,
+    Bold,
+}
+
+/// Police TrueType embarquée, encodée en Identity-H : chaque caractère est écrit par son
+/// numéro de glyphe sur deux octets.
+struct PdfFont {
+    resource: &'static str,
+    base_name: &'static str,
+    data: &'static [u8],
+    face: Face<'static>,
+    /// Glyphes utilisés et caractère correspondant, pour la table des largeurs et le copier-coller.
+    used: BTreeMap<u16, char>,
+}
+
+impl PdfFont {
+    fn load(
+        resource: &'static str,
+        base_name: &'static str,
+        data: &'static [u8],
+    ) -> AppResult<Self> {
+        let face = Face::parse(data, 0)
+            .map_err(|e| AppError::Generic(format!("Police {} illisible : {}", base_name, e)))?;
+        Ok(Self {
+            resource,
+            base_name,
+            data,
+            face,
+            used: BTreeMap::new(),
+        })
+    }
+
+    /// Glyphe d'un caractère ; un caractère absent de la police utilise le glyphe 0 (`.notdef`).
+    fn glyph(&self, c: char) -> GlyphId {
+        self.face.glyph_index(c).unwrap_or(GlyphId(0))
+    }
+
+    /// Convertit une grandeur de la police en millièmes de la taille du texte.
+    fn scale(&self, value: f64) -> f64 {
+        value * 1000.0 / self.face.units_per_em() as f64
+    }
+
+    fn advance(&self, glyph: GlyphId) -> f64 {
+        self.scale(self.face.glyph_hor_advance(glyph).unwrap_or(0) as f64)
+    }
+
+    /// Largeur du texte en points pour une taille de police donnée.
+    fn text_width(&self, text: &str, size: f64) -> f64 {
+        text.chars()
+            .map(|c| self.advance(self.glyph(c)))
+            .sum::<f64>()
+            * size
+            / 1000.0
+    }
+
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            let glyph = self.glyph(c);
+            self.used.entry(glyph.0).or_insert(c);
+            bytes.extend_from_slice(&glyph.0.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Table de correspondance glyphe → Unicode, pour la recherche et le copier-coller.
+    fn to_unicode_cmap(&self) -> String {
+        let entries = self
+            .used
+            .iter()
+            .filter(|(glyph, _)| **glyph != 0)
+            .map(|(glyph, c)| {
+                let utf16 = c
+                    .encode_utf16(&mut [0; 2])
+                    .iter()
+                    .map(|unit| format!("{:04X}", unit))
+                    .collect::<String>();
+                format!("<{:04X}> <{}>", glyph, utf16)
+            })
+            .collect::<Vec<_>>();
+
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n\
+             12 dict begin\n\
+             begincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n\
+             /CMapType 2 def\n\
+             1 begincodespacerange\n\
+             <0000> <FFFF>\n\
+             endcodespacerange\n",
+        );
+        // Un bloc `bfchar` contient au plus 100 entrées
+        for chunk in entries.chunks(100) {
+            cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+            for entry in chunk {
+                cmap.push_str(entry);
+                cmap.push('\n');
+            }
+            cmap.push_str("endbfchar\n");
+        }
+        cmap.push_str(
+            "endcmap\n\
+             CMapName currentdict /CMap defineresource pop\n\
+             end\n\
+             end\n",
+        );
+        cmap
+    }
+
+    /// Ajoute la police (fichier TrueType, descripteur, largeurs et table Unicode) au document.
+    fn embed(&self, doc: &mut Document) -> ObjectId {
+        let font_file = doc.add_object(Stream::new(
+            dictionary! { "Length1" => self.data.len() as i64 },
+            self.data.to_vec(),
+        ));
+
+        let bbox = self.face.global_bounding_box();
+        let scaled = |value: i16| self.scale(value as f64).round() as i64;
+        let descriptor = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => self.base_name,
+            "Flags" => 32,
+            "FontBBox" => vec![
+                scaled(bbox.x_min).into(),
+                scaled(bbox.y_min).into(),
+                scaled(bbox.x_max).into(),
+                scaled(bbox.y_max).into(),
+            ],
+            "ItalicAngle" => 0,
+            "Ascent" => scaled(self.face.ascender()),
+            "Descent" => scaled(self.face.descender()),
+            "CapHeight" => scaled(self.face.capital_height().unwrap_or(self.face.ascender())),
+            "StemV" => 80,
+            "FontFile2" => font_file,
+        });
+
+        // Largeurs des seuls glyphes utilisés : [glyphe [largeur] ...]
+        let widths = self
+            .used
+            .keys()
+            .flat_map(|glyph| {
+                let width = self.advance(GlyphId(*glyph)).round() as i64;
+                [
+                    Object::from(*glyph as i64),
+                    Object::Array(vec![width.into()]),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let cid_font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => self.base_name,
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal("Identity"),
+                "Supplement" => 0,
+            },
+            "FontDescriptor" => descriptor,
+            "W" => widths,
+            "CIDToGIDMap" => "Identity",
+        });
+
+        let to_unicode = doc.add_object(Stream::new(
+            dictionary! {},
+            self.to_unicode_cmap().into_bytes(),
+        ));
+        doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => self.base_name,
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![cid_font.into()],
+            "ToUnicode" => to_unicode,
+        })
+    }
+}
+
+/// Tronque `text` avec « … » pour qu'il tienne dans `max_width`.
+fn fit_text(font: &PdfFont, text: &str, size: f64, max_width: f64) -> String {
+    if font.text_width(text, size) <= max_width {
+        return text.to_string();
+    }
+    let ellipsis_width = font.text_width("…", size);
+    let mut fitted = String::new();
+    let mut width = 0.0;
+    for c in text.chars() {
+        let char_width = font.text_width(c.encode_utf8(&mut [0; 4]), size);
+        if width + char_width + ellipsis_width > max_width {
+            break;
+        }
+        width += char_width;
+        fitted.push(c);
+    }
+    fitted.truncate(fitted.trim_end().len());
+    fitted.push('…');
+    fitted
+}
+
+/// Découpe `text` en lignes d'au plus `max_width`, mot par mot.
+/// Un mot plus long que la ligne est tronqué.
+fn wrap_text(font: &PdfFont, text: &str, size: f64, max_width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if font.text_width(&candidate, size) <= max_width {
+            current = candidate;
+        } else {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            current = fit_text(font, word, size, max_width);
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Logo JPEG, inclus tel quel dans le PDF (filtre DCTDecode).
+struct JpegImage {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    components: u8,
+}
+
+impl JpegImage {
+    /// Lit les dimensions dans l'en-tête SOF ; renvoie `None` si les données ne sont pas un JPEG.
+    fn parse(data: Vec<u8>) -> Option<Self> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return None;
+        }
+        let read_u16 = |at: usize| -> Option<u16> {
+            Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+        };
+        let mut pos = 2;
+        loop {
+            if *data.get(pos)? != 0xFF {
+                return None;
+            }
+            let marker = *data.get(pos + 1)?;
+            match marker {
+                // Octets de bourrage entre deux marqueurs
+                0xFF => pos += 1,
+                // Marqueurs SOF (début d'image), hors DHT, JPG et DAC
+                0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                    let height = read_u16(pos + 5)? as u32;
+                    let width = read_u16(pos + 7)? as u32;
+                    let components = *data.get(pos + 9)?;
+                    if width == 0 || height == 0 {
+                        return None;
+                    }
+                    return Some(Self {
+                        data,
+                        width,
+                        height,
+                        components,
+                    });
+                }
+                // Début des données compressées sans SOF rencontré
+                0xDA | 0xD9 => return None,
+                _ => pos += 2 + read_u16(pos + 2)? as usize,
+            }
+        }
+    }
+
+    fn to_stream(&self) -> Stream {
+        let color_space = match self.components {
+            1 => "DeviceGray",
+            4 => "DeviceCMYK",
+            _ => "DeviceRGB",
+        };
+        let mut dict = dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => self.width as i64,
+            "Height" => self.height as i64,
+            "ColorSpace" => color_space,
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        };
+        // Les JPEG CMJN d'Adobe stockent des valeurs inversées
+        if self.components == 4 {
+            dict.set(
+                "Decode",
+                vec![
+                    1.into(),
+                    0.into(),
+                    1.into(),
+                    0.into(),
+                    1.into(),
+                    0.into(),
+                    1.into(),
+                    0.into(),
+                ],
+            );
+        }
+        // Déjà compressé : ne pas le recompresser
+        Stream::new(dict, self.data.clone()).with_compression(false)
+    }
+}
+
+/// Alignement du texte dans une colonne de tableau.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Colonne d'un tableau ; sa largeur est relative à celle des autres colonnes.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub title: String,
+    pub width: f64,
+    pub align: Align,
+}
+
+impl Column {
+    pub fn left(title: &str, width: f64) -> Self {
+        Self {
+            title: title.to_string(),
+            width,
+            align: Align::Left,
+        }
+    }
+
+    /// Colonne alignée à droite, pour les montants et les quantités.
+    pub fn right(title: &str, width: f64) -> Self {
+        Self {
+            title: title.to_string(),
+            width,
+            align: Align::Right,
+        }
+    }
+}
+
+/// Document PDF en cours de construction. Le contenu est ajouté de haut en bas ;
+/// une nouvelle page est ouverte dès que la page courante est pleine.
+pub struct PdfReport {
+    title: String,
+    company: CompanyConfig,
+    regular: PdfFont,
+    bold: PdfFont,
+    logo: Option<JpegImage>,
+    pages: Vec<Vec<Operation>>,
+    /// Position verticale du prochain élément sur la page courante.
+    y: f64,
+}
+
+impl PdfReport {
+    /// Nouveau document A4 à l'en-tête du dépôt configuré.
+    pub fn new(title: &str) -> AppResult<Self> {
+        Self::with_company(title, company_config().clone())
+    }
+
+    pub fn with_company(title: &str, company: CompanyConfig) -> AppResult<Self> {
+        let logo = company
+            .logo_path
+            .as_ref()
+            .and_then(|path| match std::fs::read(path) {
+                Ok(data) => JpegImage::parse(data).or_else(|| {
+                    log::warn!(
+                        "Logo {} ignoré : seul le format JPEG est pris en charge",
+                        path.display()
+                    );
+                    None
+                }),
+                Err(e) => {
+                    log::warn!("Logo {} illisible : {}", path.display(), e);
+                    None
+                }
+            });
+        let mut report = Self {
+            title: title.to_string(),
+            company,
+            regular: PdfFont::load(REGULAR_RESOURCE, "DejaVuSans", REGULAR_FONT)?,
+            bold: PdfFont::load(BOLD_RESOURCE, "DejaVuSans-Bold", BOLD_FONT)?,
+            logo,
+            pages: Vec::new(),
+            y: BODY_TOP,
+        };
+        report.new_page();
+        Ok(report)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    // --- Contenu ---
+
+    /// Titre du document, en tête de la première page.
+    pub fn title(&mut self, text: &str) {
+        self.ensure_space(30.0);
+        let ops = self.text_ops(text, Style::Bold, 20.0, MARGIN, self.y - 20.0);
+        self.push(ops);
+        self.y -= 30.0;
+    }
+
+    /// Titre de section ; il n'est jamais laissé seul en bas de page.
+    pub fn section(&mut self, text: &str) {
+        self.ensure_space(26.0 + HEADER_ROW_HEIGHT + 2.0 * ROW_HEIGHT);
+        self.y -= 8.0;
+        let ops = self.text_ops(text, Style::Bold, 14.0, MARGIN, self.y - 14.0);
+        self.push(ops);
+        self.y -= 22.0;
+    }
+
+    /// Paragraphe renvoyé à la ligne selon la largeur de la page.
+    pub fn paragraph(&mut self, text: &str) {
+        self.lines(text, Style::Regular, TEXT_SIZE);
+    }
+
+    /// Paragraphe en gras, pour un montant ou une conclusion à mettre en évidence.
+    pub fn strong(&mut self, text: &str) {
+        self.lines(text, Style::Bold, TEXT_SIZE + 1.0);
+    }
+
+    pub fn spacer(&mut self, height: f64) {
+        self.y -= height;
+    }
+
+    /// Tableau paginé : l'en-tête des colonnes est répété en haut de chaque nouvelle page.
+    pub fn table(&mut self, columns: &[Column], rows: &[Vec<String>]) {
+        if rows.is_empty() {
+            self.paragraph("Aucune donnée sur la période.");
+            return;
+        }
+        self.ensure_space(HEADER_ROW_HEIGHT + ROW_HEIGHT);
+        self.table_header(columns);
+        for (index, row) in rows.iter().enumerate() {
+            if self.ensure_space(ROW_HEIGHT) {
+                self.table_header(columns);
+            }
+            if index % 2 == 1 {
+                let ops = fill_rect(
+                    MARGIN,
+                    self.y - ROW_HEIGHT,
+                    CONTENT_WIDTH,
+                    ROW_HEIGHT,
+                    TABLE_STRIPE_COLOR,
+                );
+                self.push(ops);
+            }
+            self.table_row(columns, row, Style::Regular);
+        }
+        self.y -= 4.0;
+    }
+
+    /// Ligne de total en gras, sous le tableau qui la précède.
+    pub fn total_row(&mut self, columns: &[Column], cells: &[String]) {
+        self.ensure_space(ROW_HEIGHT + 2.0);
+        let ops = line_ops(MARGIN, self.y, MARGIN + CONTENT_WIDTH, self.y, 0.8);
+        self.push(ops);
+        self.table_row(columns, cells, Style::Bold);
+        self.y -= 4.0;
+    }
+
+    /// Numérote les pages puis enregistre le document dans `file_path`.
+    pub fn save(mut self, file_path: &str) -> AppResult<()> {
+        let total = self.pages.len();
+        let printed_at = format!(
+            "Édité le {}",
+            business_calendar().format_local(Utc::now(), "%d/%m/%Y à %H:%M")
+        );
+        for index in 0..total {
+            let ops = self.footer_ops(index, total, &printed_at);
+            self.pages[index].extend(ops);
+        }
+
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+
+        // Les polices sont embarquées après la numérotation : tous les glyphes sont alors connus
+        let regular = self.regular.embed(&mut doc);
+        let bold = self.bold.embed(&mut doc);
+        let mut resources = dictionary! {
+            "Font" => dictionary! {
+                REGULAR_RESOURCE => regular,
+                BOLD_RESOURCE => bold,
+            },
+        };
+        if let Some(logo) = &self.logo {
+            let logo_id = doc.add_object(logo.to_stream());
+            resources.set("XObject", dictionary! { LOGO_RESOURCE => logo_id });
+        }
+        let resources_id = doc.add_object(resources);
+
+        let mut kids = Vec::with_capacity(total);
+        for operations in self.pages {
+            let content = Content { operations }
+                .encode()
+                .map_err(|e| AppError::Generic(format!("Erreur de génération du PDF : {}", e)))?;
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => resources_id,
+            });
+            kids.push(page_id.into());
+        }
+
+        let pages = dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => total as i64,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+        };
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::String(utf16_text(&self.title), StringFormat::Hexadecimal),
+            "Producer" => Object::string_literal("depot-boissons"),
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+
+        doc.compress();
+        doc.save(file_path)?;
+        Ok(())
+    }
+
+    // --- Mise en page ---
+
+    fn font(&self, style: Style) -> &PdfFont {
+        match style {
+            Style::Regular => &self.regular,
+            Style::Bold => &self.bold,
+        }
+    }
+
+    fn push(&mut self, ops: Vec<Operation>) {
+        if let Some(page) = self.pages.last_mut() {
+            page.extend(ops);
+        }
+    }
+
+    /// Ouvre une page et y dessine l'en-tête du dépôt.
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        let ops = self.header_ops();
+        self.push(ops);
+        self.y = BODY_TOP;
+    }
+
+    /// Ouvre une nouvelle page si `height` ne tient plus sur la page courante.
+    /// Renvoie `true` si une page a été ajoutée.
+    fn ensure_space(&mut self, height: f64) -> bool {
+        if self.y - height < BODY_BOTTOM {
+            self.new_page();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn lines(&mut self, text: &str, style: Style, size: f64) {
+        for line in wrap_text(self.font(style), text, size, CONTENT_WIDTH) {
+            self.ensure_space(LINE_HEIGHT);
+            let ops = self.text_ops(&line, style, size, MARGIN, self.y - size);
+            self.push(ops);
+            self.y -= LINE_HEIGHT;
+        }
+    }
+
+    fn text_ops(&mut self, text: &str, style: Style, size: f64, x: f64, y: f64) -> Vec<Operation> {
+        let font = match style {
+            Style::Regular => &mut self.regular,
+            Style::Bold => &mut self.bold,
+        };
+        let encoded = font.encode(text);
+        vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Object::Name(font.resource.into()), size.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new(
+                "Tj",
+                vec![Object::String(encoded, StringFormat::Hexadecimal)],
+            ),
+            Operation::new("ET", vec![]),
+        ]
+    }
+
+    /// Logo, nom et coordonnées du dépôt à gauche, titre du document à droite.
+    fn header_ops(&mut self) -> Vec<Operation> {
+        let top = PAGE_HEIGHT - MARGIN;
+        let mut ops = Vec::new();
+        let mut x = MARGIN;
+        if let Some(logo) = &self.logo {
+            let ratio = logo.width as f64 / logo.height as f64;
+            let (width, height) = if LOGO_HEIGHT * ratio > LOGO_MAX_WIDTH {
+                (LOGO_MAX_WIDTH, LOGO_MAX_WIDTH / ratio)
+            } else {
+                (LOGO_HEIGHT * ratio, LOGO_HEIGHT)
+            };
+            ops.extend([
+                Operation::new("q", vec![]),
+                Operation::new(
+                    "cm",
+                    vec![
+                        width.into(),
+                        0.into(),
+                        0.into(),
+                        height.into(),
+                        MARGIN.into(),
+                        (top - height).into(),
+                    ],
+                ),
+                Operation::new("Do", vec![Object::Name(LOGO_RESOURCE.into())]),
+                Operation::new("Q", vec![]),
+            ]);
+            x += width + 10.0;
+        }
+
+        let title_width = self.bold.text_width(&self.title, TEXT_SIZE);
+        let name_width = PAGE_WIDTH - MARGIN - title_width - 20.0 - x;
+        let name = fit_text(&self.bold, &self.company.name, 14.0, name_width);
+        ops.extend(self.text_ops(&name, Style::Bold, 14.0, x, top - 14.0));
+        if let Some(contact) = self.company.contact_line() {
+            let contact = fit_text(&self.regular, &contact, 9.0, name_width);
+            ops.extend(self.text_ops(&contact, Style::Regular, 9.0, x, top - 28.0));
+        }
+        let title = self.title.clone();
+        ops.extend(self.text_ops(
+            &title,
+            Style::Bold,
+            TEXT_SIZE,
+            PAGE_WIDTH - MARGIN - title_width,
+            top - 14.0,
+        ));
+
+        let separator_y = top - HEADER_HEIGHT + 12.0;
+        ops.extend(line_ops(
+            MARGIN,
+            separator_y,
+            PAGE_WIDTH - MARGIN,
+            separator_y,
+            1.0,
+        ));
+        ops
+    }
+
+    /// Date d'édition à gauche, « Page n / total » à droite.
+    fn footer_ops(&mut self, index: usize, total: usize, printed_at: &str) -> Vec<Operation> {
+        let y = MARGIN;
+        let page_label = format!("Page {} / {}", index + 1, total);
+        let page_width = self.regular.text_width(&page_label, 8.0);
+        let mut ops = line_ops(MARGIN, y + 12.0, PAGE_WIDTH - MARGIN, y + 12.0, 0.5);
+        ops.push(Operation::new("g", vec![MUTED_GRAY.into()]));
+        ops.extend(self.text_ops(printed_at, Style::Regular, 8.0, MARGIN, y));
+        ops.extend(self.text_ops(
+            &page_label,
+            Style::Regular,
+            8.0,
+            PAGE_WIDTH - MARGIN - page_width,
+            y,
+        ));
+        ops.push(Operation::new("g", vec![0.into()]));
+        ops
+    }
+
+    fn column_widths(columns: &[Column]) -> Vec<f64> {
+        let total = columns.iter().map(|c| c.width).sum::<f64>().max(1.0);
+        columns
+            .iter()
+            .map(|c| c.width / total * CONTENT_WIDTH)
+            .collect()
+    }
+
+    fn table_header(&mut self, columns: &[Column]) {
+        let ops = fill_rect(
+            MARGIN,
+            self.y - HEADER_ROW_HEIGHT,
+            CONTENT_WIDTH,
+            HEADER_ROW_HEIGHT,
+            TABLE_HEADER_COLOR,
+        );
+        self.push(ops);
+        let titles = columns.iter().map(|c| c.title.clone()).collect::<Vec<_>>();
+        self.cells(columns, &titles, Style::Bold, HEADER_ROW_HEIGHT);
+    }
+
+    fn table_row(&mut self, columns: &[Column], cells: &[String], style: Style) {
+        self.cells(columns, cells, style, ROW_HEIGHT);
+    }
+
+    fn cells(&mut self, columns: &[Column], cells: &[String], style: Style, height: f64) {
+        let widths = Self::column_widths(columns);
+        let baseline = self.y - height + (height - TABLE_TEXT_SIZE) / 2.0 + 2.0;
+        let mut x = MARGIN;
+        for ((column, width), cell) in columns.iter().zip(&widths).zip(cells) {
+            let font = self.font(style);
+            let text = fit_text(font, cell, TABLE_TEXT_SIZE, width - 2.0 * CELL_PADDING);
+            let text_x = match column.align {
+                Align::Left => x + CELL_PADDING,
+                Align::Right => x + width - CELL_PADDING - font.text_width(&text, TABLE_TEXT_SIZE),
+            };
+            let ops = self.text_ops(&text, style, TABLE_TEXT_SIZE, text_x, baseline);
+            self.push(ops);
+            x += width;
+        }
+        self.y -= height;
+    }
+}
+
+fn line_ops(x1: f64, y1: f64, x2: f64, y2: f64, width: f64) -> Vec<Operation> {
+    vec![
+        Operation::new("q", vec![]),
+        Operation::new("w", vec![width.into()]),
+        Operation::new("m", vec![x1.into(), y1.into()]),
+        Operation::new("l", vec![x2.into(), y2.into()]),
+        Operation::new("S", vec![]),
+        Operation::new("Q", vec![]),
+    ]
+}
+
+fn fill_rect(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    (r, g, b): (f64, f64, f64),
+) -> Vec<Operation> {
+    vec![
+        Operation::new("q", vec![]),
+        Operation::new("rg", vec![r.into(), g.into(), b.into()]),
+        Operation::new("re", vec![x.into(), y.into(), width.into(), height.into()]),
+        Operation::new("f", vec![]),
+        Operation::new("Q", vec![]),
+    ]
+}
+
+/// Chaîne de texte PDF en UTF-16BE avec BOM, pour les métadonnées du document.
+fn utf16_text(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_text_is_encoded_with_embedded_glyphs() {
+        let mut font = PdfFont::load(REGULAR_RESOURCE, "DejaVuSans", REGULAR_FONT).unwrap();
+        // Caractères hors Latin-1 : ils ne doivent plus être remplacés par '?'
+        let text = "Œuvre Ngozi Ọkọ €";
+        let encoded = font.encode(text);
+        assert_eq!(encoded.len(), text.chars().count() * 2);
+        assert!(
+            text.chars()
+                .all(|c| c == ' ' || font.glyph(c) != GlyphId(0))
+        );
+        assert!(font.to_unicode_cmap().contains("<20AC>"));
+    }
+
+    #[test]
+    fn test_fit_and_wrap_respect_width() {
+        let font = PdfFont::load(REGULAR_RESOURCE, "DejaVuSans", REGULAR_FONT).unwrap();
+        let fitted = fit_text(&font, "Castel Beer 65cl — casier de 12", 9.0, 60.0);
+        assert!(fitted.ends_with('…'));
+        assert!(font.text_width(&fitted, 9.0) <= 60.0);
+
+        let lines = wrap_text(&font, &"bière ".repeat(40), 10.0, 200.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| font.text_width(l, 10.0) <= 200.0));
+    }
+
+    #[test]
+    fn test_long_table_spans_pages_with_repeated_header() {
+        let mut report = PdfReport::with_company("Rapport", CompanyConfig::default()).unwrap();
+        let columns = [Column::left("Produit", 3.0), Column::right("Quantité", 1.0)];
+        let rows = (0..120)
+            .map(|i| vec![format!("Produit {}", i), i.to_string()])
+            .collect::<Vec<_>>();
+        report.table(&columns, &rows);
+        assert!(report.page_count() >= 3);
+
+        // L'en-tête « Quantité » figure sur chaque page
+        let header = Object::String(report.bold.encode("Quantité"), StringFormat::Hexadecimal);
+        for page in &report.pages {
+            assert!(
+                page.iter()
+                    .any(|op| op.operator == "Tj" && op.operands.first() == Some(&header))
+            );
+        }
+    }
+
+    #[test]
+    fn test_jpeg_dimensions() {
+        // SOI, segment APP0 de 4 octets, puis SOF0 : 8 bits, 30 x 120 pixels, 3 composantes
+        let data = vec![
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00,
+            0x1E, 0x00, 0x78, 0x03,
+        ];
+        let image = JpegImage::parse(data).unwrap();
+        assert_eq!((image.width, image.height, image.components), (120, 30, 3));
+        assert!(JpegImage::parse(b"\x89PNG\r\n".to_vec()).is_none());
+    }
+}
@@ -0,0 +1,698 @@
+// src/services/print_queue.rs
+
+//! File d'impression persistante.
+//! Chaque document est d'abord écrit dans le répertoire de spool, puis envoyé à l'imprimante.
+//! En cas d'échec (imprimante éteinte, réseau coupé...), l'envoi est retenté avec un délai
+//! croissant ; les travaux en attente survivent à un redémarrage de l'application.
+
+use crate::{
+    config::printer_config::{self, PrinterConfig},
+    error::{AppError, AppResult},
+    services::{escpos_document::EscPosDocument, printing_service},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+use uuid::Uuid;
+
+const SPOOL_DIR: &str = "print_spool";
+
+/// Nombre d'envois tentés avant de laisser le travail en échec.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Délai avant le premier nouvel essai, doublé à chaque échec.
+const FIRST_RETRY_DELAY_SECS: i64 = 10;
+
+/// Délai maximal entre deux essais.
+const MAX_RETRY_DELAY_SECS: i64 = 600;
+
+/// Durée de conservation des travaux imprimés ou annulés.
+const KEEP_FINISHED_HOURS: i64 = 24;
+
+/// Intervalle de scrutation de la file par le thread d'impression.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Protège les lectures-écritures des fichiers de spool entre le thread d'impression et l'interface.
+static SPOOL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Notification de l'interface, remplacée à chaque ouverture de la fenêtre principale.
+static ON_CHANGE: Mutex<Option<Box<dyn Fn() + Send>>> = Mutex::new(None);
+
+/// Le thread d'impression n'est démarré qu'une fois par processus.
+static WORKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+fn lock() -> MutexGuard<'static, ()> {
+    SPOOL_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrintJobStatus {
+    /// En attente du premier envoi (ou d'une réimpression demandée).
+    Pending,
+    /// Envoi en cours.
+    Printing,
+    /// Dernier envoi en échec, nouvel essai programmé.
+    Retrying,
+    /// Tous les essais ont échoué ; seule une réimpression manuelle le relance.
+    Failed,
+    Printed,
+    Cancelled,
+}
+
+impl PrintJobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrintJobStatus::Pending => "En attente",
+            PrintJobStatus::Printing => "Impression...",
+            PrintJobStatus::Retrying => "Nouvel essai programmé",
+            PrintJobStatus::Failed => "Échec",
+            PrintJobStatus::Printed => "Imprimé",
+            PrintJobStatus::Cancelled => "Annulé",
+        }
+    }
+}
+
+/// Travail d'impression, enregistré dans `print_spool/<id>.json` ; les octets ESC/POS
+/// sont dans `print_spool/<id>.prn`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintJob {
+    pub id: Uuid,
+    /// Libellé affiché dans la file (ex : « Ticket V-000042 »).
+    pub title: String,
+    /// Imprimante visée, relue dans la configuration à chaque essai.
+    pub printer_name: String,
+    /// Suffixe du fichier `.prn` transmis aux imprimantes système.
+    pub file_suffix: String,
+    pub status: PrintJobStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub printed_at: Option<DateTime<Utc>>,
+}
+
+impl PrintJob {
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        match self.status {
+            PrintJobStatus::Pending => true,
+            PrintJobStatus::Retrying => self.next_attempt_at.is_none_or(|at| at <= now),
+            _ => false,
+        }
+    }
+
+    pub fn can_cancel(&self) -> bool {
+        matches!(
+            self.status,
+            PrintJobStatus::Pending | PrintJobStatus::Retrying | PrintJobStatus::Failed
+        )
+    }
+
+    pub fn can_reprint(&self) -> bool {
+        self.status != PrintJobStatus::Printing
+    }
+
+    /// `Ok` si le travail est imprimé ; sinon l'erreur du dernier essai, en précisant
+    /// que le document reste dans la file.
+    pub fn into_result(self) -> AppResult<()> {
+        match self.status {
+            PrintJobStatus::Printed => Ok(()),
+            PrintJobStatus::Retrying | PrintJobStatus::Pending => {
+                Err(AppError::PrintingError(format!(
+                    "{} — le document reste dans la file d'impression et sera réimprimé automatiquement",
+                    self.last_error.unwrap_or_default()
+                )))
+            }
+            status => Err(AppError::PrintingError(format!(
+                "{} ({})",
+                self.last_error.unwrap_or_default(),
+                status.label()
+            ))),
+        }
+    }
+
+    /// Enregistre le résultat d'un envoi et programme, si besoin, le prochain essai.
+    fn record_attempt(&mut self, result: AppResult<()>, now: DateTime<Utc>) {
+        self.attempts += 1;
+        match result {
+            Ok(()) => {
+                self.status = PrintJobStatus::Printed;
+                self.printed_at = Some(now);
+                self.next_attempt_at = None;
+                self.last_error = None;
+            }
+            Err(e) => {
+                log::warn!(
+                    "Échec de l'impression '{}' (essai {}/{}): {}",
+                    self.title,
+                    self.attempts,
+                    MAX_ATTEMPTS,
+                    e
+                );
+                self.last_error = Some(e.to_string());
+                if self.attempts >= MAX_ATTEMPTS {
+                    self.status = PrintJobStatus::Failed;
+                    self.next_attempt_at = None;
+                } else {
+                    self.status = PrintJobStatus::Retrying;
+                    self.next_attempt_at = Some(now + retry_delay(self.attempts));
+                }
+            }
+        }
+    }
+}
+
+/// Délai avant le nouvel essai qui suit l'échec n° `attempts` : 10 s, 20 s, 40 s... jusqu'à 10 min.
+fn retry_delay(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    Duration::seconds((FIRST_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Répertoire de spool : un fichier d'état et un fichier de données par travail.
+struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    fn open(dir: &Path) -> AppResult<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn job_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn data_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.prn", id))
+    }
+
+    /// Écrit l'état du travail via un fichier temporaire, pour ne jamais laisser un JSON tronqué.
+    fn save(&self, job: &PrintJob) -> AppResult<()> {
+        let data = serde_json::to_string_pretty(job).map_err(io::Error::from)?;
+        let tmp_path = self.dir.join(format!("{}.json.tmp", job.id));
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, self.job_path(job.id))?;
+        Ok(())
+    }
+
+    fn load(&self, id: Uuid) -> AppResult<PrintJob> {
+        let data = fs::read_to_string(self.job_path(id)).map_err(|_| {
+            AppError::ValidationError(format!("Travail d'impression {} introuvable", id))
+        })?;
+        Ok(serde_json::from_str(&data).map_err(io::Error::from)?)
+    }
+
+    /// Tous les travaux du spool, du plus récent au plus ancien ; les fichiers illisibles sont ignorés.
+    fn jobs(&self) -> AppResult<Vec<PrintJob>> {
+        let mut jobs: Vec<PrintJob> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .map_err(AppError::from)
+                .and_then(|data| serde_json::from_str(&data).map_err(|e| io::Error::from(e).into()))
+            {
+                Ok(job) => jobs.push(job),
+                Err(e) => log::warn!("Travail d'impression illisible {}: {}", path.display(), e),
+            }
+        }
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        Ok(jobs)
+    }
+
+    fn remove(&self, id: Uuid) {
+        let _ = fs::remove_file(self.job_path(id));
+        let _ = fs::remove_file(self.data_path(id));
+    }
+
+    /// Ajoute un travail à la file, déjà marqué « en cours » s'il est envoyé aussitôt.
+    fn enqueue(
+        &self,
+        title: &str,
+        file_suffix: &str,
+        bytes: &[u8],
+        printer_name: &str,
+        status: PrintJobStatus,
+    ) -> AppResult<PrintJob> {
+        let job = PrintJob {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            printer_name: printer_name.to_string(),
+            file_suffix: file_suffix.to_string(),
+            status,
+            attempts: 0,
+            last_error: None,
+            created_at: Utc::now(),
+            next_attempt_at: None,
+            printed_at: None,
+        };
+        fs::write(self.data_path(job.id), bytes)?;
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    /// Envoie un travail marqué « en cours » puis enregistre le résultat.
+    /// Le verrou n'est pas tenu pendant l'envoi, qui peut attendre l'imprimante.
+    fn send(
+        &self,
+        job: &PrintJob,
+        send: &impl Fn(&PrintJob, &[u8]) -> AppResult<()>,
+    ) -> AppResult<PrintJob> {
+        let result = fs::read(self.data_path(job.id))
+            .map_err(AppError::from)
+            .and_then(|bytes| send(job, &bytes));
+
+        let _guard = lock();
+        let mut job = self.load(job.id)?;
+        job.record_attempt(result, Utc::now());
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    /// Envoie les travaux dont l'essai est dû ; renvoie le nombre de travaux traités.
+    /// Un travail dont le résultat n'a pu être enregistré repasse « en attente » sans
+    /// bloquer les suivants.
+    fn process_due(
+        &self,
+        now: DateTime<Utc>,
+        send: &impl Fn(&PrintJob, &[u8]) -> AppResult<()>,
+    ) -> AppResult<usize> {
+        let due = {
+            let _guard = lock();
+            let mut due = self
+                .jobs()?
+                .into_iter()
+                .filter(|job| job.is_due(now))
+                .collect::<Vec<_>>();
+            // Les plus anciens d'abord, pour respecter l'ordre des tickets
+            due.reverse();
+            for job in &mut due {
+                job.status = PrintJobStatus::Printing;
+                self.save(job)?;
+            }
+            due
+        };
+
+        for job in &due {
+            if let Err(e) = self.send(job, send) {
+                log::error!("Travail d'impression '{}' non enregistré: {}", job.title, e);
+                self.release(job.id);
+            }
+        }
+        Ok(due.len())
+    }
+
+    /// Remet « en attente » un travail resté « en cours », s'il est encore dans le spool.
+    fn release(&self, id: Uuid) {
+        let _guard = lock();
+        if let Ok(mut job) = self.load(id)
+            && job.status == PrintJobStatus::Printing
+        {
+            job.status = PrintJobStatus::Pending;
+            if let Err(e) = self.save(&job) {
+                log::error!("Travail d'impression {} resté en cours: {}", id, e);
+            }
+        }
+    }
+
+    /// Au démarrage : un travail resté « en cours » a été interrompu par l'arrêt de l'application.
+    fn recover(&self) -> AppResult<()> {
+        let _guard = lock();
+        for mut job in self.jobs()? {
+            if job.status == PrintJobStatus::Printing {
+                job.status = PrintJobStatus::Pending;
+                self.save(&job)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Supprime les travaux imprimés ou annulés depuis plus de 24 h.
+    fn purge_finished(&self, now: DateTime<Utc>) -> AppResult<()> {
+        let _guard = lock();
+        let limit = now - Duration::hours(KEEP_FINISHED_HOURS);
+        for job in self.jobs()? {
+            let finished = matches!(
+                job.status,
+                PrintJobStatus::Printed | PrintJobStatus::Cancelled
+            );
+            if finished && job.printed_at.unwrap_or(job.created_at) < limit {
+                self.remove(job.id);
+            }
+        }
+        Ok(())
+    }
+
+    fn reprint(&self, id: Uuid) -> AppResult<()> {
+        let _guard = lock();
+        let mut job = self.load(id)?;
+        if !job.can_reprint() {
+            return Err(AppError::ValidationError(
+                "Ce travail est en cours d'impression".to_string(),
+            ));
+        }
+        job.status = PrintJobStatus::Pending;
+        job.attempts = 0;
+        job.last_error = None;
+        job.next_attempt_at = None;
+        self.save(&job)
+    }
+
+    fn cancel(&self, id: Uuid) -> AppResult<()> {
+        let _guard = lock();
+        let mut job = self.load(id)?;
+        if !job.can_cancel() {
+            return Err(AppError::ValidationError(format!(
+                "Un travail « {} » ne peut pas être annulé",
+                job.status.label()
+            )));
+        }
+        job.status = PrintJobStatus::Cancelled;
+        job.next_attempt_at = None;
+        self.save(&job)
+    }
+}
+
+fn spool() -> AppResult<Spool> {
+    Spool::open(Path::new(SPOOL_DIR))
+}
+
+/// Envoi d'un travail à l'imprimante de la configuration qui porte son nom.
+fn send_to_printer(job: &PrintJob, bytes: &[u8]) -> AppResult<()> {
+    let printer = printer_config::load_printers()
+        .into_iter()
+        .find(|p| p.name == job.printer_name)
+        .ok_or_else(|| {
+            AppError::PrintingError(format!("Imprimante '{}' introuvable", job.printer_name))
+        })?;
+    printing_service::send_raw(bytes, &job.file_suffix, &printer)
+}
+
+/// Place un document dans la file et tente aussitôt de l'imprimer.
+/// Le travail renvoyé est « Imprimé », ou « Nouvel essai programmé » si l'imprimante n'a pas répondu.
+pub fn submit(
+    title: &str,
+    file_suffix: &str,
+    document: &EscPosDocument,
+    printer: &PrinterConfig,
+) -> AppResult<PrintJob> {
+    let spool = spool()?;
+    let job = {
+        let _guard = lock();
+        spool.enqueue(
+            title,
+            file_suffix,
+            document.as_bytes(),
+            &printer.name,
+            PrintJobStatus::Printing,
+        )?
+    };
+    spool.send(&job, &|job: &PrintJob, bytes: &[u8]| {
+        printing_service::send_raw(bytes, &job.file_suffix, printer)
+    })
+}
+
+/// Travaux de la file, du plus récent au plus ancien.
+pub fn list_jobs() -> AppResult<Vec<PrintJob>> {
+    let spool = spool()?;
+    let _guard = lock();
+    spool.jobs()
+}
+
+/// Relance un travail (même imprimé ou annulé) au prochain passage du thread d'impression.
+pub fn reprint(id: Uuid) -> AppResult<()> {
+    spool()?.reprint(id)
+}
+
+pub fn cancel(id: Uuid) -> AppResult<()> {
+    spool()?.cancel(id)
+}
+
+/// Démarre le thread qui envoie les travaux en attente ; `on_change` est appelé après chaque
+/// passage qui a traité au moins un travail. Un nouvel appel (après une reconnexion) remplace
+/// seulement la notification.
+pub fn start_worker(on_change: impl Fn() + Send + 'static) {
+    *ON_CHANGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(on_change));
+    if WORKER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let spawned = thread::Builder::new()
+        .name("print-queue".to_string())
+        .spawn(move || {
+            let spool = match spool() {
+                Ok(spool) => spool,
+                Err(e) => {
+                    log::error!("File d'impression indisponible: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = spool
+                .recover()
+                .and_then(|_| spool.purge_finished(Utc::now()))
+            {
+                log::error!("Erreur lors de la reprise de la file d'impression: {}", e);
+            }
+
+            loop {
+                match spool.process_due(Utc::now(), &send_to_printer) {
+                    Ok(0) => {}
+                    Ok(count) => {
+                        log::info!("{} travail(aux) d'impression traité(s)", count);
+                        if let Some(on_change) =
+                            ON_CHANGE.lock().unwrap_or_else(|e| e.into_inner()).as_ref()
+                        {
+                            on_change();
+                        }
+                    }
+                    Err(e) => log::error!("Erreur de la file d'impression: {}", e),
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    if let Err(e) = spawned {
+        WORKER_STARTED.store(false, Ordering::SeqCst);
+        log::error!("Impossible de démarrer la file d'impression: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn temp_spool() -> Spool {
+        let dir = std::env::temp_dir().join(format!("print_spool_test_{}", Uuid::new_v4()));
+        Spool::open(&dir).unwrap()
+    }
+
+    fn offline(_: &PrintJob, _: &[u8]) -> AppResult<()> {
+        Err(AppError::PrintingError("Connection refused".to_string()))
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_limit() {
+        assert_eq!(retry_delay(1), Duration::seconds(10));
+        assert_eq!(retry_delay(2), Duration::seconds(20));
+        assert_eq!(retry_delay(4), Duration::seconds(80));
+        assert_eq!(retry_delay(7), Duration::seconds(600));
+        assert_eq!(retry_delay(40), Duration::seconds(600));
+    }
+
+    #[test]
+    fn test_offline_printer_is_retried_then_prints() {
+        let spool = temp_spool();
+        let job = spool
+            .enqueue(
+                "Ticket V-000042",
+                "receipt",
+                b"\x1B@",
+                "Caisse",
+                PrintJobStatus::Pending,
+            )
+            .unwrap();
+        let now = Utc::now();
+
+        assert_eq!(spool.process_due(now, &offline).unwrap(), 1);
+        let failed = spool.load(job.id).unwrap();
+        assert_eq!(failed.status, PrintJobStatus::Retrying);
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.last_error.is_some());
+        assert!(failed.next_attempt_at.unwrap() > now);
+
+        // Rien n'est renvoyé avant l'échéance
+        assert_eq!(spool.process_due(now, &offline).unwrap(), 0);
+
+        let received = Cell::new(0);
+        let online = |_: &PrintJob, bytes: &[u8]| {
+            received.set(bytes.len());
+            Ok(())
+        };
+        let later = failed.next_attempt_at.unwrap();
+        assert_eq!(spool.process_due(later, &online).unwrap(), 1);
+        let printed = spool.load(job.id).unwrap();
+        assert_eq!(printed.status, PrintJobStatus::Printed);
+        assert_eq!(printed.attempts, 2);
+        assert_eq!(printed.last_error, None);
+        assert_eq!(received.get(), 2);
+    }
+
+    #[test]
+    fn test_job_fails_after_max_attempts_and_can_be_reprinted_or_cancelled() {
+        let spool = temp_spool();
+        let job = spool
+            .enqueue(
+                "Rapport Z",
+                "z_report",
+                b"\x1B@",
+                "Caisse",
+                PrintJobStatus::Pending,
+            )
+            .unwrap();
+
+        let mut now = Utc::now();
+        for _ in 0..MAX_ATTEMPTS {
+            spool.process_due(now, &offline).unwrap();
+            now += Duration::seconds(MAX_RETRY_DELAY_SECS);
+        }
+        let failed = spool.load(job.id).unwrap();
+        assert_eq!(failed.status, PrintJobStatus::Failed);
+        assert_eq!(spool.process_due(now, &offline).unwrap(), 0);
+
+        spool.reprint(job.id).unwrap();
+        let pending = spool.load(job.id).unwrap();
+        assert_eq!(pending.status, PrintJobStatus::Pending);
+        assert_eq!(pending.attempts, 0);
+
+        spool.cancel(job.id).unwrap();
+        assert_eq!(
+            spool.load(job.id).unwrap().status,
+            PrintJobStatus::Cancelled
+        );
+        assert!(spool.cancel(job.id).is_err());
+        assert_eq!(spool.process_due(now, &offline).unwrap(), 0);
+    }
+
+    /// Sans accusé de réception de l'imprimante, le ticket reste dans la file : c'est le
+    /// cas d'une imprimante USB débranchée ou d'une file système inexistante.
+    #[test]
+    fn test_unreachable_usb_and_system_printers_are_retried() {
+        let usb = PrinterConfig {
+            name: "Caisse USB".to_string(),
+            port: "USB001".to_string(),
+            printer_type: printer_config::PrinterType::USB,
+            paper_width: 48,
+            is_default: false,
+            serial: Default::default(),
+            usb: printer_config::UsbSettings {
+                vendor_id: 0xFFFF,
+                product_id: 0xFFFF,
+                endpoint: None,
+            },
+        };
+        let mut printers = vec![usb];
+        // Sous Windows, `copy` vers un nom inconnu crée un fichier au lieu d'échouer
+        #[cfg(not(windows))]
+        printers.push(PrinterConfig {
+            name: "Caisse système".to_string(),
+            port: format!("file-inexistante-{}", Uuid::new_v4()),
+            printer_type: printer_config::PrinterType::Windows,
+            paper_width: 48,
+            is_default: false,
+            serial: Default::default(),
+            usb: Default::default(),
+        });
+
+        for printer in &printers {
+            let spool = temp_spool();
+            let job = spool
+                .enqueue(
+                    "Ticket V-000044",
+                    "receipt",
+                    b"\x1B@",
+                    &printer.name,
+                    PrintJobStatus::Pending,
+                )
+                .unwrap();
+            let send = |job: &PrintJob, bytes: &[u8]| {
+                printing_service::send_raw(bytes, &job.file_suffix, printer)
+            };
+
+            assert_eq!(spool.process_due(Utc::now(), &send).unwrap(), 1);
+            let job = spool.load(job.id).unwrap();
+            assert_eq!(job.status, PrintJobStatus::Retrying, "{}", printer.name);
+            assert!(job.last_error.is_some());
+        }
+    }
+
+    /// Un travail dont le résultat ne peut être enregistré n'empêche pas l'envoi des suivants.
+    #[test]
+    fn test_unsaved_job_does_not_block_the_next_ones() {
+        let spool = temp_spool();
+        let first = spool
+            .enqueue(
+                "Ticket V-000045",
+                "receipt",
+                b"\x1B@",
+                "Caisse",
+                PrintJobStatus::Pending,
+            )
+            .unwrap();
+        let second = spool
+            .enqueue(
+                "Ticket V-000046",
+                "receipt",
+                b"\x1B@",
+                "Caisse",
+                PrintJobStatus::Pending,
+            )
+            .unwrap();
+        // Le fichier du premier travail disparaît pendant son envoi
+        let send = |job: &PrintJob, _: &[u8]| {
+            if job.id == first.id {
+                spool.remove(job.id);
+            }
+            Ok(())
+        };
+
+        assert_eq!(spool.process_due(Utc::now(), &send).unwrap(), 2);
+        assert!(spool.load(first.id).is_err());
+        assert_eq!(
+            spool.load(second.id).unwrap().status,
+            PrintJobStatus::Printed
+        );
+    }
+
+    #[test]
+    fn test_interrupted_job_is_recovered_from_disk() {
+        let spool = temp_spool();
+        let job = spool
+            .enqueue(
+                "Ticket V-000043",
+                "receipt",
+                b"\x1B@",
+                "Caisse",
+                PrintJobStatus::Printing,
+            )
+            .unwrap();
+
+        // Nouveau démarrage sur le même répertoire
+        let restarted = Spool::open(&spool.dir).unwrap();
+        restarted.recover().unwrap();
+        let jobs = restarted.jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, job.id);
+        assert_eq!(jobs[0].status, PrintJobStatus::Pending);
+    }
+}
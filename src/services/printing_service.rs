@@ -1,46 +1,58 @@
 // src/services/printing_service.rs
 
 use crate::{
-    config::printer_config::{PrinterConfig, PrinterType},
+    config::{
+        business_calendar::business_calendar,
+        printer_config::{
+            PrinterConfig, PrinterType, SerialFlowControl, SerialParity, UsbPrinterModel,
+            find_usb_printer_model,
+        },
+    },
     error::{AppError, AppResult},
-    models::Receipt,
+    models::{Receipt, ReceiptItem},
+    queries::ZReport,
+    services::{
+        escpos_document::{CodePage, Cut, EscPosDocument, Justify},
+        print_queue::{self, PrintJob},
+    },
 };
-use escpos_rs::{Instruction, Justification, PrintData, Printer, PrinterProfile, command::Font};
-use std::collections::HashMap;
-use std::fs::OpenOptions;
+use bigdecimal::BigDecimal;
+use escpos_rs::{Printer, PrinterProfile, command::Font};
+use rusb::{Direction, TransferType, UsbContext};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
 use std::io::{self, Write};
-use std::net::TcpStream;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
 
 // Le service d'impression ne gère plus la configuration, il ne fait qu'imprimer.
+// Chaque document est construit une seule fois en ESC/POS, puis envoyé tel quel
+// à l'imprimante, quel que soit son raccordement.
+
+/// Page de codes des tickets : PC858 couvre les accents français et le symbole euro.
+const CODE_PAGE: CodePage = CodePage::Pc858;
+
+/// Délai de connexion et d'écriture pour une imprimante réseau : une imprimante éteinte
+/// ne doit pas bloquer la file d'impression.
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Délai maximal d'écriture sur un port série (l'imprimante peut bloquer par contrôle de flux).
+const SERIAL_TIMEOUT: Duration = Duration::from_secs(5);
 
 fn create_printer_from_config(config: &PrinterConfig) -> AppResult<Option<Printer>> {
     let printer_profile = match config.printer_type {
         PrinterType::USB => {
-            // For USB printers, we need vendor and product IDs
-            // These should ideally be part of the PrinterConfig
-            // For now, using placeholder values - you'll need to configure these
-            PrinterProfile::usb_builder(0x04b8, 0x0202) // Example: Epson TM-T20
-                .with_font_width(Font::FontA, config.paper_width as u8)
-                .build()
-        }
-        PrinterType::Network => {
-            // For network printers, escpos-rs doesn't have a network_builder
-            // We'll need to handle network printing differently
-            // Fall back to file-based printing for now
-            return Ok(None);
-        }
-        PrinterType::Serial => {
-            // For serial printers, escpos-rs doesn't have a serial_builder
-            // We'll need to handle serial printing differently
-            // Fall back to file-based printing for now
-            return Ok(None);
-        }
-        PrinterType::Windows => {
-            // For Windows printers, we'll fall back to file-based printing
-            // since escpos-rs doesn't directly support Windows printer names
-            return Ok(None);
+            let usb = &config.usb;
+            let mut builder = PrinterProfile::usb_builder(usb.vendor_id, usb.product_id)
+                .with_font_width(Font::FontA, config.paper_width as u8);
+            if let Some(endpoint) = usb.endpoint {
+                builder = builder.with_endpoint(endpoint)?;
+            }
+            builder.build()
         }
+        // Les autres raccordements reçoivent les octets directement (voir `send_raw`)
+        PrinterType::Network | PrinterType::Serial | PrinterType::Windows => return Ok(None),
     };
 
     match Printer::new(printer_profile) {
@@ -52,178 +64,58 @@ fn create_printer_from_config(config: &PrinterConfig) -> AppResult<Option<Printe
     }
 }
 
-fn print_receipt_with_escpos(receipt: &Receipt, config: &PrinterConfig) -> AppResult<()> {
-    let printer = match create_printer_from_config(config)? {
-        Some(printer) => printer,
-        None => {
-            // Fall back to alternative printing methods
-            return print_receipt_alternative(receipt, config);
-        }
-    };
-
-    // Print header
-    let header_instruction =
-        Instruction::text("DEPOT BOISSONS", Font::FontB, Justification::Center, None);
-    printer.instruction(&header_instruction, None)?;
-
-    let subheader_instruction = Instruction::text(
-        "Votre Partenaire Fraîcheur",
-        Font::FontA,
-        Justification::Center,
-        None,
-    );
-    printer.instruction(&subheader_instruction, None)?;
-
-    // Print receipt info
-    printer.println("")?;
-    printer.println(&format!("Ticket N°: {}", receipt.sale_number))?;
-    printer.println(&format!("Date:      {}", receipt.date))?;
-    printer.println(&format!("Vendeur:   {}", receipt.seller_name))?;
-    printer.println("")?;
-
-    // Print separator
-    let separator = "-".repeat(config.paper_width as usize);
-    printer.println(&separator)?;
-
-    // Print items header
-    let header_line = format!(
-        "{:<w1$} {:>w2$} {:>w3$} {:>w4$}",
-        "PRODUIT",
-        "QTE",
-        "P.U.",
-        "TOTAL",
-        w1 = config.paper_width as usize - 20,
-        w2 = 3,
-        w3 = 7,
-        w4 = 8
-    );
-    printer.println(&header_line)?;
-    printer.println(&separator)?;
-
-    // Print items
-    for item in &receipt.items {
-        printer.println(&item.product_name)?;
-        let item_line = format!(
-            "{:>w1$} x {:>w2$} = {:>w3$}",
-            item.quantity,
-            item.unit_price,
-            item.total_price,
-            w1 = config.paper_width as usize - 22,
-            w2 = 8,
-            w3 = 10
-        );
-        printer.println(&item_line)?;
-    }
-
-    // Print total
-    printer.println(&separator)?;
-    printer.println("")?;
-
-    let total_instruction = Instruction::text(
-        &format!("TOTAL: {} XAF", receipt.total_amount),
-        Font::FontB,
-        Justification::Right,
-        None,
-    );
-    printer.instruction(&total_instruction, None)?;
-
-    printer.println("")?;
-    printer.println("")?;
-
-    // Print footer
-    let footer_instruction = Instruction::text(
-        "Merci pour votre achat !",
-        Font::FontA,
-        Justification::Center,
-        None,
-    );
-    printer.instruction(&footer_instruction, None)?;
-
-    printer.println("")?;
-    printer.println("")?;
-    printer.println("")?;
-
-    // Cut paper if supported
-    // Note: escpos-rs might have a cut method, check the documentation
-    printer.println("\x1D\x56\x00")?; // ESC/POS cut command
-
-    Ok(())
-}
-
-fn print_test_with_escpos(config: &PrinterConfig) -> AppResult<()> {
-    let printer = match create_printer_from_config(config)? {
-        Some(printer) => printer,
-        None => {
-            return print_test_alternative(config);
-        }
-    };
-
-    let test_instruction = Instruction::text(
-        "TEST D'IMPRESSION",
-        Font::FontB,
-        Justification::Center,
-        None,
-    );
-    printer.instruction(&test_instruction, None)?;
-
-    printer.println("")?;
-    printer.println(&format!("Imprimante: {}", config.name))?;
-    printer.println("")?;
-    printer.println("")?;
-    printer.println("")?;
-
-    // Cut paper
-    printer.println("\x1D\x56\x00")?;
-
-    Ok(())
-}
-
-// Alternative printing methods for network, serial, and Windows printers
-fn print_receipt_alternative(receipt: &Receipt, config: &PrinterConfig) -> AppResult<()> {
-    let content = format_receipt_as_text(receipt, config);
-
+/// Envoie les octets d'un document ESC/POS à l'imprimante configurée.
+/// Les imprimantes système (Windows / CUPS) passent par un fichier brut ; une imprimante
+/// injoignable renvoie toujours une erreur, pour que la file d'impression réessaie.
+pub fn send_raw(bytes: &[u8], file_suffix: &str, config: &PrinterConfig) -> AppResult<()> {
     match config.printer_type {
-        PrinterType::Network => print_to_network(&content, config),
-        PrinterType::Serial => print_to_serial(&content, config),
-        PrinterType::Windows => print_receipt_to_file(receipt, config),
-        _ => {
-            // Fallback to file
-            print_receipt_to_file(receipt, config)
-        }
-    }
-}
-
-fn print_test_alternative(config: &PrinterConfig) -> AppResult<()> {
-    let content = format!("TEST D'IMPRESSION\n\nImprimante: {}\n\n\n", config.name);
-
-    match config.printer_type {
-        PrinterType::Network => print_to_network(&content, config),
-        PrinterType::Serial => print_to_serial(&content, config),
-        PrinterType::Windows => print_test_to_file(config),
-        _ => {
-            // Fallback to file
-            print_test_to_file(config)
-        }
+        PrinterType::Network => send_to_network(bytes, config),
+        PrinterType::Serial => send_to_serial(bytes, config),
+        PrinterType::USB => match create_printer_from_config(config)? {
+            Some(printer) => {
+                printer.raw(bytes)?;
+                log::info!("Successfully printed to USB printer '{}'", config.name);
+                Ok(())
+            }
+            None => Err(AppError::PrintingError(format!(
+                "imprimante USB introuvable ({:04x}:{:04x})",
+                config.usb.vendor_id, config.usb.product_id
+            ))),
+        },
+        PrinterType::Windows => send_to_system_printer(bytes, file_suffix, config),
     }
 }
 
-fn print_to_network(content: &str, config: &PrinterConfig) -> AppResult<()> {
-    // Parse IP and port from config.port (e.g., "192.168.1.100:9100")
+/// Adresse IP:PORT d'une imprimante réseau (port 9100 par défaut).
+fn network_address(config: &PrinterConfig) -> String {
     let parts: Vec<&str> = config.port.split(':').collect();
-    let ip = parts.get(0).unwrap_or(&"192.168.1.100");
+    let ip = parts.first().unwrap_or(&"192.168.1.100");
     let port = parts
         .get(1)
         .unwrap_or(&"9100")
         .parse::<u16>()
         .unwrap_or(9100);
+    format!("{}:{}", ip, port)
+}
+
+fn connect_network(address: &str) -> io::Result<TcpStream> {
+    let socket = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Adresse invalide: {}", address),
+        )
+    })?;
+    let stream = TcpStream::connect_timeout(&socket, NETWORK_TIMEOUT)?;
+    stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+    Ok(stream)
+}
 
-    let address = format!("{}:{}", ip, port);
+fn send_to_network(bytes: &[u8], config: &PrinterConfig) -> AppResult<()> {
+    let address = network_address(config);
 
-    match TcpStream::connect(&address) {
+    match connect_network(&address) {
         Ok(mut stream) => {
-            // Send ESC/POS commands directly
-            let esc_pos_content = convert_to_escpos(content);
-            stream.write_all(esc_pos_content.as_bytes())?;
+            stream.write_all(bytes)?;
             stream.flush()?;
             log::info!("Successfully printed to network printer at {}", address);
             Ok(())
@@ -235,195 +127,381 @@ fn print_to_network(content: &str, config: &PrinterConfig) -> AppResult<()> {
     }
 }
 
-fn print_to_serial(content: &str, config: &PrinterConfig) -> AppResult<()> {
-    // For serial printing, we'll try to write directly to the serial port
-    match OpenOptions::new().write(true).open(&config.port) {
-        Ok(mut file) => {
-            let esc_pos_content = convert_to_escpos(content);
-            file.write_all(esc_pos_content.as_bytes())?;
-            file.flush()?;
-            log::info!("Successfully printed to serial printer at {}", config.port);
-            Ok(())
-        }
-        Err(e) => {
+/// Ouvre le port série avec les paramètres de liaison de l'imprimante.
+fn open_serial_port(config: &PrinterConfig) -> AppResult<Box<dyn SerialPort>> {
+    let settings = &config.serial;
+    let data_bits = match settings.data_bits {
+        5 => DataBits::Five,
+        6 => DataBits::Six,
+        7 => DataBits::Seven,
+        _ => DataBits::Eight,
+    };
+    let stop_bits = match settings.stop_bits {
+        2 => StopBits::Two,
+        _ => StopBits::One,
+    };
+    let parity = match settings.parity {
+        SerialParity::None => Parity::None,
+        SerialParity::Odd => Parity::Odd,
+        SerialParity::Even => Parity::Even,
+    };
+    let flow_control = match settings.flow_control {
+        SerialFlowControl::None => FlowControl::None,
+        SerialFlowControl::Software => FlowControl::Software,
+        SerialFlowControl::Hardware => FlowControl::Hardware,
+    };
+
+    serialport::new(&config.port, settings.baud_rate)
+        .data_bits(data_bits)
+        .stop_bits(stop_bits)
+        .parity(parity)
+        .flow_control(flow_control)
+        .timeout(SERIAL_TIMEOUT)
+        .open()
+        .map_err(|e| {
             log::error!("Failed to open serial port {}: {}", config.port, e);
-            Err(AppError::from(e))
-        }
-    }
+            AppError::from(e)
+        })
 }
 
-fn convert_to_escpos(content: &str) -> String {
-    // Convert plain text to ESC/POS commands
-    let mut esc_pos = String::new();
-
-    // Initialize printer
-    esc_pos.push_str("\x1B\x40"); // ESC @ - Initialize printer
-
-    // Add the content
-    esc_pos.push_str(content);
+fn send_to_serial(bytes: &[u8], config: &PrinterConfig) -> AppResult<()> {
+    let mut port = open_serial_port(config)?;
+    port.write_all(bytes)?;
+    port.flush()?;
+    log::info!(
+        "Successfully printed to serial printer at {} ({} bauds)",
+        config.port,
+        config.serial.baud_rate
+    );
+    Ok(())
+}
 
-    // Cut paper
-    esc_pos.push_str("\x1D\x56\x00"); // GS V 0 - Cut paper
+/// Passe par un fichier temporaire, supprimé après l'envoi (la file d'impression garde sa copie).
+fn send_to_system_printer(
+    bytes: &[u8],
+    file_suffix: &str,
+    config: &PrinterConfig,
+) -> AppResult<()> {
+    let path = std::env::temp_dir().join(format!("{}_{}.prn", config.name, file_suffix));
+    std::fs::write(&path, bytes)?;
+    let result = send_file_to_printer(&path, config);
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::warn!("Impossible de supprimer {}: {}", path.display(), e);
+    }
+    result
+}
 
-    esc_pos
+/// Ligne de consigne sous un article (emballages sortis et vides rendus), si l'article est consigné.
+fn write_item_deposit_line(document: &mut EscPosDocument, item: &ReceiptItem) {
+    if item.deposit_quantity_out == 0 && item.deposit_quantity_returned == 0 {
+        return;
+    }
+    document.columns(
+        &format!(
+            "  Cons. {} sortis {} rendus",
+            item.deposit_quantity_out, item.deposit_quantity_returned
+        ),
+        &format!("{:.0}", item.deposit_amount),
+    );
 }
 
-// Fallback methods for file-based printing (Windows or when direct printing fails)
-fn print_receipt_to_file(receipt: &Receipt, config: &PrinterConfig) -> AppResult<()> {
-    let content = format_receipt_as_text(receipt, config);
-    let filename = format!("{}_receipt.txt", config.name);
-    std::fs::write(&filename, content)?;
-    log::info!("Receipt saved to file: {}", filename);
+/// Consignes du ticket : total des consignes, montant à payer et vides encore dus.
+fn write_deposit_lines(document: &mut EscPosDocument, receipt: &Receipt) {
+    if receipt.deposit_total != BigDecimal::from(0) {
+        document.columns("CONSIGNES", &format!("{:.0}", receipt.deposit_total));
+        document.bold(true);
+        document.columns("A PAYER", &format!("{:.0}", receipt.amount_due));
+        document.bold(false);
+    }
+    if !receipt.empties_owed.is_empty() {
+        document.line("Vides dus:");
+        for empties in &receipt.empties_owed {
+            document.columns(
+                &format!("  {}", empties.packaging_description),
+                &empties.quantity.to_string(),
+            );
+        }
+    }
+}
 
-    // Try to send to printer via system command
-    send_file_to_printer(&filename, config)
+/// Règlement du ticket : un moyen de paiement par ligne, monnaie rendue et reste dû.
+fn write_payment_lines(document: &mut EscPosDocument, receipt: &Receipt) {
+    for payment in &receipt.payments {
+        document.columns(&payment.method, &format!("{:.0}", payment.amount));
+        if let Some(tendered) = &payment.cash_tendered
+            && payment.change_due > BigDecimal::from(0)
+        {
+            document.line(&format!(
+                "  Remis: {:.0}  Rendu: {:.0}",
+                tendered, payment.change_due
+            ));
+        }
+        if let Some(reference) = &payment.reference {
+            document.line(&format!("  Réf: {}", reference));
+        }
+    }
+    if receipt.balance_due > BigDecimal::from(0) {
+        document.bold(true);
+        document.columns("RESTE DU", &format!("{:.0}", receipt.balance_due));
+        document.bold(false);
+    }
 }
 
-fn print_test_to_file(config: &PrinterConfig) -> AppResult<()> {
-    let content = format!("TEST D'IMPRESSION\n\nImprimante: {}\n\n\n", config.name);
-    let filename = format!("{}_test.txt", config.name);
-    std::fs::write(&filename, content)?;
-    log::info!("Test page saved to file: {}", filename);
+/// Ticket de caisse d'une vente.
+fn receipt_document(receipt: &Receipt, width: usize) -> EscPosDocument {
+    let mut document = EscPosDocument::new(width, CODE_PAGE);
+
+    // En-tête
+    document.justify(Justify::Center);
+    document.bold(true);
+    document.size(2, 2);
+    document.line("DEPOT BOISSONS");
+    document.size(1, 1);
+    document.bold(false);
+    document.line("Votre Partenaire Fraîcheur");
+    document.justify(Justify::Left);
+    document.feed(1);
+
+    // Informations de la vente
+    document.line(&format!("Ticket N°: {}", receipt.sale_number));
+    document.line(&format!("Date:      {}", receipt.date));
+    document.line(&format!("Vendeur:   {}", receipt.seller_name));
+    if let Some(customer_name) = &receipt.customer_name {
+        document.line(&format!("Client:    {}", customer_name));
+    }
 
-    // Try to send to printer via system command
-    send_file_to_printer(&filename, config)
+    // Articles
+    document.separator();
+    document.bold(true);
+    document.columns("PRODUIT", "TOTAL");
+    document.bold(false);
+    document.separator();
+    for item in &receipt.items {
+        document.line(&item.product_name);
+        document.columns(
+            &format!("  {} x {:.0}", item.quantity, item.unit_price),
+            &format!("{:.0}", item.total_price),
+        );
+        write_item_deposit_line(&mut document, item);
+    }
+    document.separator();
+
+    // Total
+    document.bold(true);
+    document.size(1, 2);
+    document.columns("TOTAL", &format!("{:.0} XAF", receipt.total_amount));
+    document.size(1, 1);
+    document.bold(false);
+    write_deposit_lines(&mut document, receipt);
+    write_payment_lines(&mut document, receipt);
+
+    // Pied de ticket
+    document.feed(1);
+    document.justify(Justify::Center);
+    document.line("Merci pour votre achat !");
+    document.feed(3);
+    document.cut(Cut::Partial);
+    document
 }
 
-fn format_receipt_as_text(receipt: &Receipt, config: &PrinterConfig) -> String {
-    let width = config.paper_width as usize;
-    let mut content = String::new();
+/// Rapport Z (ou X pour une session encore ouverte) au format ticket.
+fn z_report_document(report: &ZReport, width: usize) -> EscPosDocument {
+    let calendar = business_calendar();
+    let mut document = EscPosDocument::new(width, CODE_PAGE);
 
-    // Header
-    content.push_str(&format!("{:^width$}\n", "DEPOT BOISSONS", width = width));
-    content.push_str(&format!(
-        "{:^width$}\n",
-        "Votre Partenaire Fraîcheur",
-        width = width
+    let title = if report.session.is_open() {
+        "RAPPORT X (CAISSE OUVERTE)"
+    } else {
+        "RAPPORT Z - CLOTURE"
+    };
+    document.justify(Justify::Center);
+    document.bold(true);
+    document.line("DEPOT BOISSONS");
+    document.line(title);
+    document.bold(false);
+    document.justify(Justify::Left);
+    document.feed(1);
+    document.line(&format!("Caissier:  {}", report.cashier_name));
+    document.line(&format!(
+        "Ouverture: {}",
+        calendar.format_local(report.session.opened_at, "%d/%m/%Y %H:%M")
     ));
-    content.push_str("\n");
-
-    // Receipt info
-    content.push_str(&format!("Ticket N°: {}\n", receipt.sale_number));
-    content.push_str(&format!("Date:      {}\n", receipt.date));
-    content.push_str(&format!("Vendeur:   {}\n", receipt.seller_name));
-    content.push_str("\n");
-
-    // Items
-    content.push_str(&format!("{}\n", "-".repeat(width)));
-    content.push_str(&format!(
-        "{:<w1$} {:>w2$} {:>w3$} {:>w4$}\n",
-        "PRODUIT",
-        "QTE",
-        "P.U.",
-        "TOTAL",
-        w1 = width - 20,
-        w2 = 3,
-        w3 = 7,
-        w4 = 8
+    document.line(&format!(
+        "{}{}",
+        if report.session.is_open() {
+            "Edition:   "
+        } else {
+            "Cloture:   "
+        },
+        calendar.format_local(report.period_end, "%d/%m/%Y %H:%M")
     ));
-    content.push_str(&format!("{}\n", "-".repeat(width)));
-
-    for item in &receipt.items {
-        content.push_str(&format!("{}\n", item.product_name));
-        content.push_str(&format!(
-            "{:>w1$} x {:>w2$} = {:>w3$}\n",
-            item.quantity,
-            item.unit_price,
-            item.total_price,
-            w1 = width - 22,
-            w2 = 8,
-            w3 = 10
-        ));
+    if let Some(closed_by) = &report.closed_by_name {
+        document.line(&format!("Cloturee par: {}", closed_by));
     }
 
-    // Total
-    content.push_str(&format!("{}\n", "-".repeat(width)));
-    content.push_str("\n");
-    content.push_str(&format!(
-        "{:>width$}\n",
-        format!("TOTAL: {} XAF", receipt.total_amount),
-        width = width
-    ));
-    content.push_str("\n\n");
+    document.separator();
+    document.columns(
+        &format!("VENTES ({})", report.sales_count),
+        &format!("{:.0}", report.sales_total),
+    );
+    document.separator();
+    document.bold(true);
+    document.line("ENCAISSEMENTS");
+    document.bold(false);
+    for tender in &report.tenders {
+        document.columns(
+            &format!("  {} ({})", tender.label, tender.count),
+            &format!("{:.0}", tender.amount),
+        );
+    }
 
-    // Footer
-    content.push_str(&format!(
-        "{:^width$}\n",
-        "Merci pour votre achat !",
-        width = width
-    ));
-    content.push_str("\n\n\n");
+    document.separator();
+    document.bold(true);
+    document.line("ESPECES");
+    document.bold(false);
+    document.columns(
+        "  Fond de caisse",
+        &format!("{:.0}", report.session.opening_float),
+    );
+    document.columns("  Ventes en especes", &format!("{:.0}", report.cash_sales));
+    document.columns(
+        "  Reglements clients",
+        &format!("{:.0}", report.customer_cash_payments),
+    );
+    document.columns(
+        "  Avoirs rembourses",
+        &format!("{:.0}", -&report.cash_refunds),
+    );
+    document.columns(
+        &format!("  Ventes annulees ({})", report.voided_sales),
+        &format!("{:.0}", -&report.void_refunds),
+    );
+    for movement in &report.movements {
+        let label = movement.kind().map(|k| k.label()).unwrap_or("?");
+        document.columns(
+            &format!("  {}: {}", label, movement.reason),
+            &format!("{:.0}", movement.signed_amount()),
+        );
+    }
+    document.bold(true);
+    document.columns("ATTENDU", &format!("{:.0}", report.expected_cash));
+    if let Some(counted) = &report.session.counted_cash {
+        document.columns("COMPTE", &format!("{:.0}", counted));
+    }
+    if let Some(variance) = report.variance() {
+        let variance = if variance > BigDecimal::from(0) {
+            format!("+{:.0}", variance)
+        } else {
+            format!("{:.0}", variance)
+        };
+        document.columns("ECART", &variance);
+    }
+    document.bold(false);
+    if let Some(note) = &report.session.closing_note {
+        document.line(&format!("Note: {}", note));
+    }
 
-    content
+    document.feed(2);
+    document.line("Signature caissier:");
+    document.feed(3);
+    document.line("Signature responsable:");
+    document.feed(3);
+    document.cut(Cut::Partial);
+    document
 }
 
-fn send_file_to_printer(filename: &str, config: &PrinterConfig) -> AppResult<()> {
-    match config.printer_type {
-        PrinterType::Windows => {
-            #[cfg(windows)]
-            {
-                let output = Command::new("copy")
-                    .arg(filename)
-                    .arg(&config.port)
-                    .arg("/B")
-                    .output();
-
-                match output {
-                    Ok(result) => {
-                        if result.status.success() {
-                            log::info!("File printed successfully to {}", config.port);
-                        } else {
-                            log::error!("Print error: {}", String::from_utf8_lossy(&result.stderr));
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to execute print command: {}", e);
-                    }
-                }
-            }
+/// Page de test : styles, accents et coupe, pour valider le raccordement et la page de codes.
+fn test_page_document(config: &PrinterConfig) -> EscPosDocument {
+    let mut document = EscPosDocument::new(config.paper_width as usize, CODE_PAGE);
+    document.justify(Justify::Center);
+    document.bold(true);
+    document.size(2, 2);
+    document.line("TEST D'IMPRESSION");
+    document.size(1, 1);
+    document.bold(false);
+    document.justify(Justify::Left);
+    document.feed(1);
+    document.line(&format!("Imprimante: {}", config.name));
+    document.line("Accents: àâçéèêëîïôùûü ÀÇÉÈ €");
+    document.separator();
+    document.feed(3);
+    document.cut(Cut::Partial);
+    document
+}
 
-            #[cfg(not(windows))]
-            {
-                let output = Command::new("lpr")
-                    .arg("-P")
-                    .arg(&config.port)
-                    .arg(filename)
-                    .output();
+/// Envoie un fichier brut à une imprimante système (`copy /B` sous Windows, `lpr` ailleurs).
+fn send_file_to_printer(path: &Path, config: &PrinterConfig) -> AppResult<()> {
+    #[cfg(windows)]
+    let output = Command::new("cmd")
+        .arg("/C")
+        .arg("copy")
+        .arg("/B")
+        .arg(path)
+        .arg(&config.port)
+        .output();
 
-                match output {
-                    Ok(result) => {
-                        if result.status.success() {
-                            log::info!("File printed successfully to {}", config.port);
-                        } else {
-                            log::error!("Print error: {}", String::from_utf8_lossy(&result.stderr));
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("lpr not available: {}", e);
-                    }
-                }
-            }
-        }
-        _ => {
-            // For other printer types, the file has already been created
-            // The escpos-rs library should handle direct printing
-            log::info!("File created: {}", filename);
-        }
+    #[cfg(not(windows))]
+    let output = Command::new("lpr")
+        .arg("-o")
+        .arg("raw")
+        .arg("-P")
+        .arg(&config.port)
+        .arg(path)
+        .output();
+
+    let output = output.map_err(|e| {
+        AppError::PrintingError(format!(
+            "impossible de lancer la commande d'impression: {}",
+            e
+        ))
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(AppError::PrintingError(if stderr.is_empty() {
+            format!("la commande d'impression a échoué ({})", output.status)
+        } else {
+            stderr
+        }));
     }
-
+    log::info!("File printed successfully to {}", config.port);
     Ok(())
 }
 
-/// Imprime un reçu en utilisant la configuration fournie.
-pub fn print_receipt(receipt: &Receipt, config: &PrinterConfig) -> AppResult<()> {
+/// Imprime un reçu via la file d'impression : il est réimprimé automatiquement
+/// si l'imprimante ne répond pas.
+pub fn print_receipt(receipt: &Receipt, config: &PrinterConfig) -> AppResult<PrintJob> {
     log::info!("Printing receipt for sale #{}", receipt.sale_number);
-    print_receipt_with_escpos(receipt, config)
+    let document = receipt_document(receipt, config.paper_width as usize);
+    print_queue::submit(
+        &format!("Ticket {}", receipt.sale_number),
+        "receipt",
+        &document,
+        config,
+    )
+}
+
+/// Imprime le rapport Z d'une session de caisse (rapport X si elle est encore ouverte),
+/// via la file d'impression.
+pub fn print_z_report(report: &ZReport, config: &PrinterConfig) -> AppResult<PrintJob> {
+    log::info!(
+        "Printing Z report for cash session {} ({})",
+        report.session.id,
+        report.cashier_name
+    );
+    let document = z_report_document(report, config.paper_width as usize);
+    print_queue::submit(
+        &format!("Rapport de caisse - {}", report.cashier_name),
+        "z_report",
+        &document,
+        config,
+    )
 }
 
 /// Imprime une page de test en utilisant la configuration fournie.
 pub fn print_test_page(config: &PrinterConfig) -> AppResult<()> {
     log::info!("Printing test page for printer '{}'", config.name);
-    print_test_with_escpos(config)
+    // La page de test n'est pas mise en file : le résultat doit s'afficher immédiatement
+    send_raw(test_page_document(config).as_bytes(), "test", config)
 }
 
 /// Vérifie si une imprimante est disponible
@@ -447,17 +525,9 @@ pub fn test_printer_connection(config: &PrinterConfig) -> AppResult<bool> {
         }
         PrinterType::Network => {
             // Test network connection
-            let parts: Vec<&str> = config.port.split(':').collect();
-            let ip = parts.get(0).unwrap_or(&"192.168.1.100");
-            let port = parts
-                .get(1)
-                .unwrap_or(&"9100")
-                .parse::<u16>()
-                .unwrap_or(9100);
+            let address = network_address(config);
 
-            let address = format!("{}:{}", ip, port);
-
-            match TcpStream::connect(&address) {
+            match connect_network(&address) {
                 Ok(_) => {
                     log::info!("Network printer connection successful");
                     Ok(true)
@@ -469,8 +539,8 @@ pub fn test_printer_connection(config: &PrinterConfig) -> AppResult<bool> {
             }
         }
         PrinterType::Serial => {
-            // Test serial connection
-            match OpenOptions::new().read(true).open(&config.port) {
+            // Le port doit s'ouvrir avec les paramètres de liaison configurés
+            match open_serial_port(config) {
                 Ok(_) => {
                     log::info!("Serial printer connection successful");
                     Ok(true)
@@ -511,6 +581,101 @@ pub fn test_printer_connection(config: &PrinterConfig) -> AppResult<bool> {
     }
 }
 
+/// Liste les ports série présents sur la machine (COM3, /dev/ttyUSB0...).
+pub fn list_serial_ports() -> Vec<String> {
+    match serialport::available_ports() {
+        Ok(ports) => ports.into_iter().map(|p| p.port_name).collect(),
+        Err(e) => {
+            log::warn!("Impossible d'énumérer les ports série: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Classe d'interface USB des imprimantes.
+const USB_PRINTER_CLASS: u8 = 0x07;
+
+/// Périphérique USB branché susceptible d'être une imprimante ticket.
+#[derive(Debug, Clone)]
+pub struct UsbPrinterDevice {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Premier point de sortie bulk de l'interface d'impression.
+    pub endpoint: Option<u8>,
+    /// Nom du produit annoncé par le périphérique, s'il a pu être lu.
+    pub description: Option<String>,
+    pub model: Option<&'static UsbPrinterModel>,
+}
+
+impl UsbPrinterDevice {
+    /// Nom affiché : modèle connu, sinon nom annoncé, sinon identifiants.
+    pub fn display_name(&self) -> String {
+        self.model
+            .map(|m| m.name.to_string())
+            .or_else(|| self.description.clone())
+            .unwrap_or_else(|| format!("{:04x}:{:04x}", self.vendor_id, self.product_id))
+    }
+}
+
+/// Liste les périphériques USB de classe imprimante ou figurant parmi les modèles connus.
+pub fn list_usb_printers() -> Vec<UsbPrinterDevice> {
+    let devices = match rusb::Context::new().and_then(|context| context.devices()) {
+        Ok(devices) => devices,
+        Err(e) => {
+            log::warn!("Impossible d'énumérer les périphériques USB: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut printers = Vec::new();
+    for device in devices.iter() {
+        let Ok(descriptor) = device.device_descriptor() else {
+            continue;
+        };
+        let model = find_usb_printer_model(descriptor.vendor_id(), descriptor.product_id());
+
+        // Interfaces d'impression (certains modèles génériques se déclarent "spécifique fabricant")
+        let mut is_printer = false;
+        let mut endpoint = None;
+        if let Ok(config) = device.active_config_descriptor() {
+            for interface in config.interfaces().flat_map(|i| i.descriptors()) {
+                let printer_interface = interface.class_code() == USB_PRINTER_CLASS;
+                if !printer_interface && model.is_none() {
+                    continue;
+                }
+                is_printer |= printer_interface;
+                endpoint = endpoint.or_else(|| {
+                    interface
+                        .endpoint_descriptors()
+                        .find(|e| {
+                            e.direction() == Direction::Out
+                                && e.transfer_type() == TransferType::Bulk
+                        })
+                        .map(|e| e.address())
+                });
+            }
+        }
+        if !is_printer && model.is_none() {
+            continue;
+        }
+
+        // La lecture du nom demande l'ouverture du périphérique, qui peut être refusée
+        let description = device
+            .open()
+            .ok()
+            .and_then(|handle| handle.read_product_string_ascii(&descriptor).ok());
+
+        printers.push(UsbPrinterDevice {
+            vendor_id: descriptor.vendor_id(),
+            product_id: descriptor.product_id(),
+            endpoint,
+            description,
+            model,
+        });
+    }
+    printers
+}
+
 /// Liste les imprimantes disponibles sur le système
 pub fn list_available_printers() -> AppResult<Vec<String>> {
     let mut printers = Vec::new();
@@ -550,3 +715,117 @@ pub fn list_available_printers() -> AppResult<Vec<String>> {
 
     Ok(printers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::printer_config::{SerialSettings, UsbSettings};
+    use crate::models::{ReceiptEmpties, ReceiptPayment};
+    use std::str::FromStr;
+
+    fn amount(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_receipt_golden_bytes() {
+        let receipt = Receipt {
+            sale_number: "V-000042".to_string(),
+            date: "14/03/2025 18:05".to_string(),
+            seller_name: "Awa".to_string(),
+            customer_name: Some("Bar Le Relais".to_string()),
+            items: vec![ReceiptItem {
+                product_name: "Castel Beer 65cl".to_string(),
+                packaging_description: "Casier de 12".to_string(),
+                quantity: 2,
+                unit_price: amount("7200.00"),
+                total_price: amount("14400.00"),
+                deposit_quantity_out: 2,
+                deposit_quantity_returned: 1,
+                deposit_amount: amount("3000.00"),
+            }],
+            total_amount: amount("14400.00"),
+            deposit_total: amount("3000.00"),
+            amount_due: amount("17400.00"),
+            empties_owed: vec![ReceiptEmpties {
+                packaging_description: "Casier de 12".to_string(),
+                quantity: 1,
+            }],
+            payments: vec![ReceiptPayment {
+                method: "Espèces".to_string(),
+                amount: amount("17400.00"),
+                cash_tendered: Some(amount("20000.00")),
+                change_due: amount("2600.00"),
+                reference: None,
+            }],
+            amount_paid: amount("17400.00"),
+            change_due: amount("2600.00"),
+            balance_due: amount("0"),
+            payment_status: "Payée".to_string(),
+        };
+
+        let expected: Vec<u8> = [
+            &b"\x1B@\x1Bt\x13\x1Ba\x01\x1BE\x01\x1D!\x11DEPOT BOISSONS\n"[..],
+            b"\x1D!\x00\x1BE\x00Votre Partenaire Fra\x8Ccheur\n",
+            b"\x1Ba\x00\x1Bd\x01Ticket N\xF8: V-000042\n",
+            b"Date:      14/03/2025 18:05\n",
+            b"Vendeur:   Awa\n",
+            b"Client:    Bar Le Relais\n",
+            b"--------------------------------\n",
+            b"\x1BE\x01PRODUIT                    TOTAL\n",
+            b"\x1BE\x00--------------------------------\n",
+            b"Castel Beer 65cl\n",
+            b"  2 x 7200                 14400\n",
+            b"  Cons. 2 sortis 1 rendus   3000\n",
+            b"--------------------------------\n",
+            b"\x1BE\x01\x1D!\x01TOTAL                  14400 XAF\n",
+            b"\x1D!\x00\x1BE\x00CONSIGNES                   3000\n",
+            b"\x1BE\x01A PAYER                    17400\n",
+            b"\x1BE\x00Vides dus:\n",
+            b"  Casier de 12                 1\n",
+            b"Esp\x8Aces                    17400\n",
+            b"  Remis: 20000  Rendu: 2600\n",
+            b"\x1Bd\x01\x1Ba\x01Merci pour votre achat !\n",
+            b"\x1Bd\x03\x1DV\x01",
+        ]
+        .concat();
+        assert_eq!(
+            receipt_document(&receipt, 32).as_bytes(),
+            expected.as_slice()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_serial_printer_receives_the_document_bytes() {
+        use serialport::TTYPort;
+        use std::io::Read;
+
+        // Un pseudo-terminal tient lieu d'imprimante : l'autre extrémité reçoit les octets.
+        // Le côté esclave reste ouvert, sans exclusivité : Linux perd les octets d'un pty fermé.
+        let (mut printer_side, mut port_side) = TTYPort::pair().expect("pseudo-terminal");
+        port_side
+            .set_exclusive(false)
+            .expect("accès partagé au pseudo-terminal");
+        let port = port_side.name().expect("nom du pseudo-terminal");
+
+        let config = PrinterConfig {
+            name: "Caisse série".to_string(),
+            port,
+            printer_type: PrinterType::Serial,
+            paper_width: 32,
+            is_default: false,
+            serial: SerialSettings {
+                baud_rate: 19200,
+                ..SerialSettings::default()
+            },
+            usb: UsbSettings::default(),
+        };
+        let document = test_page_document(&config);
+        send_raw(document.as_bytes(), "test", &config).unwrap();
+
+        let mut received = vec![0; document.as_bytes().len()];
+        printer_side.read_exact(&mut received).unwrap();
+        assert_eq!(received, document.as_bytes());
+    }
+}
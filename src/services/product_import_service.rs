@@ -0,0 +1,333 @@
+// src/services/product_import_service.rs
+
+use crate::error::{AppError, AppResult};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use calamine::{Reader, open_workbook_auto};
+use std::{fs, path::Path, str::FromStr};
+
+/// Ligne du fichier importé, une fois les cellules lues et converties.
+/// Une cellule vide vaut `None` : pour un produit existant, la valeur actuelle est conservée.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    /// Numéro de la ligne dans le fichier (l'en-tête est la ligne 1).
+    pub line: usize,
+    pub sku: Option<String>,
+    pub name: Option<String>,
+    pub packaging: Option<String>,
+    /// Stock compté, en unités de vente.
+    pub stock: Option<i32>,
+    pub price: Option<BigDecimal>,
+    pub cost: Option<BigDecimal>,
+}
+
+/// Erreur de validation rattachée à une ligne du fichier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Contenu d'un fichier d'import : les lignes valides et les erreurs ligne par ligne.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedImport {
+    pub file_name: String,
+    pub rows: Vec<ImportRow>,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Sku,
+    Name,
+    Packaging,
+    Stock,
+    Price,
+    Cost,
+}
+
+impl Field {
+    /// Reconnaît une colonne d'après son en-tête normalisé (minuscules, sans accents ni séparateurs).
+    fn from_header(header: &str) -> Option<Self> {
+        match normalize_header(header).as_str() {
+            "sku" | "ref" | "reference" | "code" | "codearticle" => Some(Field::Sku),
+            "nom" | "name" | "produit" | "designation" | "article" => Some(Field::Name),
+            "conditionnement" | "packaging" | "packagingdescription" | "emballage" => {
+                Some(Field::Packaging)
+            }
+            "stock" | "quantite" | "qte" | "inventaire" | "stockinsaleunits" => Some(Field::Stock),
+            "prix" | "prixvente" | "prixdevente" | "price" | "pricepersaleunit" => {
+                Some(Field::Price)
+            }
+            "prixderevient" | "prixrevient" | "cout" | "coutunitaire" | "prixdachat" | "cost"
+            | "costprice" => Some(Field::Cost),
+            _ => None,
+        }
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            other => other,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Lit un fichier CSV ou XLSX (première feuille) et valide chacune de ses lignes.
+pub fn read_import_file(path: &Path) -> AppResult<ParsedImport> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let table = match extension.as_str() {
+        "csv" | "txt" => read_csv_table(&decode_text(fs::read(path)?))?,
+        "xlsx" | "xlsm" | "xls" | "ods" => read_sheet_table(path)?,
+        _ => {
+            return Err(AppError::ValidationError(
+                "Format de fichier non pris en charge (CSV ou XLSX attendu)".to_string(),
+            ));
+        }
+    };
+    parse_table(file_name, &table)
+}
+
+/// Les CSV enregistrés par Excel ne sont pas toujours en UTF-8 : à défaut, on les lit en Latin-1.
+fn decode_text(bytes: Vec<u8>) -> String {
+    match String::from_utf8(bytes) {
+        Ok(text) => text.trim_start_matches('\u{feff}').to_string(),
+        Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+    }
+}
+
+/// Découpe un CSV en cellules. Le séparateur (`;`, `,` ou tabulation) est déduit de l'en-tête.
+fn read_csv_table(text: &str) -> AppResult<Vec<Vec<String>>> {
+    let header = text.lines().next().unwrap_or_default();
+    let delimiter = [b';', b',', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(char::from(*d)).count())
+        .unwrap_or(b';');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    reader
+        .records()
+        .map(|record| {
+            record
+                .map(|r| r.iter().map(str::to_string).collect())
+                .map_err(|e| AppError::ValidationError(format!("Fichier CSV illisible : {}", e)))
+        })
+        .collect()
+}
+
+fn read_sheet_table(path: &Path) -> AppResult<Vec<Vec<String>>> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| AppError::ValidationError(format!("Classeur illisible : {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| {
+            AppError::ValidationError("Le classeur ne contient aucune feuille".to_string())
+        })?
+        .map_err(|e| AppError::ValidationError(format!("Feuille illisible : {}", e)))?;
+    Ok(range
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect())
+}
+
+/// Interprète un tableau de cellules dont la première ligne est l'en-tête.
+/// Les colonnes inconnues sont ignorées ; les lignes vides sont sautées.
+pub fn parse_table(file_name: String, table: &[Vec<String>]) -> AppResult<ParsedImport> {
+    let Some((header, lines)) = table.split_first() else {
+        return Err(AppError::ValidationError("Le fichier est vide".to_string()));
+    };
+    let columns = header
+        .iter()
+        .map(|title| Field::from_header(title))
+        .collect::<Vec<_>>();
+    let has = |field: Field| columns.contains(&Some(field));
+    let identified = has(Field::Sku) || (has(Field::Name) && has(Field::Packaging));
+    if !identified {
+        return Err(AppError::ValidationError(
+            "Colonnes attendues : « sku », ou « nom » et « conditionnement »".to_string(),
+        ));
+    }
+
+    let mut parsed = ParsedImport {
+        file_name,
+        ..Default::default()
+    };
+    for (index, cells) in lines.iter().enumerate() {
+        if cells.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        match parse_row(index + 2, &columns, cells) {
+            Ok(row) => parsed.rows.push(row),
+            Err(message) => parsed.errors.push(ImportRowError {
+                line: index + 2,
+                message,
+            }),
+        }
+    }
+
+    if parsed.rows.is_empty() && parsed.errors.is_empty() {
+        return Err(AppError::ValidationError(
+            "Le fichier ne contient aucune ligne de produit".to_string(),
+        ));
+    }
+    Ok(parsed)
+}
+
+fn parse_row(
+    line: usize,
+    columns: &[Option<Field>],
+    cells: &[String],
+) -> Result<ImportRow, String> {
+    let mut row = ImportRow {
+        line,
+        sku: None,
+        name: None,
+        packaging: None,
+        stock: None,
+        price: None,
+        cost: None,
+    };
+    let mut problems = Vec::new();
+
+    for (field, cell) in columns.iter().zip(cells) {
+        let (Some(field), value) = (field, cell.trim()) else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        match field {
+            Field::Sku => row.sku = Some(value.to_string()),
+            Field::Name => row.name = Some(value.to_string()),
+            Field::Packaging => row.packaging = Some(value.to_string()),
+            Field::Stock => match parse_decimal(value).and_then(|v| v.is_integer().then_some(v)) {
+                Some(stock) if stock < BigDecimal::from(0) => {
+                    problems.push("le stock ne peut pas être négatif".to_string())
+                }
+                Some(stock) => row.stock = stock.to_i32(),
+                None => problems.push(format!("stock invalide « {} »", value)),
+            },
+            Field::Price => match parse_decimal(value) {
+                Some(price) if price < BigDecimal::from(0) => {
+                    problems.push("le prix ne peut pas être négatif".to_string())
+                }
+                Some(price) => row.price = Some(price),
+                None => problems.push(format!("prix invalide « {} »", value)),
+            },
+            Field::Cost => match parse_decimal(value) {
+                Some(cost) if cost < BigDecimal::from(0) => {
+                    problems.push("le prix de revient ne peut pas être négatif".to_string())
+                }
+                Some(cost) => row.cost = Some(cost),
+                None => problems.push(format!("prix de revient invalide « {} »", value)),
+            },
+        }
+    }
+
+    if row.sku.is_none() && (row.name.is_none() || row.packaging.is_none()) {
+        problems.push("référence (SKU) ou nom et conditionnement requis".to_string());
+    }
+    if problems.is_empty() {
+        Ok(row)
+    } else {
+        Err(problems.join(" ; "))
+    }
+}
+
+/// Lit un nombre saisi à la française ou non : « 1 500,50 », « 1500.5 », « 5 000 XAF ».
+fn parse_decimal(value: &str) -> Option<BigDecimal> {
+    let lowered = value.to_lowercase();
+    let digits = lowered
+        .trim_end_matches("xaf")
+        .trim_end_matches("fcfa")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let digits = if digits.contains('.') {
+        digits.replace(',', "")
+    } else {
+        digits.replace(',', ".")
+    };
+    BigDecimal::from_str(&digits).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(csv: &str) -> Vec<Vec<String>> {
+        read_csv_table(csv).unwrap()
+    }
+
+    #[test]
+    fn test_parse_french_csv() {
+        let csv = "Référence;Désignation;Conditionnement;Stock;Prix de vente;Prix de revient\n\
+                   ISEN-C12;Isenbeck;Casier 65cl de 12;20;5 500;4200,50\n\
+                   ;Supermont;Palette 1.5L de 12;;3000 XAF;\n\
+                   ;;;;;\n";
+        let parsed = parse_table("catalogue.csv".to_string(), &table(csv)).unwrap();
+
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.rows.len(), 2);
+        let first = &parsed.rows[0];
+        assert_eq!(first.line, 2);
+        assert_eq!(first.sku.as_deref(), Some("ISEN-C12"));
+        assert_eq!(first.stock, Some(20));
+        assert_eq!(first.price, Some(BigDecimal::from(5500)));
+        assert_eq!(first.cost, BigDecimal::from_str("4200.50").ok());
+        let second = &parsed.rows[1];
+        assert_eq!(second.sku, None);
+        assert_eq!(second.packaging.as_deref(), Some("Palette 1.5L de 12"));
+        assert_eq!(second.stock, None);
+        assert_eq!(second.price, Some(BigDecimal::from(3000)));
+    }
+
+    #[test]
+    fn test_row_errors_are_reported_by_line() {
+        let csv = "sku,name,packaging,stock,price\n\
+                   A-1,Isenbeck,Casier,2.5,100\n\
+                   ,Guinness,,3,abc\n\
+                   B-2,,,-4,100\n";
+        let parsed = parse_table("import.csv".to_string(), &table(csv)).unwrap();
+
+        assert!(parsed.rows.is_empty());
+        let lines = parsed.errors.iter().map(|e| e.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert!(parsed.errors[0].message.contains("stock invalide"));
+        assert!(parsed.errors[1].message.contains("prix invalide"));
+        assert!(
+            parsed.errors[1]
+                .message
+                .contains("nom et conditionnement requis")
+        );
+        assert!(parsed.errors[2].message.contains("négatif"));
+    }
+
+    #[test]
+    fn test_missing_identification_columns() {
+        let result = parse_table("stock.csv".to_string(), &table("nom;stock\nIsenbeck;3\n"));
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+}
@@ -1,21 +1,30 @@
 // src/services/report_generator_service.rs
 
 use crate::{
+    config::business_calendar::business_calendar,
     error::{AppError, AppResult},
-    queries::ReportData,
+    models::SaleStatus,
+    queries::{
+        Granularity, InventoryReport, MarginLine, ReportData, format_percent_change, percent_change,
+    },
+    services::pdf_layout::{Column, PdfReport},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use rust_xlsxwriter::{
+    Chart, ChartType, ExcelDateTime, Format, FormatBorder, Workbook, Worksheet, utility::cell_range,
 };
-use lopdf::content::{Content, Operation};
-use lopdf::{Document, Object, Stream, dictionary};
-use rust_xlsxwriter::{Format, Workbook};
 use std::fs;
 use std::path::PathBuf;
+use uuid::Uuid;
 
 /// Trouve un chemin de sauvegarde approprié pour les rapports.
-fn get_save_path(extension: &str) -> AppResult<String> {
+fn get_save_path(prefix: &str, extension: &str) -> AppResult<String> {
     let base_path = dirs::download_dir().unwrap_or_else(|| PathBuf::from("rapports"));
     fs::create_dir_all(&base_path)?;
     let file_name = format!(
-        "rapport_ventes_{}.{}",
+        "{}_{}.{}",
+        prefix,
         chrono::Local::now().format("%Y%m%d_%H%M%S"),
         extension
     );
@@ -23,350 +32,809 @@ fn get_save_path(extension: &str) -> AppResult<String> {
     Ok(full_path.to_str().unwrap_or("rapport.fallback").to_string())
 }
 
-/// Convertit une chaîne UTF-8 en bytes Latin-1 pour PDF
-fn encode_text_for_pdf_bytes(text: &str) -> Vec<u8> {
-    text.chars()
-        .map(|c| match c {
-            'é' => 0xE9_u8,
-            'è' => 0xE8_u8,
-            'ê' => 0xEA_u8,
-            'ë' => 0xEB_u8,
-            'à' => 0xE0_u8,
-            'â' => 0xE2_u8,
-            'ä' => 0xE4_u8,
-            'ç' => 0xE7_u8,
-            'î' => 0xEE_u8,
-            'ï' => 0xEF_u8,
-            'ô' => 0xF4_u8,
-            'ö' => 0xF6_u8,
-            'ù' => 0xF9_u8,
-            'û' => 0xFB_u8,
-            'ü' => 0xFC_u8,
-            'ÿ' => 0xFF_u8,
-            'É' => 0xC9_u8,
-            'È' => 0xC8_u8,
-            'Ê' => 0xCA_u8,
-            'Ë' => 0xCB_u8,
-            'À' => 0xC0_u8,
-            'Â' => 0xC2_u8,
-            'Ä' => 0xC4_u8,
-            'Ç' => 0xC7_u8,
-            'Î' => 0xCE_u8,
-            'Ï' => 0xCF_u8,
-            'Ô' => 0xD4_u8,
-            'Ö' => 0xD6_u8,
-            'Ù' => 0xD9_u8,
-            'Û' => 0xDB_u8,
-            'Ü' => 0xDC_u8,
-            'Ÿ' => 0x9F_u8, // Ÿ en Windows-1252
-            _ => {
-                if c.is_ascii() {
-                    c as u8
-                } else {
-                    b'?' // Caractère de remplacement pour les autres
-                }
-            }
-        })
-        .collect()
+/// Indicateur clé du rapport, avec sa valeur sur la période de comparaison.
+struct KpiRow {
+    label: &'static str,
+    current: BigDecimal,
+    previous: Option<BigDecimal>,
+    is_money: bool,
 }
 
-/// Alternative : conversion des caractères spéciaux en représentation octal
-fn encode_text_for_pdf_octal(text: &str) -> Vec<u8> {
-    let mut result = Vec::new();
-    for c in text.chars() {
-        match c {
-            'é' => result.push(0xE9),
-            'è' => result.push(0xE8),
-            'ê' => result.push(0xEA),
-            'ë' => result.push(0xEB),
-            'à' => result.push(0xE0),
-            'â' => result.push(0xE2),
-            'ä' => result.push(0xE4),
-            'ç' => result.push(0xE7),
-            'î' => result.push(0xEE),
-            'ï' => result.push(0xEF),
-            'ô' => result.push(0xF4),
-            'ö' => result.push(0xF6),
-            'ù' => result.push(0xF9),
-            'û' => result.push(0xFB),
-            'ü' => result.push(0xFC),
-            'ÿ' => result.push(0xFF),
-            'É' => result.push(0xC9),
-            'È' => result.push(0xC8),
-            'Ê' => result.push(0xCA),
-            'Ë' => result.push(0xCB),
-            'À' => result.push(0xC0),
-            'Â' => result.push(0xC2),
-            'Ä' => result.push(0xC4),
-            'Ç' => result.push(0xC7),
-            'Î' => result.push(0xCE),
-            'Ï' => result.push(0xCF),
-            'Ô' => result.push(0xD4),
-            'Ö' => result.push(0xD6),
-            'Ù' => result.push(0xD9),
-            'Û' => result.push(0xDB),
-            'Ü' => result.push(0xDC),
-            _ => {
-                if c.is_ascii() {
-                    result.push(c as u8);
-                } else {
-                    result.push(b'?'); // Caractère de remplacement
-                }
-            }
+impl KpiRow {
+    fn display(&self, value: &BigDecimal) -> String {
+        if self.is_money {
+            format!("{:.0} XAF", value)
+        } else {
+            format!("{:.0}", value)
         }
     }
-    result
+}
+
+/// Indicateurs clés du rapport, accompagnés de la comparaison si elle a été demandée.
+fn kpi_rows(data: &ReportData) -> Vec<KpiRow> {
+    let current = &data.totals;
+    let previous = data.comparison.as_ref().map(|c| &c.totals);
+    let row = |label, current, previous, is_money| KpiRow {
+        label,
+        current,
+        previous,
+        is_money,
+    };
+    vec![
+        row(
+            "Chiffre d'Affaires",
+            current.total_revenue.clone(),
+            previous.map(|p| p.total_revenue.clone()),
+            true,
+        ),
+        row(
+            "Nombre de Ventes",
+            BigDecimal::from(current.total_sales),
+            previous.map(|p| BigDecimal::from(p.total_sales)),
+            false,
+        ),
+        row(
+            "Panier Moyen",
+            current.average_basket(),
+            previous.map(|p| p.average_basket()),
+            true,
+        ),
+        row(
+            "Avoirs (retours)",
+            current.total_returns.clone(),
+            previous.map(|p| p.total_returns.clone()),
+            true,
+        ),
+        row(
+            "Ventes Annulées",
+            BigDecimal::from(current.voided_sales),
+            previous.map(|p| BigDecimal::from(p.voided_sales)),
+            false,
+        ),
+        row(
+            "Marge Brute",
+            current.margin.margin(),
+            previous.map(|p| p.margin.margin()),
+            true,
+        ),
+    ]
 }
 
 // --- GÉNÉRATION PDF ---
 
+fn money(value: &BigDecimal) -> String {
+    format!("{:.0} XAF", value)
+}
+
+fn margin_rate_label(line: &MarginLine) -> String {
+    line.margin_rate()
+        .map(|rate| format!("{:.1} %", rate))
+        .unwrap_or_else(|| "n.d.".to_string())
+}
+
+/// Libellé complet d'un regroupement de la série temporelle.
+fn bucket_title(granularity: Granularity, start: NaiveDate) -> String {
+    match granularity {
+        Granularity::Day => start.format("%d/%m/%Y").to_string(),
+        Granularity::Week => format!(
+            "{} — semaine du {}",
+            granularity.bucket_label(start),
+            start.format("%d/%m/%Y")
+        ),
+        Granularity::Month => start.format("%m/%Y").to_string(),
+    }
+}
+
+/// Tableau de marges (produit, conditionnement ou vendeur).
+fn add_margin_table(report: &mut PdfReport, title: &str, label: &str, lines: &[MarginLine]) {
+    report.section(title);
+    let columns = [
+        Column::left(label, 4.0),
+        Column::right("Quantité", 1.2),
+        Column::right("CA", 2.0),
+        Column::right("Coût", 2.0),
+        Column::right("Marge", 2.0),
+        Column::right("Taux", 1.2),
+    ];
+    let rows = lines
+        .iter()
+        .map(|line| {
+            vec![
+                line.label.clone(),
+                line.quantity.to_string(),
+                money(&(&line.revenue + &line.uncosted_revenue)),
+                money(&line.cost),
+                money(&line.margin()),
+                margin_rate_label(line),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+}
+
 pub fn generate_pdf_report(data: &ReportData) -> AppResult<String> {
-    let file_path = get_save_path("pdf")?;
-
-    // Créer un nouveau document PDF
-    let mut doc = Document::with_version("1.7");
-
-    // Créer l'arborescence des pages
-    let pages_id = doc.new_object_id();
-
-    // Ajouter les polices avec encodage WinAnsi
-    let font_regular = doc.add_object(dictionary! {
-        "Type" => "Font",
-        "Subtype" => "Type1",
-        "BaseFont" => "Helvetica",
-        "Encoding" => "WinAnsiEncoding",
-    });
-
-    let font_bold = doc.add_object(dictionary! {
-        "Type" => "Font",
-        "Subtype" => "Type1",
-        "BaseFont" => "Helvetica-Bold",
-        "Encoding" => "WinAnsiEncoding",
-    });
-
-    // Ressources pour les polices
-    let resources_id = doc.add_object(dictionary! {
-        "Font" => dictionary! {
-            "F1" => font_regular,
-            "F2" => font_bold,
-        },
-    });
-
-    // Créer le contenu de la page
-    let mut content = Content {
-        operations: Vec::new(),
-    };
+    let file_path = get_save_path("rapport_ventes", "pdf")?;
+    let calendar = business_calendar();
+    let mut report = PdfReport::new("Rapport de Ventes")?;
 
-    // Fonction helper pour ajouter du texte avec gestion des accents
-    fn add_text(content: &mut Content, text: &str, font: &str, size: f64, x: f64, y: f64) {
-        // Encoder le texte en bytes pour le PDF
-        let encoded_bytes = encode_text_for_pdf_bytes(text);
-
-        content.operations.push(Operation::new("BT", vec![]));
-        content
-            .operations
-            .push(Operation::new("Tf", vec![font.into(), size.into()]));
-        content
-            .operations
-            .push(Operation::new("Td", vec![x.into(), y.into()]));
-
-        // Créer un objet String à partir des bytes
-        let text_object = Object::String(encoded_bytes, lopdf::StringFormat::Literal);
-        content
-            .operations
-            .push(Operation::new("Tj", vec![text_object]));
-        content.operations.push(Operation::new("ET", vec![]));
-    }
-
-    // Alternative avec bytes pour un contrôle plus fin
-    fn add_text_with_bytes(
-        content: &mut Content,
-        text: &str,
-        font: &str,
-        size: f64,
-        x: f64,
-        y: f64,
-    ) {
-        let encoded_bytes = encode_text_for_pdf_octal(text);
-
-        content.operations.push(Operation::new("BT", vec![]));
-        content
-            .operations
-            .push(Operation::new("Tf", vec![font.into(), size.into()]));
-        content
-            .operations
-            .push(Operation::new("Td", vec![x.into(), y.into()]));
-
-        // Créer un objet string à partir des bytes
-        let text_object = Object::String(encoded_bytes, lopdf::StringFormat::Literal);
-        content
-            .operations
-            .push(Operation::new("Tj", vec![text_object]));
-        content.operations.push(Operation::new("ET", vec![]));
-    }
-
-    // Position Y initiale (en partant du haut)
-    let mut y_pos = 800.0;
-
-    // Titre principal
-    add_text(&mut content, "Rapport de Ventes", "F2", 24.0, 50.0, y_pos);
-    y_pos -= 30.0;
-
-    // Ligne de séparation
-    content.operations.push(Operation::new("q", vec![]));
-    content
-        .operations
-        .push(Operation::new("w", vec![1.0.into()]));
-    content
-        .operations
-        .push(Operation::new("m", vec![50.0.into(), y_pos.into()]));
-    content
-        .operations
-        .push(Operation::new("l", vec![545.0.into(), y_pos.into()]));
-    content.operations.push(Operation::new("S", vec![]));
-    content.operations.push(Operation::new("Q", vec![]));
-    y_pos -= 20.0;
-
-    // Section Statistiques Clés
-    add_text(&mut content, "Statistiques Clés", "F2", 18.0, 50.0, y_pos);
-    y_pos -= 25.0;
-
-    add_text(
-        &mut content,
-        &format!("Chiffre d'Affaires: {} XAF", data.total_revenue),
-        "F1",
-        12.0,
-        50.0,
-        y_pos,
-    );
-    y_pos -= 20.0;
-
-    add_text(
-        &mut content,
-        &format!("Nombre de Ventes: {}", data.total_sales),
-        "F1",
-        12.0,
-        50.0,
-        y_pos,
-    );
-    y_pos -= 30.0;
+    report.title("Rapport de Ventes");
+    report.paragraph(&format!("Période {}", calendar.format_range(&data.range)));
+    if let Some(comparison) = &data.comparison {
+        report.paragraph(&format!(
+            "Comparaison ({}) : {}",
+            comparison.mode.label(),
+            calendar.format_range(&comparison.range)
+        ));
+    }
+
+    // Section Statistiques Clés, avec la période de comparaison si elle a été demandée
+    report.section("Statistiques Clés");
+    let mut columns = vec![
+        Column::left("Indicateur", 3.0),
+        Column::right("Période", 2.0),
+    ];
+    if data.comparison.is_some() {
+        columns.push(Column::right("Référence", 2.0));
+        columns.push(Column::right("Variation", 1.5));
+    }
+    let rows = kpi_rows(data)
+        .iter()
+        .map(|kpi| {
+            let mut row = vec![kpi.label.to_string(), kpi.display(&kpi.current)];
+            if let Some(previous) = &kpi.previous {
+                row.push(kpi.display(previous));
+                row.push(format_percent_change(&kpi.current, previous));
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
 
     // Section Top Produits
-    add_text(
-        &mut content,
-        "Top 5 Produits Vendus (par quantité)",
-        "F2",
-        18.0,
-        50.0,
-        y_pos,
+    report.section("Top 5 Produits Vendus (par quantité)");
+    let columns = [
+        Column::left("#", 0.5),
+        Column::left("Produit", 5.0),
+        Column::left("Conditionnement", 3.0),
+        Column::right("Unités vendues", 1.8),
+    ];
+    let rows = data
+        .top_products
+        .iter()
+        .enumerate()
+        .map(|(i, (product, quantity))| {
+            vec![
+                (i + 1).to_string(),
+                product.name.clone(),
+                product.packaging_description.clone(),
+                quantity.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+
+    // Section Évolution des ventes
+    report.section(&format!(
+        "Évolution des ventes (par {})",
+        data.granularity.label()
+    ));
+    let columns = [
+        Column::left("Période", 3.0),
+        Column::right("Ventes", 1.2),
+        Column::right("Unités", 1.2),
+        Column::right("Chiffre d'affaires", 2.2),
+        Column::right("Panier moyen", 2.0),
+        Column::right("Marge", 2.0),
+    ];
+    let rows = data
+        .timeseries
+        .iter()
+        .map(|point| {
+            vec![
+                bucket_title(data.granularity, point.bucket_start),
+                point.sales_count.to_string(),
+                point.units.to_string(),
+                money(&point.revenue),
+                money(&point.average_basket()),
+                money(&point.margin),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+
+    // Sections Marges : tous les produits vendus, puis par conditionnement et par vendeur
+    add_margin_table(
+        &mut report,
+        "Ventes et marges par produit",
+        "Produit",
+        &data.margins.by_product,
+    );
+    add_margin_table(
+        &mut report,
+        "Marges par conditionnement",
+        "Conditionnement",
+        &data.margins.by_packaging,
+    );
+    add_margin_table(
+        &mut report,
+        "Marges par vendeur",
+        "Vendeur",
+        &data.margins.by_seller,
     );
-    y_pos -= 25.0;
 
-    for (i, (product, quantity)) in data.top_products.iter().enumerate() {
-        if y_pos < 50.0 {
-            break; // Éviter de dépasser le bas de page
-        }
-        add_text(
-            &mut content,
-            &format!(
-                "#{}. {} - ({} unités vendues)",
-                i + 1,
-                product.name,
-                quantity
-            ),
-            "F1",
-            12.0,
-            50.0,
-            y_pos,
+    // Section Vendeurs
+    if !data.sellers.is_empty() {
+        report.section("Performances des vendeurs");
+        let columns = [
+            Column::left("Vendeur", 3.0),
+            Column::right("Ventes", 1.2),
+            Column::right("Unités", 1.2),
+            Column::right("Jours", 1.0),
+            Column::right("CA", 2.0),
+            Column::right("Panier moyen", 2.0),
+            Column::right("Commission", 2.0),
+        ];
+        let rows = data
+            .sellers
+            .iter()
+            .map(|seller| {
+                vec![
+                    seller.seller_name.clone(),
+                    seller.sales_count.to_string(),
+                    seller.units.to_string(),
+                    seller.active_days.to_string(),
+                    money(&seller.revenue),
+                    money(&seller.average_basket()),
+                    money(&seller.commission),
+                ]
+            })
+            .collect::<Vec<_>>();
+        report.table(&columns, &rows);
+    }
+
+    // Section Consignes
+    if !data.deposits.is_empty() {
+        report.section("Consignes (emballages)");
+        let columns = [
+            Column::left("Conditionnement", 3.0),
+            Column::right("Sortis", 1.0),
+            Column::right("Rendus", 1.0),
+            Column::right("Consignes nettes", 2.0),
+            Column::right("Vides en stock", 1.5),
+            Column::right("Non rendus", 1.3),
+            Column::right("Dont clients", 1.3),
+        ];
+        let rows = data
+            .deposits
+            .iter()
+            .map(|line| {
+                vec![
+                    line.packaging_description.clone(),
+                    line.quantity_out.to_string(),
+                    line.quantity_returned.to_string(),
+                    money(&line.net_amount),
+                    line.empties_in_stock.to_string(),
+                    line.empties_outstanding.to_string(),
+                    line.empties_with_customers.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        report.table(&columns, &rows);
+    }
+
+    // Section Détail des ventes : chaque vente de la période, annulées comprises
+    report.section("Détail des ventes");
+    let columns = [
+        Column::left("Numéro", 2.6),
+        Column::left("Date", 1.9),
+        Column::left("Vendeur", 1.8),
+        Column::left("Client", 2.0),
+        Column::left("Paiement", 2.0),
+        Column::left("Statut", 1.5),
+        Column::right("Unités", 1.0),
+        Column::right("Montant", 1.8),
+    ];
+    let rows = data
+        .sales
+        .iter()
+        .map(|sale| {
+            vec![
+                sale.sale_number.clone(),
+                calendar.format_local(sale.date, "%d/%m/%Y %H:%M"),
+                sale.seller.clone(),
+                sale.customer.clone().unwrap_or_else(|| "—".to_string()),
+                sale.payment_methods.join(", "),
+                sale.status.label().to_string(),
+                sale.units.to_string(),
+                money(&sale.total_amount),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+    if !data.sales.is_empty() {
+        // Le total des ventes retenues correspond au chiffre d'affaires avant avoirs
+        let kept = data
+            .sales
+            .iter()
+            .filter(|sale| sale.status != SaleStatus::Voided)
+            .collect::<Vec<_>>();
+        let total = kept
+            .iter()
+            .fold(BigDecimal::from(0), |sum, sale| sum + &sale.total_amount);
+        report.total_row(
+            &columns,
+            &[
+                format!("{} vente(s) hors annulations", kept.len()),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                kept.iter().map(|sale| sale.units).sum::<i64>().to_string(),
+                money(&total),
+            ],
         );
-        y_pos -= 15.0;
-    }
-
-    // Section additionnelle avec informations sur l'encodage
-    y_pos -= 20.0;
-    add_text(&mut content, "Généré avec succès", "F1", 10.0, 50.0, y_pos);
-    y_pos -= 15.0;
-    add_text(
-        &mut content,
-        &format!("Date: {}", chrono::Local::now().format("%d/%m/%Y à %H:%M")),
-        "F1",
-        10.0,
-        50.0,
-        y_pos,
+    }
+
+    let page_count = report.page_count();
+    report.save(&file_path)?;
+
+    log::info!(
+        "Rapport PDF généré avec succès ({} page(s)) : {}",
+        page_count,
+        file_path
     );
+    Ok(file_path)
+}
 
-    // Ajouter le contenu à la page
-    let content_stream = Stream::new(dictionary! {}, content.encode().unwrap());
-    let content_id = doc.add_object(content_stream);
-
-    // Créer la page
-    let page_id = doc.add_object(dictionary! {
-        "Type" => "Page",
-        "Parent" => pages_id,
-        "Contents" => content_id,
-        "Resources" => resources_id,
-        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
-    });
-
-    // Ajouter la page à l'arborescence
-    let pages = dictionary! {
-        "Type" => "Pages",
-        "Kids" => vec![page_id.into()],
-        "Count" => 1,
-        "Resources" => resources_id,
-        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
-    };
-    doc.objects.insert(pages_id, Object::Dictionary(pages));
+/// Relevé de commissions d'un vendeur sur la période du rapport :
+/// synthèse de son activité, règle appliquée et détail par journée.
+pub fn generate_commission_statement_pdf(data: &ReportData, seller_id: Uuid) -> AppResult<String> {
+    let seller = data
+        .sellers
+        .iter()
+        .find(|s| s.seller_id == seller_id)
+        .ok_or_else(|| {
+            AppError::ValidationError("Aucune vente de ce vendeur sur la période".to_string())
+        })?;
+    let file_path = get_save_path("releve_commissions", "pdf")?;
+    let calendar = business_calendar();
+    let mut report = PdfReport::new("Relevé de Commissions")?;
+
+    report.title("Relevé de Commissions");
+    report.strong(&format!("Vendeur : {}", seller.seller_name));
+    report.paragraph(&format!("Période {}", calendar.format_range(&data.range)));
+    let rule = seller
+        .rule
+        .as_ref()
+        .map(|rule| rule.describe())
+        .unwrap_or_else(|| "aucune règle de commission définie".to_string());
+    report.paragraph(&format!("Règle appliquée : {}", rule));
+
+    report.section("Synthèse");
+    let columns = [
+        Column::left("Indicateur", 3.0),
+        Column::right("Valeur", 2.0),
+    ];
+    let rows = [
+        ("Chiffre d'affaires", money(&seller.revenue)),
+        ("Nombre de ventes", seller.sales_count.to_string()),
+        ("Panier moyen", money(&seller.average_basket())),
+        ("Unités vendues", seller.units.to_string()),
+        ("Jours d'activité", seller.active_days.to_string()),
+    ]
+    .into_iter()
+    .map(|(label, value)| vec![label.to_string(), value])
+    .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+    report.strong(&format!("Commission due : {}", money(&seller.commission)));
+
+    report.section("Détail par journée");
+    let columns = [
+        Column::left("Date", 2.0),
+        Column::right("Ventes", 1.2),
+        Column::right("Unités", 1.2),
+        Column::right("Chiffre d'affaires", 2.5),
+        Column::right("Commission", 2.0),
+    ];
+    let rows = seller
+        .days
+        .iter()
+        .map(|day| {
+            vec![
+                day.date.format("%d/%m/%Y").to_string(),
+                day.sales_count.to_string(),
+                day.units.to_string(),
+                money(&day.revenue),
+                money(&day.commission),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&columns, &rows);
+    report.total_row(
+        &columns,
+        &[
+            "Total".to_string(),
+            seller.sales_count.to_string(),
+            seller.units.to_string(),
+            money(&seller.revenue),
+            money(&seller.commission),
+        ],
+    );
+
+    report.save(&file_path)?;
+    log::info!("Relevé de commissions généré avec succès : {}", file_path);
+    Ok(file_path)
+}
 
-    // Créer le catalogue
-    let catalog_id = doc.add_object(dictionary! {
-        "Type" => "Catalog",
-        "Pages" => pages_id,
-    });
+/// Feuille de comptage d'un inventaire : le stock théorique n'y figure pas, pour un comptage à l'aveugle.
+pub fn generate_inventory_count_sheet_pdf(report: &InventoryReport) -> AppResult<String> {
+    let file_path = get_save_path("feuille_inventaire", "pdf")?;
+    let calendar = business_calendar();
+    let mut pdf = PdfReport::new("Feuille de Comptage")?;
 
-    // Définir la racine du document
-    doc.trailer.set("Root", catalog_id);
+    pdf.title("Feuille de Comptage");
+    pdf.paragraph(&format!(
+        "Inventaire ouvert le {} par {}",
+        calendar.format_local(report.session.opened_at, "%d/%m/%Y à %H:%M"),
+        report.opened_by
+    ));
+    if let Some(note) = &report.session.note {
+        pdf.paragraph(note);
+    }
 
-    // Compresser le document
-    doc.compress();
+    let columns = [
+        Column::left("Produit", 3.0),
+        Column::left("Conditionnement", 3.0),
+        Column::left("SKU", 2.0),
+        Column::right("Quantité comptée", 2.0),
+    ];
+    let rows = report
+        .lines
+        .iter()
+        .map(|l| {
+            vec![
+                l.product_name.clone(),
+                l.packaging.clone(),
+                l.sku.clone().unwrap_or_default(),
+                String::new(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    pdf.table(&columns, &rows);
 
-    // Sauvegarder le document
-    doc.save(&file_path)?;
+    pdf.spacer(20.0);
+    pdf.paragraph("Compté par : ................................    Vérifié par : ................................");
 
-    log::info!("Rapport PDF généré avec succès : {}", file_path);
+    pdf.save(&file_path)?;
+    log::info!("Feuille de comptage générée avec succès : {}", file_path);
+    Ok(file_path)
+}
+
+/// Rapport d'écarts d'un inventaire, valorisés au prix de revient et au prix de vente figés à l'ouverture.
+pub fn generate_inventory_variance_pdf(report: &InventoryReport) -> AppResult<String> {
+    let file_path = get_save_path("ecarts_inventaire", "pdf")?;
+    let calendar = business_calendar();
+    let mut pdf = PdfReport::new("Écarts d'Inventaire")?;
+
+    pdf.title("Écarts d'Inventaire");
+    pdf.paragraph(&format!(
+        "Inventaire ouvert le {} par {}",
+        calendar.format_local(report.session.opened_at, "%d/%m/%Y à %H:%M"),
+        report.opened_by
+    ));
+    let status = report.session.status().map(|s| s.label()).unwrap_or("?");
+    match (report.session.closed_at, &report.closed_by) {
+        (Some(closed_at), Some(closed_by)) => pdf.paragraph(&format!(
+            "{} le {} par {}",
+            status,
+            calendar.format_local(closed_at, "%d/%m/%Y à %H:%M"),
+            closed_by
+        )),
+        _ => pdf.paragraph(&format!("Statut : {}", status)),
+    }
+    if let Some(note) = &report.session.note {
+        pdf.paragraph(note);
+    }
+
+    pdf.section("Synthèse");
+    let columns = [
+        Column::left("Indicateur", 3.0),
+        Column::right("Valeur", 2.0),
+    ];
+    let rows = [
+        ("Produits comptés", report.counted_count().to_string()),
+        ("Produits non comptés", report.uncounted_count().to_string()),
+        (
+            "Produits en écart",
+            report.discrepancies().count().to_string(),
+        ),
+        (
+            "Écart au prix de revient",
+            money(&report.total_variance_cost()),
+        ),
+        (
+            "Écart au prix de vente",
+            money(&report.total_variance_value()),
+        ),
+    ]
+    .into_iter()
+    .map(|(label, value)| vec![label.to_string(), value])
+    .collect::<Vec<_>>();
+    pdf.table(&columns, &rows);
+
+    pdf.section("Détail des écarts");
+    let columns = [
+        Column::left("Produit", 4.0),
+        Column::right("Théorique", 1.5),
+        Column::right("Compté", 1.5),
+        Column::right("Écart", 1.2),
+        Column::right("Valeur (revient)", 2.2),
+        Column::right("Valeur (vente)", 2.2),
+    ];
+    let rows = report
+        .discrepancies()
+        .map(|l| {
+            vec![
+                l.product_label(),
+                l.line.expected_stock.to_string(),
+                l.line.counted_stock.unwrap_or_default().to_string(),
+                format!("{:+}", l.line.variance().unwrap_or_default()),
+                l.line
+                    .variance_cost()
+                    .map(|v| money(&v))
+                    .unwrap_or_else(|| "n.d.".to_string()),
+                l.line
+                    .variance_value()
+                    .map(|v| money(&v))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    pdf.table(&columns, &rows);
+    if !rows.is_empty() {
+        pdf.total_row(
+            &columns,
+            &[
+                "Total".to_string(),
+                String::new(),
+                String::new(),
+                format!(
+                    "{:+}",
+                    report
+                        .discrepancies()
+                        .filter_map(|l| l.line.variance())
+                        .sum::<i32>()
+                ),
+                money(&report.total_variance_cost()),
+                money(&report.total_variance_value()),
+            ],
+        );
+    }
+
+    let uncounted = report
+        .lines
+        .iter()
+        .filter(|l| l.line.counted_stock.is_none())
+        .map(|l| vec![l.product_label(), l.line.expected_stock.to_string()])
+        .collect::<Vec<_>>();
+    if !uncounted.is_empty() {
+        pdf.section("Produits non comptés (stock inchangé)");
+        let columns = [
+            Column::left("Produit", 4.0),
+            Column::right("Théorique", 1.5),
+        ];
+        pdf.table(&columns, &uncounted);
+    }
+
+    pdf.save(&file_path)?;
+    log::info!(
+        "Rapport d'écarts d'inventaire généré avec succès : {}",
+        file_path
+    );
     Ok(file_path)
 }
 
 // --- GÉNÉRATION EXCEL ---
+
+/// Formats partagés par les feuilles du classeur.
+struct SheetFormats {
+    header: Format,
+    money: Format,
+    date: Format,
+    datetime: Format,
+    total: Format,
+    total_money: Format,
+}
+
+impl SheetFormats {
+    fn new() -> Self {
+        let money = "#,##0 \"XAF\"";
+        Self {
+            header: Format::new().set_bold().set_background_color("#DDEBF7"),
+            money: Format::new().set_num_format(money),
+            date: Format::new().set_num_format("dd/mm/yyyy"),
+            datetime: Format::new().set_num_format("dd/mm/yyyy hh:mm"),
+            total: Format::new().set_bold().set_border_top(FormatBorder::Thin),
+            total_money: Format::new()
+                .set_bold()
+                .set_border_top(FormatBorder::Thin)
+                .set_num_format(money),
+        }
+    }
+}
+
+fn to_number(value: &BigDecimal) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0)
+}
+
+/// Date Excel d'une journée commerciale.
+fn excel_date(date: NaiveDate) -> AppResult<ExcelDateTime> {
+    Ok(ExcelDateTime::from_ymd(
+        date.year() as u16,
+        date.month() as u8,
+        date.day() as u8,
+    )?)
+}
+
+/// Date et heure Excel d'un instant, dans l'heure locale du dépôt.
+fn excel_datetime(at: DateTime<Utc>) -> AppResult<ExcelDateTime> {
+    let local = at
+        .with_timezone(&business_calendar().timezone)
+        .naive_local();
+    Ok(excel_date(local.date())?.and_hms(
+        local.hour() as u16,
+        local.minute() as u8,
+        local.second(),
+    )?)
+}
+
+/// En-tête d'un tableau de `rows` lignes : largeur des colonnes, première ligne figée
+/// et filtre automatique.
+fn write_table_header(
+    worksheet: &mut Worksheet,
+    headers: &[(&str, f64)],
+    rows: usize,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    for (col, (title, width)) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *title, &formats.header)?;
+        worksheet.set_column_width(col as u16, *width)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+    worksheet.autofilter(0, 0, rows.max(1) as u32, headers.len() as u16 - 1)?;
+    Ok(())
+}
+
+/// Plage des données d'une colonne, par exemple `G2:G41` pour 40 lignes.
+fn column_range(col: u16, rows: usize) -> String {
+    cell_range(1, col, rows as u32, col)
+}
+
 pub fn generate_excel_report(data: &ReportData) -> AppResult<String> {
-    let file_path = get_save_path("xlsx")?;
+    let file_path = get_save_path("rapport_ventes", "xlsx")?;
     let mut workbook = Workbook::new();
+    let formats = SheetFormats::new();
     let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Synthèse")?;
 
     let title_format = Format::new().set_bold().set_font_size(16.0);
-    let header_format = Format::new().set_bold().set_background_color("#DDEBF7");
-    let money_format = Format::new().set_num_format("#,##0 \"XAF\"");
+    let header_format = &formats.header;
+    let money_format = &formats.money;
+    let percent_format = Format::new().set_num_format("+0.0%;-0.0%;0.0%");
 
     worksheet.set_column_width(0, 30.0)?;
     worksheet.set_column_width(1, 15.0)?;
+    worksheet.set_column_width(2, 25.0)?;
+    worksheet.set_column_width(3, 12.0)?;
 
+    let calendar = business_calendar();
     worksheet.write_string_with_format(0, 0, "Rapport de Ventes", &title_format)?;
-    worksheet.write_string_with_format(2, 0, "Statistiques Clés", &header_format)?;
-    worksheet.write_string(3, 0, "Chiffre d'Affaires Total")?;
-    worksheet.write_number_with_format(
-        3,
+    worksheet.write_string(
         1,
-        data.total_revenue.to_string().parse().unwrap_or(0.0),
-        &money_format,
+        0,
+        format!("Période {}", calendar.format_range(&data.range)),
     )?;
-    worksheet.write_string(4, 0, "Nombre de Ventes")?;
-    worksheet.write_number(4, 1, data.total_sales as f64)?;
-    worksheet.write_string_with_format(6, 0, "Top 5 Produits Vendus", &header_format)?;
-    worksheet.write_string(7, 0, "Produit")?;
-    worksheet.write_string(7, 1, "Quantité Vendue")?;
+    worksheet.write_string_with_format(2, 0, "Statistiques Clés", header_format)?;
+    worksheet.write_string_with_format(2, 1, "Période", header_format)?;
+    if let Some(comparison) = &data.comparison {
+        worksheet.write_string_with_format(
+            2,
+            2,
+            format!(
+                "{} ({})",
+                comparison.mode.label(),
+                calendar.format_range(&comparison.range)
+            ),
+            header_format,
+        )?;
+        worksheet.write_string_with_format(2, 3, "Variation", header_format)?;
+    }
+
+    for (i, kpi) in kpi_rows(data).iter().enumerate() {
+        let row = 3 + i as u32;
+        worksheet.write_string(row, 0, kpi.label)?;
+        let values = std::iter::once((1, &kpi.current)).chain(kpi.previous.iter().map(|p| (2, p)));
+        for (col, value) in values {
+            if kpi.is_money {
+                worksheet.write_number_with_format(row, col, to_number(value), money_format)?;
+            } else {
+                worksheet.write_number(row, col, to_number(value))?;
+            }
+        }
+        if let Some(previous) = &kpi.previous {
+            match percent_change(&kpi.current, previous) {
+                Some(change) => worksheet.write_number_with_format(
+                    row,
+                    3,
+                    to_number(&change) / 100.0,
+                    &percent_format,
+                )?,
+                None => worksheet.write_string(row, 3, "n.d.")?,
+            };
+        }
+    }
+
+    let top_row = 10;
+    worksheet.write_string_with_format(top_row, 0, "Top 5 Produits Vendus", header_format)?;
+    worksheet.write_string(top_row + 1, 0, "Produit")?;
+    worksheet.write_string(top_row + 1, 1, "Quantité Vendue")?;
     for (i, (product, quantity)) in data.top_products.iter().enumerate() {
-        worksheet.write_string(8 + i as u32, 0, &product.name)?;
-        worksheet.write_number(8 + i as u32, 1, *quantity as f64)?;
+        worksheet.write_string(top_row + 2 + i as u32, 0, &product.name)?;
+        worksheet.write_number(top_row + 2 + i as u32, 1, *quantity as f64)?;
+    }
+
+    if !data.deposits.is_empty() {
+        let start_row = top_row + 3 + data.top_products.len() as u32;
+        worksheet.write_string_with_format(start_row, 0, "Consignes", header_format)?;
+        let headers = [
+            "Conditionnement",
+            "Consigne",
+            "Sortis",
+            "Rendus",
+            "Consignes nettes",
+            "Vides en stock",
+            "Non rendus",
+            "Dus par les clients",
+        ];
+        for (col, header) in headers.iter().enumerate() {
+            worksheet.write_string(start_row + 1, col as u16, *header)?;
+        }
+        for (i, line) in data.deposits.iter().enumerate() {
+            let row = start_row + 2 + i as u32;
+            worksheet.write_string(row, 0, &line.packaging_description)?;
+            worksheet.write_number_with_format(
+                row,
+                1,
+                to_number(&line.deposit_amount),
+                money_format,
+            )?;
+            worksheet.write_number(row, 2, line.quantity_out as f64)?;
+            worksheet.write_number(row, 3, line.quantity_returned as f64)?;
+            worksheet.write_number_with_format(
+                row,
+                4,
+                to_number(&line.net_amount),
+                money_format,
+            )?;
+            worksheet.write_number(row, 5, line.empties_in_stock as f64)?;
+            worksheet.write_number(row, 6, line.empties_outstanding as f64)?;
+            worksheet.write_number(row, 7, line.empties_with_customers as f64)?;
+        }
+    }
+
+    write_sales_sheet(&mut workbook, data, &formats)?;
+    write_sale_lines_sheet(&mut workbook, data, &formats)?;
+    write_daily_sheet(&mut workbook, data, &formats)?;
+    if !data.sellers.is_empty() {
+        write_sellers_sheet(&mut workbook, data, &formats)?;
+    }
+    write_stock_sheet(&mut workbook, data, &formats)?;
+    if !data.timeseries.is_empty() {
+        write_timeseries_sheet(&mut workbook, data, &formats)?;
+    }
+    if !data.margins.by_product.is_empty() {
+        write_margins_sheet(&mut workbook, data, &formats)?;
     }
 
     workbook.save(&file_path)?;
@@ -374,26 +842,530 @@ pub fn generate_excel_report(data: &ReportData) -> AppResult<String> {
     Ok(file_path)
 }
 
+/// Feuille « Ventes » : chaque vente de la période, annulées comprises.
+/// Les totaux excluent les ventes annulées.
+fn write_sales_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Ventes")?;
+    let headers = [
+        ("Numéro", 22.0),
+        ("Date", 17.0),
+        ("Vendeur", 18.0),
+        ("Client", 24.0),
+        ("Paiement", 24.0),
+        ("Statut", 15.0),
+        ("Unités", 10.0),
+        ("Montant", 16.0),
+    ];
+    let rows = data.sales.len();
+    write_table_header(worksheet, &headers, rows, formats)?;
+
+    for (i, sale) in data.sales.iter().enumerate() {
+        let row = 1 + i as u32;
+        worksheet.write_string(row, 0, &sale.sale_number)?;
+        worksheet.write_datetime_with_format(
+            row,
+            1,
+            &excel_datetime(sale.date)?,
+            &formats.datetime,
+        )?;
+        worksheet.write_string(row, 2, &sale.seller)?;
+        worksheet.write_string(row, 3, sale.customer.as_deref().unwrap_or(""))?;
+        worksheet.write_string(row, 4, sale.payment_methods.join(", "))?;
+        worksheet.write_string(row, 5, sale.status.label())?;
+        worksheet.write_number(row, 6, sale.units as f64)?;
+        worksheet.write_number_with_format(
+            row,
+            7,
+            to_number(&sale.total_amount),
+            &formats.money,
+        )?;
+    }
+
+    if rows > 0 {
+        let total_row = rows as u32 + 2;
+        let not_voided = format!(
+            "{},\"<>{}\"",
+            column_range(5, rows),
+            SaleStatus::Voided.label()
+        );
+        worksheet.write_string_with_format(
+            total_row,
+            0,
+            "Total hors ventes annulées",
+            &formats.total,
+        )?;
+        worksheet.write_formula_with_format(
+            total_row,
+            5,
+            format!("=COUNTIFS({})", not_voided).as_str(),
+            &formats.total,
+        )?;
+        worksheet.write_formula_with_format(
+            total_row,
+            6,
+            format!("=SUMIFS({},{})", column_range(6, rows), not_voided).as_str(),
+            &formats.total,
+        )?;
+        worksheet.write_formula_with_format(
+            total_row,
+            7,
+            format!("=SUMIFS({},{})", column_range(7, rows), not_voided).as_str(),
+            &formats.total_money,
+        )?;
+    }
+    Ok(())
+}
+
+/// Feuille « Lignes de vente » : chaque article vendu sur la période.
+/// Le montant net et la marge sont calculés par formule ; la marge reste vide sans coût connu.
+fn write_sale_lines_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Lignes de vente")?;
+    let headers = [
+        ("Numéro", 22.0),
+        ("Date", 17.0),
+        ("Vendeur", 18.0),
+        ("Statut", 15.0),
+        ("Produit", 28.0),
+        ("Conditionnement", 20.0),
+        ("Quantité", 10.0),
+        ("Reprise", 10.0),
+        ("Prix unitaire", 14.0),
+        ("Coût unitaire", 14.0),
+        ("Montant net", 16.0),
+        ("Marge brute", 16.0),
+    ];
+    let lines = data
+        .sales
+        .iter()
+        .flat_map(|sale| sale.items.iter().map(move |item| (sale, item)))
+        .collect::<Vec<_>>();
+    let rows = lines.len();
+    write_table_header(worksheet, &headers, rows, formats)?;
+
+    for (i, (sale, item)) in lines.iter().enumerate() {
+        let row = 1 + i as u32;
+        // Numéro de ligne tel qu'affiché par Excel, pour les formules
+        let r = row + 1;
+        worksheet.write_string(row, 0, &sale.sale_number)?;
+        worksheet.write_datetime_with_format(
+            row,
+            1,
+            &excel_datetime(sale.date)?,
+            &formats.datetime,
+        )?;
+        worksheet.write_string(row, 2, &sale.seller)?;
+        worksheet.write_string(row, 3, sale.status.label())?;
+        worksheet.write_string(row, 4, &item.product)?;
+        worksheet.write_string(row, 5, &item.packaging)?;
+        worksheet.write_number(row, 6, item.quantity as f64)?;
+        worksheet.write_number(row, 7, item.quantity_returned as f64)?;
+        worksheet.write_number_with_format(row, 8, to_number(&item.unit_price), &formats.money)?;
+        if let Some(cost) = &item.unit_cost {
+            worksheet.write_number_with_format(row, 9, to_number(cost), &formats.money)?;
+        }
+        worksheet.write_formula_with_format(
+            row,
+            10,
+            format!("=(G{r}-H{r})*I{r}").as_str(),
+            &formats.money,
+        )?;
+        worksheet.write_formula_with_format(
+            row,
+            11,
+            format!("=IF(J{r}=\"\",\"\",(G{r}-H{r})*(I{r}-J{r}))").as_str(),
+            &formats.money,
+        )?;
+    }
+
+    if rows > 0 {
+        let total_row = rows as u32 + 2;
+        let not_voided = format!(
+            "{},\"<>{}\"",
+            column_range(3, rows),
+            SaleStatus::Voided.label()
+        );
+        worksheet.write_string_with_format(
+            total_row,
+            0,
+            "Total hors ventes annulées",
+            &formats.total,
+        )?;
+        for (col, format) in [
+            (6, &formats.total),
+            (7, &formats.total),
+            (10, &formats.total_money),
+            (11, &formats.total_money),
+        ] {
+            worksheet.write_formula_with_format(
+                total_row,
+                col,
+                format!("=SUMIFS({},{})", column_range(col, rows), not_voided).as_str(),
+                format,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Feuille « Journalier » : totaux de chaque journée commerciale de la période.
+fn write_daily_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Journalier")?;
+    let headers = [
+        ("Date", 12.0),
+        ("Ventes", 10.0),
+        ("Unités vendues", 14.0),
+        ("Chiffre d'affaires", 18.0),
+        ("Panier moyen", 16.0),
+        ("Marge brute", 16.0),
+    ];
+    let rows = data.daily.len();
+    write_table_header(worksheet, &headers, rows, formats)?;
+
+    for (i, day) in data.daily.iter().enumerate() {
+        let row = 1 + i as u32;
+        let r = row + 1;
+        worksheet.write_datetime_with_format(
+            row,
+            0,
+            &excel_date(day.bucket_start)?,
+            &formats.date,
+        )?;
+        worksheet.write_number(row, 1, day.sales_count as f64)?;
+        worksheet.write_number(row, 2, day.units as f64)?;
+        worksheet.write_number_with_format(row, 3, to_number(&day.revenue), &formats.money)?;
+        worksheet.write_formula_with_format(
+            row,
+            4,
+            format!("=IF(B{r}=0,0,D{r}/B{r})").as_str(),
+            &formats.money,
+        )?;
+        worksheet.write_number_with_format(row, 5, to_number(&day.margin), &formats.money)?;
+    }
+
+    if rows > 0 {
+        let total_row = rows as u32 + 2;
+        let r = total_row + 1;
+        worksheet.write_string_with_format(total_row, 0, "Total", &formats.total)?;
+        for (col, format) in [
+            (1, &formats.total),
+            (2, &formats.total),
+            (3, &formats.total_money),
+            (5, &formats.total_money),
+        ] {
+            worksheet.write_formula_with_format(
+                total_row,
+                col,
+                format!("=SUM({})", column_range(col, rows)).as_str(),
+                format,
+            )?;
+        }
+        worksheet.write_formula_with_format(
+            total_row,
+            4,
+            format!("=IF(B{r}=0,0,D{r}/B{r})").as_str(),
+            &formats.total_money,
+        )?;
+    }
+    Ok(())
+}
+
+/// Feuille « Stock » : stock actuel de chaque produit, valorisé au prix de revient
+/// et au prix de vente. La valeur au coût reste vide pour un produit sans prix de revient.
+fn write_stock_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Stock")?;
+    let headers = [
+        ("Produit", 30.0),
+        ("Conditionnement", 20.0),
+        ("Référence", 14.0),
+        ("Stock", 10.0),
+        ("Prix de revient", 16.0),
+        ("Prix de vente", 16.0),
+        ("Valeur au coût", 18.0),
+        ("Valeur de vente", 18.0),
+    ];
+    let rows = data.stock.len();
+    write_table_header(worksheet, &headers, rows, formats)?;
+
+    for (i, product) in data.stock.iter().enumerate() {
+        let row = 1 + i as u32;
+        let r = row + 1;
+        worksheet.write_string(row, 0, &product.name)?;
+        worksheet.write_string(row, 1, &product.packaging_description)?;
+        worksheet.write_string(row, 2, product.sku.as_deref().unwrap_or(""))?;
+        worksheet.write_number(row, 3, product.stock_in_sale_units as f64)?;
+        if let Some(cost) = &product.cost_price {
+            worksheet.write_number_with_format(row, 4, to_number(cost), &formats.money)?;
+        }
+        worksheet.write_number_with_format(
+            row,
+            5,
+            to_number(&product.price_per_sale_unit),
+            &formats.money,
+        )?;
+        worksheet.write_formula_with_format(
+            row,
+            6,
+            format!("=IF(E{r}=\"\",\"\",D{r}*E{r})").as_str(),
+            &formats.money,
+        )?;
+        worksheet.write_formula_with_format(
+            row,
+            7,
+            format!("=D{r}*F{r}").as_str(),
+            &formats.money,
+        )?;
+    }
+
+    if rows > 0 {
+        let total_row = rows as u32 + 2;
+        worksheet.write_string_with_format(total_row, 0, "Total", &formats.total)?;
+        for (col, format) in [
+            (3, &formats.total),
+            (6, &formats.total_money),
+            (7, &formats.total_money),
+        ] {
+            worksheet.write_formula_with_format(
+                total_row,
+                col,
+                format!("=SUM({})", column_range(col, rows)).as_str(),
+                format,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Feuille « Marges » : marge brute par produit, par conditionnement et par vendeur.
+fn write_margins_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Marges")?;
+    worksheet.set_column_width(0, 40.0)?;
+    for col in 1..=4 {
+        worksheet.set_column_width(col, 16.0)?;
+    }
+    let percent_format = Format::new().set_num_format("0.0%");
+
+    let sections = [
+        ("Par produit", &data.margins.by_product),
+        ("Par conditionnement", &data.margins.by_packaging),
+        ("Par vendeur", &data.margins.by_seller),
+    ];
+    let mut row = 0;
+    for (title, lines) in sections {
+        let headers = [
+            title,
+            "Unités",
+            "Chiffre d'affaires",
+            "Marge brute",
+            "Taux de marge",
+        ];
+        for (col, header) in headers.iter().enumerate() {
+            worksheet.write_string_with_format(row, col as u16, *header, &formats.header)?;
+        }
+        row += 1;
+        for line in lines.iter() {
+            worksheet.write_string(row, 0, &line.label)?;
+            worksheet.write_number(row, 1, line.quantity as f64)?;
+            worksheet.write_number_with_format(
+                row,
+                2,
+                to_number(&(&line.revenue + &line.uncosted_revenue)),
+                &formats.money,
+            )?;
+            worksheet.write_number_with_format(
+                row,
+                3,
+                to_number(&line.margin()),
+                &formats.money,
+            )?;
+            match line.margin_rate() {
+                Some(rate) => {
+                    worksheet.write_number_with_format(
+                        row,
+                        4,
+                        to_number(&rate) / 100.0,
+                        &percent_format,
+                    )?;
+                }
+                None => {
+                    worksheet.write_string(row, 4, "n.d.")?;
+                }
+            }
+            row += 1;
+        }
+        row += 1;
+    }
+    Ok(())
+}
+
+/// Feuille « Vendeurs » : performance et commission de chaque vendeur sur la période.
+fn write_sellers_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Vendeurs")?;
+    let headers = [
+        ("Vendeur", 25.0),
+        ("Chiffre d'affaires", 16.0),
+        ("Ventes", 16.0),
+        ("Panier moyen", 16.0),
+        ("Unités vendues", 16.0),
+        ("Jours d'activité", 16.0),
+        ("Règle de commission", 40.0),
+        ("Commission", 16.0),
+    ];
+    let rows = data.sellers.len();
+    write_table_header(worksheet, &headers, rows, formats)?;
+
+    for (i, seller) in data.sellers.iter().enumerate() {
+        let row = 1 + i as u32;
+        worksheet.write_string(row, 0, &seller.seller_name)?;
+        worksheet.write_number_with_format(row, 1, to_number(&seller.revenue), &formats.money)?;
+        worksheet.write_number(row, 2, seller.sales_count as f64)?;
+        worksheet.write_number_with_format(
+            row,
+            3,
+            to_number(&seller.average_basket()),
+            &formats.money,
+        )?;
+        worksheet.write_number(row, 4, seller.units as f64)?;
+        worksheet.write_number(row, 5, seller.active_days as f64)?;
+        worksheet.write_string(
+            row,
+            6,
+            seller
+                .rule
+                .as_ref()
+                .map(|rule| rule.describe())
+                .unwrap_or_else(|| "Aucune".to_string()),
+        )?;
+        worksheet.write_number_with_format(
+            row,
+            7,
+            to_number(&seller.commission),
+            &formats.money,
+        )?;
+    }
+
+    let total_row = rows as u32 + 2;
+    let r = total_row + 1;
+    worksheet.write_string_with_format(total_row, 0, "Total", &formats.total)?;
+    for (col, format) in [
+        (1, &formats.total_money),
+        (2, &formats.total),
+        (4, &formats.total),
+        (7, &formats.total_money),
+    ] {
+        worksheet.write_formula_with_format(
+            total_row,
+            col,
+            format!("=SUM({})", column_range(col, rows)).as_str(),
+            format,
+        )?;
+    }
+    worksheet.write_formula_with_format(
+        total_row,
+        3,
+        format!("=IF(C{r}=0,0,B{r}/C{r})").as_str(),
+        &formats.total_money,
+    )?;
+    Ok(())
+}
+
+/// Feuille « Évolution » : la série temporelle du rapport et son graphique natif.
+fn write_timeseries_sheet(
+    workbook: &mut Workbook,
+    data: &ReportData,
+    formats: &SheetFormats,
+) -> AppResult<()> {
+    const SHEET_NAME: &str = "Évolution";
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(SHEET_NAME)?;
+    worksheet.set_column_width(0, 14.0)?;
+    worksheet.set_column_width(1, 18.0)?;
+    worksheet.set_column_width(4, 16.0)?;
+    worksheet.set_column_width(5, 16.0)?;
+
+    let headers = [
+        "Période",
+        "Chiffre d'affaires",
+        "Ventes",
+        "Unités vendues",
+        "Panier moyen",
+        "Marge brute",
+    ];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &formats.header)?;
+    }
+    for (i, point) in data.timeseries.iter().enumerate() {
+        let row = 1 + i as u32;
+        worksheet.write_string(row, 0, data.granularity.bucket_label(point.bucket_start))?;
+        worksheet.write_number_with_format(row, 1, to_number(&point.revenue), &formats.money)?;
+        worksheet.write_number(row, 2, point.sales_count as f64)?;
+        worksheet.write_number(row, 3, point.units as f64)?;
+        worksheet.write_number_with_format(
+            row,
+            4,
+            to_number(&point.average_basket()),
+            &formats.money,
+        )?;
+        worksheet.write_number_with_format(row, 5, to_number(&point.margin), &formats.money)?;
+    }
+
+    let last_row = data.timeseries.len() as u32;
+    let mut chart = Chart::new(ChartType::Column);
+    chart
+        .add_series()
+        .set_name("Chiffre d'affaires")
+        .set_categories((SHEET_NAME, 1, 0, last_row, 0))
+        .set_values((SHEET_NAME, 1, 1, last_row, 1));
+    chart.title().set_name(&format!(
+        "Chiffre d'affaires par {}",
+        data.granularity.label()
+    ));
+    chart.legend().set_hidden();
+    worksheet.insert_chart(1, 7, &chart)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_encode_text_for_pdf_bytes() {
-        let text = "Café français avec des accents: é è ê à ç";
-        let encoded = encode_text_for_pdf_bytes(text);
-        // Vérifier que les bytes correspondent aux caractères attendus
-        assert!(encoded.contains(&0xE9)); // é
-        assert!(encoded.contains(&0xE8)); // è
-        assert!(encoded.contains(&0xEA)); // ê
-        assert!(encoded.contains(&0xE0)); // à
-        assert!(encoded.contains(&0xE7)); // ç
-    }
-
-    #[test]
-    fn test_encode_cafe() {
-        let text = "Café";
-        let encoded = encode_text_for_pdf_bytes(text);
-        assert_eq!(encoded, vec![67, 97, 102, 0xE9]); // C a f é
+    fn test_bucket_title() {
+        let date = NaiveDate::from_ymd_opt(2025, 7, 14).unwrap();
+        assert_eq!(bucket_title(Granularity::Day, date), "14/07/2025");
+        assert_eq!(
+            bucket_title(Granularity::Week, date),
+            "S29 — semaine du 14/07/2025"
+        );
+        assert_eq!(bucket_title(Granularity::Month, date), "07/2025");
     }
 }